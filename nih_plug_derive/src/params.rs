@@ -29,8 +29,9 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
     // JSON. The `nested` fields should also implement the `Params` trait and their fields will be
     // inherited and added to this field's param mapping list. The order follows the declaration
     // order We'll also enforce that there are no duplicate keys for `id` fields at compile time.
-    // TODO: This duplication check doesn't work for nested fields since we don't know anything
-    //       about the fields on the nested structs
+    // This check only covers this struct's own fields since we don't know anything about the
+    // fields on the nested structs here. IDs coming from nested structs are checked using the
+    // `StaticParamIds` implementation generated below, if the nested structs implement that trait.
     let mut params: Vec<Param> = Vec::new();
     let mut persistent_fields: Vec<PersistentField> = Vec::new();
     for field in fields.named {
@@ -91,35 +92,55 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                     }
                 };
             } else if attr.path.is_ident("persist") {
+                // This can either be a `#[persist = "key"]` attribute, or a `#[persist(key =
+                // "key", skip_presets, skip_duplicates)]` attribute where the last two options are
+                // optional
+                let mut persist_key: Option<syn::LitStr> = None;
+                let mut persist_skip_presets = false;
+                let mut persist_skip_duplicates = false;
                 match attr.parse_meta() {
                     Ok(syn::Meta::NameValue(syn::MetaNameValue {
                         lit: syn::Lit::Str(s),
                         ..
                     })) => {
-                        if processed_attribute {
-                            return syn::Error::new(
-                                attr.span(),
-                                "Duplicate or incompatible attribute found",
-                            )
-                            .to_compile_error()
-                            .into();
-                        }
-
-                        if persistent_fields.iter().any(|p| p.key == s) {
-                            return syn::Error::new(
-                                field.span(),
-                                "Multiple persistent fields with the same key found",
-                            )
-                            .to_compile_error()
-                            .into();
+                        persist_key = Some(s);
+                    }
+                    Ok(syn::Meta::List(syn::MetaList {
+                        nested: persist_attrs,
+                        ..
+                    })) => {
+                        for persist_attr in persist_attrs {
+                            match persist_attr {
+                                syn::NestedMeta::Meta(syn::Meta::NameValue(
+                                    syn::MetaNameValue {
+                                        path,
+                                        lit: syn::Lit::Str(s),
+                                        ..
+                                    },
+                                )) if path.is_ident("key") => {
+                                    persist_key = Some(s.clone());
+                                }
+                                syn::NestedMeta::Meta(syn::Meta::Path(p))
+                                    if p.is_ident("skip_presets") =>
+                                {
+                                    persist_skip_presets = true;
+                                }
+                                syn::NestedMeta::Meta(syn::Meta::Path(p))
+                                    if p.is_ident("skip_duplicates") =>
+                                {
+                                    persist_skip_duplicates = true;
+                                }
+                                _ => {
+                                    return syn::Error::new(
+                                        persist_attr.span(),
+                                        "Unknown attribute. See the Params trait documentation \
+                                         for more information.",
+                                    )
+                                    .to_compile_error()
+                                    .into()
+                                }
+                            }
                         }
-
-                        persistent_fields.push(PersistentField {
-                            key: s,
-                            field: field_name.clone(),
-                        });
-
-                        processed_attribute = true;
                     }
                     _ => {
                         return syn::Error::new(
@@ -131,6 +152,46 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                         .into()
                     }
                 };
+
+                let key = match persist_key {
+                    Some(key) => key,
+                    None => {
+                        return syn::Error::new(
+                            attr.span(),
+                            "The persist attribute requires a key: \
+                             #[persist(key = \"foo_bar\")]",
+                        )
+                        .to_compile_error()
+                        .into()
+                    }
+                };
+
+                if processed_attribute {
+                    return syn::Error::new(
+                        attr.span(),
+                        "Duplicate or incompatible attribute found",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+
+                if persistent_fields.iter().any(|p| p.key == key) {
+                    return syn::Error::new(
+                        field.span(),
+                        "Multiple persistent fields with the same key found",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+
+                persistent_fields.push(PersistentField {
+                    field: field_name.clone(),
+                    key,
+                    skip_presets: persist_skip_presets,
+                    skip_duplicates: persist_skip_duplicates,
+                });
+
+                processed_attribute = true;
             } else if attr.path.is_ident("nested") {
                 // This one is more complicated. Supports an `array` attribute, an `id_prefix =
                 // "foo"` attribute, and a `group = "group name"` attribute. All are optional, and
@@ -209,15 +270,18 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                         params.push(Param::Nested(match (nested_array, nested_id_prefix) {
                             (true, None) => NestedParams::Array {
                                 field: field_name.clone(),
+                                ty: field.ty.clone(),
                                 group: nested_group,
                             },
                             (false, Some(id_prefix)) => NestedParams::Prefixed {
                                 field: field_name.clone(),
+                                ty: field.ty.clone(),
                                 id_prefix,
                                 group: nested_group,
                             },
                             (false, None) => NestedParams::Inline {
                                 field: field_name.clone(),
+                                ty: field.ty.clone(),
                                 group: nested_group,
                             },
                             (true, Some(_)) => {
@@ -263,6 +327,32 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
         }
     };
 
+    // The parameter IDs are also described as a constant so duplicate IDs can be detected at
+    // compile time, including IDs coming from nested structs. This is not possible for generic
+    // structs since the nested structs' IDs would depend on the type parameters.
+    let has_generics = !ast.generics.params.is_empty();
+    let static_param_ids_tokens = if has_generics {
+        quote! { &[::nih_plug::params::internals::ParamIdEntry::Unchecked] }
+    } else {
+        let param_id_entry_tokens = params.iter().map(|p| p.param_id_entry_tokens());
+
+        quote! { &[#(#param_id_entry_tokens),*] }
+    };
+    let check_param_ids_tokens = if has_generics {
+        quote! {}
+    } else {
+        quote! {
+            const _: () = {
+                const NUM_PARAM_IDS: usize = ::nih_plug::params::internals::num_param_ids(
+                    <#struct_name as ::nih_plug::params::internals::StaticParamIds>::PARAM_IDS,
+                );
+                ::nih_plug::params::internals::check_param_ids::<NUM_PARAM_IDS>(
+                    <#struct_name as ::nih_plug::params::internals::StaticParamIds>::PARAM_IDS,
+                );
+            };
+        }
+    };
+
     let (serialize_fields_tokens, deserialize_fields_tokens) = {
         // Like with `param_map()`, we'll try to do the serialization for this struct and then
         // recursively call the child parameter structs. We don't know anything about the actual
@@ -271,45 +361,80 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
         let (serialize_fields_self_tokens, deserialize_fields_match_self_tokens): (Vec<_>, Vec<_>) =
             persistent_fields
                 .into_iter()
-                .map(|PersistentField { field, key }| {
-                    (
-                        quote! {
-                            match ::nih_plug::params::persist::PersistentField::map(
-                                &self.#field,
-                                ::nih_plug::params::persist::serialize_field,
-                            ) {
-                                Ok(data) => {
-                                    serialized.insert(String::from(#key), data);
-                                }
-                                Err(err) => {
-                                    ::nih_plug::nih_debug_assert_failure!(
-                                        "Could not serialize '{}': {}",
-                                        #key,
-                                        err
-                                    )
+                .map(|persistent_field| {
+                    let PersistentField {
+                        field,
+                        key,
+                        skip_presets,
+                        skip_duplicates,
+                    } = persistent_field;
+
+                    let serialize_tokens = quote! {
+                        match ::nih_plug::params::persist::PersistentField::map(
+                            &self.#field,
+                            ::nih_plug::params::persist::serialize_field,
+                        ) {
+                            Ok(data) => {
+                                serialized.insert(String::from(#key), data);
+                            }
+                            Err(err) => {
+                                ::nih_plug::nih_debug_assert_failure!(
+                                    "Could not serialize '{}': {}",
+                                    #key,
+                                    err
+                                )
+                            }
+                        };
+                    };
+                    let deserialize_tokens = quote! {
+                        match ::nih_plug::params::persist::deserialize_field(&data) {
+                            Ok(deserialized) => {
+                                ::nih_plug::params::persist::PersistentField::set(
+                                    &self.#field,
+                                    deserialized,
+                                );
+                            }
+                            Err(err) => {
+                                ::nih_plug::nih_debug_assert_failure!(
+                                    "Could not deserialize '{}': {}",
+                                    #key,
+                                    err
+                                )
+                            }
+                        };
+                    };
+
+                    // Fields can opt out of being stored in presets and in duplicated plugin
+                    // instances. Those fields are still matched on when deserializing so they don't
+                    // trigger the unknown field assertion.
+                    let mut skipped_contexts = Vec::new();
+                    if skip_presets {
+                        skipped_contexts
+                            .push(quote! { ::nih_plug::params::persist::StateContext::Preset });
+                    }
+                    if skip_duplicates {
+                        skipped_contexts
+                            .push(quote! { ::nih_plug::params::persist::StateContext::Duplicate });
+                    }
+
+                    if skipped_contexts.is_empty() {
+                        (serialize_tokens, quote! { #key => { #deserialize_tokens } })
+                    } else {
+                        (
+                            quote! {
+                                if !matches!(context, #(#skipped_contexts)|*) {
+                                    #serialize_tokens
                                 }
-                            };
-                        },
-                        quote! {
-                            #key => {
-                                match ::nih_plug::params::persist::deserialize_field(&data) {
-                                    Ok(deserialized) => {
-                                        ::nih_plug::params::persist::PersistentField::set(
-                                            &self.#field,
-                                            deserialized,
-                                        );
-                                    }
-                                    Err(err) => {
-                                        ::nih_plug::nih_debug_assert_failure!(
-                                            "Could not deserialize '{}': {}",
-                                            #key,
-                                            err
-                                        )
+                            },
+                            quote! {
+                                #key => {
+                                    if !matches!(context, #(#skipped_contexts)|*) {
+                                        #deserialize_tokens
                                     }
-                                };
-                            }
-                        },
-                    )
+                                }
+                            },
+                        )
+                    }
                 })
                 .unzip();
 
@@ -323,8 +448,10 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                 })
                 .map(|nested| match nested {
                     NestedParams::Inline { field, .. } => (
-                        quote! { serialized.extend(self.#field.serialize_fields()); },
-                        quote! { self.#field.deserialize_fields(serialized); },
+                        quote! {
+                            serialized.extend(self.#field.serialize_fields_for_context(context));
+                        },
+                        quote! { self.#field.deserialize_fields_for_context(serialized, context); },
                     ),
                     NestedParams::Prefixed {
                         field, id_prefix, ..
@@ -332,7 +459,7 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                         quote! {
                             let prefixed = self
                                 .#field
-                                .serialize_fields()
+                                .serialize_fields_for_context(context)
                                 .into_iter()
                                 .map(|(key, value)| (format!("{}_{}", #id_prefix, key), value));
 
@@ -348,7 +475,7 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                                 })
                                 .collect();
 
-                            self.#field.deserialize_fields(&matching_fields);
+                            self.#field.deserialize_fields_for_context(&matching_fields, context);
                         },
                     ),
                     NestedParams::Array { field, .. } => (
@@ -356,7 +483,7 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                             for (field_idx, field) in self.#field.iter().enumerate() {
                                 let idx = field_idx + 1;
                                 let suffixed = field
-                                    .serialize_fields()
+                                    .serialize_fields_for_context(context)
                                     .into_iter()
                                     .map(|(key, value)| (format!("{}_{}", key, idx), value));
 
@@ -375,7 +502,7 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                                     })
                                     .collect();
 
-                                field.deserialize_fields(&matching_fields);
+                                field.deserialize_fields_for_context(&matching_fields, context);
                             }
                         },
                     ),
@@ -417,13 +544,42 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
             }
 
             fn serialize_fields(&self) -> ::std::collections::BTreeMap<String, Vec<u8>> {
-                #serialize_fields_tokens
+                self.serialize_fields_for_context(
+                    ::nih_plug::params::persist::StateContext::Project,
+                )
             }
 
             fn deserialize_fields(&self, serialized: &::std::collections::BTreeMap<String, Vec<u8>>) {
+                self.deserialize_fields_for_context(
+                    serialized,
+                    ::nih_plug::params::persist::StateContext::Project,
+                )
+            }
+
+            #[allow(unused_variables)]
+            fn serialize_fields_for_context(
+                &self,
+                context: ::nih_plug::params::persist::StateContext,
+            ) -> ::std::collections::BTreeMap<String, Vec<u8>> {
+                #serialize_fields_tokens
+            }
+
+            #[allow(unused_variables)]
+            fn deserialize_fields_for_context(
+                &self,
+                serialized: &::std::collections::BTreeMap<String, Vec<u8>>,
+                context: ::nih_plug::params::persist::StateContext,
+            ) {
                 #deserialize_fields_tokens
             }
         }
+
+        impl #impl_generics ::nih_plug::params::internals::StaticParamIds for #struct_name #ty_generics #where_clause {
+            const PARAM_IDS: &'static [::nih_plug::params::internals::ParamIdEntry] =
+                #static_param_ids_tokens;
+        }
+
+        #check_param_ids_tokens
     }
     .into()
 }
@@ -455,6 +611,17 @@ impl Param {
             Param::Nested(params) => params.param_map_tokens(),
         }
     }
+
+    /// Generate a `ParamIdEntry` describing this field's parameter ID or the IDs of the nested
+    /// parameter struct for the `StaticParamIds` implementation.
+    fn param_id_entry_tokens(&self) -> proc_macro2::TokenStream {
+        match self {
+            Param::Single { id, .. } => {
+                quote! { ::nih_plug::params::internals::ParamIdEntry::Param(#id) }
+            }
+            Param::Nested(params) => params.param_id_entry_tokens(),
+        }
+    }
 }
 
 /// A field containing data that must be stored in the plugin's state.
@@ -464,6 +631,11 @@ struct PersistentField {
     field: syn::Ident,
     /// The field's unique key.
     key: syn::LitStr,
+    /// Whether the field should be left out of presets, set with the `skip_presets` option.
+    skip_presets: bool,
+    /// Whether the field should not be copied to duplicated plugin instances, set with the
+    /// `skip_duplicates` option.
+    skip_duplicates: bool,
 }
 
 /// A field containing another object whose parameters and persistent fields should be added to this
@@ -473,25 +645,85 @@ enum NestedParams {
     /// The nested struct's parameters are taken as is.
     Inline {
         field: syn::Ident,
+        ty: syn::Type,
         group: Option<syn::LitStr>,
     },
     /// The nested struct's parameters will get an ID prefix. The original parmaeter with ID `foo`
     /// will become `{id_prefix}_foo`.
     Prefixed {
         field: syn::Ident,
+        ty: syn::Type,
         id_prefix: syn::LitStr,
         group: Option<syn::LitStr>,
     },
-    /// This field is an array-like data structure containing nested parameter structs. The
-    /// parameter `foo` will get the new parameter ID `foo_{array_idx + 1}`, and if the group name
-    /// is set then the group will be `{group_name} {array_idx + 1}`.
+    /// This field is an array-like data structure containing nested parameter structs, like a
+    /// fixed-size array or a `Vec`. The parameter `foo` will get the new parameter ID
+    /// `foo_{array_idx + 1}`, and if the group name is set then the group will be
+    /// `{group_name} {array_idx + 1}`.
     Array {
         field: syn::Ident,
+        ty: syn::Type,
         group: Option<syn::LitStr>,
     },
 }
 
 impl NestedParams {
+    /// Generate a `ParamIdEntry` for the nested struct's parameter IDs. If the nested struct's type
+    /// does not implement `StaticParamIds` then the IDs cannot be checked at compile time. The
+    /// length of nested arrays is taken from the field's type, so the same applies if the field's
+    /// type is not a fixed-size array type (e.g. a `Vec` whose length is only known at runtime).
+    /// Only this field's IDs are skipped in those cases, the struct's other IDs are still checked.
+    fn param_id_entry_tokens(&self) -> proc_macro2::TokenStream {
+        // This falls back to `ParamIdEntry::Unchecked` if `ty` does not implement `StaticParamIds`
+        let nested_param_ids_tokens = |ty: &syn::Type| {
+            quote! {
+                {
+                    use ::nih_plug::params::internals::UncheckedParamIds as _;
+                    ::nih_plug::params::internals::ParamIdsOf::<#ty>::PARAM_IDS
+                }
+            }
+        };
+
+        match self {
+            NestedParams::Inline { ty, .. } => {
+                let nested_param_ids = nested_param_ids_tokens(ty);
+                quote! {
+                    ::nih_plug::params::internals::ParamIdEntry::Nested {
+                        id_prefix: None,
+                        array_len: None,
+                        entries: #nested_param_ids,
+                    }
+                }
+            }
+            NestedParams::Prefixed { ty, id_prefix, .. } => {
+                let nested_param_ids = nested_param_ids_tokens(ty);
+                quote! {
+                    ::nih_plug::params::internals::ParamIdEntry::Nested {
+                        id_prefix: Some(#id_prefix),
+                        array_len: None,
+                        entries: #nested_param_ids,
+                    }
+                }
+            }
+            NestedParams::Array {
+                ty: syn::Type::Array(syn::TypeArray { elem, len, .. }),
+                ..
+            } => {
+                let nested_param_ids = nested_param_ids_tokens(elem);
+                quote! {
+                    ::nih_plug::params::internals::ParamIdEntry::Nested {
+                        id_prefix: None,
+                        array_len: Some(#len),
+                        entries: #nested_param_ids,
+                    }
+                }
+            }
+            NestedParams::Array { .. } => quote! {
+                ::nih_plug::params::internals::ParamIdEntry::Unchecked
+            },
+        }
+    }
+
     /// Constrruct an iterator that iterates over all parmaeters of a nested parameter object. This
     /// takes ID prefixes and suffixes into account, and prefixes the group to the parameter's
     /// existing groups if the `group` attribute on the `#[nested]` macro was specified.
@@ -507,6 +739,7 @@ impl NestedParams {
             NestedParams::Inline {
                 field,
                 group: Some(group),
+                ..
             } => quote! {
                 self.#field.param_map().into_iter().map(|(param_id, param_ptr, nested_group_name)| {
                     if nested_group_name.is_empty() {
@@ -516,13 +749,16 @@ impl NestedParams {
                     }
                 })
             },
-            NestedParams::Inline { field, group: None } => quote! {
+            NestedParams::Inline {
+                field, group: None, ..
+            } => quote! {
                 self.#field.param_map();
             },
             NestedParams::Prefixed {
                 field,
                 id_prefix,
                 group: Some(group),
+                ..
             } => quote! {
                 self.#field.param_map().into_iter().map(|(param_id, param_ptr, nested_group_name)| {
                     let param_id = format!("{}_{}", #id_prefix, param_id);
@@ -538,6 +774,7 @@ impl NestedParams {
                 field,
                 id_prefix,
                 group: None,
+                ..
             } => quote! {
                 self.#field.param_map().into_iter().map(|(param_id, param_ptr, nested_group_name)| {
                     let param_id = format!("{}_{}", #id_prefix, param_id);
@@ -550,6 +787,7 @@ impl NestedParams {
             NestedParams::Array {
                 field,
                 group: Some(group),
+                ..
             } => quote! {
                 self.#field.iter().enumerate().flat_map(|(idx, params)| {
                     let idx = idx + 1;
@@ -567,7 +805,9 @@ impl NestedParams {
                     })
                 })
             },
-            NestedParams::Array { field, group: None } => quote! {
+            NestedParams::Array {
+                field, group: None, ..
+            } => quote! {
                 self.#field.iter().enumerate().flat_map(|(idx, params)| {
                     let idx = idx + 1;
 
@@ -1,3 +1,6 @@
+use std::sync::{Arc, Mutex};
+
+use nih_plug::params::persist::Persisted;
 use nih_plug::prelude::*;
 
 #[derive(Params)]
@@ -102,6 +105,58 @@ impl Default for NestedArrayParams {
     }
 }
 
+#[derive(Params)]
+struct NestedVecParams {
+    #[id = "one"]
+    pub one: BoolParam,
+
+    #[nested(array, group = "Nested Params")]
+    pub lots_of_twos: Vec<FlatParams>,
+}
+
+impl NestedVecParams {
+    fn new(num_twos: usize) -> Self {
+        NestedVecParams {
+            one: BoolParam::new("one", true),
+            lots_of_twos: (0..num_twos).map(|_| FlatParams::default()).collect(),
+        }
+    }
+}
+
+#[derive(Params, Default)]
+struct PersistParams {
+    #[persist = "value"]
+    pub value: Mutex<u32>,
+}
+
+#[derive(Params, Default)]
+struct PersistedParams {
+    #[persist = "value"]
+    pub value: Arc<Persisted<String>>,
+}
+
+#[derive(Params)]
+struct NestedVecPersistParams {
+    #[nested(array)]
+    pub persists: Vec<PersistParams>,
+}
+
+#[derive(Params, Default)]
+struct ContextPersistParams {
+    #[persist = "always"]
+    pub always: Mutex<u32>,
+    #[persist(key = "no_presets", skip_presets)]
+    pub no_presets: Mutex<u32>,
+    #[persist(key = "no_duplicates", skip_duplicates)]
+    pub no_duplicates: Mutex<u32>,
+}
+
+#[derive(Params, Default)]
+struct NestedContextPersistParams {
+    #[nested(id_prefix = "inner")]
+    pub inner: ContextPersistParams,
+}
+
 mod param_order {
     use super::*;
 
@@ -183,6 +238,18 @@ mod param_order {
             ]
         );
     }
+
+    #[test]
+    fn nested_vec() {
+        let p = NestedVecParams::new(2);
+
+        // `Vec`s work the same way as arrays
+        let param_ids: Vec<String> = p.param_map().into_iter().map(|(id, _, _)| id).collect();
+        assert_eq!(
+            param_ids,
+            ["one", "one_1", "two_1", "three_1", "one_2", "two_2", "three_2"]
+        );
+    }
 }
 
 mod param_groups {
@@ -289,4 +356,134 @@ mod param_groups {
             ]
         );
     }
+
+    #[test]
+    fn nested_vec() {
+        let p = NestedVecParams::new(2);
+
+        let param_groups: Vec<String> = p
+            .param_map()
+            .into_iter()
+            .map(|(_, _, group)| group)
+            .collect();
+        assert_eq!(
+            param_groups,
+            [
+                "",
+                "Nested Params 1",
+                "Nested Params 1",
+                "Nested Params 1",
+                "Nested Params 2",
+                "Nested Params 2",
+                "Nested Params 2",
+            ]
+        );
+    }
+}
+
+mod persist {
+    use super::*;
+
+    #[test]
+    fn nested_vec() {
+        let p = NestedVecPersistParams {
+            persists: (0..3).map(|_| PersistParams::default()).collect(),
+        };
+        *p.persists[1].value.lock().unwrap() = 42;
+
+        // The keys get the same suffixes as the parameter IDs
+        let serialized = p.serialize_fields();
+        let keys: Vec<&str> = serialized.keys().map(|key| key.as_str()).collect();
+        assert_eq!(keys, ["value_1", "value_2", "value_3"]);
+
+        let restored = NestedVecPersistParams {
+            persists: (0..3).map(|_| PersistParams::default()).collect(),
+        };
+        restored.deserialize_fields(&serialized);
+        assert_eq!(*restored.persists[0].value.lock().unwrap(), 0);
+        assert_eq!(*restored.persists[1].value.lock().unwrap(), 42);
+        assert_eq!(*restored.persists[2].value.lock().unwrap(), 0);
+    }
+
+    #[test]
+    fn persisted() {
+        let p = PersistedParams::default();
+        p.value.set(String::from("foo"));
+
+        let restored = PersistedParams::default();
+        restored.deserialize_fields(&p.serialize_fields());
+        assert_eq!(*restored.value.read(), "foo");
+        assert_eq!(restored.value.revision(), 1);
+    }
+
+    #[test]
+    fn skipped_contexts() {
+        let p = NestedContextPersistParams::default();
+        let keys = |context| -> Vec<String> {
+            p.serialize_fields_for_context(context)
+                .into_keys()
+                .collect()
+        };
+
+        assert_eq!(
+            keys(StateContext::Project),
+            ["inner_always", "inner_no_duplicates", "inner_no_presets"]
+        );
+        assert_eq!(
+            keys(StateContext::Preset),
+            ["inner_always", "inner_no_duplicates"]
+        );
+        assert_eq!(
+            keys(StateContext::Duplicate),
+            ["inner_always", "inner_no_presets"]
+        );
+        assert_eq!(p.serialize_fields().len(), 3);
+    }
+
+    #[test]
+    fn skipped_contexts_deserialize() {
+        let p = ContextPersistParams::default();
+        *p.always.lock().unwrap() = 1;
+        *p.no_presets.lock().unwrap() = 2;
+        *p.no_duplicates.lock().unwrap() = 3;
+        let serialized = p.serialize_fields();
+
+        // Fields that opted out of a context are not restored even if they are present
+        let restored = ContextPersistParams::default();
+        restored.deserialize_fields_for_context(&serialized, StateContext::Preset);
+        assert_eq!(*restored.always.lock().unwrap(), 1);
+        assert_eq!(*restored.no_presets.lock().unwrap(), 0);
+        assert_eq!(*restored.no_duplicates.lock().unwrap(), 3);
+    }
+}
+
+mod static_param_ids {
+    use nih_plug::params::internals::{num_param_ids, StaticParamIds};
+
+    use super::*;
+
+    #[test]
+    fn nested() {
+        // The compile time description of the parameter IDs used for the duplicate ID checks should
+        // contain exactly the same parameters as the parameter map
+        assert_eq!(
+            num_param_ids(GroupedGroupedParams::PARAM_IDS),
+            GroupedGroupedParams::default().param_map().len()
+        );
+        assert_eq!(
+            num_param_ids(NestedParams::PARAM_IDS),
+            NestedParams::default().param_map().len()
+        );
+        assert_eq!(
+            num_param_ids(NestedArrayParams::PARAM_IDS),
+            NestedArrayParams::default().param_map().len()
+        );
+    }
+
+    #[test]
+    fn unchecked() {
+        // The length of a `Vec` is only known at runtime, so those IDs can't be checked. The
+        // struct's other IDs are still checked.
+        assert_eq!(num_param_ids(NestedVecParams::PARAM_IDS), 1);
+    }
 }
@@ -0,0 +1,11 @@
+use nih_plug::prelude::*;
+
+#[derive(Params)]
+struct DuplicateParams {
+    #[id = "foo"]
+    pub foo: BoolParam,
+    #[id = "foo"]
+    pub bar: BoolParam,
+}
+
+fn main() {}
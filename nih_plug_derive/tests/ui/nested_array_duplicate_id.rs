@@ -0,0 +1,18 @@
+use nih_plug::prelude::*;
+
+#[derive(Params)]
+struct InnerParams {
+    #[id = "bar"]
+    pub bar: BoolParam,
+}
+
+#[derive(Params)]
+struct OuterParams {
+    // The third array element's parameter ID becomes `bar_3`
+    #[id = "bar_3"]
+    pub bar_3: BoolParam,
+    #[nested(array)]
+    pub inners: [InnerParams; 4],
+}
+
+fn main() {}
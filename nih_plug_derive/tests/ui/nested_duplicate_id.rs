@@ -0,0 +1,17 @@
+use nih_plug::prelude::*;
+
+#[derive(Params)]
+struct InnerParams {
+    #[id = "foo"]
+    pub foo: BoolParam,
+}
+
+#[derive(Params)]
+struct OuterParams {
+    #[id = "foo"]
+    pub foo: BoolParam,
+    #[nested(group = "Inner")]
+    pub inner: InnerParams,
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use nih_plug::prelude::*;
+
+#[derive(Params)]
+struct InnerParams {
+    #[id = "bar"]
+    pub bar: BoolParam,
+}
+
+#[derive(Params)]
+struct OuterParams {
+    // The nested parameter's ID becomes `foo_bar`
+    #[id = "foo_bar"]
+    pub foo_bar: BoolParam,
+    #[nested(id_prefix = "foo")]
+    pub inner: InnerParams,
+}
+
+fn main() {}
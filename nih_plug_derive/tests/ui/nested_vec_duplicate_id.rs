@@ -0,0 +1,23 @@
+use nih_plug::prelude::*;
+
+#[derive(Params)]
+struct InnerParams {
+    #[id = "bar"]
+    pub bar: BoolParam,
+}
+
+#[derive(Params)]
+struct OuterParams {
+    #[id = "foo"]
+    pub foo: BoolParam,
+    #[nested(id_prefix = "foo")]
+    pub inner: InnerParams,
+    // The IDs in a `Vec` are only known at runtime, but that should not disable the check for the
+    // other IDs
+    #[nested(array)]
+    pub inners: Vec<InnerParams>,
+    #[id = "foo_bar"]
+    pub foo_bar: BoolParam,
+}
+
+fn main() {}
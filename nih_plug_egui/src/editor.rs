@@ -34,9 +34,25 @@ pub(crate) struct EguiEditor<T> {
 
     pub(crate) plugin_keyboard_events: Arc<Mutex<Vec<EguiKeyboardInput>>>,
 
+    /// Wheel and focus events sent by the host through the plugin API instead of to the window.
+    /// These are applied to egui's input state at the start of the next frame.
+    pub(crate) plugin_host_events: Arc<Mutex<Vec<HostEvent>>>,
+
     pub(crate) clipboard_ctx:  Arc<Mutex<Option<copypasta::ClipboardContext>>>,
 }
 
+/// An input event sent by the host through [`Editor::on_mouse_wheel()`] or
+/// [`Editor::on_focus_changed()`].
+pub(crate) enum HostEvent {
+    /// A vertical scroll distance in lines.
+    MouseWheel(f32),
+    FocusChanged(bool),
+}
+
+/// The number of points egui scrolls for every line of a host-delivered wheel event. This matches
+/// what egui's own integrations use for line based scroll events.
+const POINTS_PER_SCROLL_LINE: f32 = 50.0;
+
 impl<T> Editor for EguiEditor<T>
 where
     T: 'static + Send + Sync,
@@ -51,6 +67,7 @@ where
         let update = self.update.clone();
         let state = self.user_state.clone();
         let plugin_keyboard_events = self.plugin_keyboard_events.clone();
+        let plugin_host_events = self.plugin_host_events.clone();
 
         let (physical_width, physical_height) = self.egui_state.size();
         let window = EguiWindow::open_parented(
@@ -85,6 +102,20 @@ where
                         event.apply_on_input(egui_ctx.input_mut().deref_mut());
                     }
                 }
+                if let Ok(mut plugin_host_events) = plugin_host_events.try_lock() {
+                    for event in plugin_host_events.drain(..) {
+                        match event {
+                            HostEvent::MouseWheel(delta) => {
+                                egui_ctx.input_mut().scroll_delta.y +=
+                                    delta * POINTS_PER_SCROLL_LINE;
+                            }
+                            // Text fields should stop capturing keys once the host takes away
+                            // the editor's focus
+                            HostEvent::FocusChanged(false) => egui_ctx.memory().stop_text_input(),
+                            HostEvent::FocusChanged(true) => (),
+                        }
+                    }
+                }
 
                 let setter = ParamSetter::new(context.as_ref());
 
@@ -127,6 +158,30 @@ where
         assert_eq!(keyboard_event.state, keyboard_types::KeyState::Up);
         self.handle_keyboard_event(keyboard_event)
     }
+
+    fn on_mouse_wheel(&self, delta: f32) -> bool {
+        if !self.egui_state.is_open() {
+            return false;
+        }
+
+        match self.plugin_host_events.lock() {
+            Ok(mut plugin_host_events) => {
+                plugin_host_events.push(HostEvent::MouseWheel(delta));
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn on_focus_changed(&self, focused: bool) {
+        if !self.egui_state.is_open() {
+            return;
+        }
+
+        if let Ok(mut plugin_host_events) = self.plugin_host_events.lock() {
+            plugin_host_events.push(HostEvent::FocusChanged(focused));
+        }
+    }
 }
 
 impl<T> EguiEditor<T> where T: 'static + Send + Sync {
@@ -60,6 +60,7 @@ where
         build: Arc::new(build),
         update: Arc::new(update),
         plugin_keyboard_events: Arc::new(Mutex::new(vec![])),
+        plugin_host_events: Arc::new(Mutex::new(vec![])),
 
         clipboard_ctx: Arc::new(Mutex::new(match copypasta::ClipboardContext::new() {
             Ok(clipboard_ctx) => Some(clipboard_ctx),
@@ -8,7 +8,7 @@ use nih_plug::prelude::{Editor, GuiContext, ParentWindowHandle};
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use crate::{wrapper, IcedEditor, IcedState, ParameterUpdate};
+use crate::{wrapper, HostEvent, IcedEditor, IcedState, ParameterUpdate};
 
 /// An [`Editor`] implementation that renders an iced [`Application`].
 pub(crate) struct IcedEditorWrapper<E: IcedEditor> {
@@ -22,6 +22,10 @@ pub(crate) struct IcedEditorWrapper<E: IcedEditor> {
     /// A subscription for sending messages about parameter updates to the `IcedEditor`.
     pub(crate) parameter_updates_sender: channel::Sender<ParameterUpdate>,
     pub(crate) parameter_updates_receiver: Arc<channel::Receiver<ParameterUpdate>>,
+    /// Input events sent by the host through the plugin API, passed on to
+    /// [`IcedEditor::host_event()`].
+    pub(crate) host_events_sender: channel::Sender<HostEvent>,
+    pub(crate) host_events_receiver: Arc<channel::Receiver<HostEvent>>,
 }
 
 impl<E: IcedEditor> Editor for IcedEditorWrapper<E> {
@@ -80,6 +84,7 @@ impl<E: IcedEditor> Editor for IcedEditorWrapper<E> {
                 flags: (
                     context,
                     self.parameter_updates_receiver.clone(),
+                    self.host_events_receiver.clone(),
                     self.initialization_flags.clone(),
                 ),
             },
@@ -108,6 +113,25 @@ impl<E: IcedEditor> Editor for IcedEditorWrapper<E> {
             let _ = self.parameter_updates_sender.try_send(ParameterUpdate);
         }
     }
+
+    fn on_mouse_wheel(&self, delta: f32) -> bool {
+        // The editor handles these events asynchronously, so we need to know up front whether it
+        // does anything with them at all
+        E::handles_host_mouse_wheel()
+            && self.iced_state.is_open()
+            && self
+                .host_events_sender
+                .try_send(HostEvent::MouseWheel(delta))
+                .is_ok()
+    }
+
+    fn on_focus_changed(&self, focused: bool) {
+        if self.iced_state.is_open() {
+            let _ = self
+                .host_events_sender
+                .try_send(HostEvent::FocusChanged(focused));
+        }
+    }
 }
 
 /// The window handle used for [`IcedEditorWrapper`].
@@ -130,6 +130,8 @@ pub fn create_iced_editor<E: IcedEditor>(
     // one parameter update, since we're only storing _that_ a parameter update has happened and not
     // which parameter so we'd need to redraw the entire GUI either way.
     let (parameter_updates_sender, parameter_updates_receiver) = channel::bounded(1);
+    // Input events the host sends through the plugin API are also passed to the editor this way
+    let (host_events_sender, host_events_receiver) = channel::unbounded();
 
     Some(Box::new(editor::IcedEditorWrapper::<E> {
         iced_state,
@@ -145,6 +147,8 @@ pub fn create_iced_editor<E: IcedEditor>(
 
         parameter_updates_sender,
         parameter_updates_receiver: Arc::new(parameter_updates_receiver),
+        host_events_sender,
+        host_events_receiver: Arc::new(host_events_receiver),
     }))
 }
 
@@ -218,6 +222,20 @@ pub trait IcedEditor: 'static + Send + Sync + Sized {
         }
     }
 
+    /// Handle an input event the host sent through the plugin API instead of to the editor's
+    /// window. Some hosts only deliver mouse wheel events this way. The returned message, if any,
+    /// is passed to [`update()`][Self::update()].
+    fn host_event(&self, _event: HostEvent) -> Option<Self::Message> {
+        None
+    }
+
+    /// Whether [`host_event()`][Self::host_event()] handles [`HostEvent::MouseWheel`]. The host is
+    /// told that its mouse wheel events went unhandled when this returns `false`, which lets it
+    /// scroll its own view instead. Override this together with `host_event()`.
+    fn handles_host_mouse_wheel() -> bool {
+        false
+    }
+
     /// Handle a parameter update using the GUI context.
     fn handle_param_message(&self, message: ParamMessage) {
         // We can't use the fancy ParamSetter here because this needs to be type erased
@@ -278,5 +296,15 @@ impl IcedState {
     }
 }
 
+/// An input event sent by the host through the plugin API instead of to the editor's window. See
+/// [`IcedEditor::host_event()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HostEvent {
+    /// The mouse wheel was scrolled vertically by this many lines. Positive values scroll up.
+    MouseWheel(f32),
+    /// The editor gained or lost keyboard focus.
+    FocusChanged(bool),
+}
+
 /// A marker struct to indicate that a parameter update has happened.
 pub(crate) struct ParameterUpdate;
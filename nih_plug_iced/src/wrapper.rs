@@ -7,8 +7,8 @@ use std::sync::Arc;
 
 use crate::futures::FutureExt;
 use crate::{
-    futures, subscription, Application, Color, Command, Element, IcedEditor, ParameterUpdate,
-    Subscription, WindowQueue, WindowScalePolicy, WindowSubs,
+    futures, subscription, Application, Color, Command, Element, HostEvent, IcedEditor,
+    ParameterUpdate, Subscription, WindowQueue, WindowScalePolicy, WindowSubs,
 };
 
 /// Wraps an `iced_baseview` [`Application`] around [`IcedEditor`]. Needed to allow editors to
@@ -20,14 +20,19 @@ pub(crate) struct IcedEditorWrapperApplication<E: IcedEditor> {
     /// update gets sent, we will trigger a [`Message::parameterUpdate`] which causes the UI to be
     /// redrawn.
     parameter_updates_receiver: Arc<channel::Receiver<ParameterUpdate>>,
+    /// Input events sent by the host through the plugin API. These are converted to editor messages
+    /// using [`IcedEditor::host_event()`].
+    host_events_receiver: Arc<channel::Receiver<HostEvent>>,
 }
 
 /// This wraps around `E::Message` to add a parameter update message which can be handled directly
 /// by this wrapper. That parameter update message simply forces a redraw of the GUI whenever there
-/// is a parameter update.
+/// is a parameter update. Input events sent by the host are also handled here before they're
+/// passed on to the editor.
 pub enum Message<E: IcedEditor> {
     EditorMessage(E::Message),
     ParameterUpdate,
+    HostEvent(HostEvent),
 }
 
 impl<E: IcedEditor> std::fmt::Debug for Message<E> {
@@ -35,6 +40,7 @@ impl<E: IcedEditor> std::fmt::Debug for Message<E> {
         match self {
             Self::EditorMessage(arg0) => f.debug_tuple("EditorMessage").field(arg0).finish(),
             Self::ParameterUpdate => write!(f, "ParameterUpdate"),
+            Self::HostEvent(arg0) => f.debug_tuple("HostEvent").field(arg0).finish(),
         }
     }
 }
@@ -44,6 +50,7 @@ impl<E: IcedEditor> Clone for Message<E> {
         match self {
             Self::EditorMessage(arg0) => Self::EditorMessage(arg0.clone()),
             Self::ParameterUpdate => Self::ParameterUpdate,
+            Self::HostEvent(arg0) => Self::HostEvent(*arg0),
         }
     }
 }
@@ -54,11 +61,12 @@ impl<E: IcedEditor> Application for IcedEditorWrapperApplication<E> {
     type Flags = (
         Arc<dyn GuiContext>,
         Arc<channel::Receiver<ParameterUpdate>>,
+        Arc<channel::Receiver<HostEvent>>,
         E::InitializationFlags,
     );
 
     fn new(
-        (context, parameter_updates_receiver, flags): Self::Flags,
+        (context, parameter_updates_receiver, host_events_receiver, flags): Self::Flags,
     ) -> (Self, Command<Self::Message>) {
         let (editor, command) = E::new(flags, context);
 
@@ -66,6 +74,7 @@ impl<E: IcedEditor> Application for IcedEditorWrapperApplication<E> {
             Self {
                 editor,
                 parameter_updates_receiver,
+                host_events_receiver,
             },
             command.map(Message::EditorMessage),
         )
@@ -84,6 +93,13 @@ impl<E: IcedEditor> Application for IcedEditorWrapperApplication<E> {
                 .map(Message::EditorMessage),
             // This message only exists to force a redraw
             Message::ParameterUpdate => Command::none(),
+            Message::HostEvent(event) => match self.editor.host_event(event) {
+                Some(message) => self
+                    .editor
+                    .update(window, message)
+                    .map(Message::EditorMessage),
+                None => Command::none(),
+            },
         }
     }
 
@@ -121,6 +137,18 @@ impl<E: IcedEditor> Application for IcedEditorWrapperApplication<E> {
                     Err(_) => futures::future::pending().boxed(),
                 },
             ),
+            subscription::unfold(
+                "host events",
+                self.host_events_receiver.clone(),
+                |host_events_receiver| match host_events_receiver.try_recv() {
+                    Ok(event) => futures::future::ready((
+                        Some(Message::HostEvent(event)),
+                        host_events_receiver,
+                    ))
+                    .boxed(),
+                    Err(_) => futures::future::pending().boxed(),
+                },
+            ),
             self.editor
                 .subscription(&mut editor_window_subs)
                 .map(Message::EditorMessage),
@@ -2,6 +2,7 @@
 
 use baseview::{WindowHandle, WindowScalePolicy};
 use crossbeam::atomic::AtomicCell;
+use crossbeam::queue::SegQueue;
 use nih_plug::editor::SpawnedWindow;
 use nih_plug::prelude::{Editor, GuiContext, ParentWindowHandle};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -29,6 +30,17 @@ pub(crate) struct ViziaEditor {
     /// to compute a property in an event handler. Like when positioning an element based on the
     /// display value's width.
     pub(crate) emit_parameters_changed_event: Arc<AtomicBool>,
+    /// Wheel and focus events sent by the host through the plugin API instead of to the window.
+    /// These are emitted as regular vizia window events during the next idle callback.
+    pub(crate) host_events: Arc<SegQueue<HostEvent>>,
+}
+
+/// An input event sent by the host through [`Editor::on_mouse_wheel()`] or
+/// [`Editor::on_focus_changed()`].
+pub(crate) enum HostEvent {
+    /// A vertical scroll distance in lines.
+    MouseWheel(f32),
+    FocusChanged(bool),
 }
 
 impl Editor for ViziaEditor {
@@ -85,7 +97,35 @@ impl Editor for ViziaEditor {
         .user_scale_factor(user_scale_factor)
         .on_idle({
             let emit_parameters_changed_event = self.emit_parameters_changed_event.clone();
+            let host_events = self.host_events.clone();
             move |cx| {
+                while let Some(event) = host_events.pop() {
+                    match event {
+                        // Vizia also sends its own wheel events to the hovered element
+                        HostEvent::MouseWheel(delta) => {
+                            let target = cx.hovered();
+                            cx.emit_custom(
+                                Event::new(WindowEvent::MouseScroll(0.0, delta))
+                                    .target(target)
+                                    .propagate(Propagation::Up),
+                            );
+                        }
+                        HostEvent::FocusChanged(focused) => {
+                            let target = cx.focused();
+                            let event = if focused {
+                                WindowEvent::FocusIn
+                            } else {
+                                WindowEvent::FocusOut
+                            };
+                            cx.emit_custom(
+                                Event::new(event)
+                                    .target(target)
+                                    .propagate(Propagation::Direct),
+                            );
+                        }
+                    }
+                }
+
                 if emit_parameters_changed_event
                     .compare_exchange(true, false, Ordering::AcqRel, Ordering::Relaxed)
                     .is_ok()
@@ -138,6 +178,21 @@ impl Editor for ViziaEditor {
     fn on_key_up(&self,  _keyboard_event: &keyboard_types::KeyboardEvent) -> bool {
         false // TODO
     }
+
+    fn on_mouse_wheel(&self, delta: f32) -> bool {
+        if self.vizia_state.is_open() {
+            self.host_events.push(HostEvent::MouseWheel(delta));
+            true
+        } else {
+            false
+        }
+    }
+
+    fn on_focus_changed(&self, focused: bool) {
+        if self.vizia_state.is_open() {
+            self.host_events.push(HostEvent::FocusChanged(focused));
+        }
+    }
 }
 
 /// The window handle used for [`ViziaEditor`].
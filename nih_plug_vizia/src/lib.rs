@@ -4,6 +4,7 @@
 #![allow(clippy::type_complexity)]
 
 use crossbeam::atomic::AtomicCell;
+use crossbeam::queue::SegQueue;
 use nih_plug::params::persist::PersistentField;
 use nih_plug::prelude::{Editor, GuiContext};
 use serde::{Deserialize, Serialize};
@@ -61,6 +62,7 @@ where
         scaling_factor: AtomicCell::new(Some(1.0)),
 
         emit_parameters_changed_event: Arc::new(AtomicBool::new(false)),
+        host_events: Arc::new(SegQueue::new()),
     }))
 }
 
@@ -49,6 +49,10 @@ pub enum ParamEvent<'a, P: Param> {
     SetParameterNormalized(&'a P, f32),
     /// End an automation gesture for a parameter.
     EndSetParameter(&'a P),
+    /// Show the host's context menu for a parameter at the specified position in physical pixels
+    /// relative to the window. If the host does not support parameter context menus, then the
+    /// parameter is reset to its default value instead.
+    ShowContextMenu(&'a P, f32, f32),
 }
 
 /// The same as [`ParamEvent`], but type erased.
@@ -61,6 +65,10 @@ pub enum RawParamEvent {
     SetParameterNormalized(ParamPtr, f32),
     /// End an automation gesture for a parameter.
     EndSetParameter(ParamPtr),
+    /// Show the host's context menu for a parameter at the specified position in physical pixels
+    /// relative to the window. If the host does not support parameter context menus, then the
+    /// parameter is reset to its default value instead.
+    ShowContextMenu(ParamPtr, f32, f32),
     /// Sent by the wrapper to indicate that one or more parameter values have changed. Useful when
     /// using properties based on a parameter's value that are computed inside of an event handler.
     ParametersChanged,
@@ -92,6 +100,19 @@ impl Model for ParamModel {
                 self.context.raw_set_parameter_normalized(p, v)
             },
             RawParamEvent::EndSetParameter(p) => unsafe { self.context.raw_end_set_parameter(p) },
+            RawParamEvent::ShowContextMenu(p, x, y) => unsafe {
+                if !self
+                    .context
+                    .raw_show_context_menu(p, x.round() as i32, y.round() as i32)
+                {
+                    // Right clicking a parameter used to reset it, so we'll keep doing that when
+                    // the host can't show a menu
+                    self.context.raw_begin_set_parameter(p);
+                    self.context
+                        .raw_set_parameter_normalized(p, p.default_normalized_value());
+                    self.context.raw_end_set_parameter(p);
+                }
+            },
             // This can be used by widgets to be notified when parameter values have changed
             RawParamEvent::ParametersChanged => (),
         });
@@ -146,6 +167,9 @@ impl<P: Param> From<ParamEvent<'_, P>> for RawParamEvent {
                 RawParamEvent::SetParameterNormalized(p.as_ptr(), v)
             }
             ParamEvent::EndSetParameter(p) => RawParamEvent::EndSetParameter(p.as_ptr()),
+            ParamEvent::ShowContextMenu(p, x, y) => {
+                RawParamEvent::ShowContextMenu(p.as_ptr(), x, y)
+            }
         }
     }
 }
@@ -223,6 +223,17 @@ impl ParamWidgetBase {
         cx.emit(RawParamEvent::EndSetParameter(self.param_ptr));
     }
 
+    /// Ask the host to show its context menu for the parameter at the current mouse cursor
+    /// position. If the host does not support parameter context menus, then the parameter is reset
+    /// to its default value instead. Usually this is done on right click.
+    pub fn show_context_menu(&self, cx: &mut EventContext) {
+        cx.emit(RawParamEvent::ShowContextMenu(
+            self.param_ptr,
+            cx.mouse.cursorx,
+            cx.mouse.cursory,
+        ));
+    }
+
     param_ptr_forward!(pub fn name(&self) -> &str);
     param_ptr_forward!(pub fn unit(&self) -> &'static str);
     param_ptr_forward!(pub fn poly_modulation_id(&self) -> Option<u32>);
@@ -15,6 +15,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use nih_plug::debug::*;
+use nih_plug::util::filter::{BiquadCoefficients, NEUTRAL_Q};
 use realfft::num_complex::Complex32;
 use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
 use std::f32;
@@ -22,7 +23,6 @@ use std::sync::Arc;
 
 use self::filter::{FftFirFilter, FirCoefficients, FFT_INPUT_SIZE, FFT_SIZE};
 use crate::crossover::fir::filter::FILTER_SIZE;
-use crate::crossover::iir::biquad::{BiquadCoefficients, NEUTRAL_Q};
 use crate::{NUM_BANDS, NUM_CHANNELS};
 
 pub mod filter;
@@ -117,18 +117,22 @@ impl FirCrossover {
     }
 
     /// Split the signal into bands using the crossovers previously configured through `.update()`.
-    /// The split bands will be written to `band_outputs`. The main output should be cleared
-    /// separately. For efficiency's sake this processes an entire channel at once to minimize the
-    /// number of FFT operations needed. Since this process delays the signal by `FFT_INPUT_SIZE`
-    /// samples, the latency should be reported to the host.
+    /// The split bands will be written to `band_outputs`. Bands without an output are not
+    /// processed at all. The main output should be cleared separately. For efficiency's sake this
+    /// processes an entire channel at once to minimize the number of FFT operations needed. Since
+    /// this process delays the signal by `FFT_INPUT_SIZE` samples, the latency should be reported
+    /// to the host.
     pub fn process(
         &mut self,
         num_bands: usize,
         main_input: &[f32],
-        mut band_outputs: [&mut &mut [f32]; NUM_BANDS],
+        mut band_outputs: [Option<&mut [f32]>; NUM_BANDS],
         channel_idx: usize,
     ) {
-        nih_debug_assert!(main_input.len() == band_outputs[0].len());
+        nih_debug_assert!(band_outputs
+            .iter()
+            .flatten()
+            .all(|band_output| band_output.len() == main_input.len()));
         nih_debug_assert!(channel_idx < NUM_CHANNELS as usize);
 
         // We'll copy already processed output to `band_outputs` while storing input for the next
@@ -153,11 +157,13 @@ impl FirCrossover {
                     .zip(self.band_output_buffers.iter())
                     .take(num_bands)
                 {
-                    band_output[current_sample_idx..current_sample_idx + process_num_samples]
-                        .copy_from_slice(
-                            &band_output_buffers[channel_idx][io_buffers_next_indices
-                                ..io_buffers_next_indices + process_num_samples],
-                        );
+                    if let Some(band_output) = band_output {
+                        band_output[current_sample_idx..current_sample_idx + process_num_samples]
+                            .copy_from_slice(
+                                &band_output_buffers[channel_idx][io_buffers_next_indices
+                                    ..io_buffers_next_indices + process_num_samples],
+                            );
+                    }
                 }
 
                 // This is tracked per-channel because both channels are processed individually
@@ -185,12 +191,17 @@ impl FirCrossover {
                 // to be able to modify the input, we need to make a copy of this first:
                 let input_fft = *self.complex_scratch_buffer;
 
-                for (band_output_buffers, band_filter) in self
+                for ((band_output_buffers, band_filter), band_output) in self
                     .band_output_buffers
                     .iter_mut()
                     .zip(self.band_filters.iter_mut())
+                    .zip(band_outputs.iter())
                     .take(num_bands)
                 {
+                    if band_output.is_none() {
+                        continue;
+                    }
+
                     band_filter.process(
                         &input_fft,
                         &mut band_output_buffers[channel_idx],
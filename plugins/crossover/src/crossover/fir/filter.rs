@@ -14,11 +14,11 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use nih_plug::util::filter::{Biquad, BiquadCoefficients};
 use realfft::num_complex::Complex32;
 use realfft::{ComplexToReal, RealToComplex};
 use std::f32;
 
-use crate::crossover::iir::biquad::{Biquad, BiquadCoefficients};
 use crate::NUM_CHANNELS;
 
 /// We're doing FFT convolution here since otherwise there's no way to get decent low-frequency
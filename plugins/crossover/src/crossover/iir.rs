@@ -16,13 +16,11 @@
 
 use nih_plug::buffer::ChannelSamples;
 use nih_plug::debug::*;
+use nih_plug::util::filter::{Biquad, BiquadCoefficients, NEUTRAL_Q};
 use std::simd::f32x2;
 
-use self::biquad::{Biquad, BiquadCoefficients, NEUTRAL_Q};
 use crate::NUM_BANDS;
 
-pub mod biquad;
-
 #[derive(Debug)]
 pub struct IirCrossover {
     /// The kind of crossover to use. `.update_filters()` must be called after changing this.
@@ -87,45 +85,54 @@ impl IirCrossover {
         }
     }
 
-    /// Split the signal into bands using the crossovers previously configured through `.update()`.
-    /// The split bands will be written to `band_outputs`. `main_io` is not written to, and should
-    /// be cleared separately.
+    /// Split the signal into bands using the crossovers previously configured through `.update()`
+    /// and return the split bands. `main_io` is not written to, and should be cleared separately.
+    /// Bands that are not set in `bands_active` skip their phase compensation and are returned as
+    /// silence, as are the bands above `num_bands`.
     pub fn process(
         &mut self,
         num_bands: usize,
         main_io: &ChannelSamples,
-        mut band_outputs: [ChannelSamples; NUM_BANDS],
-    ) {
+        bands_active: [bool; NUM_BANDS],
+    ) -> [f32x2; NUM_BANDS] {
         nih_debug_assert!(num_bands >= 2);
         nih_debug_assert!(num_bands <= NUM_BANDS);
         // Required for the SIMD, so we'll just do a hard assert or the unchecked conversions will
         // be unsound
         assert!(main_io.len() == 2);
 
+        let mut band_outputs = [f32x2::splat(0.0); NUM_BANDS];
         let mut samples: f32x2 = unsafe { main_io.to_simd_unchecked() };
         match self.mode {
             IirCrossoverType::LinkwitzRiley24 => {
-                for (crossover_idx, (crossover, band_channel_samples)) in self
+                for (crossover_idx, ((crossover, band_samples), band_active)) in self
                     .crossovers
                     .iter_mut()
                     .zip(band_outputs.iter_mut())
+                    .zip(bands_active)
                     .take(num_bands - 1)
                     .enumerate()
                 {
+                    // The crossovers are chained, so they need to run even if the band they split
+                    // off is not used
                     let (lp_samples, hp_samples) = crossover.process_lr24(samples);
+                    samples = hp_samples;
 
                     // The low-pass result needs to have the same phase shift applied to it that
                     // higher bands would get
-                    let lp_samples = self.all_passes.compensate_lr24(lp_samples, crossover_idx);
-
-                    unsafe { band_channel_samples.from_simd_unchecked(lp_samples) };
-                    samples = hp_samples;
+                    if band_active {
+                        *band_samples = self.all_passes.compensate_lr24(lp_samples, crossover_idx);
+                    }
                 }
 
                 // And the final high-passed result should be written to the last band
-                unsafe { band_outputs[num_bands - 1].from_simd_unchecked(samples) };
+                if bands_active[num_bands - 1] {
+                    band_outputs[num_bands - 1] = samples;
+                }
             }
         }
+
+        band_outputs
     }
 
     /// Update the crossover frequencies for all filters. `num_bands` is assumed to be in `[2,
@@ -48,6 +48,10 @@ struct Crossover {
     fir_crossover: FirCrossover,
     /// Set when the number of bands has changed and the filters must be updated.
     should_update_filters: Arc<AtomicBool>,
+    /// The number of bands during the last processing cycle. The supported bus configurations
+    /// depend on the number of bands, so the wrapper is asked to rescan the audio ports when this
+    /// changes.
+    num_bands: usize,
 }
 
 #[derive(Params)]
@@ -106,6 +110,7 @@ impl CrossoverParams {
                     max: NUM_BANDS as i32,
                 },
             )
+            .show_as_list()
             .with_callback({
                 let should_update_filters = should_update_filters.clone();
 
@@ -154,6 +159,7 @@ impl Default for Crossover {
             iir_crossover: IirCrossover::new(IirCrossoverType::LinkwitzRiley24),
             fir_crossover: FirCrossover::new(FirCrossoverType::LinkwitzRiley24LinearPhase),
             should_update_filters,
+            num_bands: 2,
         }
     }
 }
@@ -190,9 +196,13 @@ impl Plugin for Crossover {
     }
 
     fn accepts_bus_config(&self, config: &BusConfig) -> bool {
-        // Only do stereo
+        // Only do stereo, and there should be an output for every band. Since CLAP hosts can
+        // choose a configuration with fewer aux outputs, changing the number of bands triggers an
+        // audio ports rescan.
+        let num_bands = self.params.num_bands.value() as u32;
         config.num_input_channels == NUM_CHANNELS
             && config.num_output_channels == NUM_CHANNELS
+            && (num_bands..=NUM_BANDS as u32).contains(&config.aux_output_busses.num_busses)
             && config.aux_output_busses.num_channels == NUM_CHANNELS
     }
 
@@ -203,6 +213,7 @@ impl Plugin for Crossover {
         context: &mut impl InitContext<Self>,
     ) -> bool {
         self.buffer_config = *buffer_config;
+        self.num_bands = self.params.num_bands.value() as usize;
 
         // Make sure the filter states match the current parameters
         self.update_filters(1);
@@ -229,17 +240,30 @@ impl Plugin for Crossover {
         aux: &mut AuxiliaryBuffers,
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
+        let num_bands = self.params.num_bands.value() as usize;
+        if num_bands != self.num_bands {
+            self.num_bands = num_bands;
+            context.request_audio_ports_rescan();
+        }
+
+        // Bands without an active output don't need to be processed. Until the host restarts the
+        // plugin after a rescan there may also be fewer outputs than there are bands.
+        let num_band_outputs = aux.outputs.len();
+        let bands_active: [bool; NUM_BANDS] = std::array::from_fn(|band_idx| {
+            band_idx < num_band_outputs && context.aux_output_active(band_idx)
+        });
+
         // Right now both crossover types only do 24 dB/octave Linkwitz-Riley style crossovers
         match self.params.crossover_type.value() {
             CrossoverType::LinkwitzRiley24 => {
                 context.set_latency_samples(0);
 
-                self.process_iir(buffer, aux);
+                self.process_iir(buffer, aux, bands_active);
             }
             CrossoverType::LinkwitzRiley24LinearPhase => {
                 context.set_latency_samples(self.fir_crossover.latency());
 
-                self.process_fir(buffer, aux);
+                self.process_fir(buffer, aux, bands_active);
             }
         }
 
@@ -248,55 +272,38 @@ impl Plugin for Crossover {
 }
 
 impl Crossover {
-    /// Takes care of all of the boilerplate in zipping the outputs together to get a nice iterator
-    /// friendly and SIMD-able interface for the processing function. Prevents having to branch per
-    /// sample. The closure receives an input sample and it should write the output samples for each
-    /// band to the array.
-    fn process_iir(&mut self, buffer: &mut Buffer, aux: &mut AuxiliaryBuffers) {
-        let aux_outputs = &mut aux.outputs;
-        let (band_1_buffer, aux_outputs) = aux_outputs.split_first_mut().unwrap();
-        let (band_2_buffer, aux_outputs) = aux_outputs.split_first_mut().unwrap();
-        let (band_3_buffer, aux_outputs) = aux_outputs.split_first_mut().unwrap();
-        let (band_4_buffer, aux_outputs) = aux_outputs.split_first_mut().unwrap();
-        let (band_5_buffer, _) = aux_outputs.split_first_mut().unwrap();
-
-        // Snoclists for days
-        for (
-            (
-                (
-                    ((main_channel_samples, band_1_channel_samples), band_2_channel_samples),
-                    band_3_channel_samples,
-                ),
-                band_4_channel_samples,
-            ),
-            band_5_channel_samples,
-        ) in buffer
-            .iter_samples()
-            .zip(band_1_buffer.iter_samples())
-            .zip(band_2_buffer.iter_samples())
-            .zip(band_3_buffer.iter_samples())
-            .zip(band_4_buffer.iter_samples())
-            .zip(band_5_buffer.iter_samples())
-        {
-            // We can avoid a lot of hardcoding and conditionals by restoring the original array structure
-            let bands = [
-                band_1_channel_samples,
-                band_2_channel_samples,
-                band_3_channel_samples,
-                band_4_channel_samples,
-                band_5_channel_samples,
-            ];
-
+    /// Split the main input into bands per-sample using the IIR crossovers, and write the bands to
+    /// the active aux outputs.
+    fn process_iir(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        bands_active: [bool; NUM_BANDS],
+    ) {
+        for (sample_idx, main_channel_samples) in buffer.iter_samples().enumerate() {
             // Only update the filters when needed
             if self.should_update_filters() {
                 self.update_filters(1);
             }
 
-            self.iir_crossover.process(
+            let band_samples = self.iir_crossover.process(
                 self.params.num_bands.value() as usize,
                 &main_channel_samples,
-                bands,
+                bands_active,
             );
+            for ((band_buffer, band_samples), band_active) in
+                aux.outputs.iter_mut().zip(band_samples).zip(bands_active)
+            {
+                if band_active {
+                    for (channel, sample) in band_buffer
+                        .as_slice()
+                        .iter_mut()
+                        .zip(band_samples.to_array())
+                    {
+                        channel[sample_idx] = sample;
+                    }
+                }
+            }
 
             // The main output should be silent as the signal is already evenly split over the other
             // bands
@@ -308,29 +315,29 @@ impl Crossover {
 
     /// `process_iir()`, but for the linear-phase FIR crossovers. This processes an entire channel
     /// at once instead of processing per-sample since we use FFT convolution.
-    fn process_fir(&mut self, buffer: &mut Buffer, aux: &mut AuxiliaryBuffers) {
+    fn process_fir(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        bands_active: [bool; NUM_BANDS],
+    ) {
         // In theory we could do smoothing in between processed blocks, but this hsould be fine
         if self.should_update_filters() {
             self.update_filters(buffer.len() as u32);
         }
 
-        let aux_outputs = &mut aux.outputs;
-        let (band_1_buffer, aux_outputs) = aux_outputs.split_first_mut().unwrap();
-        let (band_2_buffer, aux_outputs) = aux_outputs.split_first_mut().unwrap();
-        let (band_3_buffer, aux_outputs) = aux_outputs.split_first_mut().unwrap();
-        let (band_4_buffer, aux_outputs) = aux_outputs.split_first_mut().unwrap();
-        let (band_5_buffer, _) = aux_outputs.split_first_mut().unwrap();
-
-        // We can avoid a lot of hardcoding and conditionals by restoring the original array structure
         for channel_idx in 0..buffer.channels() {
             let main_io = &mut buffer.as_slice()[channel_idx];
-            let band_outputs = [
-                &mut band_1_buffer.as_slice()[channel_idx],
-                &mut band_2_buffer.as_slice()[channel_idx],
-                &mut band_3_buffer.as_slice()[channel_idx],
-                &mut band_4_buffer.as_slice()[channel_idx],
-                &mut band_5_buffer.as_slice()[channel_idx],
-            ];
+            let mut band_outputs: [Option<&mut [f32]>; NUM_BANDS] = Default::default();
+            for ((band_output, band_buffer), band_active) in band_outputs
+                .iter_mut()
+                .zip(aux.outputs.iter_mut())
+                .zip(bands_active)
+            {
+                if band_active {
+                    *band_output = Some(&mut band_buffer.as_slice()[channel_idx]);
+                }
+            }
 
             self.fir_crossover.process(
                 self.params.num_bands.value() as usize,
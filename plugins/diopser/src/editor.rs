@@ -17,6 +17,7 @@
 use atomic_float::AtomicF32;
 use nih_plug::nih_debug_assert_failure;
 use nih_plug::prelude::{Editor, Plugin};
+use nih_plug::util::spectrum::SpectrumOutput;
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::widgets::*;
 use nih_plug_vizia::{assets, create_vizia_editor, ViziaState, ViziaTheming};
@@ -24,7 +25,6 @@ use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 
 use self::button::SafeModeButton;
-use crate::spectrum::SpectrumOutput;
 use crate::{Diopser, DiopserParams};
 
 mod analyzer;
@@ -138,7 +138,7 @@ fn spectrum_analyzer(cx: &mut Context) {
 
         VStack::new(cx, |cx| {
             ZStack::new(cx, |cx| {
-                analyzer::SpectrumAnalyzer::new(cx, Data::spectrum, Data::sample_rate)
+                analyzer::SpectrumAnalyzer::new(cx, Data::spectrum)
                     .width(Percentage(100.0))
                     .height(Percentage(100.0));
 
@@ -14,21 +14,17 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use atomic_float::AtomicF32;
 use nih_plug::nih_debug_assert;
 use nih_plug::prelude::FloatRange;
+use nih_plug::util::spectrum::SpectrumOutput;
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::vizia::vg;
-use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 
-use crate::spectrum::SpectrumOutput;
-
 /// A very abstract spectrum analyzer. This draws the magnitude spectrum's bins as vertical lines
 /// with the same distirubtion as the filter frequency parmaeter..
 pub struct SpectrumAnalyzer {
     spectrum: Arc<Mutex<SpectrumOutput>>,
-    sample_rate: Arc<AtomicF32>,
 
     /// The same range as that used by the filter frequency parameter. We'll use this to make sure
     /// we draw the spectrum analyzer's ticks at locations that match the frequency parameter linked
@@ -38,18 +34,12 @@ pub struct SpectrumAnalyzer {
 
 impl SpectrumAnalyzer {
     /// Creates a new [`SpectrumAnalyzer`]. The uses custom drawing.
-    pub fn new<LSpectrum, LRate>(
-        cx: &mut Context,
-        spectrum: LSpectrum,
-        sample_rate: LRate,
-    ) -> Handle<Self>
+    pub fn new<LSpectrum>(cx: &mut Context, spectrum: LSpectrum) -> Handle<Self>
     where
         LSpectrum: Lens<Target = Arc<Mutex<SpectrumOutput>>>,
-        LRate: Lens<Target = Arc<AtomicF32>>,
     {
         Self {
             spectrum: spectrum.get(cx),
-            sample_rate: sample_rate.get(cx),
 
             frequency_range: crate::filter_frequency_range(),
         }
@@ -76,16 +66,14 @@ impl View for SpectrumAnalyzer {
         // open
         let mut spectrum = self.spectrum.lock().unwrap();
         let spectrum = spectrum.read();
-        let nyquist = self.sample_rate.load(Ordering::Relaxed) / 2.0;
 
         // This skips background and border drawing
         let line_width = cx.style.dpi_factor as f32 * 1.5;
         let paint = vg::Paint::color(cx.font_color().cloned().unwrap_or_default().into())
             .with_line_width(line_width);
-        for (bin_idx, magnetude) in spectrum.iter().enumerate() {
+        for (frequency, magnetude) in spectrum.frequencies.iter().zip(&spectrum.magnitudes) {
             // We'll match up the bin's x-coordinate with the filter frequency parameter
-            let frequency = (bin_idx as f32 / spectrum.len() as f32) * nyquist;
-            let t = self.frequency_range.normalize(frequency);
+            let t = self.frequency_range.normalize(*frequency);
             if t <= 0.0 || t >= 1.0 {
                 continue;
             }
@@ -21,16 +21,17 @@ compile_error!("Compiling without SIMD support is currently not supported");
 
 use atomic_float::AtomicF32;
 use nih_plug::prelude::*;
+use nih_plug::util::filter;
+use nih_plug::util::spectrum::{
+    SpectrumBinning, SpectrumChannelMode, SpectrumConfig, SpectrumInput, SpectrumNormalization,
+    SpectrumOutput,
+};
 use nih_plug_vizia::ViziaState;
 use std::simd::f32x2;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
-use crate::spectrum::{SpectrumInput, SpectrumOutput};
-
 mod editor;
-mod filter;
-mod spectrum;
 
 /// How many all-pass filters we can have in series at most. The filter stages parameter determines
 /// how many filters are actually active.
@@ -44,6 +45,11 @@ const MAX_AUTOMATION_STEP_SIZE: u32 = 512;
 /// The maximum number of samples to iterate over at a time.
 const MAX_BLOCK_SIZE: usize = 64;
 
+/// The FFT window size used for the spectrum analyzer.
+const SPECTRUM_WINDOW_SIZE: usize = 2048;
+/// The number of overlapping windows for the spectrum analyzer. Don't need that much precision here.
+const SPECTRUM_WINDOW_OVERLAP: usize = 2;
+
 /// The filter frequency parameter's range. Also used in the `SpectrumAnalyzer` widget.
 pub(crate) fn filter_frequency_range() -> FloatRange {
     FloatRange::Skewed {
@@ -147,9 +153,22 @@ impl Default for Diopser {
         let should_update_filters = Arc::new(AtomicBool::new(false));
         let bypass_smoother = Arc::new(Smoother::new(SmoothingStyle::Linear(10.0)));
 
-        // We only do stereo right now so this is simple
-        let (spectrum_input, spectrum_output) =
-            SpectrumInput::new(Self::DEFAULT_OUTPUT_CHANNELS as usize);
+        // We only do stereo right now so this is simple. The spectrum behaves like a peak meter, the
+        // upwards step is immediate and it takes 100 ms to go down 12 dB.
+        let (spectrum_input, spectrum_output) = SpectrumInput::new(
+            Self::DEFAULT_OUTPUT_CHANNELS as usize,
+            SpectrumConfig {
+                window_size: SPECTRUM_WINDOW_SIZE,
+                overlap_times: SPECTRUM_WINDOW_OVERLAP,
+                attack_ms: 0.0,
+                release_ms: 100.0,
+                binning: SpectrumBinning::Linear,
+                // Both channels are fed through the same peak meter-like ballistics, which also
+                // results in quasi-mono summing
+                normalization: SpectrumNormalization::WindowSize,
+                channel_mode: SpectrumChannelMode::Sequential,
+            },
+        );
 
         Self {
             params: Arc::new(DiopserParams::new(
@@ -1,5 +1,6 @@
 use atomic_float::AtomicF32;
 use nih_plug::prelude::*;
+use nih_plug::util::metering::EnvelopeFollower;
 use nih_plug_egui::{create_egui_editor, egui, widgets, EguiState};
 use std::sync::Arc;
 
@@ -10,8 +11,8 @@ const PEAK_METER_DECAY_MS: f64 = 150.0;
 pub struct Gain {
     params: Arc<GainParams>,
 
-    /// Needed to normalize the peak meter's response based on the sample rate.
-    peak_meter_decay_weight: f32,
+    /// Computes the peak meter's value with an instant attack and a smooth decay.
+    peak_meter_envelope: EnvelopeFollower,
     /// The current data for the peak meter. This is stored as an [`Arc`] so we can share it between
     /// the GUI and the audio processing parts. If you have more state to share, then it's a good
     /// idea to put all of that in a struct behind a single `Arc`.
@@ -23,8 +24,8 @@ pub struct Gain {
 #[derive(Params)]
 pub struct GainParams {
     /// The editor state, saved together with the parameter state so the custom scaling can be
-    /// restored.
-    #[persist = "editor-state"]
+    /// restored. This is not stored in presets so loading a preset doesn't resize the editor.
+    #[persist(key = "editor-state", skip_presets)]
     editor_state: Arc<EguiState>,
 
     #[id = "gain"]
@@ -40,7 +41,12 @@ impl Default for Gain {
         Self {
             params: Arc::new(GainParams::default()),
 
-            peak_meter_decay_weight: 1.0,
+            // The envelope follower's release time is a time constant, so this is scaled to make the
+            // meter decay by 12 dB after `PEAK_METER_DECAY_MS` milliseconds of pure silence
+            peak_meter_envelope: EnvelopeFollower::new(
+                0.0,
+                (PEAK_METER_DECAY_MS / 4.0f64.ln()) as f32,
+            ),
             peak_meter: Arc::new(AtomicF32::new(util::MINUS_INFINITY_DB)),
         }
     }
@@ -160,11 +166,8 @@ impl Plugin for Gain {
         buffer_config: &BufferConfig,
         _context: &mut impl InitContext<Self>,
     ) -> bool {
-        // After `PEAK_METER_DECAY_MS` milliseconds of pure silence, the peak meter's value should
-        // have dropped by 12 dB
-        self.peak_meter_decay_weight = 0.25f64
-            .powf((buffer_config.sample_rate as f64 * PEAK_METER_DECAY_MS / 1000.0).recip())
-            as f32;
+        self.peak_meter_envelope
+            .set_sample_rate(buffer_config.sample_rate);
 
         true
     }
@@ -188,14 +191,9 @@ impl Plugin for Gain {
             // To save resources, a plugin can (and probably should!) only perform expensive
             // calculations that are only displayed on the GUI while the GUI is open
             if self.params.editor_state.is_open() {
-                amplitude = (amplitude / num_samples as f32).abs();
-                let current_peak_meter = self.peak_meter.load(std::sync::atomic::Ordering::Relaxed);
-                let new_peak_meter = if amplitude > current_peak_meter {
-                    amplitude
-                } else {
-                    current_peak_meter * self.peak_meter_decay_weight
-                        + amplitude * (1.0 - self.peak_meter_decay_weight)
-                };
+                let new_peak_meter = self
+                    .peak_meter_envelope
+                    .process(amplitude / num_samples as f32);
 
                 self.peak_meter
                     .store(new_peak_meter, std::sync::atomic::Ordering::Relaxed)
@@ -47,6 +47,12 @@ struct PubertySimulator {
     /// Contains a Hann window function of the current window length, passed to the overlap-add
     /// helper. Allocated with a `MAX_WINDOW_SIZE` initial capacity.
     window_function: Vec<f32>,
+    /// The gain compensation for the squared Hann window, the overlap, and the extra gain
+    /// introduced by the IDFT operation. This only needs to be recomputed when the window size or
+    /// the overlap amount changes.
+    gain_compensation: f32,
+    /// The overlap amount `gain_compensation` was last computed for.
+    gain_compensation_overlap_times: usize,
 
     /// The algorithms for the FFT and IFFT operations, for each supported order so we can switch
     /// between them without replanning or allocations. Initialized during `initialize()`.
@@ -103,6 +109,8 @@ impl Default for PubertySimulator {
 
             stft: util::StftHelper::new(2, MAX_WINDOW_SIZE, 0),
             window_function: Vec::with_capacity(MAX_WINDOW_SIZE),
+            gain_compensation: 1.0,
+            gain_compensation_overlap_times: 0,
 
             plan_for_order: None,
             complex_fft_buffer: Vec::with_capacity(MAX_WINDOW_SIZE / 2 + 1),
@@ -210,6 +218,7 @@ impl Plugin for PubertySimulator {
 
             context.set_latency_samples(self.stft.latency_samples());
         }
+        self.update_gain_compensation(self.overlap_times());
 
         true
     }
@@ -225,25 +234,26 @@ impl Plugin for PubertySimulator {
         _aux: &mut AuxiliaryBuffers,
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        // Compensate for the window function, the overlap, and the extra gain introduced by the
-        // IDFT operation
         let window_size = self.window_size();
         let overlap_times = self.overlap_times();
         let sample_rate = context.transport().sample_rate;
-        // The overlap gain compensation is based on a squared Hann window, which will sum perfectly
-        // at four times overlap or higher. We'll apply a regular Hann window before the analysis
-        // and after the synthesis.
-        let gain_compensation: f32 =
-            ((overlap_times as f32 / 4.0) * 1.5).recip() / window_size as f32;
 
         // If the window size has changed since the last process call, reset the buffers and chance
         // our latency. All of these buffers already have enough capacity
-        if self.window_function.len() != window_size {
+        let window_size_changed = self.window_function.len() != window_size;
+        if window_size_changed {
             self.resize_for_window(window_size);
 
             context.set_latency_samples(self.stft.latency_samples());
         }
 
+        // Compensate for the window function, the overlap, and the extra gain introduced by the
+        // IDFT operation
+        if window_size_changed || self.gain_compensation_overlap_times != overlap_times {
+            self.update_gain_compensation(overlap_times);
+        }
+        let gain_compensation = self.gain_compensation;
+
         // These plans have already been made during initialization we can switch between versions
         // without reallocating
         let fft_plan = &mut self.plan_for_order.as_mut().unwrap()
@@ -413,6 +423,18 @@ impl PubertySimulator {
             .resize(window_size / 2 + 1, Complex32::default());
         util::window::hann_in_place(&mut self.window_function);
     }
+
+    /// Recompute the gain compensation for the current window function and `overlap_times`.
+    fn update_gain_compensation(&mut self, overlap_times: usize) {
+        // We'll apply a regular Hann window before the analysis and after the synthesis. This
+        // squared Hann window will sum perfectly at four times overlap or higher.
+        self.gain_compensation = util::window::cola_normalization_factor(
+            &self.window_function,
+            Some(&self.window_function),
+            overlap_times,
+        ) / self.window_function.len() as f32;
+        self.gain_compensation_overlap_times = overlap_times;
+    }
 }
 
 impl ClapPlugin for PubertySimulator {
@@ -15,13 +15,13 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use nih_plug::prelude::*;
+use nih_plug::util::dry_wet_mixer::{DryWetMixer, MixingStyle};
 use nih_plug_vizia::ViziaState;
 use realfft::num_complex::Complex32;
 use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
 use std::sync::Arc;
 
 mod compressor_bank;
-mod dry_wet_mixer;
 mod editor;
 
 const MIN_WINDOW_ORDER: usize = 6;
@@ -56,8 +56,14 @@ struct SpectralCompressor {
     /// Contains a Hann window function of the current window length, passed to the overlap-add
     /// helper. Allocated with a `MAX_WINDOW_SIZE` initial capacity.
     window_function: Vec<f32>,
+    /// The gain compensation for the squared Hann window, the overlap, and the extra gain
+    /// introduced by the IDFT operation. This only needs to be recomputed when the window size or
+    /// the overlap amount changes.
+    gain_compensation: f32,
+    /// The overlap amount `gain_compensation` was last computed for.
+    gain_compensation_overlap_times: usize,
     /// A mixer to mix the dry signal back into the processed signal with latency compensation.
-    dry_wet_mixer: dry_wet_mixer::DryWetMixer,
+    dry_wet_mixer: DryWetMixer,
     /// Spectral per-bin upwards and downwards compressors with soft-knee settings. This is where
     /// the magic happens.
     compressor_bank: compressor_bank::CompressorBank,
@@ -157,7 +163,9 @@ impl Default for SpectralCompressor {
             // These three will be set to the correct values in the initialize function
             stft: util::StftHelper::new(Self::DEFAULT_OUTPUT_CHANNELS as usize, MAX_WINDOW_SIZE, 0),
             window_function: Vec::with_capacity(MAX_WINDOW_SIZE),
-            dry_wet_mixer: dry_wet_mixer::DryWetMixer::new(0, 0, 0),
+            gain_compensation: 1.0,
+            gain_compensation_overlap_times: 0,
+            dry_wet_mixer: DryWetMixer::new(0, 0, 0),
             compressor_bank,
 
             // This is initialized later since we don't want to do non-trivial computations before
@@ -331,7 +339,10 @@ impl Plugin for SpectralCompressor {
 
         let window_size = self.window_size();
         self.resize_for_window(window_size);
+        self.update_gain_compensation(self.overlap_times());
         context.set_latency_samples(self.stft.latency_samples());
+        self.dry_wet_mixer
+            .set_latency_samples(self.stft.latency_samples());
 
         true
     }
@@ -351,9 +362,15 @@ impl Plugin for SpectralCompressor {
         // our latency. All of these buffers already have enough capacity so this won't allocate.
         let window_size = self.window_size();
         let overlap_times = self.overlap_times();
-        if self.window_function.len() != window_size {
+        let window_size_changed = self.window_function.len() != window_size;
+        if window_size_changed {
             self.resize_for_window(window_size);
             context.set_latency_samples(self.stft.latency_samples());
+            self.dry_wet_mixer
+                .set_latency_samples(self.stft.latency_samples());
+        }
+        if window_size_changed || self.gain_compensation_overlap_times != overlap_times {
+            self.update_gain_compensation(overlap_times);
         }
 
         // These plans have already been made during initialization we can switch between versions
@@ -368,19 +385,13 @@ impl Plugin for SpectralCompressor {
             (20.0 / ((self.buffer_config.sample_rate / 2.0) / num_bins as f32)).floor() as usize
                 + 1;
 
-        // The overlap gain compensation is based on a squared Hann window, which will sum perfectly
-        // at four times overlap or higher. We'll apply a regular Hann window before the analysis
-        // and after the synthesis.
-        let gain_compensation: f32 =
-            ((overlap_times as f32 / 4.0) * 1.5).recip() / window_size as f32;
-
         // We'll apply the square root of the total gain compensation at the DFT and the IDFT
         // stages. That way the compressor threshold values make much more sense. This version of
         // Spectral Compressor does not have in input gain option and instead has the curve
         // threshold option. When sidechaining is enabled this is used to gain up the sidechain
         // signal instead.
-        let input_gain = gain_compensation.sqrt();
-        let output_gain = self.params.global.output_gain.value() * gain_compensation.sqrt();
+        let input_gain = self.gain_compensation.sqrt();
+        let output_gain = self.params.global.output_gain.value() * self.gain_compensation.sqrt();
         // TODO: Auto makeup gain
 
         // This is mixed in later with latency compensation applied
@@ -451,8 +462,7 @@ impl Plugin for SpectralCompressor {
                 .smoothed
                 .next_step(buffer.len() as u32),
             // The dry and wet signals are in phase, so we can do a linear mix
-            dry_wet_mixer::MixingStyle::Linear,
-            self.stft.latency_samples() as usize,
+            MixingStyle::Linear,
         );
 
         ProcessStatus::Normal
@@ -484,6 +494,18 @@ impl SpectralCompressor {
             .resize(&self.buffer_config, window_size);
         self.compressor_bank.reset();
     }
+
+    /// Recompute the gain compensation for the current window function and `overlap_times`.
+    fn update_gain_compensation(&mut self, overlap_times: usize) {
+        // We'll apply a regular Hann window before the analysis and after the synthesis. This
+        // squared Hann window will sum perfectly at four times overlap or higher.
+        self.gain_compensation = util::window::cola_normalization_factor(
+            &self.window_function,
+            Some(&self.window_function),
+            overlap_times,
+        ) / self.window_function.len() as f32;
+        self.gain_compensation_overlap_times = overlap_times;
+    }
 }
 
 // These separate functions are needed to avoid having to either duplicate the main process function
@@ -2,10 +2,16 @@
 
 use std::marker::PhantomData;
 
+use crate::context::process::ProcessContext;
+use crate::midi::NoteEvent;
+use crate::plugin::Plugin;
+
 mod blocks;
+mod event_blocks;
 mod samples;
 
 pub use blocks::{Block, BlockChannelsIter, BlocksIter};
+pub use event_blocks::EventBlocksIter;
 pub use samples::{ChannelSamples, ChannelSamplesIter, SamplesIter};
 
 /// The audio buffers used during processing. This contains the output audio output buffers with the
@@ -108,6 +114,65 @@ impl<'a> Buffer<'a> {
         }
     }
 
+    /// Iterate over the buffer in blocks that are split at note event timings, with the specified
+    /// maximum block size. This is useful for polyphonic synthesizers and other plugins that need
+    /// to handle events sample accurately while still rendering audio in blocks. Every yielded
+    /// item consists of the block's offset from the start of the buffer, the block itself, and a
+    /// slice containing the events that happen at the start of that block. Events never happen in
+    /// the middle of a block, so handling all of a block's events before processing the block
+    /// results in sample accurate event handling.
+    ///
+    /// This first moves all of the process call's events from the context to `events`, so the
+    /// context can still be used while iterating over the blocks. That vector should be
+    /// preallocated with enough capacity for all of the events in a single process call, for
+    /// instance 1024 events, to avoid allocations on the audio thread.
+    ///
+    /// When [`Plugin::SAMPLE_ACCURATE_AUTOMATION`] is enabled, the wrapper already splits the
+    /// process call at parameter changes, so parameter values stay constant within the entire
+    /// buffer and the smoothers can be used as normal within these blocks.
+    ///
+    /// ```ignore
+    /// for (block_start, block, events) in buffer.iter_event_blocks(context, &mut self.events, 64) {
+    ///     for event in events {
+    ///         // Handle the event, the context is still usable here
+    ///     }
+    ///
+    ///     // Process `block`, which starts `block_start` samples into the buffer
+    /// }
+    /// ```
+    pub fn iter_event_blocks<'slice, 'events, P: Plugin>(
+        &'slice mut self,
+        context: &mut impl ProcessContext<P>,
+        events: &'events mut Vec<NoteEvent>,
+        max_block_size: usize,
+    ) -> EventBlocksIter<'slice, 'a, 'events> {
+        nih_debug_assert_ne!(max_block_size, 0);
+
+        events.clear();
+        while let Some(event) = context.next_event() {
+            events.push(event);
+        }
+
+        self.iter_event_blocks_from_slice(events, max_block_size)
+    }
+
+    /// The same as [`iter_event_blocks()`][Self::iter_event_blocks()], but with the events passed
+    /// directly.
+    fn iter_event_blocks_from_slice<'slice, 'events>(
+        &'slice mut self,
+        events: &'events [NoteEvent],
+        max_block_size: usize,
+    ) -> EventBlocksIter<'slice, 'a, 'events> {
+        EventBlocksIter {
+            buffers: self.output_slices.as_mut_slice(),
+            events,
+            max_block_size,
+            current_block_start: 0,
+            next_event_idx: 0,
+            _marker: PhantomData,
+        }
+    }
+
     /// Access the raw output slice vector. This needs to be resized to match the number of output
     /// channels during the plugin's initialization. Then during audio processing, these slices
     /// should be updated to point to the plugin's audio buffers.
@@ -22,11 +22,11 @@ pub struct BlocksIter<'slice, 'sample: 'slice> {
 /// supports direct access to the block's samples if needed.
 pub struct Block<'slice, 'sample: 'slice> {
     /// The raw output buffers.
-    pub(self) buffers: *mut [&'sample mut [f32]],
-    pub(self) current_block_start: usize,
+    pub(super) buffers: *mut [&'sample mut [f32]],
+    pub(super) current_block_start: usize,
     /// The index of the last sample in the block plus one.
-    pub(self) current_block_end: usize,
-    pub(self) _marker: PhantomData<&'slice mut [&'sample mut [f32]]>,
+    pub(super) current_block_end: usize,
+    pub(super) _marker: PhantomData<&'slice mut [&'sample mut [f32]]>,
 }
 
 /// An iterator over all channels in a block yielded by [`Block`], returning an entire channel slice
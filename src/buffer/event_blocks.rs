@@ -0,0 +1,171 @@
+//! Iterators for splitting a buffer into blocks at note event timings.
+
+use std::marker::PhantomData;
+
+use super::Block;
+use crate::midi::NoteEvent;
+
+/// An iterator over all samples in the buffer, split into blocks at note event timings and with a
+/// maximum size of `max_block_size`. See
+/// [`Buffer::iter_event_blocks()`][super::Buffer::iter_event_blocks()]. Yields the block's offset
+/// from the start of the buffer, the block, and the events that happen at the start of the block.
+pub struct EventBlocksIter<'slice, 'sample: 'slice, 'events> {
+    /// The raw output buffers.
+    pub(super) buffers: *mut [&'sample mut [f32]],
+    /// All of the buffer's events, sorted by timing.
+    pub(super) events: &'events [NoteEvent],
+    pub(super) max_block_size: usize,
+    pub(super) current_block_start: usize,
+    /// The index in `events` of the first event that has not yet been yielded.
+    pub(super) next_event_idx: usize,
+    pub(super) _marker: PhantomData<&'slice mut [&'sample mut [f32]]>,
+}
+
+impl<'slice, 'sample, 'events> Iterator for EventBlocksIter<'slice, 'sample, 'events> {
+    type Item = (usize, Block<'slice, 'sample>, &'events [NoteEvent]);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let buffer_len = unsafe { (*self.buffers).first().map_or(0, |channel| channel.len()) };
+        // Events should still be handled for empty buffers, so in that case we'll yield a single
+        // empty block containing all events
+        let has_remaining_events = self.next_event_idx < self.events.len();
+        if self.current_block_start >= buffer_len
+            && !(buffer_len == 0 && self.current_block_start == 0 && has_remaining_events)
+        {
+            return None;
+        }
+
+        let current_block_start = self.current_block_start;
+        let events_start_idx = self.next_event_idx;
+        let mut events_end_idx = events_start_idx;
+        while events_end_idx < self.events.len()
+            && self.events[events_end_idx].timing() as usize <= current_block_start
+        {
+            events_end_idx += 1;
+        }
+
+        // The block is cut short if the next event happens before the end of the block
+        let mut current_block_end = (current_block_start + self.max_block_size).min(buffer_len);
+        match self.events.get(events_end_idx) {
+            Some(event) if (event.timing() as usize) < current_block_end => {
+                current_block_end = event.timing() as usize;
+            }
+            _ => (),
+        }
+
+        // Events should never happen after the end of the buffer, but if they do then they're
+        // included in the last block so they don't get lost
+        if current_block_end == buffer_len {
+            nih_debug_assert_eq!(
+                events_end_idx,
+                self.events.len(),
+                "Found events after the end of the buffer"
+            );
+            events_end_idx = self.events.len();
+        }
+
+        let block = Block {
+            buffers: self.buffers,
+            current_block_start,
+            current_block_end,
+            _marker: PhantomData,
+        };
+
+        // Empty buffers only get a single block, this makes sure the next call returns `None`
+        self.current_block_start = current_block_end.max(current_block_start + 1);
+        self.next_event_idx = events_end_idx;
+
+        Some((
+            current_block_start,
+            block,
+            &self.events[events_start_idx..events_end_idx],
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Buffer;
+    use super::*;
+
+    fn note_on(timing: u32, note: u8) -> NoteEvent {
+        NoteEvent::NoteOn {
+            timing,
+            voice_id: None,
+            channel: 0,
+            note,
+            velocity: 1.0,
+        }
+    }
+
+    /// Split a buffer with `num_samples` samples and return the start and end indices of the
+    /// blocks along with the notes from the events in each block.
+    fn split(
+        num_samples: usize,
+        events: &[NoteEvent],
+        max_block_size: usize,
+    ) -> Vec<(usize, usize, Vec<u8>)> {
+        let mut real_buffers = vec![vec![0.0; num_samples]; 2];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.with_raw_vec(|output_slices| {
+                let (first_channel, other_channels) = real_buffers.split_at_mut(1);
+                *output_slices = vec![&mut first_channel[0], &mut other_channels[0]];
+            })
+        };
+
+        buffer
+            .iter_event_blocks_from_slice(events, max_block_size)
+            .map(|(block_start, block, events)| {
+                let notes = events
+                    .iter()
+                    .map(|event| match event {
+                        NoteEvent::NoteOn { note, .. } => *note,
+                        _ => unreachable!(),
+                    })
+                    .collect();
+
+                (block_start, block_start + block.len(), notes)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn no_events() {
+        assert_eq!(split(100, &[], 64), [(0, 64, vec![]), (64, 100, vec![])]);
+    }
+
+    #[test]
+    fn split_at_events() {
+        let events = [
+            note_on(0, 1),
+            note_on(10, 2),
+            note_on(10, 3),
+            note_on(70, 4),
+        ];
+        assert_eq!(
+            split(100, &events, 64),
+            [(0, 10, vec![1]), (10, 70, vec![2, 3]), (70, 100, vec![4])]
+        );
+
+        // The maximum block size is relative to the start of the block
+        let events = [note_on(20, 1), note_on(90, 2)];
+        assert_eq!(
+            split(100, &events, 64),
+            [
+                (0, 20, vec![]),
+                (20, 84, vec![1]),
+                (84, 90, vec![]),
+                (90, 100, vec![2]),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_buffer() {
+        let events = [note_on(0, 1), note_on(0, 2)];
+        assert_eq!(split(0, &events, 64), [(0, 0, vec![1, 2])]);
+        assert_eq!(split(0, &[], 64), []);
+    }
+}
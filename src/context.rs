@@ -24,3 +24,47 @@ impl Display for PluginApi {
         }
     }
 }
+
+/// Information about the track the plugin is inserted on, as reported by the host. This can be used
+/// to label a plugin's GUI or to pick sensible defaults for the track's type. It can be obtained
+/// through [`InitContext::track_info()`][init::InitContext::track_info()] and
+/// [`GuiContext::track_info()`][gui::GuiContext::track_info()], and
+/// [`Plugin::track_info_changed()`][crate::prelude::Plugin::track_info_changed()] is called when
+/// it changes.
+///
+/// This is provided by CLAP hosts supporting the `track-info` extension and by VST3 hosts
+/// supporting `IInfoListener`. Hosts only report parts of this information, so all fields are
+/// optional.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackInfo {
+    /// The track's name.
+    pub name: Option<String>,
+    /// The track's color.
+    pub color: Option<TrackColor>,
+    /// The type of track the plugin is inserted on. This is only reported by CLAP hosts.
+    pub track_type: Option<TrackType>,
+    /// The number of audio channels on the track. This is only reported by CLAP hosts.
+    pub audio_channel_count: Option<u32>,
+}
+
+/// A track's color in the sRGB color space, with an alpha channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackColor {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+/// The type of track a plugin is inserted on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackType {
+    /// A regular audio or instrument track.
+    Regular,
+    /// A bus or group track.
+    Bus,
+    /// A return or FX track.
+    Return,
+    /// The master track.
+    Master,
+}
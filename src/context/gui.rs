@@ -2,7 +2,8 @@
 
 use std::sync::Arc;
 
-use super::PluginApi;
+use super::{PluginApi, TrackInfo};
+use crate::midi::learn::MidiLearnStatus;
 use crate::params::internals::ParamPtr;
 use crate::params::Param;
 use crate::plugin::Plugin;
@@ -58,14 +59,103 @@ pub trait GuiContext: Send + Sync + 'static {
     unsafe fn raw_end_set_parameter(&self, param: ParamPtr);
 
     /// Serialize the plugin's current state to a serde-serializable object. Useful for implementing
-    /// preset handling within a plugin's GUI.
+    /// preset handling within a plugin's GUI. This is treated as
+    /// [`StateContext::Preset`][crate::params::persist::StateContext::Preset] state, so persistent
+    /// fields marked with `skip_presets` are left out.
     fn get_state(&self) -> PluginState;
 
     /// Restore the state from a previously serialized state object. This will block the GUI thread
     /// until the state has been restored and a parameter value rescan has been requested from the
     /// host. If the plugin is currently processing audio, then the parameter values will be
-    /// restored at the end of the current processing cycle.
+    /// restored at the end of the current processing cycle. Like with
+    /// [`get_state()`][Self::get_state()], persistent fields marked with `skip_presets` are not
+    /// restored.
     fn set_state(&self, state: PluginState);
+
+    /// Let the wrapper and the host know that the names returned from
+    /// [`Plugin::note_names()`][crate::prelude::Plugin::note_names()] have changed. The wrapper
+    /// will query the plugin for the new names on the main thread.
+    fn notify_note_names_changed(&self) {}
+
+    /// Ask the wrapper to query the plugin's supported bus configurations through
+    /// [`Plugin::accepts_bus_config()`][crate::prelude::Plugin::accepts_bus_config()] again, for
+    /// instance because the plugin's number of auxiliary outputs depends on a parameter. If the
+    /// current configuration is no longer supported, then the wrapper will switch to a different
+    /// one. If the plugin is currently active, then the host will be asked to restart the plugin
+    /// first. This is only supported by CLAP.
+    fn request_audio_ports_rescan(&self);
+
+    /// Information about the track the plugin is inserted on, if the host provides it. See
+    /// [`TrackInfo`] for more information.
+    fn track_info(&self) -> Option<TrackInfo>;
+
+    /// Ask the host to show its context menu for a parameter at the specified position. This gives
+    /// the user access to the host's automation and MIDI learn options for that parameter. Create a
+    /// [`ParamSetter`] and use [`ParamSetter::show_context_menu()`] instead for a safe, user
+    /// friendly API. Returns `false` if the host does not support parameter context menus.
+    ///
+    /// # Safety
+    ///
+    /// The implementing function still needs to check if `param` actually exists. This function is
+    /// mostly marked as unsafe for API reasons.
+    unsafe fn raw_show_context_menu(&self, param: ParamPtr, x: i32, y: i32) -> bool;
+
+    /// Arm MIDI learn for a parameter. The next MIDI CC the plugin receives will be bound to this
+    /// parameter. Create a [`ParamSetter`] and use [`ParamSetter::begin_midi_learn()`] instead for
+    /// a safe, user friendly API. This requires the plugin's
+    /// [`MIDI_INPUT`][crate::prelude::Plugin::MIDI_INPUT] to be at least
+    /// [`MidiConfig::MidiCCs`][crate::prelude::MidiConfig::MidiCCs].
+    ///
+    /// # Safety
+    ///
+    /// The implementing function still needs to check if `param` actually exists. This function is
+    /// mostly marked as unsafe for API reasons.
+    unsafe fn raw_begin_midi_learn(&self, param: ParamPtr);
+
+    /// Disarm MIDI learn if a parameter is currently waiting for a MIDI CC.
+    fn cancel_midi_learn(&self);
+
+    /// Remove a parameter's MIDI CC binding. Create a [`ParamSetter`] and use
+    /// [`ParamSetter::clear_midi_mapping()`] instead for a safe, user friendly API.
+    ///
+    /// # Safety
+    ///
+    /// The implementing function still needs to check if `param` actually exists. This function is
+    /// mostly marked as unsafe for API reasons.
+    unsafe fn raw_clear_midi_mapping(&self, param: ParamPtr);
+
+    /// Get a parameter's MIDI learn status. Create a [`ParamSetter`] and use
+    /// [`ParamSetter::midi_learn_status()`] instead for a safe, user friendly API.
+    ///
+    /// # Safety
+    ///
+    /// The implementing function still needs to check if `param` actually exists. This function is
+    /// mostly marked as unsafe for API reasons.
+    unsafe fn raw_midi_learn_status(&self, param: ParamPtr) -> MidiLearnStatus;
+}
+
+/// An entry the plugin adds to the host's context menu for a parameter. These are returned from
+/// [`Plugin::context_menu_entries()`][crate::prelude::Plugin::context_menu_entries()], and selecting
+/// one of them calls
+/// [`Plugin::context_menu_action()`][crate::prelude::Plugin::context_menu_action()] with the
+/// entry's action ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContextMenuEntry {
+    /// A regular menu item.
+    Action {
+        label: String,
+        action_id: u32,
+        enabled: bool,
+    },
+    /// A menu item with a check mark.
+    Toggle {
+        label: String,
+        action_id: u32,
+        enabled: bool,
+        checked: bool,
+    },
+    /// A separator line between two groups of menu items.
+    Separator,
 }
 
 /// An way to run background tasks from the plugin's GUI, equivalent to the
@@ -183,4 +273,39 @@ impl<'a> ParamSetter<'a> {
     pub fn end_set_parameter<P: Param>(&self, param: &P) {
         unsafe { self.raw_context.raw_end_set_parameter(param.as_ptr()) };
     }
+
+    /// Ask the host to show its context menu for a parameter, for instance when the user
+    /// right clicks on the parameter's widget. `x` and `y` are the menu's position in physical
+    /// pixels relative to the top left corner of the editor window. Any entries returned from
+    /// [`Plugin::context_menu_entries()`][crate::prelude::Plugin::context_menu_entries()] are
+    /// added to the host's menu. Returns `false` if the host does not support parameter context
+    /// menus. The menu can only be shown from the host's GUI thread, so if the editor runs on its
+    /// own thread then the menu is shown asynchronously.
+    pub fn show_context_menu<P: Param>(&self, param: &P, x: i32, y: i32) -> bool {
+        unsafe { self.raw_context.raw_show_context_menu(param.as_ptr(), x, y) }
+    }
+
+    /// Arm MIDI learn for a parameter. The next MIDI CC the plugin receives gets bound to the
+    /// parameter, after which that CC controls the parameter instead of being sent to the plugin.
+    /// Bindings are stored as part of the plugin's state. This only works if the plugin's
+    /// [`MIDI_INPUT`][crate::prelude::Plugin::MIDI_INPUT] is at least
+    /// [`MidiConfig::MidiCCs`][crate::prelude::MidiConfig::MidiCCs].
+    pub fn begin_midi_learn<P: Param>(&self, param: &P) {
+        unsafe { self.raw_context.raw_begin_midi_learn(param.as_ptr()) };
+    }
+
+    /// Disarm MIDI learn if a parameter is currently waiting for a MIDI CC.
+    pub fn cancel_midi_learn(&self) {
+        self.raw_context.cancel_midi_learn();
+    }
+
+    /// Remove a parameter's MIDI CC binding, if it has one.
+    pub fn clear_midi_mapping<P: Param>(&self, param: &P) {
+        unsafe { self.raw_context.raw_clear_midi_mapping(param.as_ptr()) };
+    }
+
+    /// Check whether a parameter is waiting for a MIDI CC or which CC it is bound to.
+    pub fn midi_learn_status<P: Param>(&self, param: &P) -> MidiLearnStatus {
+        unsafe { self.raw_context.raw_midi_learn_status(param.as_ptr()) }
+    }
 }
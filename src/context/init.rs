@@ -1,6 +1,6 @@
 //! A context passed during plugin initialization.
 
-use super::PluginApi;
+use super::{PluginApi, TrackInfo};
 use crate::plugin::Plugin;
 
 /// Callbacks the plugin can make while it is being initialized. This is passed to the plugin during
@@ -34,4 +34,23 @@ pub trait InitContext<P: Plugin> {
     /// runtime allows the host to better optimize polyphonic modulation, or to switch to strictly
     /// monophonic modulation when dropping the capacity down to 1.
     fn set_current_voice_capacity(&self, capacity: u32);
+
+    /// Whether the auxiliary input bus with index `index` is active. Hosts can deactivate
+    /// auxiliary busses they don't use, in which case the plugin can skip any work related to
+    /// those busses. Busses are active by default, and this can only change while the plugin is
+    /// deactivated. Returns `false` for indices that are out of bounds.
+    fn aux_input_active(&self, index: usize) -> bool;
+
+    /// The same as [`aux_input_active()`][Self::aux_input_active()], but for auxiliary output
+    /// busses.
+    fn aux_output_active(&self, index: usize) -> bool;
+
+    /// Let the wrapper and the host know that the names returned from
+    /// [`Plugin::note_names()`][crate::prelude::Plugin::note_names()] have changed. The wrapper
+    /// will query the plugin for the new names on the main thread.
+    fn notify_note_names_changed(&self) {}
+
+    /// Information about the track the plugin is inserted on, if the host provides it. See
+    /// [`TrackInfo`] for more information.
+    fn track_info(&self) -> Option<TrackInfo>;
 }
@@ -92,6 +92,29 @@ pub trait ProcessContext<P: Plugin> {
     /// monophonic modulation when dropping the capacity down to 1.
     fn set_current_voice_capacity(&self, capacity: u32);
 
+    /// Whether the auxiliary input bus with index `index` is active. Hosts can deactivate
+    /// auxiliary busses they don't use, in which case the plugin can skip any work related to
+    /// those busses. Busses are active by default, and this can only change while the plugin is
+    /// deactivated. Returns `false` for indices that are out of bounds.
+    fn aux_input_active(&self, index: usize) -> bool;
+
+    /// The same as [`aux_input_active()`][Self::aux_input_active()], but for auxiliary output
+    /// busses.
+    fn aux_output_active(&self, index: usize) -> bool;
+
+    /// Ask the wrapper to query the plugin's supported bus configurations through
+    /// [`Plugin::accepts_bus_config()`][crate::prelude::Plugin::accepts_bus_config()] again, for
+    /// instance because the plugin's number of auxiliary outputs depends on a parameter. If the
+    /// current configuration is no longer supported, then the wrapper will switch to a different
+    /// one. If the plugin is currently active, then the host will be asked to restart the plugin
+    /// first. This is only supported by CLAP.
+    fn request_audio_ports_rescan(&self);
+
+    /// Let the wrapper and the host know that the names returned from
+    /// [`Plugin::note_names()`][crate::prelude::Plugin::note_names()] have changed. The wrapper
+    /// will query the plugin for the new names on the main thread.
+    fn notify_note_names_changed(&self) {}
+
     // TODO: Add this, this works similar to [GuiContext::set_parameter] but it adds the parameter
     //       change to a queue (or directly to the VST3 plugin's parameter output queues) instead of
     //       using main thread host automation (and all the locks involved there).
@@ -6,6 +6,7 @@ use std::sync::Arc;
 use crate::context::gui::GuiContext;
 
 /// An editor for a [`Plugin`][crate::prelude::Plugin].
+#[allow(unused_variables)]
 pub trait Editor: Send {
     /// Create an instance of the plugin's editor and embed it in the parent window. As explained in
     /// [`Plugin::editor()`][crate::prelude::Plugin::editor()], you can then read the parameter
@@ -62,12 +63,24 @@ pub trait Editor: Send {
     /// not allocate.
     fn param_values_changed(&self);
 
-    /// Handle key presses.
+    /// Handle key presses forwarded by the VST3 and standalone wrappers. Returns whether the event
+    /// was handled. CLAP does not have a way for the host to forward key events to the plugin, so
+    /// this is never called for CLAP plugins.
     fn on_key_down(&self, keyboard_event: &keyboard_types::KeyboardEvent) -> bool;
 
-    /// Handle key releases.
+    /// Handle key releases. See [`on_key_down()`][Self::on_key_down()].
     fn on_key_up(&self,  keyboard_event: &keyboard_types::KeyboardEvent) -> bool;
 
+    /// Handle mouse wheel events sent by the host. Some hosts only deliver wheel events this way
+    /// instead of sending them to the editor's window. `delta` is the vertical scroll distance in
+    /// lines, where positive values scroll up. Returns whether the event was handled.
+    fn on_mouse_wheel(&self, delta: f32) -> bool {
+        false
+    }
+
+    /// Called when the host tells the editor that it gained or lost keyboard focus.
+    fn on_focus_changed(&self, focused: bool) {}
+
 
     // TODO: Reconsider adding a tick function here for the Linux `IRunLoop`. To keep this platform
     //       and API agnostic, add a way to ask the GuiContext if the wrapper already provides a
@@ -4,6 +4,8 @@ use midi_consts::channel_event as midi;
 
 pub use midi_consts::channel_event::control_change;
 
+pub mod learn;
+
 /// Determines which note events a plugin receives.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum MidiConfig {
@@ -289,7 +291,9 @@ pub enum NoteEvent {
         value: f32,
     },
     /// A MIDI program change event, available on [`MidiConfig::MidiCCs`] and up. VST3 plugins
-    /// cannot receive these events.
+    /// cannot receive these events. If a VST3 plugin has programs, then MIDI program changes
+    /// select one of those programs instead. See
+    /// [`Vst3Plugin::vst3_programs()`][crate::prelude::Vst3Plugin::vst3_programs()].
     MidiProgramChange {
         timing: u32,
         /// The affected channel, in `0..16`.
@@ -299,6 +303,19 @@ pub enum NoteEvent {
     },
 }
 
+/// A name for a MIDI note, returned from
+/// [`Plugin::note_names()`][crate::prelude::Plugin::note_names()]. Hosts can use these to label the
+/// keys in their piano rolls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteName {
+    /// The note's channel, in `0..16`, or `None` if the name applies to all channels.
+    pub channel: Option<u8>,
+    /// The note's MIDI key number, in `0..128`.
+    pub note: u8,
+    /// The name for the note.
+    pub name: String,
+}
+
 impl NoteEvent {
     /// Returns the sample within the current buffer this event belongs to.
     pub fn timing(&self) -> u32 {
@@ -0,0 +1,366 @@
+//! MIDI learn, for binding incoming MIDI CCs to a plugin's parameters at runtime. A parameter is
+//! armed from the editor using
+//! [`ParamSetter::begin_midi_learn()`][crate::prelude::ParamSetter::begin_midi_learn()], after which
+//! the wrapper binds the next MIDI CC it receives to that parameter. This requires
+//! [`Plugin::MIDI_INPUT`][crate::prelude::Plugin::MIDI_INPUT] to be set to
+//! [`MidiConfig::MidiCCs`][super::MidiConfig::MidiCCs].
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// The key the MIDI learn mappings are stored under in
+/// [`PluginState::fields`][crate::prelude::PluginState::fields]. Storing the mappings there instead
+/// of in a dedicated field keeps older state files loadable.
+pub const MIDI_MAPPINGS_FIELD_KEY: &str = "nih_plug:midi_mappings";
+
+/// The number of MIDI channels and CCs a mapping can be made for.
+const NUM_CHANNELS: usize = 16;
+const NUM_CCS: usize = 128;
+
+/// Stored in the atomics below when there's no parameter. Parameter hashes are 32-bit values, so
+/// this can never collide with an actual hash.
+const NO_PARAM: u64 = u64::MAX;
+
+/// A binding between a MIDI CC and a parameter, created through MIDI learn. These are persisted as
+/// part of the plugin's state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MidiMapping {
+    /// The ID of the parameter the CC is bound to.
+    pub param_id: String,
+    /// The MIDI channel, in `0..16`.
+    pub channel: u8,
+    /// The CC number, in `0..128`.
+    pub cc: u8,
+}
+
+/// The MIDI learn status of a parameter, returned from
+/// [`ParamSetter::midi_learn_status()`][crate::prelude::ParamSetter::midi_learn_status()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiLearnStatus {
+    /// The parameter is not bound to any MIDI CC.
+    Unmapped,
+    /// The parameter is armed for MIDI learn, and the next incoming MIDI CC will be bound to it.
+    Learning,
+    /// The parameter is bound to a MIDI CC.
+    Mapped { channel: u8, cc: u8 },
+}
+
+/// The wrappers' MIDI learn state. The armed parameter and the mappings are stored in atomics so
+/// CCs can be bound and looked up from the audio thread without locking or allocating.
+pub(crate) struct MidiLearn {
+    /// The hash of the parameter that's armed for MIDI learn, or [`NO_PARAM`].
+    armed: AtomicU64,
+    /// The hash of the parameter bound to every channel and CC combination, or [`NO_PARAM`].
+    /// Indexed by `channel * NUM_CCS + cc`.
+    mappings: Box<[AtomicU64]>,
+    /// The parameter IDs for every parameter hash. Used to persist the mappings using the stable
+    /// parameter IDs.
+    param_id_by_hash: HashMap<u32, String>,
+    /// Set when the wrapper has scheduled a task to tell the host about parameter values changed
+    /// through mapped CCs. Works the same way as the macro parameter host notifications.
+    host_notification_pending: AtomicBool,
+}
+
+impl MidiLearn {
+    /// Create the MIDI learn state for the parameters in a wrapper's parameter ID to hash map.
+    pub fn new(param_id_to_hash: &HashMap<String, u32>) -> Self {
+        Self {
+            armed: AtomicU64::new(NO_PARAM),
+            mappings: (0..NUM_CHANNELS * NUM_CCS)
+                .map(|_| AtomicU64::new(NO_PARAM))
+                .collect(),
+            param_id_by_hash: param_id_to_hash
+                .iter()
+                .map(|(param_id, hash)| (*hash, param_id.clone()))
+                .collect(),
+            host_notification_pending: AtomicBool::new(false),
+        }
+    }
+
+    /// Arm a parameter so the next incoming MIDI CC will be bound to it. This replaces any
+    /// previously armed parameter.
+    pub fn arm(&self, param_hash: u32) {
+        self.armed.store(param_hash as u64, Ordering::SeqCst);
+    }
+
+    /// Disarm the armed parameter, if there is one.
+    pub fn cancel(&self) {
+        self.armed.store(NO_PARAM, Ordering::SeqCst);
+    }
+
+    /// Remove the mapping for a parameter. If the parameter is currently armed, then it is also
+    /// disarmed.
+    pub fn clear(&self, param_hash: u32) {
+        let _ = self.armed.compare_exchange(
+            param_hash as u64,
+            NO_PARAM,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        );
+
+        for mapping in self.mappings.iter() {
+            let _ = mapping.compare_exchange(
+                param_hash as u64,
+                NO_PARAM,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            );
+        }
+    }
+
+    /// Get the MIDI learn status for a parameter.
+    pub fn status(&self, param_hash: u32) -> MidiLearnStatus {
+        if self.armed.load(Ordering::SeqCst) == param_hash as u64 {
+            return MidiLearnStatus::Learning;
+        }
+
+        match self
+            .mappings
+            .iter()
+            .position(|mapping| mapping.load(Ordering::SeqCst) == param_hash as u64)
+        {
+            Some(idx) => MidiLearnStatus::Mapped {
+                channel: (idx / NUM_CCS) as u8,
+                cc: (idx % NUM_CCS) as u8,
+            },
+            None => MidiLearnStatus::Unmapped,
+        }
+    }
+
+    /// If a parameter is armed, bind it to this channel and CC and disarm it. A parameter can only
+    /// be bound to a single CC, so any existing mapping for the parameter is removed. Returns the
+    /// hash of the parameter that was bound, if any. Safe to call from the audio thread.
+    pub fn learn(&self, channel: u8, cc: u8) -> Option<u32> {
+        let idx = mapping_idx(channel, cc)?;
+        let armed = self.armed.swap(NO_PARAM, Ordering::SeqCst);
+        if armed == NO_PARAM {
+            return None;
+        }
+
+        for mapping in self.mappings.iter() {
+            let _ = mapping.compare_exchange(armed, NO_PARAM, Ordering::SeqCst, Ordering::SeqCst);
+        }
+        self.mappings[idx].store(armed, Ordering::SeqCst);
+
+        Some(armed as u32)
+    }
+
+    /// Handle an incoming MIDI CC. If a parameter is armed, then it gets bound to this CC first.
+    /// Returns the hash of the parameter the CC is bound to, in which case the CC's value should be
+    /// applied to that parameter instead of passing the CC on to the plugin. Safe to call from the
+    /// audio thread.
+    pub fn handle_cc(&self, channel: u8, cc: u8) -> Option<u32> {
+        if let Some(param_hash) = self.learn(channel, cc) {
+            return Some(param_hash);
+        }
+
+        match self.mappings[mapping_idx(channel, cc)?].load(Ordering::SeqCst) {
+            NO_PARAM => None,
+            param_hash => Some(param_hash as u32),
+        }
+    }
+
+    /// Get all current mappings, sorted by channel and CC.
+    pub fn mappings(&self) -> Vec<MidiMapping> {
+        self.mappings
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, mapping)| {
+                let param_hash = match mapping.load(Ordering::SeqCst) {
+                    NO_PARAM => return None,
+                    param_hash => param_hash as u32,
+                };
+
+                Some(MidiMapping {
+                    param_id: self.param_id_by_hash.get(&param_hash)?.clone(),
+                    channel: (idx / NUM_CCS) as u8,
+                    cc: (idx % NUM_CCS) as u8,
+                })
+            })
+            .collect()
+    }
+
+    /// Replace all current mappings. Mappings for unknown parameters or out of range channels and
+    /// CCs are ignored.
+    pub fn set_mappings(&self, mappings: &[MidiMapping]) {
+        for mapping in self.mappings.iter() {
+            mapping.store(NO_PARAM, Ordering::SeqCst);
+        }
+
+        for mapping in mappings {
+            let param_hash = self
+                .param_id_by_hash
+                .iter()
+                .find(|(_, param_id)| **param_id == mapping.param_id)
+                .map(|(param_hash, _)| *param_hash);
+            match (param_hash, mapping_idx(mapping.channel, mapping.cc)) {
+                (Some(param_hash), Some(idx)) => {
+                    self.mappings[idx].store(param_hash as u64, Ordering::SeqCst)
+                }
+                _ => nih_debug_assert_failure!("Ignoring invalid MIDI mapping {:?}", mapping),
+            }
+        }
+    }
+
+    /// Serialize the current mappings so they can be stored in [`MIDI_MAPPINGS_FIELD_KEY`]. Returns
+    /// `None` if there are no mappings, in which case nothing needs to be stored.
+    pub fn serialize_mappings(&self) -> Option<Vec<u8>> {
+        let mappings = self.mappings();
+        if mappings.is_empty() {
+            return None;
+        }
+
+        match bincode::serialize(&mappings) {
+            Ok(serialized) => Some(serialized),
+            Err(err) => {
+                nih_debug_assert_failure!("Could not serialize MIDI mappings: {}", err);
+                None
+            }
+        }
+    }
+
+    /// Restore mappings previously serialized with
+    /// [`serialize_mappings()`][Self::serialize_mappings()].
+    pub fn deserialize_mappings(&self, serialized: &[u8]) {
+        match bincode::deserialize::<Vec<MidiMapping>>(serialized) {
+            Ok(mappings) => self.set_mappings(&mappings),
+            Err(err) => nih_debug_assert_failure!("Could not deserialize MIDI mappings: {}", err),
+        }
+    }
+
+    /// Should be called before scheduling a task to tell the host about parameter values changed
+    /// through mapped CCs. Returns `false` if such a task is already pending, in which case it
+    /// shouldn't be scheduled again. [`host_notified()`][Self::host_notified()] must be called after
+    /// the task has run or if it could not be scheduled.
+    pub fn begin_host_notification(&self) -> bool {
+        !self.host_notification_pending.swap(true, Ordering::SeqCst)
+    }
+
+    /// Allow [`begin_host_notification()`][Self::begin_host_notification()] to return `true`
+    /// again.
+    pub fn host_notified(&self) {
+        self.host_notification_pending
+            .store(false, Ordering::SeqCst);
+    }
+}
+
+/// The index in [`MidiLearn::mappings`] for a channel and CC, or `None` if either is out of range.
+fn mapping_idx(channel: u8, cc: u8) -> Option<usize> {
+    if (channel as usize) < NUM_CHANNELS && (cc as usize) < NUM_CCS {
+        Some(channel as usize * NUM_CCS + cc as usize)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_midi_learn() -> MidiLearn {
+        MidiLearn::new(&HashMap::from([
+            (String::from("gain"), 1),
+            (String::from("pan"), 2),
+        ]))
+    }
+
+    #[test]
+    fn learn_binds_armed_param() {
+        let midi_learn = make_midi_learn();
+        assert_eq!(midi_learn.handle_cc(0, 7), None);
+
+        midi_learn.arm(1);
+        assert_eq!(midi_learn.status(1), MidiLearnStatus::Learning);
+        assert_eq!(midi_learn.handle_cc(3, 7), Some(1));
+        assert_eq!(
+            midi_learn.status(1),
+            MidiLearnStatus::Mapped { channel: 3, cc: 7 }
+        );
+
+        // The parameter is no longer armed, and the binding is only for that channel
+        assert_eq!(midi_learn.handle_cc(3, 7), Some(1));
+        assert_eq!(midi_learn.handle_cc(0, 7), None);
+    }
+
+    #[test]
+    fn relearning_moves_mapping() {
+        let midi_learn = make_midi_learn();
+        midi_learn.arm(1);
+        midi_learn.handle_cc(0, 1);
+        midi_learn.arm(1);
+        midi_learn.handle_cc(0, 2);
+
+        assert_eq!(midi_learn.handle_cc(0, 1), None);
+        assert_eq!(midi_learn.handle_cc(0, 2), Some(1));
+
+        // Binding another parameter to the same CC replaces the old binding
+        midi_learn.arm(2);
+        midi_learn.handle_cc(0, 2);
+        assert_eq!(midi_learn.status(1), MidiLearnStatus::Unmapped);
+        assert_eq!(midi_learn.handle_cc(0, 2), Some(2));
+    }
+
+    #[test]
+    fn cancel_and_clear() {
+        let midi_learn = make_midi_learn();
+        midi_learn.arm(1);
+        midi_learn.cancel();
+        assert_eq!(midi_learn.learn(0, 1), None);
+
+        midi_learn.arm(1);
+        midi_learn.learn(0, 1);
+        midi_learn.clear(1);
+        assert_eq!(midi_learn.status(1), MidiLearnStatus::Unmapped);
+        assert_eq!(midi_learn.handle_cc(0, 1), None);
+    }
+
+    #[test]
+    fn out_of_range_ccs_are_ignored() {
+        let midi_learn = make_midi_learn();
+        midi_learn.arm(1);
+        assert_eq!(midi_learn.handle_cc(16, 1), None);
+        assert_eq!(midi_learn.handle_cc(0, 128), None);
+        assert_eq!(midi_learn.status(1), MidiLearnStatus::Learning);
+    }
+
+    #[test]
+    fn serialization_roundtrip() {
+        let midi_learn = make_midi_learn();
+        midi_learn.arm(1);
+        midi_learn.learn(2, 10);
+        midi_learn.arm(2);
+        midi_learn.learn(0, 74);
+        assert_eq!(
+            midi_learn.mappings(),
+            [
+                MidiMapping {
+                    param_id: String::from("pan"),
+                    channel: 0,
+                    cc: 74,
+                },
+                MidiMapping {
+                    param_id: String::from("gain"),
+                    channel: 2,
+                    cc: 10,
+                },
+            ]
+        );
+
+        let serialized = midi_learn.serialize_mappings().unwrap();
+        let restored = make_midi_learn();
+        restored.deserialize_mappings(&serialized);
+        assert_eq!(restored.mappings(), midi_learn.mappings());
+    }
+
+    #[test]
+    fn no_mappings_are_not_serialized() {
+        let midi_learn = make_midi_learn();
+        assert_eq!(midi_learn.serialize_mappings(), None);
+
+        midi_learn.arm(1);
+        midi_learn.learn(0, 1);
+        midi_learn.clear(1);
+        assert_eq!(midi_learn.serialize_mappings(), None);
+    }
+}
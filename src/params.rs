@@ -20,6 +20,7 @@ mod float;
 mod integer;
 
 pub mod internals;
+pub mod macros;
 pub mod persist;
 pub mod range;
 pub mod smoothing;
@@ -50,6 +51,11 @@ bitflags::bitflags! {
         /// Don't show this parameter when generating a generic UI for the plugin using one of
         /// NIH-plug's generic UI widgets.
         const HIDE_IN_GENERIC_UI = 1 << 3;
+        /// Tell the host that this discrete parameter should be displayed as a list of options,
+        /// for instance in a dropdown, instead of as a slider. The options' display strings can be
+        /// queried using [`Param::value_strings()`]. This is always set for [`EnumParam`]s, and it
+        /// has no effect on continuous parameters.
+        const LIST = 1 << 4;
     }
 }
 
@@ -171,6 +177,38 @@ pub trait Param: Display {
     /// Flags to control the parameter's behavior. See [`ParamFlags`].
     fn flags(&self) -> ParamFlags;
 
+    /// If this is a discrete parameter with the [`ParamFlags::LIST`] flag set, then this returns
+    /// the display string for every possible value in order, starting at the parameter's minimum
+    /// value. The string at index `i` corresponds to the normalized value `i / step_count`. Returns
+    /// `None` for parameters that should not be displayed as a list. This is what the wrappers
+    /// report to hosts that support value lists, and GUIs can use it to build dropdowns.
+    fn value_strings(&self, include_unit: bool) -> Option<Vec<String>> {
+        if !self.flags().contains(ParamFlags::LIST) {
+            return None;
+        }
+
+        let step_count = self.step_count()?;
+        Some(
+            (0..=step_count)
+                .map(|step| {
+                    let normalized = if step_count == 0 {
+                        0.0
+                    } else {
+                        step as f32 / step_count as f32
+                    };
+
+                    self.normalized_value_to_string(normalized, include_unit)
+                })
+                .collect(),
+        )
+    }
+
+    /// The parameters this parameter drives if it is a macro parameter. See
+    /// [`FloatParam::with_macro_targets()`]. This is empty for regular parameters.
+    fn macro_targets(&self) -> &[macros::MacroTarget] {
+        &[]
+    }
+
     /// Internal implementation detail for implementing [`Params`][Params]. This should
     /// not be used directly.
     fn as_ptr(&self) -> internals::ParamPtr;
@@ -224,13 +262,29 @@ pub(crate) trait ParamMut: Param {
 /// preset data. These fields should be [`PersistentField`][persist::PersistentField]s annotated
 /// with the `#[persist = "key"]` attribute containing types that can be serialized and deserialized
 /// with [Serde](https://serde.rs/).
+/// [`Persisted<T>`][persist::Persisted] can be used to find out when a field has been restored and to
+/// version a field's data independently of the rest of the plugin's state.
+///
+/// ## `#[persist(key = "key", skip_presets, skip_duplicates)]`
+///
+/// This works the same as `#[persist = "key"]`, but the field can opt out of being stored in some
+/// [`StateContext`][persist::StateContext]s. With `skip_presets` the field is not written to or
+/// restored from presets, which is useful for things like an editor's size. With
+/// `skip_duplicates` the field is not copied over when the host duplicates the plugin instance,
+/// which is useful for instance-specific caches. The field is always stored in the host's project.
+/// Both options are optional.
 ///
 /// ## `#[nested]`, `#[nested(group_name = "group name")]`
 ///
 /// Finally, the `Params` object may include parameters from other objects. Setting a group name is
 /// optional, but some hosts can use this information to display the parameters in a tree structure.
 /// Parameter IDs and persisting keys still need to be **unique** when using nested parameter
-/// structs.
+/// structs. Duplicate parameter IDs, including those created by the `id_prefix` and `array` options
+/// below, are detected at compile time. This only works for nested structs that also use the derive
+/// macro or that implement [`StaticParamIds`][internals::StaticParamIds] themselves. The IDs from
+/// other nested structs and from `#[nested(array)]` fields that are not fixed-size arrays (like a
+/// `Vec`) are skipped by these checks, and duplicates involving those IDs are only caught at
+/// runtime in debug builds.
 ///
 /// Take a look at the example gain example plugin to see how this is used.
 ///
@@ -251,6 +305,12 @@ pub(crate) trait ParamMut: Param {
 /// parameter will belong to the group `Foo {array_index + 1}`, and it will have the renamed
 /// parameter ID `bar_{array_index + 1}`. The same thing applies to persistent field keys.
 ///
+/// Both fixed-size arrays and `Vec`s are supported, so the number of nested parameter structs can
+/// also be decided when the `Params` object is created, for instance based on a const generic or a
+/// Cargo feature. The length of a `Vec` must not change after the plugin has been created since the
+/// wrappers build their parameter lists once. If a `Params` struct contains a `Vec` like this, then
+/// duplicate parameter IDs are only detected at runtime using a debug assertion.
+///
 /// # Safety
 ///
 /// This implementation is safe when using from the wrapper because the plugin's returned `Params`
@@ -287,6 +347,32 @@ pub unsafe trait Params: 'static + Send + Sync {
     /// [`persist::deserialize_field()`] under the hood.
     #[allow(unused_variables)]
     fn deserialize_fields(&self, serialized: &BTreeMap<String, Vec<u8>>) {}
+
+    /// The same as [`serialize_fields()`][Self::serialize_fields()], but fields that opted out of
+    /// being stored in the given [`StateContext`][persist::StateContext] are left out. The wrappers
+    /// call this function instead of `serialize_fields()`. The default implementation ignores the
+    /// context and calls `serialize_fields()`.
+    #[allow(unused_variables)]
+    fn serialize_fields_for_context(
+        &self,
+        context: persist::StateContext,
+    ) -> BTreeMap<String, Vec<u8>> {
+        self.serialize_fields()
+    }
+
+    /// The same as [`deserialize_fields()`][Self::deserialize_fields()], but fields that opted out
+    /// of being stored in the given [`StateContext`][persist::StateContext] are not restored, even
+    /// if they are present in `serialized`. The wrappers call this function instead of
+    /// `deserialize_fields()`. The default implementation ignores the context and calls
+    /// `deserialize_fields()`.
+    #[allow(unused_variables)]
+    fn deserialize_fields_for_context(
+        &self,
+        serialized: &BTreeMap<String, Vec<u8>>,
+        context: persist::StateContext,
+    ) {
+        self.deserialize_fields(serialized)
+    }
 }
 
 /// This may be useful when building generic UIs using nested `Params` objects.
@@ -302,4 +388,20 @@ unsafe impl<P: Params> Params for Arc<P> {
     fn deserialize_fields(&self, serialized: &BTreeMap<String, Vec<u8>>) {
         self.as_ref().deserialize_fields(serialized)
     }
+
+    fn serialize_fields_for_context(
+        &self,
+        context: persist::StateContext,
+    ) -> BTreeMap<String, Vec<u8>> {
+        self.as_ref().serialize_fields_for_context(context)
+    }
+
+    fn deserialize_fields_for_context(
+        &self,
+        serialized: &BTreeMap<String, Vec<u8>>,
+        context: persist::StateContext,
+    ) {
+        self.as_ref()
+            .deserialize_fields_for_context(serialized, context)
+    }
 }
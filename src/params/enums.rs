@@ -315,6 +315,7 @@ impl<T: Enum + PartialEq + 'static> EnumParam<T> {
 
         Self {
             inner: EnumParamInner {
+                // Hosts can display enums as dropdowns
                 inner: IntParam::new(
                     name,
                     T::to_index(default) as i32,
@@ -322,7 +323,8 @@ impl<T: Enum + PartialEq + 'static> EnumParam<T> {
                         min: 0,
                         max: variants.len() as i32 - 1,
                     },
-                ),
+                )
+                .show_as_list(),
                 variants,
                 ids,
             },
@@ -420,3 +422,57 @@ impl EnumParamInner {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq)]
+    enum Mode {
+        Low,
+        High,
+    }
+
+    impl Enum for Mode {
+        fn variants() -> &'static [&'static str] {
+            &["Low", "High"]
+        }
+
+        fn ids() -> Option<&'static [&'static str]> {
+            None
+        }
+
+        fn to_index(self) -> usize {
+            match self {
+                Mode::Low => 0,
+                Mode::High => 1,
+            }
+        }
+
+        fn from_index(index: usize) -> Self {
+            match index {
+                1 => Mode::High,
+                _ => Mode::Low,
+            }
+        }
+    }
+
+    #[test]
+    fn enums_are_lists() {
+        let param = EnumParam::new("Mode", Mode::Low);
+        assert!(param.flags().contains(ParamFlags::LIST));
+        assert_eq!(param.step_count(), Some(1));
+        assert_eq!(
+            param.value_strings(false),
+            Some(vec![String::from("Low"), String::from("High")])
+        );
+
+        // The type erased version used by the wrappers should behave the same way
+        let param_ptr = param.as_ptr();
+        assert!(unsafe { param_ptr.flags() }.contains(ParamFlags::LIST));
+        assert_eq!(
+            unsafe { param_ptr.value_strings(false) },
+            Some(vec![String::from("Low"), String::from("High")])
+        );
+    }
+}
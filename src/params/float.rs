@@ -6,6 +6,7 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use super::internals::ParamPtr;
+use super::macros::MacroTarget;
 use super::range::FloatRange;
 use super::smoothing::{Smoother, SmoothingStyle};
 use super::{Param, ParamFlags, ParamMut};
@@ -71,6 +72,9 @@ pub struct FloatParam {
     /// The input string may or may not contain the unit, so you will need to be able to handle
     /// that.
     string_to_value: Option<Arc<dyn Fn(&str) -> Option<f32> + Send + Sync>>,
+    /// If this parameter is a macro parameter, then these are the parameters it drives. See
+    /// [`with_macro_targets()`][Self::with_macro_targets()].
+    macro_targets: Vec<MacroTarget>,
 }
 
 impl Display for FloatParam {
@@ -182,6 +186,10 @@ impl Param for FloatParam {
         self.flags
     }
 
+    fn macro_targets(&self) -> &[MacroTarget] {
+        &self.macro_targets
+    }
+
     fn as_ptr(&self) -> ParamPtr {
         ParamPtr::FloatParam(self as *const _ as *mut _)
     }
@@ -264,6 +272,7 @@ impl FloatParam {
             poly_modulation_id: None,
             value_to_string: None,
             string_to_value: None,
+            macro_targets: Vec::new(),
         }
     }
 
@@ -364,6 +373,23 @@ impl FloatParam {
         self
     }
 
+    /// Turn this parameter into a macro parameter. Whenever this parameter's value is changed by the
+    /// host or by the plugin's editor, the wrapper will set the target parameters to the values
+    /// computed from this parameter's normalized value using the targets'
+    /// [`MacroCurve`][super::macros::MacroCurve]s. The targets' smoothers are updated the same way
+    /// they would be for regular automation, and the host is told that the targets' values have
+    /// changed (a values rescan for CLAP, and `kParamValuesChanged` for VST3). Polyphonic and
+    /// monophonic modulation of the macro parameter are not forwarded to its targets.
+    ///
+    /// Targets are referred to by their full parameter IDs. A target cannot be another macro
+    /// parameter. Consider marking the target parameters as
+    /// [`non_automatable()`][Self::non_automatable()] so the host does not record automation for
+    /// them when the macro parameter is being automated.
+    pub fn with_macro_targets(mut self, targets: Vec<MacroTarget>) -> Self {
+        self.macro_targets = targets;
+        self
+    }
+
     /// Mark the parameter as non-automatable. This means that the parameter cannot be changed from
     /// an automation lane. The parameter can however still be manually changed by the user from
     /// either the plugin's own GUI or from the host's generic UI.
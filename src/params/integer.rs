@@ -333,6 +333,16 @@ impl IntParam {
         self
     }
 
+    /// Tell the host that this parameter should be displayed as a list of options instead of as a
+    /// slider. Hosts that support this will show a dropdown containing the formatted value for
+    /// every step in the parameter's range. This is mostly useful for parameters with a small
+    /// range and a custom [`value_to_string`][Self::with_value_to_string()] function. See
+    /// [`ParamFlags::LIST`].
+    pub fn show_as_list(mut self) -> Self {
+        self.flags.insert(ParamFlags::LIST);
+        self
+    }
+
     /// Mark the parameter as non-automatable. This means that the parameter cannot be changed from
     /// an automation lane. The parameter can however still be manually changed by the user from
     /// either the plugin's own GUI or from the host's generic UI.
@@ -356,3 +366,26 @@ impl IntParam {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn int_params_are_not_lists_by_default() {
+        let param = IntParam::new("Int", 0, IntRange::Linear { min: 0, max: 2 });
+        assert!(!param.flags().contains(ParamFlags::LIST));
+        assert_eq!(param.value_strings(false), None);
+
+        let param = param.show_as_list();
+        assert!(param.flags().contains(ParamFlags::LIST));
+        assert_eq!(
+            param.value_strings(false),
+            Some(vec![
+                String::from("0"),
+                String::from("1"),
+                String::from("2")
+            ])
+        );
+    }
+}
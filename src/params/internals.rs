@@ -1,5 +1,8 @@
 //! Implementation details for the parameter management.
 
+use std::marker::PhantomData;
+
+use super::macros::MacroTarget;
 use super::{Param, ParamFlags, ParamMut};
 
 /// Internal pointers to parameters. This is an implementation detail used by the wrappers for type
@@ -72,7 +75,9 @@ impl ParamPtr {
     param_ptr_forward!(pub unsafe fn next_normalized_step(&self, from: f32, finer: bool) -> f32);
     param_ptr_forward!(pub unsafe fn normalized_value_to_string(&self, normalized: f32, include_unit: bool) -> String);
     param_ptr_forward!(pub unsafe fn string_to_normalized_value(&self, string: &str) -> Option<f32>);
+    param_ptr_forward!(pub unsafe fn value_strings(&self, include_unit: bool) -> Option<Vec<String>>);
     param_ptr_forward!(pub unsafe fn flags(&self) -> ParamFlags);
+    param_ptr_forward!(pub unsafe fn macro_targets(&self) -> &[MacroTarget]);
 
     param_ptr_forward!(pub(crate) unsafe fn set_normalized_value(&self, normalized: f32));
     param_ptr_forward!(pub(crate) unsafe fn modulate_value(&self, modulation_offset: f32));
@@ -168,3 +173,437 @@ impl ParamPtr {
         }
     }
 }
+
+/// The maximum nesting depth for `#[nested(array)]` parameter structs supported by the compile
+/// time parameter ID checks.
+const MAX_NESTED_ARRAY_DEPTH: usize = 16;
+
+/// A compile time description of the parameter IDs in a [`Params`][super::Params] object. This is
+/// implemented by the `Params` derive macro, and it's used to check for duplicate parameter IDs at
+/// compile time, including IDs coming from nested parameter structs with ID prefixes and nested
+/// arrays. This is a separate trait because [`Params`][super::Params] needs to stay object safe.
+///
+/// Implementing this is optional. If you implement [`Params`][super::Params] yourself and you use
+/// that struct in a `#[nested]` field of a derived `Params` struct without also implementing this
+/// trait, then that field's parameter IDs are skipped by the compile time checks. The same applies
+/// to `#[nested(array)]` fields that are not fixed-size arrays, like a `Vec`. The other parameter
+/// IDs of the containing struct are still checked, and duplicates involving the skipped IDs are
+/// only detected at runtime when the plugin gets loaded in debug builds.
+pub trait StaticParamIds {
+    /// The parameter IDs defined by this struct, in the same structure as
+    /// [`Params::param_map()`][super::Params::param_map()].
+    const PARAM_IDS: &'static [ParamIdEntry];
+}
+
+impl<P: StaticParamIds> StaticParamIds for std::sync::Arc<P> {
+    const PARAM_IDS: &'static [ParamIdEntry] = P::PARAM_IDS;
+}
+
+/// Used by the `Params` derive macro to get a nested struct's [`StaticParamIds::PARAM_IDS`]. If the
+/// struct does not implement [`StaticParamIds`], then the inherent constant does not apply and
+/// [`UncheckedParamIds::PARAM_IDS`] is used instead. [`UncheckedParamIds`] needs to be in scope for
+/// this to work.
+#[doc(hidden)]
+pub struct ParamIdsOf<T: ?Sized>(PhantomData<T>);
+
+impl<T: StaticParamIds + ?Sized> ParamIdsOf<T> {
+    pub const PARAM_IDS: &'static [ParamIdEntry] = T::PARAM_IDS;
+}
+
+/// The fallback for [`ParamIdsOf`] for nested structs that don't implement [`StaticParamIds`].
+#[doc(hidden)]
+pub trait UncheckedParamIds {
+    const PARAM_IDS: &'static [ParamIdEntry] = &[ParamIdEntry::Unchecked];
+}
+
+impl<T: ?Sized> UncheckedParamIds for ParamIdsOf<T> {}
+
+/// A single entry in [`StaticParamIds::PARAM_IDS`].
+#[derive(Debug)]
+pub enum ParamIdEntry {
+    /// A parameter with this ID.
+    Param(&'static str),
+    /// The parameters from a nested parameter struct.
+    Nested {
+        /// The ID prefix added to all nested parameter IDs, if any. An ID `foo` becomes
+        /// `{id_prefix}_foo`.
+        id_prefix: Option<&'static str>,
+        /// If this is a nested array, then the number of elements in the array. An ID `foo`
+        /// becomes `foo_{array_idx + 1}`.
+        array_len: Option<usize>,
+        /// The nested struct's IDs.
+        entries: &'static [ParamIdEntry],
+    },
+    /// The parameter IDs are not known at compile time. These IDs are skipped by the compile time
+    /// checks, but the other entries are still checked.
+    Unchecked,
+}
+
+/// The parameter ID hashes that are compared during the compile time checks. These are computed
+/// incrementally while walking the [`ParamIdEntry`] tree so the full IDs never need to be
+/// allocated.
+#[derive(Clone, Copy)]
+struct ParamIdHashes {
+    /// A 64-bit FNV-1a hash used as a stand-in for comparing the full parameter IDs.
+    id: u64,
+    /// The same hash the wrappers compute for parameter IDs using `hash_param_id()`, before the
+    /// final masking step. The plugin APIs identify parameters using these hashes, so these
+    /// also need to be unique.
+    rabin: u32,
+}
+
+impl ParamIdHashes {
+    const fn new() -> Self {
+        Self {
+            id: 0xcbf29ce484222325,
+            rabin: 0,
+        }
+    }
+
+    const fn push_byte(self, byte: u8) -> Self {
+        Self {
+            id: (self.id ^ byte as u64).wrapping_mul(0x100000001b3),
+            rabin: self.rabin.wrapping_mul(31).wrapping_add(byte as u32),
+        }
+    }
+
+    const fn push_str(mut self, string: &str) -> Self {
+        let bytes = string.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            self = self.push_byte(bytes[i]);
+            i += 1;
+        }
+
+        self
+    }
+
+    /// Push a decimal representation of `number`, just like `format!("{}", number)` would.
+    const fn push_number(mut self, number: usize) -> Self {
+        let mut divisor = 1;
+        while number / divisor >= 10 {
+            divisor *= 10;
+        }
+
+        while divisor > 0 {
+            self = self.push_byte(b'0' + ((number / divisor) % 10) as u8);
+            divisor /= 10;
+        }
+
+        self
+    }
+
+    /// The final `(id, hash)` pair for a parameter, with the hash masked the same way as in
+    /// `hash_param_id()`.
+    const fn finish(self) -> (u64, u32) {
+        (self.id, self.rabin & !(1 << 31))
+    }
+}
+
+/// Compute the hashes for all parameters in `entries` and write them to `hashes` starting at
+/// `num_hashes`. `array_indices` contains the one-based array indices for all enclosing nested
+/// arrays, with the outermost array first. Returns the updated `hashes` array and length.
+const fn collect_param_id_hashes<const N: usize>(
+    entries: &[ParamIdEntry],
+    prefix: ParamIdHashes,
+    array_indices: [usize; MAX_NESTED_ARRAY_DEPTH],
+    array_depth: usize,
+    mut hashes: [(u64, u32); N],
+    mut num_hashes: usize,
+) -> ([(u64, u32); N], usize) {
+    let mut i = 0;
+    while i < entries.len() {
+        match &entries[i] {
+            ParamIdEntry::Param(id) => {
+                // The suffix of the innermost array comes first, see the `Params` derive macro
+                let mut hash = prefix.push_str(id);
+                let mut depth = array_depth;
+                while depth > 0 {
+                    depth -= 1;
+                    hash = hash.push_byte(b'_').push_number(array_indices[depth]);
+                }
+
+                hashes[num_hashes] = hash.finish();
+                num_hashes += 1;
+            }
+            ParamIdEntry::Nested {
+                id_prefix,
+                array_len,
+                entries,
+            } => {
+                let prefix = match id_prefix {
+                    Some(id_prefix) => prefix.push_str(id_prefix).push_byte(b'_'),
+                    None => prefix,
+                };
+
+                match array_len {
+                    Some(array_len) => {
+                        if array_depth >= MAX_NESTED_ARRAY_DEPTH {
+                            panic!("Nested parameter arrays are nested too deeply");
+                        }
+
+                        let mut array_idx = 1;
+                        while array_idx <= *array_len {
+                            let mut array_indices = array_indices;
+                            array_indices[array_depth] = array_idx;
+
+                            let result = collect_param_id_hashes(
+                                entries,
+                                prefix,
+                                array_indices,
+                                array_depth + 1,
+                                hashes,
+                                num_hashes,
+                            );
+                            hashes = result.0;
+                            num_hashes = result.1;
+
+                            array_idx += 1;
+                        }
+                    }
+                    None => {
+                        let result = collect_param_id_hashes(
+                            entries,
+                            prefix,
+                            array_indices,
+                            array_depth,
+                            hashes,
+                            num_hashes,
+                        );
+                        hashes = result.0;
+                        num_hashes = result.1;
+                    }
+                }
+            }
+            ParamIdEntry::Unchecked => (),
+        }
+
+        i += 1;
+    }
+
+    (hashes, num_hashes)
+}
+
+/// The number of parameter IDs described by `entries` that are known at compile time. IDs from
+/// [`ParamIdEntry::Unchecked`] entries are not counted. Used together with [`check_param_ids()`]
+/// by the `Params` derive macro.
+#[doc(hidden)]
+pub const fn num_param_ids(entries: &[ParamIdEntry]) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while i < entries.len() {
+        match &entries[i] {
+            ParamIdEntry::Param(_) => count += 1,
+            ParamIdEntry::Nested {
+                array_len, entries, ..
+            } => {
+                let nested_count = num_param_ids(entries);
+                count += match array_len {
+                    Some(array_len) => nested_count * *array_len,
+                    None => nested_count,
+                };
+            }
+            ParamIdEntry::Unchecked => (),
+        }
+
+        i += 1;
+    }
+
+    count
+}
+
+/// Fail const evaluation if `entries` contains duplicate parameter IDs, or if two parameter IDs
+/// hash to the same value in the plugin wrappers. `N` must be equal to
+/// [`num_param_ids(entries)`][num_param_ids()]. IDs that are not known at compile time are
+/// skipped. Used by the `Params` derive macro.
+#[doc(hidden)]
+pub const fn check_param_ids<const N: usize>(entries: &[ParamIdEntry]) {
+    if num_param_ids(entries) != N {
+        panic!("Mismatched parameter ID count");
+    }
+
+    let (hashes, _) = collect_param_id_hashes(
+        entries,
+        ParamIdHashes::new(),
+        [0; MAX_NESTED_ARRAY_DEPTH],
+        0,
+        [(0, 0); N],
+        0,
+    );
+
+    // Duplicates are found by sorting the hashes and comparing neighbouring values, since a
+    // pairwise comparison would noticeably slow down compilation for large parameter sets
+    let mut ids = [0; N];
+    let mut rabin_hashes = [0; N];
+    let mut i = 0;
+    while i < N {
+        ids[i] = hashes[i].0;
+        rabin_hashes[i] = hashes[i].1 as u64;
+        i += 1;
+    }
+    let ids = sort_hashes(ids);
+    let rabin_hashes = sort_hashes(rabin_hashes);
+
+    let mut i = 1;
+    while i < N {
+        if ids[i - 1] == ids[i] {
+            panic!(
+                "The Params struct contains duplicate parameter IDs. Parameter IDs must also be \
+                 unique after adding the ID prefixes and array index suffixes from #[nested] \
+                 fields."
+            );
+        }
+
+        i += 1;
+    }
+
+    let mut i = 1;
+    while i < N {
+        if rabin_hashes[i - 1] == rabin_hashes[i] {
+            panic!(
+                "Two of the Params struct's parameter IDs have the same hash. Try renaming one of \
+                 the parameters."
+            );
+        }
+
+        i += 1;
+    }
+}
+
+/// Sort `values` in ascending order using heapsort. The sort functions from the standard library
+/// cannot be used in const functions.
+const fn sort_hashes<const N: usize>(mut values: [u64; N]) -> [u64; N] {
+    let mut start = N / 2;
+    let mut end = N;
+    while end > 1 {
+        if start > 0 {
+            // While building the max-heap every parent node gets sifted down once
+            start -= 1;
+        } else {
+            // Afterwards the largest remaining value is moved to the end of the unsorted range
+            end -= 1;
+            let largest = values[0];
+            values[0] = values[end];
+            values[end] = largest;
+        }
+
+        let mut root = start;
+        while 2 * root + 1 < end {
+            let mut child = 2 * root + 1;
+            if child + 1 < end && values[child] < values[child + 1] {
+                child += 1;
+            }
+
+            if values[root] < values[child] {
+                let parent = values[root];
+                values[root] = values[child];
+                values[child] = parent;
+                root = child;
+            } else {
+                break;
+            }
+        }
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort_hashes_heapsort() {
+        assert_eq!(sort_hashes([]), []);
+        assert_eq!(sort_hashes([3]), [3]);
+        assert_eq!(
+            sort_hashes([5, 1, 4, 1, 5, 9, 2, 6, 5, 3]),
+            [1, 1, 2, 3, 4, 5, 5, 5, 6, 9]
+        );
+        assert_eq!(sort_hashes([u64::MAX, 0, 7]), [0, 7, u64::MAX]);
+    }
+
+    #[test]
+    fn nested_param_ids() {
+        const INNER: &[ParamIdEntry] = &[ParamIdEntry::Param("foo"), ParamIdEntry::Param("bar")];
+        const OUTER: &[ParamIdEntry] = &[
+            ParamIdEntry::Param("foo"),
+            ParamIdEntry::Nested {
+                id_prefix: Some("inner"),
+                array_len: None,
+                entries: INNER,
+            },
+            ParamIdEntry::Nested {
+                id_prefix: None,
+                array_len: Some(3),
+                entries: INNER,
+            },
+        ];
+        const NUM_PARAM_IDS: usize = num_param_ids(OUTER);
+
+        assert_eq!(NUM_PARAM_IDS, 9);
+        check_param_ids::<NUM_PARAM_IDS>(OUTER);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate parameter IDs")]
+    fn duplicate_prefixed_param_ids() {
+        const INNER: &[ParamIdEntry] = &[ParamIdEntry::Param("bar")];
+        const OUTER: &[ParamIdEntry] = &[
+            ParamIdEntry::Param("foo_bar"),
+            ParamIdEntry::Nested {
+                id_prefix: Some("foo"),
+                array_len: None,
+                entries: INNER,
+            },
+        ];
+
+        check_param_ids::<2>(OUTER);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate parameter IDs")]
+    fn duplicate_array_param_ids() {
+        const INNER: &[ParamIdEntry] = &[ParamIdEntry::Param("bar")];
+        const OUTER: &[ParamIdEntry] = &[
+            ParamIdEntry::Param("bar_3"),
+            ParamIdEntry::Nested {
+                id_prefix: None,
+                array_len: Some(4),
+                entries: INNER,
+            },
+        ];
+
+        check_param_ids::<5>(OUTER);
+    }
+
+    #[test]
+    fn unchecked_param_ids() {
+        const OUTER: &[ParamIdEntry] = &[
+            ParamIdEntry::Param("foo"),
+            ParamIdEntry::Unchecked,
+            ParamIdEntry::Nested {
+                id_prefix: None,
+                array_len: Some(2),
+                entries: &[ParamIdEntry::Param("bar"), ParamIdEntry::Unchecked],
+            },
+        ];
+        const NUM_PARAM_IDS: usize = num_param_ids(OUTER);
+
+        assert_eq!(NUM_PARAM_IDS, 3);
+        check_param_ids::<NUM_PARAM_IDS>(OUTER);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate parameter IDs")]
+    fn duplicate_param_ids_next_to_unchecked() {
+        // Unknown IDs only skip their own entries, the rest of the struct is still checked
+        const OUTER: &[ParamIdEntry] = &[
+            ParamIdEntry::Param("foo"),
+            ParamIdEntry::Param("foo"),
+            ParamIdEntry::Unchecked,
+        ];
+
+        check_param_ids::<2>(OUTER);
+    }
+}
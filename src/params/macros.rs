@@ -0,0 +1,291 @@
+//! Macro parameters. A macro parameter is a regular host-visible [`FloatParam`][super::FloatParam]
+//! that also drives one or more other parameters from the same plugin through per-target mapping
+//! curves. See [`FloatParam::with_macro_targets()`][super::FloatParam::with_macro_targets()] for
+//! more information.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::internals::ParamPtr;
+
+/// A parameter that should be driven by a macro parameter. Created using [`MacroTarget::new()`]
+/// and passed to [`FloatParam::with_macro_targets()`][super::FloatParam::with_macro_targets()].
+#[derive(Clone)]
+pub struct MacroTarget {
+    /// The target parameter's ID. This is the parameter's full ID as it appears in the plugin's
+    /// [`Params::param_map()`][super::Params::param_map()], so if the target lives in a nested
+    /// parameter struct with an ID prefix or in a nested array then the prefix or the index suffix
+    /// needs to be included here.
+    param_id: String,
+    /// The curve used to map the macro's normalized value to the target's normalized value.
+    curve: MacroCurve,
+}
+
+/// Determines how a macro parameter's normalized `[0, 1]` value is mapped to one of its targets'
+/// normalized values. All of these mappings are clamped to the `[0, 1]` range.
+#[derive(Clone)]
+pub enum MacroCurve {
+    /// Linearly interpolate between `start` and `end` in the target's normalized range. `start` may
+    /// be larger than `end` to make the target move in the opposite direction of the macro.
+    Linear { start: f32, end: f32 },
+    /// The same as [`Linear`][Self::Linear], but the macro's normalized value is raised to the
+    /// power of `exponent` first. Values below 1.0 make the target move more quickly near the start
+    /// of the macro's range, and values above 1.0 make it move more slowly.
+    Skewed { start: f32, end: f32, exponent: f32 },
+    /// Use a custom function to map the macro's normalized value to the target's normalized value.
+    /// This function is called on the audio thread and must thus be realtime-safe.
+    Custom(Arc<dyn Fn(f32) -> f32 + Send + Sync>),
+}
+
+impl MacroTarget {
+    /// Drive the parameter with ID `param_id` using `curve`. This is the parameter's full ID as it
+    /// appears in the plugin's [`Params::param_map()`][super::Params::param_map()], so for
+    /// parameters in nested structs the ID prefix or array index suffix needs to be included.
+    pub fn new(param_id: impl Into<String>, curve: MacroCurve) -> Self {
+        Self {
+            param_id: param_id.into(),
+            curve,
+        }
+    }
+
+    /// The target parameter's ID.
+    pub fn param_id(&self) -> &str {
+        &self.param_id
+    }
+
+    /// The curve used to map the macro's value to the target's value.
+    pub fn curve(&self) -> &MacroCurve {
+        &self.curve
+    }
+}
+
+impl MacroCurve {
+    /// Map a macro parameter's normalized value to a target parameter's normalized value using this
+    /// curve.
+    pub fn map(&self, normalized: f32) -> f32 {
+        let normalized = normalized.clamp(0.0, 1.0);
+        let mapped = match self {
+            MacroCurve::Linear { start, end } => start + ((end - start) * normalized),
+            MacroCurve::Skewed {
+                start,
+                end,
+                exponent,
+            } => start + ((end - start) * normalized.powf(*exponent)),
+            MacroCurve::Custom(f) => f(normalized),
+        };
+
+        mapped.clamp(0.0, 1.0)
+    }
+}
+
+/// The macro parameters from a plugin's parameter map, resolved to pointers to the parameters they
+/// drive. This is used by the wrappers to update the targets whenever a macro parameter changes,
+/// and to make sure the host only gets told about those changes once per main thread callback.
+pub(crate) struct MacroLinks {
+    /// The targets and curves for every macro parameter.
+    targets: HashMap<ParamPtr, Vec<(ParamPtr, MacroCurve)>>,
+    /// Set when the wrapper has scheduled a task to tell the host to rescan the parameter values
+    /// after a macro parameter has changed. Automating a macro parameter would otherwise cause one
+    /// task to be scheduled for every automation event.
+    host_notification_pending: AtomicBool,
+}
+
+impl MacroLinks {
+    /// Resolve the macro targets for all parameters in a
+    /// [`Params::param_map()`][super::Params::param_map()]. Targets that don't exist or that are
+    /// themselves macro parameters are ignored with a debug assertion failure.
+    ///
+    /// # Safety
+    ///
+    /// The parameter pointers in `param_map` must be valid.
+    pub unsafe fn from_param_map(param_map: &[(String, ParamPtr, String)]) -> Self {
+        let param_by_id: HashMap<&str, ParamPtr> = param_map
+            .iter()
+            .map(|(id, ptr, _)| (id.as_str(), *ptr))
+            .collect();
+
+        let mut targets = HashMap::new();
+        for (id, ptr, _) in param_map {
+            let macro_targets = ptr.macro_targets();
+            if macro_targets.is_empty() {
+                continue;
+            }
+
+            let mut resolved_targets = Vec::with_capacity(macro_targets.len());
+            for target in macro_targets {
+                match param_by_id.get(target.param_id()) {
+                    Some(target_ptr) if target_ptr == ptr => nih_debug_assert_failure!(
+                        "Macro parameter '{}' cannot target itself, ignoring",
+                        id
+                    ),
+                    Some(target_ptr) if !target_ptr.macro_targets().is_empty() => {
+                        nih_debug_assert_failure!(
+                            "Macro parameter '{}' targets '{}' which is also a macro parameter, \
+                             macros cannot be chained",
+                            id,
+                            target.param_id()
+                        )
+                    }
+                    Some(target_ptr) => {
+                        resolved_targets.push((*target_ptr, target.curve().clone()))
+                    }
+                    None => nih_debug_assert_failure!(
+                        "Macro parameter '{}' targets unknown parameter '{}', ignoring",
+                        id,
+                        target.param_id()
+                    ),
+                }
+            }
+
+            targets.insert(*ptr, resolved_targets);
+        }
+
+        Self {
+            targets,
+            host_notification_pending: AtomicBool::new(false),
+        }
+    }
+
+    /// If `param` is a macro parameter, update its targets to match the macro's current unmodulated
+    /// value. If `sample_rate` is set, then the targets' smoothers are updated as well. Returns
+    /// `true` if `param` was a macro parameter, in which case the host should be told that the
+    /// other parameters' values have changed.
+    ///
+    /// # Safety
+    ///
+    /// The parameter pointers this object was created from must still be valid.
+    pub unsafe fn update_targets(&self, param: ParamPtr, sample_rate: Option<f32>) -> bool {
+        match self.targets.get(&param) {
+            Some(targets) => {
+                let macro_value = param.unmodulated_normalized_value();
+                for (target, curve) in targets {
+                    target.set_normalized_value(curve.map(macro_value));
+                    if let Some(sample_rate) = sample_rate {
+                        target.update_smoother(sample_rate, false);
+                    }
+                }
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Should be called before scheduling a task to tell the host about changed target values.
+    /// Returns `false` if such a task is already pending, in which case it shouldn't be scheduled
+    /// again. [`host_notified()`][Self::host_notified()] must be called after the task has run or
+    /// if it could not be scheduled.
+    pub fn begin_host_notification(&self) -> bool {
+        !self.host_notification_pending.swap(true, Ordering::SeqCst)
+    }
+
+    /// Allow [`begin_host_notification()`][Self::begin_host_notification()] to return `true`
+    /// again.
+    pub fn host_notified(&self) {
+        self.host_notification_pending
+            .store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::range::FloatRange;
+    use crate::params::{FloatParam, Param};
+
+    fn make_param_map(params: &[(&str, &FloatParam)]) -> Vec<(String, ParamPtr, String)> {
+        params
+            .iter()
+            .map(|(id, param)| (id.to_string(), param.as_ptr(), String::new()))
+            .collect()
+    }
+
+    #[test]
+    fn linear_curve() {
+        let curve = MacroCurve::Linear {
+            start: 0.2,
+            end: 0.6,
+        };
+        approx::assert_relative_eq!(curve.map(0.0), 0.2);
+        approx::assert_relative_eq!(curve.map(0.5), 0.4);
+        approx::assert_relative_eq!(curve.map(1.0), 0.6);
+    }
+
+    #[test]
+    fn inverted_linear_curve() {
+        let curve = MacroCurve::Linear {
+            start: 1.0,
+            end: 0.0,
+        };
+        approx::assert_relative_eq!(curve.map(0.25), 0.75);
+    }
+
+    #[test]
+    fn skewed_curve() {
+        let curve = MacroCurve::Skewed {
+            start: 0.0,
+            end: 1.0,
+            exponent: 2.0,
+        };
+        approx::assert_relative_eq!(curve.map(0.5), 0.25);
+    }
+
+    #[test]
+    fn custom_curve_is_clamped() {
+        let curve = MacroCurve::Custom(Arc::new(|x| x * 4.0));
+        approx::assert_relative_eq!(curve.map(0.1), 0.4);
+        approx::assert_relative_eq!(curve.map(0.5), 1.0);
+    }
+
+    #[test]
+    fn update_targets() {
+        let target_a = FloatParam::new(
+            "A",
+            0.0,
+            FloatRange::Linear {
+                min: 0.0,
+                max: 10.0,
+            },
+        );
+        let target_b = FloatParam::new("B", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 });
+        let macro_param = FloatParam::new("Macro", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+            .with_macro_targets(vec![
+                MacroTarget::new(
+                    "a",
+                    MacroCurve::Linear {
+                        start: 0.0,
+                        end: 1.0,
+                    },
+                ),
+                MacroTarget::new(
+                    "b",
+                    MacroCurve::Linear {
+                        start: 1.0,
+                        end: 0.5,
+                    },
+                ),
+            ]);
+
+        let param_map =
+            make_param_map(&[("macro", &macro_param), ("a", &target_a), ("b", &target_b)]);
+        let links = unsafe { MacroLinks::from_param_map(&param_map) };
+
+        unsafe { macro_param.as_ptr().set_normalized_value(0.5) };
+        assert!(unsafe { links.update_targets(macro_param.as_ptr(), None) });
+        approx::assert_relative_eq!(target_a.value(), 5.0);
+        approx::assert_relative_eq!(target_b.value(), 0.75);
+
+        // Regular parameters don't drive anything
+        assert!(!unsafe { links.update_targets(target_a.as_ptr(), None) });
+    }
+
+    #[test]
+    fn host_notification_deduplication() {
+        let links = unsafe { MacroLinks::from_param_map(&[]) };
+        assert!(links.begin_host_notification());
+        assert!(!links.begin_host_notification());
+        links.host_notified();
+        assert!(links.begin_host_notification());
+    }
+}
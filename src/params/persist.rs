@@ -1,6 +1,9 @@
 //! Traits and helpers for persistent fields. See the [`Params`][super::Params] trait for more
 //! information.
 
+use parking_lot::{RwLock, RwLockReadGuard};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 
 /// Re-export for use in the [`Params`][super::Params] proc-macro.
@@ -8,6 +11,27 @@ pub use bincode::deserialize as deserialize_field;
 /// Re-export for use in the [`Params`][super::Params] proc-macro.
 pub use bincode::serialize as serialize_field;
 
+/// The reason the plugin's state is being saved or loaded. Persistent fields can opt out of being
+/// stored in presets or in duplicated plugin instances using the `skip_presets` and
+/// `skip_duplicates` options on the `#[persist(...)]` attribute. See
+/// [`Params`][super::Params] for more information.
+///
+/// Only CLAP hosts supporting the `state-context` extension tell the plugin why the state is being
+/// saved. Everywhere else the state is treated as [`Project`][Self::Project] state, except for the
+/// state objects used by the plugin's own preset management through
+/// [`GuiContext`][crate::prelude::GuiContext], which are treated as
+/// [`Preset`][Self::Preset] state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateContext {
+    /// The state is saved as part of the host's project, or it is restored from one. This is also
+    /// used when the host does not specify a context.
+    Project,
+    /// The state is saved to or loaded from a preset.
+    Preset,
+    /// The state is copied to a duplicated plugin instance.
+    Duplicate,
+}
+
 /// Handles the functionality needed for persisting a non-parameter fields in a plugin's state.
 /// These types can be used with [`Params`][super::Params]' `#[persist = "..."]` attributes.
 ///
@@ -184,6 +208,193 @@ impl_persistent_atomic!(std::sync::atomic::AtomicUsize, usize);
 impl_persistent_atomic!(atomic_float::AtomicF32, f32);
 impl_persistent_atomic!(atomic_float::AtomicF64, f64);
 
+/// A typed persistent field that keeps track of when its value changes, and that stores its own
+/// data format version alongside the serialized data. This can be used as a
+/// [`PersistentField`] with the `#[persist = "key"]` attribute like any other persistent field.
+///
+/// Every change to the stored value, including restoring the plugin's state after the host loaded
+/// a preset or a project, increments the field's [revision][Self::revision()]. An editor can
+/// compare this against the last revision it has seen to find out that the value has changed.
+/// Alternatively, a callback can be set using [`with_callback()`][Self::with_callback()].
+///
+/// The field's data is versioned independently of the plugin's [`PluginState`] version. When the
+/// serialized data's version does not match the version set with
+/// [`with_version()`][Self::with_version()], then the function set with
+/// [`with_migration()`][Self::with_migration()] is used to convert the old data to a `T`. Because
+/// of that, replacing an existing persistent field with a `Persisted<T>` changes the field's
+/// serialized format, and the old data for that field cannot be restored.
+///
+/// [`PluginState`]: crate::wrapper::state::PluginState
+pub struct Persisted<T> {
+    /// The stored value.
+    value: RwLock<T>,
+    /// Incremented every time the value changes.
+    revision: AtomicU32,
+    /// The version stored alongside the serialized data.
+    version: u32,
+    /// Converts data serialized with an older (or newer) version to a `T`. Returns `None` if the
+    /// data could not be migrated, in which case the current value is kept.
+    migration: Option<Arc<dyn Fn(u32, &[u8]) -> Option<T> + Send + Sync>>,
+    /// Called with the new value after the value has changed.
+    value_changed: Option<Arc<dyn Fn(&T) + Send + Sync>>,
+}
+
+/// The serialized representation of a [`Persisted<T>`]. The `T` value is serialized separately so
+/// it can be migrated when the version does not match.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersistedData {
+    version: u32,
+    data: Vec<u8>,
+}
+
+impl<T: Default> Default for Persisted<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> Persisted<T> {
+    /// Create a new persisted field with a version of 0.
+    pub fn new(value: T) -> Self {
+        Self {
+            value: RwLock::new(value),
+            revision: AtomicU32::new(0),
+            version: 0,
+            migration: None,
+            value_changed: None,
+        }
+    }
+
+    /// Set the version stored alongside the serialized data. Increment this whenever `T`'s
+    /// serialized representation changes, and use [`with_migration()`][Self::with_migration()] to
+    /// convert the old data.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set a function that converts data serialized using a different version to a `T`. This
+    /// function receives the serialized data's version and the `T` value as serialized using
+    /// [`serialize_field()`] by that version. If the function returns `None` or if no migration
+    /// function has been set, then the current value is kept.
+    pub fn with_migration(
+        mut self,
+        migration: Arc<dyn Fn(u32, &[u8]) -> Option<T> + Send + Sync>,
+    ) -> Self {
+        self.migration = Some(migration);
+        self
+    }
+
+    /// Run a callback whenever the value changes, including when the plugin's state gets restored.
+    /// The callback is called from the thread that changed the value while the value is locked for
+    /// reading, so it should not try to modify the value.
+    pub fn with_callback(mut self, callback: Arc<dyn Fn(&T) + Send + Sync>) -> Self {
+        self.value_changed = Some(callback);
+        self
+    }
+
+    /// The number of times the value has changed. This can be compared with a previously stored
+    /// revision to check whether the value has been changed, for instance because the host
+    /// restored the plugin's state.
+    pub fn revision(&self) -> u32 {
+        self.revision.load(Ordering::SeqCst)
+    }
+
+    /// Get a copy of the current value.
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.value.read().clone()
+    }
+
+    /// Lock the value for reading.
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.value.read()
+    }
+
+    /// Replace the current value.
+    pub fn set(&self, new_value: T) {
+        *self.value.write() = new_value;
+        self.value_changed();
+    }
+
+    /// Modify the current value in place.
+    pub fn update<F: FnOnce(&mut T)>(&self, f: F) {
+        f(&mut self.value.write());
+        self.value_changed();
+    }
+
+    fn value_changed(&self) {
+        self.revision.fetch_add(1, Ordering::SeqCst);
+        if let Some(f) = &self.value_changed {
+            f(&self.value.read());
+        }
+    }
+}
+
+impl<'a, T> PersistentField<'a, PersistedData> for Persisted<T>
+where
+    T: Serialize + serde::de::DeserializeOwned + Send + Sync,
+{
+    fn set(&self, new_value: PersistedData) {
+        let value = if new_value.version == self.version {
+            match deserialize_field(&new_value.data) {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    nih_debug_assert_failure!("Could not deserialize persisted field: {}", err);
+                    None
+                }
+            }
+        } else {
+            match &self.migration {
+                Some(migration) => migration(new_value.version, &new_value.data),
+                None => {
+                    nih_debug_assert_failure!(
+                        "Persisted field data has version {} but the field has version {}, and no \
+                         migration has been set",
+                        new_value.version,
+                        self.version
+                    );
+                    None
+                }
+            }
+        };
+
+        if let Some(value) = value {
+            Persisted::set(self, value);
+        }
+    }
+
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&PersistedData) -> R,
+    {
+        // Serializing to a `Vec<u8>` can't fail for types that can be serialized in the first place
+        let data = serialize_field(&*self.value.read()).unwrap_or_default();
+
+        f(&PersistedData {
+            version: self.version,
+            data,
+        })
+    }
+}
+
+impl<'a, T> PersistentField<'a, PersistedData> for Arc<Persisted<T>>
+where
+    T: Serialize + serde::de::DeserializeOwned + Send + Sync,
+{
+    fn set(&self, new_value: PersistedData) {
+        PersistentField::set(self.as_ref(), new_value);
+    }
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&PersistedData) -> R,
+    {
+        self.as_ref().map(f)
+    }
+}
+
 /// Can be used with the `#[serde(with = "nih_plug::params::internals::serialize_atomic_cell")]`
 /// attribute to serialize `AtomicCell<T>`s.
 pub mod serialize_atomic_cell {
@@ -206,3 +417,61 @@ pub mod serialize_atomic_cell {
         T::deserialize(deserializer).map(AtomicCell::new)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T, U>(from: &Persisted<T>, to: &Persisted<U>)
+    where
+        T: Serialize + serde::de::DeserializeOwned + Send + Sync,
+        U: Serialize + serde::de::DeserializeOwned + Send + Sync,
+    {
+        let data = PersistentField::map(from, serialize_field).unwrap();
+        PersistentField::set(to, deserialize_field::<PersistedData>(&data).unwrap());
+    }
+
+    #[test]
+    fn restore_increments_revision() {
+        let from = Persisted::new(String::from("foo"));
+        let to = Persisted::new(String::new());
+        assert_eq!(to.revision(), 0);
+
+        round_trip(&from, &to);
+        assert_eq!(to.get(), "foo");
+        assert_eq!(to.revision(), 1);
+
+        to.update(|value| value.push_str("bar"));
+        assert_eq!(*to.read(), "foobar");
+        assert_eq!(to.revision(), 2);
+    }
+
+    #[test]
+    fn callback() {
+        let last_value = Arc::new(AtomicU32::new(0));
+        let field = Persisted::new(0u32).with_callback({
+            let last_value = last_value.clone();
+            Arc::new(move |value: &u32| last_value.store(*value, Ordering::SeqCst))
+        });
+
+        round_trip(&Persisted::new(42u32), &field);
+        assert_eq!(last_value.load(Ordering::SeqCst), 42);
+    }
+
+    #[test]
+    fn migration() {
+        // Version 0 stored a `u8`, version 1 stores a `u32`
+        let old = Persisted::new(12u8);
+        let new = Persisted::new(0u32)
+            .with_version(1)
+            .with_migration(Arc::new(|version, data: &[u8]| match version {
+                0 => deserialize_field::<u8>(data)
+                    .ok()
+                    .map(|value| value as u32 * 2),
+                _ => None,
+            }));
+
+        round_trip(&old, &new);
+        assert_eq!(new.get(), 24);
+    }
+}
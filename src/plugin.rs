@@ -3,10 +3,12 @@
 use std::sync::Arc;
 
 use crate::buffer::Buffer;
+use crate::context::gui::ContextMenuEntry;
 use crate::context::init::InitContext;
 use crate::context::process::ProcessContext;
+use crate::context::TrackInfo;
 use crate::editor::Editor;
-use crate::midi::MidiConfig;
+use crate::midi::{MidiConfig, NoteName};
 use crate::params::Params;
 use crate::prelude::AsyncExecutor;
 use crate::wrapper::clap::features::ClapFeature;
@@ -138,15 +140,60 @@ pub trait Plugin: Default + Send + 'static {
     /// This is an advanced feature that the vast majority of plugins won't need to implement.
     fn filter_state(state: &mut PluginState) {}
 
+    /// Names for the plugin's MIDI notes, for instance for the pads of a drum sampler. Hosts can
+    /// display these names in their piano rolls. Notes without a name don't need to be included,
+    /// and a name with a [`NoteName::channel`] of `None` applies to all channels. This is only used
+    /// when [`MIDI_INPUT`][Self::MIDI_INPUT] is set to [`MidiConfig::Basic`] or higher.
+    ///
+    /// This is called from the main thread when the host queries the note names. The wrapper caches
+    /// the result, so if the names change at runtime then you'll need to call
+    /// `notify_note_names_changed()` on the process or GUI context to make the wrapper query the
+    /// names again and to let the host know that it should refresh its note names.
+    ///
+    /// VST3 only supports note names as part of a program list. The VST3 wrapper will add a program
+    /// list for the note names if this function returns any names when the plugin is created.
+    fn note_names(&self) -> Vec<NoteName> {
+        Vec::new()
+    }
+
+    /// Called from the main thread when the host reports new information about the track the
+    /// plugin is inserted on, for instance because the user renamed the track. The current
+    /// information is also available through
+    /// [`InitContext::track_info()`][crate::prelude::InitContext::track_info()] and
+    /// [`GuiContext::track_info()`][crate::prelude::GuiContext::track_info()].
+    fn track_info_changed(&mut self, track_info: &TrackInfo) {}
+
+    /// Entries to add to the host's context menu for a parameter, or for the plugin as a whole if
+    /// `param_id` is `None`. The host shows this menu when the editor calls
+    /// [`ParamSetter::show_context_menu()`][crate::prelude::ParamSetter::show_context_menu()], and
+    /// CLAP hosts may also show it from their own parameter lists. This is called from the host's
+    /// GUI thread, which is not necessarily the thread the editor runs on.
+    fn context_menu_entries(&self, param_id: Option<&str>) -> Vec<ContextMenuEntry> {
+        Vec::new()
+    }
+
+    /// Called from the host's GUI thread when the user selects one of the entries returned from
+    /// [`context_menu_entries()`][Self::context_menu_entries()].
+    fn context_menu_action(&mut self, param_id: Option<&str>, action_id: u32) {}
+
     //
     // The following functions follow the lifetime of the plugin.
     //
 
     /// Whether the plugin supports a bus config. This only acts as a check, and the plugin
-    /// shouldn't do anything beyond returning true or false.
+    /// shouldn't do anything beyond returning true or false. The CLAP wrapper will also offer
+    /// configurations with fewer auxiliary busses than specified in
+    /// [`DEFAULT_AUX_INPUTS`][Self::DEFAULT_AUX_INPUTS] and
+    /// [`DEFAULT_AUX_OUTPUTS`][Self::DEFAULT_AUX_OUTPUTS] if the plugin accepts those. If the
+    /// supported configurations change at runtime, then the plugin can call the context's
+    /// `request_audio_ports_rescan()` method to have the wrapper query them again.
     fn accepts_bus_config(&self, config: &BusConfig) -> bool {
         config.num_input_channels == Self::DEFAULT_INPUT_CHANNELS
             && config.num_output_channels == Self::DEFAULT_OUTPUT_CHANNELS
+            && config.aux_input_busses.num_busses
+                == Self::DEFAULT_AUX_INPUTS.unwrap_or_default().num_busses
+            && config.aux_output_busses.num_busses
+                == Self::DEFAULT_AUX_OUTPUTS.unwrap_or_default().num_busses
     }
 
     /// Initialize the plugin for the given bus and buffer configurations. These configurations will
@@ -252,6 +299,22 @@ pub trait Vst3Plugin: Plugin {
     /// [`VST3_CLASS_ID`][Self::VST3_CLASS_ID`] in the correct order for the current platform so
     /// projects and presets can be shared between platforms. This should not be overridden.
     const PLATFORM_VST3_CLASS_ID: [u8; 16] = swap_vst3_uid_byte_order(Self::VST3_CLASS_ID);
+
+    /// Named programs to expose to the host through a VST3 program list. Some hosts only offer a
+    /// preset browser for plugins that have program lists. Selecting a program, either from the
+    /// host or through a MIDI program change message, loads the program's state the same way
+    /// [`GuiContext::set_state()`][crate::prelude::GuiContext::set_state()] would. This is queried
+    /// once when the plugin instance is created. The selected program is stored in the project's
+    /// state.
+    ///
+    /// MIDI program changes are only mapped to these programs by the host through VST3's MIDI
+    /// mapping interface. Program changes the plugin receives as
+    /// [`NoteEvent::MidiProgramChange`][crate::prelude::NoteEvent::MidiProgramChange] events, for
+    /// instance when it's loaded as a CLAP plugin, are not mapped to programs. The plugin needs to
+    /// handle those itself.
+    fn vst3_programs(&self) -> Vec<Vst3Program> {
+        Vec::new()
+    }
 }
 
 #[cfg(not(target_os = "windows"))]
@@ -374,6 +437,17 @@ pub enum ProcessMode {
     Offline,
 }
 
+/// A named program exposed to VST3 hosts through a program list. See
+/// [`Vst3Plugin::vst3_programs()`].
+#[derive(Debug, Clone)]
+pub struct Vst3Program {
+    /// The program's name as shown by the host.
+    pub name: String,
+    /// The state that gets loaded when the program is selected. A plugin's current state can be
+    /// obtained through [`GuiContext::get_state()`][crate::prelude::GuiContext::get_state()].
+    pub state: PluginState,
+}
+
 /// Configuration for the plugin's polyphonic modulation options, if it supports .
 pub struct PolyModulationConfig {
     /// The maximum number of voices this plugin will ever use. Call the context's
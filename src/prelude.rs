@@ -11,14 +11,18 @@ pub use crate::formatters;
 pub use crate::util;
 
 pub use crate::buffer::Buffer;
-pub use crate::context::gui::{AsyncExecutor, GuiContext, ParamSetter};
+pub use crate::context::gui::{AsyncExecutor, ContextMenuEntry, GuiContext, ParamSetter};
 pub use crate::context::init::InitContext;
 pub use crate::context::process::ProcessContext;
+pub use crate::context::{TrackColor, TrackInfo, TrackType};
 // This also includes the derive macro
 pub use crate::editor::{Editor, ParentWindowHandle};
-pub use crate::midi::{control_change, MidiConfig, NoteEvent};
+pub use crate::midi::learn::{MidiLearnStatus, MidiMapping};
+pub use crate::midi::{control_change, MidiConfig, NoteEvent, NoteName};
 pub use crate::params::enums::{Enum, EnumParam};
 pub use crate::params::internals::ParamPtr;
+pub use crate::params::macros::{MacroCurve, MacroTarget};
+pub use crate::params::persist::StateContext;
 pub use crate::params::range::{FloatRange, IntRange};
 pub use crate::params::smoothing::{Smoothable, Smoother, SmoothingStyle};
 pub use crate::params::Params;
@@ -26,6 +30,7 @@ pub use crate::params::{BoolParam, FloatParam, IntParam, Param, ParamFlags};
 pub use crate::plugin::{
     AuxiliaryBuffers, AuxiliaryIOConfig, BufferConfig, BusConfig, ClapPlugin, Plugin,
     PolyModulationConfig, PortNames, ProcessMode, ProcessStatus, TaskExecutor, Vst3Plugin,
+    Vst3Program,
 };
 pub use crate::wrapper::clap::features::ClapFeature;
 pub use crate::wrapper::state::PluginState;
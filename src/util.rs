@@ -1,9 +1,24 @@
 //! General conversion functions and utilities.
 
+pub mod delay;
+pub mod dry_wet_mixer;
+pub mod filter;
+pub mod metering;
+pub mod oversampling;
+pub mod resampling;
+pub mod spectral;
+pub mod spectrum;
 mod stft;
+pub mod voices;
 pub mod window;
 
+pub use delay::DelayLine;
+pub use dry_wet_mixer::DryWetMixer;
+pub use oversampling::Oversampler;
+pub use resampling::Resampler;
+pub use spectral::SpectralHelper;
 pub use stft::StftHelper;
+pub use voices::VoiceManager;
 
 pub const MINUS_INFINITY_DB: f32 = -100.0;
 pub const MINUS_INFINITY_GAIN: f32 = 1e-5; // 10f32.powf(MINUS_INFINITY_DB / 20)
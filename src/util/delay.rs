@@ -0,0 +1,191 @@
+//! A variable length delay line. This can be used for latency compensation, lookahead, and delay
+//! and modulation effects.
+
+/// A single channel delay line that supports both whole sample and fractional, interpolated delay
+/// times. Samples are written using [`push()`][Self::push()] or [`write()`][Self::write()], and
+/// they can then be read back at any delay up to the maximum delay time set when creating the
+/// delay line.
+///
+/// For lookahead processing, like in a limiter, the signal can be delayed by the lookahead time
+/// using [`process()`][Self::process()] while the detector looks at the undelayed signal, or at
+/// the [`peak()`][Self::peak()] of the samples that have not yet been output.
+#[derive(Debug, Clone)]
+pub struct DelayLine {
+    /// The ring buffer. This always has a power of two length so indices can be wrapped using a
+    /// bitmask.
+    buffer: Vec<f32>,
+    /// The position in `buffer` the next sample will be written to.
+    write_pos: usize,
+    /// The maximum delay in samples.
+    max_delay: usize,
+}
+
+impl DelayLine {
+    /// Create a delay line that can delay a signal by up to `max_delay_samples` samples. This
+    /// allocates, so it should not be called from the audio thread.
+    pub fn new(max_delay_samples: usize) -> Self {
+        let mut delay_line = Self {
+            buffer: Vec::new(),
+            write_pos: 0,
+            max_delay: 0,
+        };
+        delay_line.resize(max_delay_samples);
+
+        delay_line
+    }
+
+    /// Change the maximum delay time. This allocates if the delay line grows, and it always clears
+    /// the delay line.
+    pub fn resize(&mut self, max_delay_samples: usize) {
+        // The interpolated reads need two extra samples past the maximum delay
+        let len = (max_delay_samples + 3).next_power_of_two();
+        self.buffer.resize(len, 0.0);
+        self.max_delay = max_delay_samples;
+
+        self.reset();
+    }
+
+    /// The maximum delay in samples.
+    pub fn max_delay_samples(&self) -> usize {
+        self.max_delay
+    }
+
+    /// Fill the delay line with silence.
+    pub fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.write_pos = 0;
+    }
+
+    /// Write a sample to the delay line.
+    #[inline]
+    pub fn push(&mut self, sample: f32) {
+        self.buffer[self.write_pos] = sample;
+        self.write_pos = (self.write_pos + 1) & (self.buffer.len() - 1);
+    }
+
+    /// Write a block of samples to the delay line. This is equivalent to calling
+    /// [`push()`][Self::push()] for every sample in `samples`.
+    pub fn write(&mut self, samples: &[f32]) {
+        // Anything that doesn't fit in the buffer would be overwritten anyways
+        let buffer_len = self.buffer.len();
+        let samples = &samples[samples.len().saturating_sub(buffer_len)..];
+
+        let num_samples_before_wrap = samples.len().min(buffer_len - self.write_pos);
+        let (before_wrap, after_wrap) = samples.split_at(num_samples_before_wrap);
+        self.buffer[self.write_pos..self.write_pos + num_samples_before_wrap]
+            .copy_from_slice(before_wrap);
+        self.buffer[..after_wrap.len()].copy_from_slice(after_wrap);
+
+        self.write_pos = (self.write_pos + samples.len()) & (buffer_len - 1);
+    }
+
+    /// Read the sample that was written `delay` samples before the last written sample. A delay of
+    /// zero returns the last written sample. The delay is clamped to the maximum delay.
+    #[inline]
+    pub fn read(&self, delay: usize) -> f32 {
+        nih_debug_assert!(delay <= self.max_delay);
+
+        let delay = delay.min(self.max_delay);
+        self.buffer[self.write_pos.wrapping_sub(delay + 1) & (self.buffer.len() - 1)]
+    }
+
+    /// Read the signal at a fractional delay using four point Hermite interpolation. This can be
+    /// used for modulated delays and for delay times that aren't a whole number of samples. The
+    /// delay is clamped to the maximum delay.
+    #[inline]
+    pub fn read_interpolated(&self, delay: f32) -> f32 {
+        nih_debug_assert!(delay >= 0.0 && delay <= self.max_delay as f32);
+
+        let delay = delay.clamp(0.0, self.max_delay as f32);
+        let delay_samples = delay as usize;
+        let frac = delay - delay_samples as f32;
+
+        // These are ordered by increasing delay. There is no newer sample to interpolate from for
+        // delays below one sample, so the newest sample is repeated instead.
+        let y0 = self.read(delay_samples.saturating_sub(1));
+        let y1 = self.read(delay_samples);
+        let y2 = self.read_unclamped(delay_samples + 1);
+        let y3 = self.read_unclamped(delay_samples + 2);
+
+        let c1 = 0.5 * (y2 - y0);
+        let c2 = y0 - (2.5 * y1) + (2.0 * y2) - (0.5 * y3);
+        let c3 = (0.5 * (y3 - y0)) + (1.5 * (y1 - y2));
+
+        ((((c3 * frac) + c2) * frac + c1) * frac) + y1
+    }
+
+    /// Write a sample to the delay line and return the sample from `delay` samples ago. With a
+    /// delay of zero this returns the sample that was just written.
+    #[inline]
+    pub fn process(&mut self, sample: f32, delay: usize) -> f32 {
+        self.push(sample);
+        self.read(delay)
+    }
+
+    /// The absolute peak value of the last `num_samples` written samples. For a lookahead limiter
+    /// this would be the samples that have not yet been output by [`process()`][Self::process()].
+    /// This iterates over all of those samples, so keep `num_samples` reasonably small.
+    pub fn peak(&self, num_samples: usize) -> f32 {
+        nih_debug_assert!(num_samples <= self.max_delay + 1);
+
+        (0..num_samples.min(self.max_delay + 1))
+            .map(|delay| self.read(delay).abs())
+            .fold(0.0, f32::max)
+    }
+
+    /// The same as [`read()`][Self::read()], but without clamping the delay to the maximum delay.
+    /// The buffer always has room for a couple extra samples.
+    #[inline]
+    fn read_unclamped(&self, delay: usize) -> f32 {
+        self.buffer[self.write_pos.wrapping_sub(delay + 1) & (self.buffer.len() - 1)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_sample_delays() {
+        let mut delay_line = DelayLine::new(10);
+        for i in 0..20 {
+            let output = delay_line.process(i as f32, 5);
+            assert_eq!(output, (i as f32 - 5.0).max(0.0));
+        }
+
+        assert_eq!(delay_line.read(0), 19.0);
+        assert_eq!(delay_line.read(10), 9.0);
+        assert_eq!(delay_line.peak(3), 19.0);
+    }
+
+    #[test]
+    fn block_writes() {
+        let mut pushed = DelayLine::new(100);
+        let mut written = DelayLine::new(100);
+
+        let samples: Vec<f32> = (0..250).map(|i| i as f32).collect();
+        for sample in &samples {
+            pushed.push(*sample);
+        }
+        for block in samples.chunks(37) {
+            written.write(block);
+        }
+
+        for delay in 0..=100 {
+            assert_eq!(pushed.read(delay), written.read(delay));
+        }
+    }
+
+    #[test]
+    fn interpolated_delays() {
+        // Hermite interpolation reproduces a linear ramp exactly
+        let mut delay_line = DelayLine::new(16);
+        for i in 0..32 {
+            delay_line.push(i as f32);
+        }
+
+        approx::assert_relative_eq!(delay_line.read_interpolated(4.0), 27.0);
+        approx::assert_relative_eq!(delay_line.read_interpolated(4.25), 26.75, epsilon = 1e-5);
+        approx::assert_relative_eq!(delay_line.read_interpolated(10.5), 20.5, epsilon = 1e-5);
+    }
+}
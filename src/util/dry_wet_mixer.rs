@@ -0,0 +1,211 @@
+//! A latency compensated dry/wet mixer.
+
+use super::delay::DelayLine;
+use crate::buffer::Buffer;
+
+/// A dry/wet mixer with latency compensation that operates on entire buffers. The dry signal is
+/// written to the mixer at the start of the process function with
+/// [`write_dry()`][Self::write_dry()], and after processing it's mixed back in with
+/// [`mix_in_dry()`][Self::mix_in_dry()], delayed by the plugin's latency so the two signals line
+/// up.
+///
+/// Whenever the plugin calls
+/// [`ProcessContext::set_latency_samples()`][crate::prelude::ProcessContext::set_latency_samples()],
+/// it should also pass the new latency to
+/// [`set_latency_samples()`][Self::set_latency_samples()]. The mixer then crossfades from the old
+/// delay to the new delay during the next block to avoid clicks.
+#[derive(Debug)]
+pub struct DryWetMixer {
+    /// The delay lines for the latency compensation, one per channel. These can hold the maximum
+    /// latency plus the maximum block size worth of samples.
+    delay_lines: Vec<DelayLine>,
+    /// The maximum block size passed to the constructor or to [`resize()`][Self::resize()].
+    max_block_size: usize,
+    /// The maximum latency passed to the constructor or to [`resize()`][Self::resize()].
+    max_latency: usize,
+
+    /// The latency the dry signal should be delayed by.
+    latency: usize,
+    /// The latency used for the last block. If this is different from `latency`, then the next
+    /// block crossfades between the two.
+    previous_latency: usize,
+}
+
+/// The mixing style for the [`DryWetMixer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MixingStyle {
+    /// Linear crossfading. This is the right choice when the wet signal is in phase with the dry
+    /// signal, as the two will otherwise add up to more than unity gain in the middle.
+    Linear,
+    /// Equal power crossfading. The dry and wet gains are the square roots of the linear gains,
+    /// so the sum of their powers stays constant. This is the better choice when the dry and wet
+    /// signals are uncorrelated, like with reverbs and delays.
+    EqualPower,
+}
+
+impl MixingStyle {
+    /// Compute the wet and dry gains for a ratio in `[0, 1]`.
+    fn gains(self, ratio: f32) -> (f32, f32) {
+        match self {
+            MixingStyle::Linear => (ratio, 1.0 - ratio),
+            MixingStyle::EqualPower => (ratio.sqrt(), (1.0 - ratio).sqrt()),
+        }
+    }
+}
+
+impl DryWetMixer {
+    /// Set up the mixer for the given parameters. This allocates, so it should not be called from
+    /// the audio thread.
+    pub fn new(num_channels: usize, max_block_size: usize, max_latency: usize) -> Self {
+        let mut mixer = Self {
+            delay_lines: Vec::new(),
+            max_block_size: 0,
+            max_latency: 0,
+
+            latency: 0,
+            previous_latency: 0,
+        };
+        mixer.resize(num_channels, max_block_size, max_latency);
+
+        mixer
+    }
+
+    /// Resize the internal buffers to fit new parameters. This should be called from the plugin's
+    /// `initialize()` function. The latency is clamped to the new maximum latency.
+    pub fn resize(&mut self, num_channels: usize, max_block_size: usize, max_latency: usize) {
+        let max_delay = (max_block_size + max_latency).saturating_sub(1);
+
+        self.delay_lines
+            .resize_with(num_channels, || DelayLine::new(max_delay));
+        for delay_line in &mut self.delay_lines {
+            delay_line.resize(max_delay);
+        }
+        self.max_block_size = max_block_size;
+        self.max_latency = max_latency;
+
+        self.latency = self.latency.min(max_latency);
+        self.previous_latency = self.latency;
+    }
+
+    /// Clear out the buffers.
+    pub fn reset(&mut self) {
+        for delay_line in &mut self.delay_lines {
+            delay_line.reset();
+        }
+        self.previous_latency = self.latency;
+    }
+
+    /// The latency the dry signal is currently being delayed by.
+    pub fn latency_samples(&self) -> u32 {
+        self.latency as u32
+    }
+
+    /// Change the latency the dry signal is delayed by. This should be called whenever the plugin
+    /// reports a new latency to the host. The new latency is clamped to the maximum latency.
+    pub fn set_latency_samples(&mut self, samples: u32) {
+        nih_debug_assert!(samples as usize <= self.max_latency);
+
+        self.latency = (samples as usize).min(self.max_latency);
+    }
+
+    /// Write the dry signal into the buffer. This should be called at the start of the process
+    /// function.
+    pub fn write_dry(&mut self, buffer: &Buffer) {
+        nih_debug_assert_eq!(buffer.channels(), self.delay_lines.len());
+        nih_debug_assert!(buffer.len() <= self.max_block_size);
+
+        for (buffer_channel, delay_line) in buffer
+            .as_slice_immutable()
+            .iter()
+            .zip(self.delay_lines.iter_mut())
+        {
+            delay_line.write(buffer_channel);
+        }
+    }
+
+    /// Mix the dry signal into the buffer. The ratio is a `[0, 1]` value where 0 results in an
+    /// all-dry signal, and 1 results in an all-wet signal. This should be called at the end of the
+    /// process function, after [`write_dry()`][Self::write_dry()] has been called with the same
+    /// buffer at the start of the process function.
+    pub fn mix_in_dry(&mut self, buffer: &mut Buffer, ratio: f32, style: MixingStyle) {
+        nih_debug_assert_eq!(buffer.channels(), self.delay_lines.len());
+        nih_debug_assert!(buffer.len() <= self.max_block_size);
+
+        let ratio = ratio.clamp(0.0, 1.0);
+        if ratio == 1.0 {
+            self.previous_latency = self.latency;
+            return;
+        }
+        let (wet_t, dry_t) = style.gains(ratio);
+
+        // The last sample in the buffer corresponds to the last sample written to the delay line
+        let num_samples = buffer.len();
+        for (buffer_channel, delay_line) in
+            buffer.as_slice().iter_mut().zip(self.delay_lines.iter())
+        {
+            for (sample_idx, sample) in buffer_channel.iter_mut().enumerate() {
+                let delay = num_samples - 1 - sample_idx;
+                let dry_sample = if self.previous_latency == self.latency {
+                    delay_line.read(self.latency + delay)
+                } else {
+                    let t = (sample_idx + 1) as f32 / num_samples as f32;
+                    let old_dry_sample = delay_line.read(self.previous_latency + delay);
+                    let new_dry_sample = delay_line.read(self.latency + delay);
+
+                    old_dry_sample + ((new_dry_sample - old_dry_sample) * t)
+                };
+
+                *sample = (*sample * wet_t) + (dry_sample * dry_t);
+            }
+        }
+
+        self.previous_latency = self.latency;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_compensation() {
+        const LATENCY: usize = 7;
+
+        let mut mixer = DryWetMixer::new(1, 16, 32);
+        mixer.set_latency_samples(LATENCY as u32);
+        mixer.reset();
+
+        // The 'processing' delays the signal by the latency, so a 50% linear mix should result in
+        // the same delayed signal
+        let input: Vec<f32> = (0..64).map(|i| (i as f32 * 0.1).sin()).collect();
+        let mut output = Vec::new();
+        for (block_idx, block) in input.chunks(16).enumerate() {
+            let mut samples = block.to_vec();
+            let mut buffer = Buffer::default();
+            unsafe {
+                buffer.with_raw_vec(|output_slices| {
+                    *output_slices = vec![&mut samples[..]];
+                })
+            };
+
+            mixer.write_dry(&buffer);
+            for (sample_idx, sample) in buffer.as_slice()[0].iter_mut().enumerate() {
+                let input_idx = (block_idx * 16 + sample_idx).checked_sub(LATENCY);
+                *sample = input_idx.map(|idx| input[idx]).unwrap_or(0.0);
+            }
+            mixer.mix_in_dry(&mut buffer, 0.5, MixingStyle::Linear);
+
+            output.extend_from_slice(&samples);
+        }
+
+        for (expected, actual) in input.iter().zip(&output[LATENCY..]) {
+            approx::assert_relative_eq!(expected, actual, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn equal_power_gains() {
+        let (wet, dry) = MixingStyle::EqualPower.gains(0.3);
+        approx::assert_relative_eq!((wet * wet) + (dry * dry), 1.0, epsilon = 1e-6);
+    }
+}
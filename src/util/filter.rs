@@ -0,0 +1,761 @@
+//! Common IIR filters. This contains biquad filters with coefficients from the RBJ Audio EQ
+//! Cookbook, a topology-preserving transform state variable filter, and a helper for running
+//! several filters in series. All filters work on either plain `f32`s or on SIMD vectors through
+//! the [`SimdType`] trait, and their frequency responses can be evaluated for drawing them in a
+//! GUI.
+
+use std::f32::consts;
+use std::ops::{Add, Mul, Sub};
+
+#[cfg(feature = "simd")]
+use std::simd::{f32x16, f32x2, f32x4, f32x8};
+
+use super::spectral::Complex32;
+
+/// The Q value for a second order Butterworth filter. Two of these filters in series result in a
+/// fourth order Linkwitz-Riley filter.
+pub const NEUTRAL_Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Either an `f32` or some SIMD vector type of `f32`s that can be used with these filters.
+pub trait SimdType:
+    Mul<Output = Self> + Sub<Output = Self> + Add<Output = Self> + Copy + Sized
+{
+    /// Create a value where all lanes are set to `value`.
+    fn from_f32(value: f32) -> Self;
+}
+
+/// A filter that processes a single sample at a time. This is used by [`FilterCascade`].
+pub trait Filter<T: SimdType> {
+    /// Process a single sample.
+    fn process(&mut self, sample: T) -> T;
+
+    /// Reset the filter's state to zero.
+    fn reset(&mut self);
+}
+
+/// Something with a frequency response that can be evaluated, like a filter's coefficients.
+pub trait FrequencyResponse {
+    /// Evaluate the complex frequency response at `frequency` Hertz. Use `.norm()` on the result
+    /// to get the linear magnitude response and `.arg()` to get the phase response in radians.
+    fn frequency_response(&self, sample_rate: f32, frequency: f32) -> Complex32;
+
+    /// The magnitude response at `frequency` Hertz in decibels.
+    fn magnitude_response_db(&self, sample_rate: f32, frequency: f32) -> f32 {
+        super::gain_to_db(self.frequency_response(sample_rate, frequency).norm())
+    }
+}
+
+/// A simple biquad filter. The coefficients for the most common filter types can be computed using
+/// the functions on [`BiquadCoefficients`].
+///
+/// Based on <https://en.wikipedia.org/wiki/Digital_biquad_filter#Transposed_direct_forms>.
+///
+/// The type parameter T  should be either an `f32` or a SIMD type.
+#[derive(Clone, Copy, Debug)]
+pub struct Biquad<T> {
+    pub coefficients: BiquadCoefficients<T>,
+    s1: T,
+    s2: T,
+}
+
+/// The coefficients `[b0, b1, b2, a1, a2]` for [`Biquad`]. These coefficients are all
+/// prenormalized, i.e. they have been divided by `a0`.
+///
+/// The type parameter T  should be either an `f32` or a SIMD type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BiquadCoefficients<T> {
+    b0: T,
+    b1: T,
+    b2: T,
+    a1: T,
+    a2: T,
+}
+
+/// A state variable filter using the topology-preserving transform, based on Andrew Simper's
+/// [linear trapezoidal SVF](https://cytomic.com/files/dsp/SvfLinearTrapOptimised2.pdf). Unlike
+/// [`Biquad`]s, these filters behave well when their coefficients are modulated at audio rate.
+///
+/// The type parameter T  should be either an `f32` or a SIMD type.
+#[derive(Clone, Copy, Debug)]
+pub struct Svf<T> {
+    pub coefficients: SvfCoefficients<T>,
+    ic1eq: T,
+    ic2eq: T,
+}
+
+/// The coefficients for a [`Svf`]. `g` and `k` determine the filter's cutoff frequency and
+/// damping, and the `m*` coefficients determine how the high-pass, band-pass and low-pass outputs
+/// are mixed to form the filter's output.
+///
+/// The type parameter T  should be either an `f32` or a SIMD type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvfCoefficients<T> {
+    g: T,
+    k: T,
+    a1: T,
+    a2: T,
+    a3: T,
+    m0: T,
+    m1: T,
+    m2: T,
+}
+
+/// Several filters of the same type in series, for instance to create steeper slopes.
+#[derive(Clone, Copy, Debug)]
+pub struct FilterCascade<F, const N: usize> {
+    pub filters: [F; N],
+}
+
+impl<T: SimdType> Default for Biquad<T> {
+    /// Before setting constants the filter should just act as an identity function.
+    fn default() -> Self {
+        Self {
+            coefficients: BiquadCoefficients::identity(),
+            s1: T::from_f32(0.0),
+            s2: T::from_f32(0.0),
+        }
+    }
+}
+
+impl<T: SimdType> Biquad<T> {
+    /// Process a single sample.
+    #[inline]
+    pub fn process(&mut self, sample: T) -> T {
+        let result = self.coefficients.b0 * sample + self.s1;
+
+        self.s1 = self.coefficients.b1 * sample - self.coefficients.a1 * result + self.s2;
+        self.s2 = self.coefficients.b2 * sample - self.coefficients.a2 * result;
+
+        result
+    }
+
+    /// Reset the state to zero, useful after making making large, non-interpolatable changes to the
+    /// filter coefficients.
+    pub fn reset(&mut self) {
+        self.s1 = T::from_f32(0.0);
+        self.s2 = T::from_f32(0.0);
+    }
+}
+
+impl<T: SimdType> Filter<T> for Biquad<T> {
+    #[inline]
+    fn process(&mut self, sample: T) -> T {
+        Biquad::process(self, sample)
+    }
+
+    fn reset(&mut self) {
+        Biquad::reset(self)
+    }
+}
+
+impl FrequencyResponse for Biquad<f32> {
+    fn frequency_response(&self, sample_rate: f32, frequency: f32) -> Complex32 {
+        self.coefficients.frequency_response(sample_rate, frequency)
+    }
+}
+
+impl<T: SimdType> BiquadCoefficients<T> {
+    /// Convert scalar coefficients into the correct vector type.
+    pub fn from_f32s(scalar: BiquadCoefficients<f32>) -> Self {
+        Self {
+            b0: T::from_f32(scalar.b0),
+            b1: T::from_f32(scalar.b1),
+            b2: T::from_f32(scalar.b2),
+            a1: T::from_f32(scalar.a1),
+            a2: T::from_f32(scalar.a2),
+        }
+    }
+
+    /// Filter coefficients that would cause the sound to be passed through as is.
+    pub fn identity() -> Self {
+        Self::from_f32s(BiquadCoefficients {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+        })
+    }
+
+    /// Create coefficients from a biquad's transfer function's coefficients, for filters that
+    /// aren't covered by the other functions. The coefficients are normalized by dividing them by
+    /// `a0`.
+    pub fn from_coefficients(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        nih_debug_assert_ne!(a0, 0.0);
+
+        Self::from_f32s(BiquadCoefficients {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        })
+    }
+
+    /// Compute the coefficients for a low-pass filter.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn lowpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_intermediates(sample_rate, frequency, q);
+
+        let b0 = (1.0 - cos_omega0) / 2.0;
+        let b1 = 1.0 - cos_omega0;
+        let b2 = (1.0 - cos_omega0) / 2.0;
+        let a0 = 1.0 + alpha;
+        let a1 = -2.0 * cos_omega0;
+        let a2 = 1.0 - alpha;
+
+        Self::from_coefficients(b0, b1, b2, a0, a1, a2)
+    }
+
+    /// Compute the coefficients for a high-pass filter.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn highpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_intermediates(sample_rate, frequency, q);
+
+        let b0 = (1.0 + cos_omega0) / 2.0;
+        let b1 = -(1.0 + cos_omega0);
+        let b2 = (1.0 + cos_omega0) / 2.0;
+        let a0 = 1.0 + alpha;
+        let a1 = -2.0 * cos_omega0;
+        let a2 = 1.0 - alpha;
+
+        Self::from_coefficients(b0, b1, b2, a0, a1, a2)
+    }
+
+    /// Compute the coefficients for a band-pass filter with a constant 0 dB peak gain.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn bandpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_intermediates(sample_rate, frequency, q);
+
+        let b0 = alpha;
+        let b1 = 0.0;
+        let b2 = -alpha;
+        let a0 = 1.0 + alpha;
+        let a1 = -2.0 * cos_omega0;
+        let a2 = 1.0 - alpha;
+
+        Self::from_coefficients(b0, b1, b2, a0, a1, a2)
+    }
+
+    /// Compute the coefficients for a notch filter.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn notch(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_intermediates(sample_rate, frequency, q);
+
+        let b0 = 1.0;
+        let b1 = -2.0 * cos_omega0;
+        let b2 = 1.0;
+        let a0 = 1.0 + alpha;
+        let a1 = -2.0 * cos_omega0;
+        let a2 = 1.0 - alpha;
+
+        Self::from_coefficients(b0, b1, b2, a0, a1, a2)
+    }
+
+    /// Compute the coefficients for an all-pass filter.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn allpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_intermediates(sample_rate, frequency, q);
+
+        let b0 = 1.0 - alpha;
+        let b1 = -2.0 * cos_omega0;
+        let b2 = 1.0 + alpha;
+        let a0 = 1.0 + alpha;
+        let a1 = -2.0 * cos_omega0;
+        let a2 = 1.0 - alpha;
+
+        Self::from_coefficients(b0, b1, b2, a0, a1, a2)
+    }
+
+    /// Compute the coefficients for a peaking EQ filter that boosts or cuts the frequencies around
+    /// `frequency` by `gain_db` decibels.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn peaking(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_intermediates(sample_rate, frequency, q);
+        let a = 10.0f32.powf(gain_db / 40.0);
+
+        let b0 = 1.0 + (alpha * a);
+        let b1 = -2.0 * cos_omega0;
+        let b2 = 1.0 - (alpha * a);
+        let a0 = 1.0 + (alpha / a);
+        let a1 = -2.0 * cos_omega0;
+        let a2 = 1.0 - (alpha / a);
+
+        Self::from_coefficients(b0, b1, b2, a0, a1, a2)
+    }
+
+    /// Compute the coefficients for a low shelf filter that boosts or cuts the frequencies below
+    /// `frequency` by `gain_db` decibels. A `q` value of [`NEUTRAL_Q`] results in the steepest
+    /// slope without any overshoot.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn low_shelf(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_intermediates(sample_rate, frequency, q);
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let two_sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        let b0 = a * ((a + 1.0) - ((a - 1.0) * cos_omega0) + two_sqrt_a_alpha);
+        let b1 = 2.0 * a * ((a - 1.0) - ((a + 1.0) * cos_omega0));
+        let b2 = a * ((a + 1.0) - ((a - 1.0) * cos_omega0) - two_sqrt_a_alpha);
+        let a0 = (a + 1.0) + ((a - 1.0) * cos_omega0) + two_sqrt_a_alpha;
+        let a1 = -2.0 * ((a - 1.0) + ((a + 1.0) * cos_omega0));
+        let a2 = (a + 1.0) + ((a - 1.0) * cos_omega0) - two_sqrt_a_alpha;
+
+        Self::from_coefficients(b0, b1, b2, a0, a1, a2)
+    }
+
+    /// Compute the coefficients for a high shelf filter that boosts or cuts the frequencies above
+    /// `frequency` by `gain_db` decibels. A `q` value of [`NEUTRAL_Q`] results in the steepest
+    /// slope without any overshoot.
+    ///
+    /// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+    pub fn high_shelf(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let (cos_omega0, alpha) = cookbook_intermediates(sample_rate, frequency, q);
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let two_sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        let b0 = a * ((a + 1.0) + ((a - 1.0) * cos_omega0) + two_sqrt_a_alpha);
+        let b1 = -2.0 * a * ((a - 1.0) + ((a + 1.0) * cos_omega0));
+        let b2 = a * ((a + 1.0) + ((a - 1.0) * cos_omega0) - two_sqrt_a_alpha);
+        let a0 = (a + 1.0) - ((a - 1.0) * cos_omega0) + two_sqrt_a_alpha;
+        let a1 = 2.0 * ((a - 1.0) - ((a + 1.0) * cos_omega0));
+        let a2 = (a + 1.0) - ((a - 1.0) * cos_omega0) - two_sqrt_a_alpha;
+
+        Self::from_coefficients(b0, b1, b2, a0, a1, a2)
+    }
+}
+
+impl FrequencyResponse for BiquadCoefficients<f32> {
+    fn frequency_response(&self, sample_rate: f32, frequency: f32) -> Complex32 {
+        // This is just the filter's transfer function evaluated at `z = e^(j * omega)`
+        let omega = consts::TAU * (frequency / sample_rate);
+        let z1 = Complex32::from_polar(1.0, -omega);
+        let z2 = z1 * z1;
+
+        let numerator = self.b0 + (z1 * self.b1) + (z2 * self.b2);
+        let denominator = 1.0 + (z1 * self.a1) + (z2 * self.a2);
+
+        numerator / denominator
+    }
+}
+
+/// Compute the `cos(omega0)` and `alpha` values used in all of the cookbook formulas.
+fn cookbook_intermediates(sample_rate: f32, frequency: f32, q: f32) -> (f32, f32) {
+    nih_debug_assert!(sample_rate > 0.0);
+    nih_debug_assert!(frequency > 0.0);
+    nih_debug_assert!(frequency < sample_rate / 2.0);
+    nih_debug_assert!(q > 0.0);
+
+    let omega0 = consts::TAU * (frequency / sample_rate);
+    let cos_omega0 = omega0.cos();
+    let alpha = omega0.sin() / (2.0 * q);
+
+    (cos_omega0, alpha)
+}
+
+impl<T: SimdType> Default for Svf<T> {
+    /// Before setting constants the filter should just act as an identity function.
+    fn default() -> Self {
+        Self {
+            coefficients: SvfCoefficients::identity(),
+            ic1eq: T::from_f32(0.0),
+            ic2eq: T::from_f32(0.0),
+        }
+    }
+}
+
+impl<T: SimdType> Svf<T> {
+    /// Process a single sample.
+    #[inline]
+    pub fn process(&mut self, sample: T) -> T {
+        let SvfCoefficients {
+            a1,
+            a2,
+            a3,
+            m0,
+            m1,
+            m2,
+            ..
+        } = self.coefficients;
+
+        let v3 = sample - self.ic2eq;
+        let v1 = a1 * self.ic1eq + a2 * v3;
+        let v2 = self.ic2eq + a2 * self.ic1eq + a3 * v3;
+        self.ic1eq = v1 + v1 - self.ic1eq;
+        self.ic2eq = v2 + v2 - self.ic2eq;
+
+        m0 * sample + m1 * v1 + m2 * v2
+    }
+
+    /// Reset the state to zero. Unlike with [`Biquad`]s, this is usually not needed after changing
+    /// the coefficients.
+    pub fn reset(&mut self) {
+        self.ic1eq = T::from_f32(0.0);
+        self.ic2eq = T::from_f32(0.0);
+    }
+}
+
+impl<T: SimdType> Filter<T> for Svf<T> {
+    #[inline]
+    fn process(&mut self, sample: T) -> T {
+        Svf::process(self, sample)
+    }
+
+    fn reset(&mut self) {
+        Svf::reset(self)
+    }
+}
+
+impl FrequencyResponse for Svf<f32> {
+    fn frequency_response(&self, sample_rate: f32, frequency: f32) -> Complex32 {
+        self.coefficients.frequency_response(sample_rate, frequency)
+    }
+}
+
+impl<T: SimdType> SvfCoefficients<T> {
+    /// Convert scalar coefficients into the correct vector type.
+    pub fn from_f32s(scalar: SvfCoefficients<f32>) -> Self {
+        Self {
+            g: T::from_f32(scalar.g),
+            k: T::from_f32(scalar.k),
+            a1: T::from_f32(scalar.a1),
+            a2: T::from_f32(scalar.a2),
+            a3: T::from_f32(scalar.a3),
+            m0: T::from_f32(scalar.m0),
+            m1: T::from_f32(scalar.m1),
+            m2: T::from_f32(scalar.m2),
+        }
+    }
+
+    /// Filter coefficients that would cause the sound to be passed through as is.
+    pub fn identity() -> Self {
+        Self::from_g_k(0.0, 1.0, 1.0, 0.0, 0.0)
+    }
+
+    /// Compute the coefficients for a low-pass filter.
+    pub fn lowpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let g = prewarp(sample_rate, frequency, q);
+
+        Self::from_g_k(g, q.recip(), 0.0, 0.0, 1.0)
+    }
+
+    /// Compute the coefficients for a high-pass filter.
+    pub fn highpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let g = prewarp(sample_rate, frequency, q);
+        let k = q.recip();
+
+        Self::from_g_k(g, k, 1.0, -k, -1.0)
+    }
+
+    /// Compute the coefficients for a band-pass filter with a constant 0 dB peak gain.
+    pub fn bandpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let g = prewarp(sample_rate, frequency, q);
+        let k = q.recip();
+
+        Self::from_g_k(g, k, 0.0, k, 0.0)
+    }
+
+    /// Compute the coefficients for a notch filter.
+    pub fn notch(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let g = prewarp(sample_rate, frequency, q);
+        let k = q.recip();
+
+        Self::from_g_k(g, k, 1.0, -k, 0.0)
+    }
+
+    /// Compute the coefficients for an all-pass filter.
+    pub fn allpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let g = prewarp(sample_rate, frequency, q);
+        let k = q.recip();
+
+        Self::from_g_k(g, k, 1.0, -2.0 * k, 0.0)
+    }
+
+    /// Compute the coefficients for a peaking EQ filter that boosts or cuts the frequencies around
+    /// `frequency` by `gain_db` decibels.
+    pub fn peaking(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let g = prewarp(sample_rate, frequency, q);
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let k = (q * a).recip();
+
+        Self::from_g_k(g, k, 1.0, k * ((a * a) - 1.0), 0.0)
+    }
+
+    /// Compute the coefficients for a low shelf filter that boosts or cuts the frequencies below
+    /// `frequency` by `gain_db` decibels.
+    pub fn low_shelf(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let g = prewarp(sample_rate, frequency, q) / a.sqrt();
+        let k = q.recip();
+
+        Self::from_g_k(g, k, 1.0, k * (a - 1.0), (a * a) - 1.0)
+    }
+
+    /// Compute the coefficients for a high shelf filter that boosts or cuts the frequencies above
+    /// `frequency` by `gain_db` decibels.
+    pub fn high_shelf(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let g = prewarp(sample_rate, frequency, q) * a.sqrt();
+        let k = q.recip();
+
+        Self::from_g_k(g, k, a * a, k * (1.0 - a) * a, 1.0 - (a * a))
+    }
+
+    /// Compute the remaining coefficients from the prewarped cutoff `g`, the damping `k`, and the
+    /// output mixing coefficients.
+    fn from_g_k(g: f32, k: f32, m0: f32, m1: f32, m2: f32) -> Self {
+        let a1 = (1.0 + g * (g + k)).recip();
+        let a2 = g * a1;
+        let a3 = g * a2;
+
+        Self::from_f32s(SvfCoefficients {
+            g,
+            k,
+            a1,
+            a2,
+            a3,
+            m0,
+            m1,
+            m2,
+        })
+    }
+}
+
+impl FrequencyResponse for SvfCoefficients<f32> {
+    fn frequency_response(&self, sample_rate: f32, frequency: f32) -> Complex32 {
+        // The trapezoidal integration is equivalent to the bilinear transform, so this is the
+        // analog prototype's transfer function `m0 + (m1 * s + m2) / (s^2 + k * s + 1)` evaluated
+        // at the prewarped frequency, normalized to the filter's cutoff frequency
+        if self.g == 0.0 {
+            return Complex32::new(self.m0, 0.0);
+        }
+
+        let s = Complex32::new(0.0, (consts::PI * (frequency / sample_rate)).tan() / self.g);
+        let denominator = (s * s) + (s * self.k) + 1.0;
+
+        self.m0 + (((s * self.m1) + self.m2) / denominator)
+    }
+}
+
+/// Compute the SVF's `g` coefficient for a cutoff frequency.
+fn prewarp(sample_rate: f32, frequency: f32, q: f32) -> f32 {
+    nih_debug_assert!(sample_rate > 0.0);
+    nih_debug_assert!(frequency > 0.0);
+    nih_debug_assert!(frequency < sample_rate / 2.0);
+    nih_debug_assert!(q > 0.0);
+
+    (consts::PI * (frequency / sample_rate)).tan()
+}
+
+impl<F: Default, const N: usize> Default for FilterCascade<F, N> {
+    fn default() -> Self {
+        Self {
+            filters: [(); N].map(|_| F::default()),
+        }
+    }
+}
+
+impl<F, const N: usize> FilterCascade<F, N> {
+    /// Process a single sample through all filters in order.
+    #[inline]
+    pub fn process<T>(&mut self, sample: T) -> T
+    where
+        T: SimdType,
+        F: Filter<T>,
+    {
+        self.filters
+            .iter_mut()
+            .fold(sample, |sample, filter| filter.process(sample))
+    }
+
+    /// Reset all filters' states to zero.
+    pub fn reset<T>(&mut self)
+    where
+        T: SimdType,
+        F: Filter<T>,
+    {
+        for filter in &mut self.filters {
+            filter.reset();
+        }
+    }
+}
+
+impl<F: FrequencyResponse, const N: usize> FrequencyResponse for FilterCascade<F, N> {
+    fn frequency_response(&self, sample_rate: f32, frequency: f32) -> Complex32 {
+        self.filters
+            .iter()
+            .fold(Complex32::new(1.0, 0.0), |response, filter| {
+                response * filter.frequency_response(sample_rate, frequency)
+            })
+    }
+}
+
+impl SimdType for f32 {
+    #[inline(always)]
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+}
+
+#[cfg(feature = "simd")]
+macro_rules! impl_simd_type {
+    ($ty:ty) => {
+        impl SimdType for $ty {
+            #[inline(always)]
+            fn from_f32(value: f32) -> Self {
+                <$ty>::splat(value)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "simd")]
+impl_simd_type!(f32x2);
+#[cfg(feature = "simd")]
+impl_simd_type!(f32x4);
+#[cfg(feature = "simd")]
+impl_simd_type!(f32x8);
+#[cfg(feature = "simd")]
+impl_simd_type!(f32x16);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    #[test]
+    fn lowpass_response() {
+        let coefficients = BiquadCoefficients::<f32>::lowpass(SAMPLE_RATE, 1000.0, NEUTRAL_Q);
+
+        approx::assert_relative_eq!(
+            coefficients.frequency_response(SAMPLE_RATE, 0.0).norm(),
+            1.0,
+            epsilon = 1e-4
+        );
+        approx::assert_relative_eq!(
+            coefficients.magnitude_response_db(SAMPLE_RATE, 1000.0),
+            -3.01,
+            epsilon = 0.01
+        );
+        assert!(coefficients.magnitude_response_db(SAMPLE_RATE, 10000.0) < -35.0);
+    }
+
+    #[test]
+    fn from_coefficients_normalizes() {
+        assert_eq!(
+            BiquadCoefficients::<f32>::from_coefficients(2.0, 0.0, 0.0, 2.0, 0.0, 0.0),
+            BiquadCoefficients::<f32>::identity()
+        );
+        assert_eq!(
+            BiquadCoefficients::<f32>::from_coefficients(0.5, 1.0, 0.5, 4.0, -2.0, 1.0),
+            BiquadCoefficients::<f32>::from_coefficients(0.125, 0.25, 0.125, 1.0, -0.5, 0.25)
+        );
+    }
+
+    #[test]
+    fn peaking_response() {
+        let coefficients = BiquadCoefficients::<f32>::peaking(SAMPLE_RATE, 2000.0, 1.0, 6.0);
+
+        approx::assert_relative_eq!(
+            coefficients.magnitude_response_db(SAMPLE_RATE, 2000.0),
+            6.0,
+            epsilon = 1e-3
+        );
+        approx::assert_relative_eq!(
+            coefficients.magnitude_response_db(SAMPLE_RATE, 20.0),
+            0.0,
+            epsilon = 0.01
+        );
+    }
+
+    #[test]
+    fn svf_matches_biquad_response() {
+        let pairs = [
+            (
+                BiquadCoefficients::<f32>::lowpass(SAMPLE_RATE, 500.0, 2.0),
+                SvfCoefficients::<f32>::lowpass(SAMPLE_RATE, 500.0, 2.0),
+            ),
+            (
+                BiquadCoefficients::highpass(SAMPLE_RATE, 500.0, 0.5),
+                SvfCoefficients::highpass(SAMPLE_RATE, 500.0, 0.5),
+            ),
+            (
+                BiquadCoefficients::notch(SAMPLE_RATE, 3000.0, 1.0),
+                SvfCoefficients::notch(SAMPLE_RATE, 3000.0, 1.0),
+            ),
+            (
+                BiquadCoefficients::peaking(SAMPLE_RATE, 3000.0, 1.0, -9.0),
+                SvfCoefficients::peaking(SAMPLE_RATE, 3000.0, 1.0, -9.0),
+            ),
+            (
+                BiquadCoefficients::low_shelf(SAMPLE_RATE, 200.0, NEUTRAL_Q, 6.0),
+                SvfCoefficients::low_shelf(SAMPLE_RATE, 200.0, NEUTRAL_Q, 6.0),
+            ),
+            (
+                BiquadCoefficients::high_shelf(SAMPLE_RATE, 8000.0, NEUTRAL_Q, -6.0),
+                SvfCoefficients::high_shelf(SAMPLE_RATE, 8000.0, NEUTRAL_Q, -6.0),
+            ),
+        ];
+
+        for (biquad, svf) in pairs {
+            for frequency in [50.0, 200.0, 1000.0, 3000.0, 10000.0] {
+                approx::assert_relative_eq!(
+                    biquad.magnitude_response_db(SAMPLE_RATE, frequency),
+                    svf.magnitude_response_db(SAMPLE_RATE, frequency),
+                    epsilon = 0.05
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn svf_processing_matches_response() {
+        // A low-pass filter's DC gain should be 1 after the filter has settled
+        let mut filter = Svf::<f32> {
+            coefficients: SvfCoefficients::lowpass(SAMPLE_RATE, 1000.0, NEUTRAL_Q),
+            ..Svf::default()
+        };
+        let mut output = 0.0;
+        for _ in 0..4800 {
+            output = filter.process(1.0);
+        }
+        approx::assert_relative_eq!(output, 1.0, epsilon = 1e-4);
+
+        let mut filter = Svf::<f32> {
+            coefficients: SvfCoefficients::highpass(SAMPLE_RATE, 1000.0, NEUTRAL_Q),
+            ..Svf::default()
+        };
+        for _ in 0..4800 {
+            output = filter.process(1.0);
+        }
+        approx::assert_relative_eq!(output, 0.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn cascade_response() {
+        let mut cascade = FilterCascade::<Biquad<f32>, 2>::default();
+        for filter in &mut cascade.filters {
+            filter.coefficients = BiquadCoefficients::lowpass(SAMPLE_RATE, 1000.0, NEUTRAL_Q);
+        }
+
+        // Two Butterworth filters in series form a Linkwitz-Riley filter
+        approx::assert_relative_eq!(
+            cascade.magnitude_response_db(SAMPLE_RATE, 1000.0),
+            -6.02,
+            epsilon = 0.01
+        );
+
+        let mut output = 0.0;
+        for _ in 0..4800 {
+            output = cascade.process(1.0);
+        }
+        approx::assert_relative_eq!(output, 1.0, epsilon = 1e-4);
+    }
+}
@@ -0,0 +1,742 @@
+//! Level metering. This contains an attack/release envelope follower, a sliding window RMS meter, a
+//! true-peak detector, and a loudness meter that implements the momentary, short-term, and
+//! integrated loudness measurements from ITU-R BS.1770 and EBU R128.
+//!
+//! The envelope follower, RMS meter, and true-peak detector all process a single channel. Their
+//! values can be sent to an editor by storing them in an `Arc<AtomicF32>` like in the `gain_gui`
+//! examples. The [`LoudnessMeter`] publishes its readings through a shared [`LoudnessReadings`]
+//! object instead. All meters are realtime-safe after their sample rate has been set.
+
+use atomic_float::AtomicF32;
+use std::f64::consts;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use super::filter::{Biquad, BiquadCoefficients};
+use super::oversampling::History;
+use super::{window, MINUS_INFINITY_DB};
+use crate::buffer::Buffer;
+
+/// The number of taps per phase in the true-peak detector's interpolation filter.
+const TRUE_PEAK_TAPS_PER_PHASE: usize = 12;
+/// The true-peak detector's oversampling amount.
+const TRUE_PEAK_OVERSAMPLING_FACTOR: usize = 4;
+
+/// The loudness meter computes the mean square of 100 ms blocks. Four of these form a momentary
+/// loudness block, and 30 of them form a short-term loudness block.
+const LOUDNESS_SUB_BLOCK_MS: f32 = 100.0;
+const MOMENTARY_SUB_BLOCKS: usize = 4;
+const SHORT_TERM_SUB_BLOCKS: usize = 30;
+/// Gating blocks quieter than this are ignored completely for the integrated loudness.
+const ABSOLUTE_GATE_LUFS: f32 = -70.0;
+/// Gating blocks this many LU below the absolute gated loudness are ignored for the integrated
+/// loudness.
+const RELATIVE_GATE_LU: f32 = -10.0;
+/// The integrated loudness keeps track of the gating blocks using a histogram. This keeps the
+/// memory usage constant so the measurement can run indefinitely. The bins cover the loudness range
+/// between the absolute gate and this value. Louder blocks are put in the last bin.
+const HISTOGRAM_MAX_LUFS: f32 = 30.0;
+/// The width of a single histogram bin. This is the precision of the relative gate.
+const HISTOGRAM_BIN_WIDTH_LU: f32 = 0.1;
+const HISTOGRAM_NUM_BINS: usize =
+    ((HISTOGRAM_MAX_LUFS - ABSOLUTE_GATE_LUFS) / HISTOGRAM_BIN_WIDTH_LU) as usize;
+
+/// Follows a signal's envelope with separate attack and release times. Rising levels are tracked
+/// using the attack time and falling levels are tracked using the release time. Both are time
+/// constants, so the envelope reaches about 63% of a sudden change after that amount of time. An
+/// attack time of zero makes the follower respond to peaks instantly, which makes it a peak meter
+/// with a decay.
+#[derive(Debug, Clone)]
+pub struct EnvelopeFollower {
+    attack_ms: f32,
+    release_ms: f32,
+    sample_rate: f32,
+
+    attack_coefficient: f32,
+    release_coefficient: f32,
+    /// The current envelope value.
+    value: f32,
+}
+
+/// A meter that computes the root mean square of the last couple milliseconds of a signal using a
+/// sliding window.
+#[derive(Debug, Clone)]
+pub struct RmsMeter {
+    window_ms: f32,
+
+    /// The squares of the last `window.len()` samples, used as a ring buffer.
+    window: Vec<f32>,
+    window_pos: usize,
+    /// The sum of all values in `window`. This is stored as an `f64` to avoid the accumulated
+    /// rounding errors becoming noticeable when the window is long.
+    sum_of_squares: f64,
+}
+
+/// Measures a signal's true peak level as described in ITU-R BS.1770 Annex 2. The signal is
+/// oversampled by a factor four using a polyphase interpolation filter, and the detector returns the
+/// absolute peak value of the interpolated samples. This catches inter-sample peaks that a sample
+/// peak meter would miss. The detected peaks are delayed by half the interpolation filter's length,
+/// or six samples.
+#[derive(Debug, Clone)]
+pub struct TruePeakDetector {
+    /// The interpolation filter's taps for every phase, in reverse order so they line up with
+    /// `history`.
+    phases: [[f32; TRUE_PEAK_TAPS_PER_PHASE]; TRUE_PEAK_OVERSAMPLING_FACTOR],
+    history: History,
+}
+
+/// Measures the loudness of a signal according to ITU-R BS.1770 and EBU R128. The signal is
+/// K-weighted, and the weighted mean squares of all channels are combined into the momentary
+/// (400 ms), short-term (3 s), and integrated (gated, since the last reset) loudness in LUFS. The
+/// readings are updated every 100 ms.
+///
+/// The readings are also published to a [`LoudnessReadings`] object that can be shared with an
+/// editor by calling [`readings()`][Self::readings()].
+#[derive(Debug)]
+pub struct LoudnessMeter {
+    sample_rate: f32,
+
+    /// The weight for every channel. These default to 1.0, which is correct for mono and stereo.
+    /// For surround audio the surround channels should have a weight of 1.41.
+    channel_weights: Vec<f32>,
+    /// The K-weighting pre-filter and RLB high-pass filter for every channel.
+    filters: Vec<[Biquad<f32>; 2]>,
+
+    /// The length of a 100 ms sub-block in samples.
+    sub_block_len: usize,
+    /// The number of samples processed for the current sub-block.
+    sub_block_pos: usize,
+    /// The sum of the channels' weighted squared K-weighted samples for the current sub-block.
+    sub_block_sum: f64,
+    /// The mean squares of the last [`SHORT_TERM_SUB_BLOCKS`] sub-blocks, used as a ring buffer.
+    sub_blocks: [f64; SHORT_TERM_SUB_BLOCKS],
+    sub_blocks_pos: usize,
+    /// The number of sub-blocks processed since the last reset. The gating blocks for the
+    /// integrated loudness only start once there is enough audio to fill an entire block.
+    num_sub_blocks_processed: usize,
+
+    /// The number of gating blocks in every histogram bin.
+    histogram_counts: Box<[u32; HISTOGRAM_NUM_BINS]>,
+    /// The sum of the mean squares of the gating blocks in every histogram bin. This allows the
+    /// integrated loudness to be computed exactly, with only the relative gate being quantized to
+    /// the histogram bins.
+    histogram_energies: Box<[f64; HISTOGRAM_NUM_BINS]>,
+
+    momentary_lufs: f32,
+    short_term_lufs: f32,
+    integrated_lufs: f32,
+    readings: Arc<LoudnessReadings>,
+}
+
+/// The readings from a [`LoudnessMeter`], in LUFS. These can be read from any thread. Silence is
+/// reported as [`MINUS_INFINITY_DB`].
+#[derive(Debug)]
+pub struct LoudnessReadings {
+    momentary_lufs: AtomicF32,
+    short_term_lufs: AtomicF32,
+    integrated_lufs: AtomicF32,
+}
+
+impl EnvelopeFollower {
+    /// Create a new envelope follower with the specified attack and release time constants. The
+    /// sample rate needs to be set with [`set_sample_rate()`][Self::set_sample_rate()] before use.
+    pub fn new(attack_ms: f32, release_ms: f32) -> Self {
+        let mut follower = Self {
+            attack_ms,
+            release_ms,
+            sample_rate: 44100.0,
+
+            attack_coefficient: 0.0,
+            release_coefficient: 0.0,
+            value: 0.0,
+        };
+        follower.update_coefficients();
+
+        follower
+    }
+
+    /// Set the sample rate. This should be called from the plugin's `initialize()` function.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        nih_debug_assert!(sample_rate > 0.0);
+
+        self.sample_rate = sample_rate;
+        self.update_coefficients();
+    }
+
+    /// Change the attack and release times.
+    pub fn set_times(&mut self, attack_ms: f32, release_ms: f32) {
+        self.attack_ms = attack_ms;
+        self.release_ms = release_ms;
+        self.update_coefficients();
+    }
+
+    /// Reset the envelope to zero.
+    pub fn reset(&mut self) {
+        self.value = 0.0;
+    }
+
+    /// The current envelope value.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Process a sample and return the new envelope value. The sample is rectified first, so this
+    /// can be used directly on an audio signal.
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let input = sample.abs();
+        let coefficient = if input > self.value {
+            self.attack_coefficient
+        } else {
+            self.release_coefficient
+        };
+        self.value = input + ((self.value - input) * coefficient);
+
+        self.value
+    }
+
+    fn update_coefficients(&mut self) {
+        self.attack_coefficient = time_constant_coefficient(self.attack_ms, self.sample_rate);
+        self.release_coefficient = time_constant_coefficient(self.release_ms, self.sample_rate);
+    }
+}
+
+impl RmsMeter {
+    /// Create a new RMS meter that measures the last `window_ms` milliseconds. The sample rate needs
+    /// to be set with [`set_sample_rate()`][Self::set_sample_rate()] before use.
+    pub fn new(window_ms: f32) -> Self {
+        nih_debug_assert!(window_ms > 0.0);
+
+        Self {
+            window_ms,
+
+            window: vec![0.0],
+            window_pos: 0,
+            sum_of_squares: 0.0,
+        }
+    }
+
+    /// Set the sample rate. This allocates the window, so it should be called from the plugin's
+    /// `initialize()` function. This also resets the meter.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        nih_debug_assert!(sample_rate > 0.0);
+
+        let window_len = ((self.window_ms / 1000.0) * sample_rate).round().max(1.0) as usize;
+        self.window.resize(window_len, 0.0);
+        self.reset();
+    }
+
+    /// Clear the window.
+    pub fn reset(&mut self) {
+        self.window.fill(0.0);
+        self.window_pos = 0;
+        self.sum_of_squares = 0.0;
+    }
+
+    /// The current RMS value.
+    pub fn value(&self) -> f32 {
+        // Rounding errors may cause the sum to become very slightly negative
+        (self.sum_of_squares.max(0.0) / self.window.len() as f64).sqrt() as f32
+    }
+
+    /// Process a sample and return the RMS value over the window.
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let square = sample * sample;
+        self.sum_of_squares += square as f64 - self.window[self.window_pos] as f64;
+        self.window[self.window_pos] = square;
+        self.window_pos = (self.window_pos + 1) % self.window.len();
+
+        self.value()
+    }
+}
+
+impl Default for TruePeakDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TruePeakDetector {
+    /// Create a new true-peak detector. This allocates, so it should not be called from the audio
+    /// thread.
+    pub fn new() -> Self {
+        // This is a windowed sinc filter with a cutoff at the original Nyquist frequency. The
+        // first phase passes the original samples through unchanged.
+        let num_taps = TRUE_PEAK_TAPS_PER_PHASE * TRUE_PEAK_OVERSAMPLING_FACTOR;
+        let center = num_taps / 2;
+        let window = window::blackman(num_taps + 3);
+
+        let mut phases = [[0.0; TRUE_PEAK_TAPS_PER_PHASE]; TRUE_PEAK_OVERSAMPLING_FACTOR];
+        for (phase_idx, phase) in phases.iter_mut().enumerate() {
+            for (tap_idx, tap) in phase.iter_mut().rev().enumerate() {
+                let filter_idx = (tap_idx * TRUE_PEAK_OVERSAMPLING_FACTOR) + phase_idx;
+                let x = consts::PI * (filter_idx as f64 - center as f64)
+                    / TRUE_PEAK_OVERSAMPLING_FACTOR as f64;
+                let sinc = if x == 0.0 { 1.0 } else { x.sin() / x };
+
+                *tap = sinc as f32 * window[filter_idx + 1];
+            }
+
+            // Every phase should have unity gain at DC
+            let sum: f32 = phase.iter().sum();
+            for tap in phase.iter_mut() {
+                *tap /= sum;
+            }
+        }
+
+        Self {
+            phases,
+            history: History::new(TRUE_PEAK_TAPS_PER_PHASE),
+        }
+    }
+
+    /// Clear the interpolation filter's history.
+    pub fn reset(&mut self) {
+        self.history.reset();
+    }
+
+    /// Process a sample and return the absolute peak value of the corresponding interpolated
+    /// samples. Use [`util::gain_to_db()`][super::gain_to_db()] to convert this to dBTP.
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        self.history.push(sample);
+        let history = self.history.as_slice();
+
+        self.phases.iter().fold(0.0f32, |peak, phase| {
+            let interpolated: f32 = phase.iter().zip(history).map(|(tap, x)| tap * x).sum();
+            peak.max(interpolated.abs())
+        })
+    }
+}
+
+impl Default for LoudnessReadings {
+    fn default() -> Self {
+        Self {
+            momentary_lufs: AtomicF32::new(MINUS_INFINITY_DB),
+            short_term_lufs: AtomicF32::new(MINUS_INFINITY_DB),
+            integrated_lufs: AtomicF32::new(MINUS_INFINITY_DB),
+        }
+    }
+}
+
+impl LoudnessReadings {
+    /// The loudness over the last 400 milliseconds.
+    pub fn momentary_lufs(&self) -> f32 {
+        self.momentary_lufs.load(Ordering::Relaxed)
+    }
+
+    /// The loudness over the last three seconds.
+    pub fn short_term_lufs(&self) -> f32 {
+        self.short_term_lufs.load(Ordering::Relaxed)
+    }
+
+    /// The gated loudness since the meter was last reset.
+    pub fn integrated_lufs(&self) -> f32 {
+        self.integrated_lufs.load(Ordering::Relaxed)
+    }
+}
+
+impl LoudnessMeter {
+    /// Create a loudness meter for `num_channels` channels. The sample rate needs to be set with
+    /// [`set_sample_rate()`][Self::set_sample_rate()] before use. This allocates, so it should not
+    /// be called from the audio thread.
+    pub fn new(num_channels: usize) -> Self {
+        let mut meter = Self {
+            sample_rate: 48000.0,
+
+            channel_weights: vec![1.0; num_channels],
+            filters: vec![[Biquad::default(); 2]; num_channels],
+
+            sub_block_len: 1,
+            sub_block_pos: 0,
+            sub_block_sum: 0.0,
+            sub_blocks: [0.0; SHORT_TERM_SUB_BLOCKS],
+            sub_blocks_pos: 0,
+            num_sub_blocks_processed: 0,
+
+            histogram_counts: Box::new([0; HISTOGRAM_NUM_BINS]),
+            histogram_energies: Box::new([0.0; HISTOGRAM_NUM_BINS]),
+
+            momentary_lufs: MINUS_INFINITY_DB,
+            short_term_lufs: MINUS_INFINITY_DB,
+            integrated_lufs: MINUS_INFINITY_DB,
+            readings: Arc::new(LoudnessReadings::default()),
+        };
+        meter.set_sample_rate(48000.0);
+
+        meter
+    }
+
+    /// Set the sample rate. This should be called from the plugin's `initialize()` function. This
+    /// also resets the meter.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        nih_debug_assert!(sample_rate > 0.0);
+
+        self.sample_rate = sample_rate;
+        self.sub_block_len = ((LOUDNESS_SUB_BLOCK_MS / 1000.0) * sample_rate)
+            .round()
+            .max(1.0) as usize;
+
+        let coefficients = k_weighting_coefficients(sample_rate);
+        for filters in &mut self.filters {
+            filters[0].coefficients = coefficients[0];
+            filters[1].coefficients = coefficients[1];
+        }
+
+        self.reset();
+    }
+
+    /// Change a channel's weight. This is 1.0 by default. The left and right surround channels in a
+    /// 5.1 layout should have a weight of 1.41, and the LFE channel should have a weight of 0.0.
+    pub fn set_channel_weight(&mut self, channel_idx: usize, weight: f32) {
+        self.channel_weights[channel_idx] = weight;
+    }
+
+    /// Reset all measurements, including the integrated loudness.
+    pub fn reset(&mut self) {
+        for filters in &mut self.filters {
+            filters[0].reset();
+            filters[1].reset();
+        }
+
+        self.sub_block_pos = 0;
+        self.sub_block_sum = 0.0;
+        self.sub_blocks.fill(0.0);
+        self.sub_blocks_pos = 0;
+        self.num_sub_blocks_processed = 0;
+        self.histogram_counts.fill(0);
+        self.histogram_energies.fill(0.0);
+
+        self.momentary_lufs = MINUS_INFINITY_DB;
+        self.short_term_lufs = MINUS_INFINITY_DB;
+        self.integrated_lufs = MINUS_INFINITY_DB;
+        self.publish_readings();
+    }
+
+    /// Get a reference to the shared readings object. This can be passed to the editor.
+    pub fn readings(&self) -> Arc<LoudnessReadings> {
+        self.readings.clone()
+    }
+
+    /// The loudness over the last 400 milliseconds.
+    pub fn momentary_lufs(&self) -> f32 {
+        self.momentary_lufs
+    }
+
+    /// The loudness over the last three seconds.
+    pub fn short_term_lufs(&self) -> f32 {
+        self.short_term_lufs
+    }
+
+    /// The gated loudness since the meter was last reset.
+    pub fn integrated_lufs(&self) -> f32 {
+        self.integrated_lufs
+    }
+
+    /// Measure the audio in `buffer`. The buffer is not modified.
+    pub fn process(&mut self, buffer: &Buffer) {
+        nih_debug_assert_eq!(buffer.channels(), self.filters.len());
+
+        let channels = buffer.as_slice_immutable();
+        for sample_idx in 0..buffer.len() {
+            let mut sum = 0.0;
+            for ((channel, filters), weight) in channels
+                .iter()
+                .zip(self.filters.iter_mut())
+                .zip(self.channel_weights.iter())
+            {
+                let [pre_filter, rlb_filter] = filters;
+                let filtered = rlb_filter.process(pre_filter.process(channel[sample_idx]));
+                sum += weight * filtered * filtered;
+            }
+
+            self.sub_block_sum += sum as f64;
+            self.sub_block_pos += 1;
+            if self.sub_block_pos == self.sub_block_len {
+                self.finish_sub_block();
+            }
+        }
+    }
+
+    /// Update the readings after a 100 ms sub-block has been processed.
+    fn finish_sub_block(&mut self) {
+        self.sub_blocks[self.sub_blocks_pos] = self.sub_block_sum / self.sub_block_len as f64;
+        self.sub_blocks_pos = (self.sub_blocks_pos + 1) % SHORT_TERM_SUB_BLOCKS;
+        self.num_sub_blocks_processed += 1;
+        self.sub_block_pos = 0;
+        self.sub_block_sum = 0.0;
+
+        let momentary_energy = (0..MOMENTARY_SUB_BLOCKS)
+            .map(|offset| {
+                self.sub_blocks[(self.sub_blocks_pos + SHORT_TERM_SUB_BLOCKS - 1 - offset)
+                    % SHORT_TERM_SUB_BLOCKS]
+            })
+            .sum::<f64>()
+            / MOMENTARY_SUB_BLOCKS as f64;
+        let short_term_energy = self.sub_blocks.iter().sum::<f64>() / SHORT_TERM_SUB_BLOCKS as f64;
+        self.momentary_lufs = energy_to_lufs(momentary_energy);
+        self.short_term_lufs = energy_to_lufs(short_term_energy);
+
+        // The momentary blocks overlap by 75%, and they're also used as the gating blocks
+        if self.num_sub_blocks_processed >= MOMENTARY_SUB_BLOCKS
+            && self.momentary_lufs >= ABSOLUTE_GATE_LUFS
+        {
+            let bin_idx = (((self.momentary_lufs - ABSOLUTE_GATE_LUFS) / HISTOGRAM_BIN_WIDTH_LU)
+                as usize)
+                .min(HISTOGRAM_NUM_BINS - 1);
+            self.histogram_counts[bin_idx] += 1;
+            self.histogram_energies[bin_idx] += momentary_energy;
+
+            self.integrated_lufs = self.compute_integrated_lufs();
+        }
+
+        self.publish_readings();
+    }
+
+    /// Compute the integrated loudness from the gating block histogram.
+    fn compute_integrated_lufs(&self) -> f32 {
+        let gated_mean = |first_bin_idx: usize| {
+            let count: u64 = self.histogram_counts[first_bin_idx..]
+                .iter()
+                .map(|&count| count as u64)
+                .sum();
+            let energy: f64 = self.histogram_energies[first_bin_idx..].iter().sum();
+
+            if count > 0 {
+                energy / count as f64
+            } else {
+                0.0
+            }
+        };
+
+        // All blocks in the histogram have already passed the absolute gate
+        let relative_gate = energy_to_lufs(gated_mean(0)) + RELATIVE_GATE_LU;
+        let relative_gate_bin_idx = ((relative_gate - ABSOLUTE_GATE_LUFS) / HISTOGRAM_BIN_WIDTH_LU)
+            .max(0.0)
+            .round() as usize;
+
+        energy_to_lufs(gated_mean(
+            relative_gate_bin_idx.min(HISTOGRAM_NUM_BINS - 1),
+        ))
+    }
+
+    fn publish_readings(&self) {
+        self.readings
+            .momentary_lufs
+            .store(self.momentary_lufs, Ordering::Relaxed);
+        self.readings
+            .short_term_lufs
+            .store(self.short_term_lufs, Ordering::Relaxed);
+        self.readings
+            .integrated_lufs
+            .store(self.integrated_lufs, Ordering::Relaxed);
+    }
+}
+
+/// Compute the one pole filter coefficient for a time constant. Zero results in a coefficient of
+/// zero, i.e. an instant response.
+fn time_constant_coefficient(time_ms: f32, sample_rate: f32) -> f32 {
+    if time_ms > 0.0 {
+        (-1.0 / ((time_ms / 1000.0) * sample_rate)).exp()
+    } else {
+        0.0
+    }
+}
+
+/// Convert a (weighted) mean square to LUFS, treating silence as [`MINUS_INFINITY_DB`].
+fn energy_to_lufs(energy: f64) -> f32 {
+    if energy > 0.0 {
+        ((-0.691 + (10.0 * energy.log10())) as f32).max(MINUS_INFINITY_DB)
+    } else {
+        MINUS_INFINITY_DB
+    }
+}
+
+/// Compute the coefficients for the K-weighting pre-filter (a high shelf modelling the head's
+/// acoustic effects) and the RLB high-pass filter for any sample rate. At 48 kHz these match the
+/// coefficients from ITU-R BS.1770. Based on the formulas used by libebur128.
+fn k_weighting_coefficients(sample_rate: f32) -> [BiquadCoefficients<f32>; 2] {
+    let sample_rate = sample_rate as f64;
+
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (consts::PI * f0 / sample_rate).tan();
+    let vh = 10.0f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let pre_filter = BiquadCoefficients::from_coefficients(
+        (vh + (vb * k / q) + (k * k)) as f32,
+        (2.0 * ((k * k) - vh)) as f32,
+        (vh - (vb * k / q) + (k * k)) as f32,
+        (1.0 + (k / q) + (k * k)) as f32,
+        (2.0 * ((k * k) - 1.0)) as f32,
+        (1.0 - (k / q) + (k * k)) as f32,
+    );
+
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (consts::PI * f0 / sample_rate).tan();
+    let a0 = 1.0 + (k / q) + (k * k);
+    let rlb_filter = BiquadCoefficients::from_coefficients(
+        1.0,
+        -2.0,
+        1.0,
+        1.0,
+        (2.0 * ((k * k) - 1.0) / a0) as f32,
+        ((1.0 - (k / q) + (k * k)) / a0) as f32,
+    );
+
+    [pre_filter, rlb_filter]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::db_to_gain;
+    use crate::util::filter::FrequencyResponse;
+
+    fn sine(frequency: f32, sample_rate: f32, phase: f32, num_samples: usize) -> Vec<f32> {
+        (0..num_samples)
+            .map(|i| ((i as f32 * std::f32::consts::TAU * frequency / sample_rate) + phase).sin())
+            .collect()
+    }
+
+    fn process_stereo(meter: &mut LoudnessMeter, samples: &[f32]) {
+        let mut left = samples.to_vec();
+        let mut right = samples.to_vec();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.with_raw_vec(|output_slices| {
+                *output_slices = vec![&mut left[..], &mut right[..]];
+            })
+        };
+
+        meter.process(&buffer);
+    }
+
+    #[test]
+    fn k_weighting_48k_reference() {
+        // These are the coefficients from ITU-R BS.1770-4, table 1 and 2
+        let [pre_filter, rlb_filter] = k_weighting_coefficients(48000.0);
+        let expected_pre_filter = BiquadCoefficients::from_coefficients(
+            1.53512485958697,
+            -2.69169618940638,
+            1.19839281085285,
+            1.0,
+            -1.69065929318241,
+            0.73248077421585,
+        );
+        let expected_rlb_filter = BiquadCoefficients::from_coefficients(
+            1.0,
+            -2.0,
+            1.0,
+            1.0,
+            -1.99004745483398,
+            0.99007225036621,
+        );
+
+        for frequency in [20.0, 100.0, 1000.0, 10000.0] {
+            approx::assert_relative_eq!(
+                pre_filter.magnitude_response_db(48000.0, frequency),
+                expected_pre_filter.magnitude_response_db(48000.0, frequency),
+                epsilon = 1e-3
+            );
+            approx::assert_relative_eq!(
+                rlb_filter.magnitude_response_db(48000.0, frequency),
+                expected_rlb_filter.magnitude_response_db(48000.0, frequency),
+                epsilon = 1e-3
+            );
+        }
+    }
+
+    #[test]
+    fn loudness_stereo_sine() {
+        // EBU Tech 3341 test case 1: a stereo 1 kHz sine wave at -23 dBFS should measure -23 LUFS
+        let mut meter = LoudnessMeter::new(2);
+        meter.set_sample_rate(48000.0);
+
+        let signal: Vec<f32> = sine(1000.0, 48000.0, 0.0, 48000 * 5)
+            .into_iter()
+            .map(|sample| sample * db_to_gain(-23.0))
+            .collect();
+        process_stereo(&mut meter, &signal);
+
+        approx::assert_relative_eq!(meter.momentary_lufs(), -23.0, epsilon = 0.1);
+        approx::assert_relative_eq!(meter.short_term_lufs(), -23.0, epsilon = 0.1);
+        approx::assert_relative_eq!(meter.integrated_lufs(), -23.0, epsilon = 0.1);
+        approx::assert_relative_eq!(meter.readings().integrated_lufs(), -23.0, epsilon = 0.1);
+    }
+
+    #[test]
+    fn loudness_relative_gate() {
+        // EBU Tech 3341 test case 3, with shorter durations: the quiet parts are 13 LU below the
+        // loud part, so they should not count towards the integrated loudness
+        let mut meter = LoudnessMeter::new(2);
+        meter.set_sample_rate(48000.0);
+
+        let quiet: Vec<f32> = sine(1000.0, 48000.0, 0.0, 48000 * 2)
+            .into_iter()
+            .map(|sample| sample * db_to_gain(-36.0))
+            .collect();
+        let loud: Vec<f32> = sine(1000.0, 48000.0, 0.0, 48000 * 20)
+            .into_iter()
+            .map(|sample| sample * db_to_gain(-23.0))
+            .collect();
+        process_stereo(&mut meter, &quiet);
+        process_stereo(&mut meter, &loud);
+        process_stereo(&mut meter, &quiet);
+
+        approx::assert_relative_eq!(meter.integrated_lufs(), -23.0, epsilon = 0.1);
+
+        meter.reset();
+        assert_eq!(meter.integrated_lufs(), MINUS_INFINITY_DB);
+    }
+
+    #[test]
+    fn true_peak_inter_sample() {
+        // This sine wave at a quarter of the sample rate only hits +-0.707 at the sample positions
+        let signal = sine(12000.0, 48000.0, std::f32::consts::FRAC_PI_4, 256);
+        let sample_peak = signal.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+        approx::assert_relative_eq!(sample_peak, std::f32::consts::FRAC_1_SQRT_2, epsilon = 1e-4);
+
+        let mut detector = TruePeakDetector::new();
+        let true_peak = signal
+            .iter()
+            .map(|sample| detector.process(*sample))
+            .fold(0.0f32, f32::max);
+        approx::assert_relative_eq!(true_peak, 1.0, epsilon = 0.02);
+    }
+
+    #[test]
+    fn rms_sine() {
+        let mut meter = RmsMeter::new(100.0);
+        meter.set_sample_rate(48000.0);
+
+        let mut rms = 0.0;
+        for sample in sine(1000.0, 48000.0, 0.0, 48000) {
+            rms = meter.process(sample);
+        }
+        approx::assert_relative_eq!(rms, std::f32::consts::FRAC_1_SQRT_2, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn envelope_follower_time_constants() {
+        let mut follower = EnvelopeFollower::new(10.0, 100.0);
+        follower.set_sample_rate(1000.0);
+
+        let mut value = 0.0;
+        for _ in 0..10 {
+            value = follower.process(-1.0);
+        }
+        approx::assert_relative_eq!(value, 1.0 - (-1.0f32).exp(), epsilon = 1e-4);
+
+        follower.set_times(0.0, 100.0);
+        follower.reset();
+        assert_eq!(follower.process(0.5), 0.5);
+        for _ in 0..100 {
+            value = follower.process(0.0);
+        }
+        approx::assert_relative_eq!(value, 0.5 * (-1.0f32).exp(), epsilon = 1e-4);
+    }
+}
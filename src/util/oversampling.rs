@@ -0,0 +1,759 @@
+//! Oversampling using cascaded polyphase half-band filters. Each stage doubles the sample rate, so
+//! the [`Oversampler`] supports 2x, 4x, 8x, and 16x oversampling.
+
+use std::f64::consts;
+
+use crate::buffer::Buffer;
+
+use super::window;
+
+/// The largest supported oversampling factor, as a power of two.
+const MAX_FACTOR_LOG2: usize = 4;
+
+/// For the FIR filters, the number of non-zero side taps divided by two for every stage. The first
+/// stage needs the steepest filter since the input signal may contain content all the way up to the
+/// Nyquist frequency. The later stages only need to filter out the images of the already band
+/// limited signal, so they can use much shorter filters. A stage with `K` in this array has
+/// `4 * K - 1` taps and a latency of `2 * K - 1` samples at the stage's input sample rate.
+const FIR_HALF_SIDE_TAPS: [usize; MAX_FACTOR_LOG2] = [16, 8, 5, 4];
+
+/// For the IIR filters, the number of all-pass coefficients and the normalized transition
+/// bandwidth for every stage. These are used to design the elliptic half-band filters.
+const IIR_PARAMETERS: [(usize, f64); MAX_FACTOR_LOG2] = [(12, 0.04), (6, 0.2), (4, 0.3), (4, 0.3)];
+
+/// The kind of half-band filters used by the [`Oversampler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OversamplingFilter {
+    /// Linear phase polyphase FIR filters. These don't alter the signal's phase, but they introduce
+    /// a couple dozen samples of latency. This latency is reported exactly by
+    /// [`Oversampler::latency_samples()`].
+    Fir,
+    /// Minimum phase polyphase IIR filters built from two chains of all-pass filters. These have
+    /// very little latency and they are cheaper to compute than the FIR filters, but they do
+    /// introduce phase shifts near the Nyquist frequency. Since the group delay depends on the
+    /// frequency, [`Oversampler::latency_samples()`] reports the rounded group delay at DC.
+    Iir,
+}
+
+/// Up- and downsamples audio so it can be processed at a higher sample rate, for instance to reduce
+/// aliasing in nonlinear processes like distortion. Every channel is upsampled, passed to a
+/// processing function at the higher sample rate, and then downsampled back to the original sample
+/// rate.
+///
+/// The oversampler should be created with the plugin, and
+/// [`initialize()`][Self::initialize()] should be called from the plugin's `initialize()` function.
+/// After that point all of the oversampler's functions are realtime-safe. The resulting latency
+/// should be reported to the host using
+/// [`ProcessContext::set_latency_samples()`][crate::prelude::ProcessContext::set_latency_samples()].
+/// Don't forget to also compensate any smoothers for the oversampling factor using
+/// [`Smoother::for_oversampling_factor()`][crate::prelude::Smoother::for_oversampling_factor()].
+#[derive(Debug)]
+pub struct Oversampler {
+    filter: OversamplingFilter,
+    /// The current oversampling factor, as a power of two. This is also the number of active
+    /// stages.
+    factor_log2: usize,
+    /// The maximum oversampling factor set during construction, as a power of two.
+    max_factor_log2: usize,
+
+    /// The filter states for every channel.
+    channels: Vec<ChannelState>,
+    /// The latency of every stage's up- and downsampling filters combined, in samples at the stage's
+    /// output sample rate.
+    stage_latencies: Vec<f32>,
+    /// The number of samples at the oversampled rate the oversampled signal needs to be delayed by
+    /// to round the latency up to a whole number of samples at the original sample rate.
+    compensation_delay: usize,
+    /// The latency at the original sample rate, computed when changing the oversampling factor.
+    latency: u32,
+
+    /// The largest block that will be processed at a time. Larger buffers are split up into blocks
+    /// of this size.
+    max_block_size: usize,
+    /// Scratch buffers for every stage's output when upsampling. These are sized for the maximum
+    /// oversampling factor and the maximum block size in [`initialize()`][Self::initialize()].
+    scratch_buffers: Vec<Vec<f32>>,
+}
+
+/// The up- and downsampling filters for a single channel.
+#[derive(Debug, Clone)]
+struct ChannelState {
+    stages: Vec<Stage>,
+    /// Used to delay the oversampled signal by `compensation_delay` samples.
+    compensation_buffer: Vec<f32>,
+    compensation_pos: usize,
+}
+
+/// A single 2x up- and downsampling stage.
+#[derive(Debug, Clone)]
+enum Stage {
+    Fir(FirStage),
+    Iir(IirStage),
+}
+
+/// A linear phase half-band FIR filter with `4 * K - 1` taps. Half of the taps in a half-band filter
+/// are zero and the center tap is 0.5, so only the `2 * K` remaining taps need to be stored. The
+/// polyphase implementation only computes the output samples that aren't discarded or trivially
+/// zero.
+#[derive(Debug, Clone)]
+struct FirStage {
+    /// The `2 * K` non-zero taps, excluding the center tap. These are symmetrical.
+    coefficients: Vec<f32>,
+    /// The last `2 * K` input samples for the upsampling filter.
+    upsampling_history: History,
+    /// The last `2 * K` even input samples for the downsampling filter.
+    downsampling_even_history: History,
+    /// The last `K + 1` odd input samples for the downsampling filter.
+    downsampling_odd_history: History,
+}
+
+/// A half-band IIR filter consisting of two parallel chains of first order all-pass filters, based
+/// on Laurent de Soras' [HIIR](http://ldesoras.free.fr/prod.html#src_hiir) library.
+#[derive(Debug, Clone)]
+struct IirStage {
+    upsampling_chains: [AllpassChain; 2],
+    downsampling_chains: [AllpassChain; 2],
+}
+
+/// A series of first order all-pass filters at the stage's lower sample rate.
+#[derive(Debug, Clone)]
+struct AllpassChain {
+    coefficients: Vec<f32>,
+    /// The previous input and output sample for each all-pass filter.
+    states: Vec<(f32, f32)>,
+}
+
+/// A delay line for a filter's input history. Samples are stored twice so the last `len` samples can
+/// always be read as a single contiguous slice. This is also used by the resampler.
+#[derive(Debug, Clone)]
+pub(super) struct History {
+    samples: Vec<f32>,
+    pos: usize,
+}
+
+impl Oversampler {
+    /// Create a new oversampler for `num_channels` channels with oversampling factors up to
+    /// `max_factor`, which needs to be 1, 2, 4, 8, or 16. The oversampling factor is initially set
+    /// to `max_factor`. This allocates, so it should not be called from the audio thread.
+    /// [`initialize()`][Self::initialize()] needs to be called before processing audio.
+    pub fn new(num_channels: usize, max_factor: usize, filter: OversamplingFilter) -> Self {
+        let max_factor_log2 = factor_to_log2(max_factor);
+
+        let stages: Vec<Stage> = (0..max_factor_log2)
+            .map(|stage_idx| match filter {
+                OversamplingFilter::Fir => Stage::Fir(FirStage::new(FIR_HALF_SIDE_TAPS[stage_idx])),
+                OversamplingFilter::Iir => {
+                    let (num_coefficients, transition) = IIR_PARAMETERS[stage_idx];
+                    Stage::Iir(IirStage::new(num_coefficients, transition))
+                }
+            })
+            .collect();
+        let stage_latencies = stages.iter().map(Stage::latency).collect();
+
+        let mut oversampler = Self {
+            filter,
+            factor_log2: max_factor_log2,
+            max_factor_log2,
+
+            channels: vec![
+                ChannelState {
+                    stages,
+                    compensation_buffer: vec![0.0; 1 << max_factor_log2],
+                    compensation_pos: 0,
+                };
+                num_channels
+            ],
+            stage_latencies,
+            compensation_delay: 0,
+            latency: 0,
+
+            max_block_size: 0,
+            scratch_buffers: Vec::new(),
+        };
+        oversampler.update_latency();
+
+        oversampler
+    }
+
+    /// Allocate the scratch buffers for processing blocks of up to `max_block_size` samples and
+    /// reset the filters. Larger buffers will still be processed correctly, they will just be split
+    /// up into smaller blocks. This should be called from the plugin's `initialize()` function.
+    pub fn initialize(&mut self, max_block_size: usize) {
+        nih_debug_assert_ne!(max_block_size, 0);
+
+        self.max_block_size = max_block_size.max(1);
+        self.scratch_buffers = (1..=self.max_factor_log2)
+            .map(|stage_factor_log2| vec![0.0; self.max_block_size << stage_factor_log2])
+            .collect();
+
+        self.reset();
+    }
+
+    /// Reset the filters' states. Call this from the plugin's `reset()` function.
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            for stage in &mut channel.stages {
+                stage.reset();
+            }
+            channel.compensation_buffer.fill(0.0);
+            channel.compensation_pos = 0;
+        }
+    }
+
+    /// The kind of filters used by this oversampler.
+    pub fn filter(&self) -> OversamplingFilter {
+        self.filter
+    }
+
+    /// The current oversampling factor.
+    pub fn factor(&self) -> usize {
+        1 << self.factor_log2
+    }
+
+    /// The maximum oversampling factor this oversampler was created with.
+    pub fn max_factor(&self) -> usize {
+        1 << self.max_factor_log2
+    }
+
+    /// Change the oversampling factor. `factor` needs to be 1, 2, 4, 8, or 16, and it cannot exceed
+    /// the maximum factor passed to [`new()`][Self::new()]. This resets the filters if the factor
+    /// changes. The new latency should be reported to the host afterwards. This function is
+    /// realtime-safe.
+    pub fn set_factor(&mut self, factor: usize) {
+        let factor_log2 = factor_to_log2(factor).min(self.max_factor_log2);
+        nih_debug_assert!(factor <= self.max_factor());
+
+        if factor_log2 != self.factor_log2 {
+            self.factor_log2 = factor_log2;
+            self.update_latency();
+            self.reset();
+        }
+    }
+
+    /// The latency introduced by the oversampling filters at the current oversampling factor, in
+    /// samples at the original sample rate. This is exact for [`OversamplingFilter::Fir`], and it's
+    /// the rounded group delay at DC for [`OversamplingFilter::Iir`].
+    pub fn latency_samples(&self) -> u32 {
+        self.latency
+    }
+
+    /// Upsample every channel in `buffer`, call `f` with the channel index and the upsampled
+    /// samples, and downsample the result back into `buffer`. The upsampled slice will contain
+    /// [`factor()`][Self::factor()] times as many samples as the block being processed. Buffers
+    /// larger than the maximum block size passed to [`initialize()`][Self::initialize()] are
+    /// processed in smaller blocks, and within every block `f` is called once for every channel.
+    ///
+    /// If the oversampler has not yet been initialized, or if the buffer contains more channels
+    /// than the oversampler was created for, then the buffer is passed through as is and `f` is
+    /// not called.
+    pub fn process<F>(&mut self, buffer: &mut Buffer, mut f: F)
+    where
+        F: FnMut(usize, &mut [f32]),
+    {
+        if self.max_block_size == 0 {
+            nih_debug_assert_failure!("The oversampler has not yet been initialized");
+            return;
+        }
+        if buffer.channels() > self.channels.len() {
+            nih_debug_assert_failure!(
+                "The buffer contains {} channels, but the oversampler only supports {}",
+                buffer.channels(),
+                self.channels.len()
+            );
+            return;
+        }
+
+        let num_samples = buffer.len();
+        let mut block_start = 0;
+        while block_start < num_samples {
+            let block_end = (block_start + self.max_block_size).min(num_samples);
+            for (channel_idx, channel) in buffer.as_slice().iter_mut().enumerate() {
+                self.process_block(channel_idx, &mut channel[block_start..block_end], &mut f);
+            }
+
+            block_start = block_end;
+        }
+    }
+
+    /// Process a single block of at most `max_block_size` samples for a single channel.
+    fn process_block<F>(&mut self, channel_idx: usize, block: &mut [f32], f: &mut F)
+    where
+        F: FnMut(usize, &mut [f32]),
+    {
+        let num_stages = self.factor_log2;
+        if num_stages == 0 {
+            f(channel_idx, block);
+            return;
+        }
+
+        let channel = &mut self.channels[channel_idx];
+        let block_len = block.len();
+
+        channel.stages[0].upsample(block, &mut self.scratch_buffers[0][..block_len * 2]);
+        for stage_idx in 1..num_stages {
+            let (lower, upper) = self.scratch_buffers.split_at_mut(stage_idx);
+            channel.stages[stage_idx].upsample(
+                &lower[stage_idx - 1][..block_len << stage_idx],
+                &mut upper[0][..block_len << (stage_idx + 1)],
+            );
+        }
+
+        let oversampled = &mut self.scratch_buffers[num_stages - 1][..block_len << num_stages];
+        if self.compensation_delay > 0 {
+            let buffer_len = channel.compensation_buffer.len();
+            for sample in oversampled.iter_mut() {
+                let read_pos =
+                    (channel.compensation_pos + buffer_len - self.compensation_delay) % buffer_len;
+                channel.compensation_buffer[channel.compensation_pos] = *sample;
+                *sample = channel.compensation_buffer[read_pos];
+                channel.compensation_pos = (channel.compensation_pos + 1) % buffer_len;
+            }
+        }
+
+        f(channel_idx, oversampled);
+
+        for stage_idx in (1..num_stages).rev() {
+            let (lower, upper) = self.scratch_buffers.split_at_mut(stage_idx);
+            channel.stages[stage_idx].downsample(
+                &upper[0][..block_len << (stage_idx + 1)],
+                &mut lower[stage_idx - 1][..block_len << stage_idx],
+            );
+        }
+        channel.stages[0].downsample(&self.scratch_buffers[0][..block_len * 2], block);
+    }
+
+    /// Recompute the latency and the compensation delay for the current oversampling factor.
+    fn update_latency(&mut self) {
+        // The latencies are first summed at the highest sample rate
+        let num_stages = self.factor_log2;
+        let oversampled_latency: f32 = self.stage_latencies[..num_stages]
+            .iter()
+            .enumerate()
+            .map(|(stage_idx, latency)| latency * (1 << (num_stages - stage_idx - 1)) as f32)
+            .sum();
+
+        let factor = 1 << num_stages;
+        match self.filter {
+            OversamplingFilter::Fir => {
+                // The FIR filters' latencies are always integers, so we can pad them to a whole
+                // number of samples at the original sample rate
+                let oversampled_latency = oversampled_latency.round() as usize;
+                self.compensation_delay = (factor - (oversampled_latency % factor)) % factor;
+                self.latency = ((oversampled_latency + self.compensation_delay) / factor) as u32;
+            }
+            OversamplingFilter::Iir => {
+                self.compensation_delay = 0;
+                self.latency = (oversampled_latency / factor as f32).round() as u32;
+            }
+        }
+    }
+}
+
+impl Stage {
+    fn upsample(&mut self, input: &[f32], output: &mut [f32]) {
+        nih_debug_assert_eq!(input.len() * 2, output.len());
+
+        match self {
+            Stage::Fir(stage) => stage.upsample(input, output),
+            Stage::Iir(stage) => stage.upsample(input, output),
+        }
+    }
+
+    fn downsample(&mut self, input: &[f32], output: &mut [f32]) {
+        nih_debug_assert_eq!(input.len(), output.len() * 2);
+
+        match self {
+            Stage::Fir(stage) => stage.downsample(input, output),
+            Stage::Iir(stage) => stage.downsample(input, output),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            Stage::Fir(stage) => stage.reset(),
+            Stage::Iir(stage) => stage.reset(),
+        }
+    }
+
+    /// The combined latency of upsampling and downsampling in samples at the higher sample rate.
+    fn latency(&self) -> f32 {
+        match self {
+            // Both filters are centered around the center tap
+            Stage::Fir(stage) => (stage.coefficients.len() * 2 - 2) as f32,
+            // A first order all-pass filter `(a + z^-1) / (1 + a * z^-1)` has a group delay of `(1
+            // - a) / (1 + a)` samples at DC. These run at half of the sample rate, and the
+            // downsampler is one sample ahead of the upsampler so that cancels out the delay
+            // between the two polyphase branches.
+            Stage::Iir(stage) => stage
+                .upsampling_chains
+                .iter()
+                .flat_map(|chain| chain.coefficients.iter())
+                .map(|&a| 2.0 * (1.0 - a) / (1.0 + a))
+                .sum(),
+        }
+    }
+}
+
+impl FirStage {
+    fn new(half_side_taps: usize) -> Self {
+        let num_side_taps = half_side_taps * 2;
+
+        // This is a windowed sinc filter with its cutoff at a quarter of the sample rate. The taps
+        // at even distances from the center are exactly zero. The window is two samples longer
+        // than the filter so the first and last taps don't end up being zero.
+        let num_taps = num_side_taps * 2 - 1;
+        let center = (num_taps / 2) as f64;
+        let window = window::blackman(num_taps + 2);
+        let mut coefficients: Vec<f32> = (0..num_side_taps)
+            .map(|side_tap_idx| {
+                let tap_idx = side_tap_idx * 2;
+                let x = consts::FRAC_PI_2 * (tap_idx as f64 - center);
+
+                (0.5 * (x.sin() / x)) as f32 * window[tap_idx + 1]
+            })
+            .collect();
+
+        // The center tap is 0.5, so the side taps should sum to 0.5 for a unity gain at DC
+        let gain_compensation = 0.5 / coefficients.iter().sum::<f32>();
+        for coefficient in &mut coefficients {
+            *coefficient *= gain_compensation;
+        }
+
+        Self {
+            coefficients,
+            upsampling_history: History::new(num_side_taps),
+            downsampling_even_history: History::new(num_side_taps),
+            downsampling_odd_history: History::new(half_side_taps + 1),
+        }
+    }
+
+    fn upsample(&mut self, input: &[f32], output: &mut [f32]) {
+        let center_idx = self.coefficients.len() / 2;
+        for (sample, output) in input.iter().zip(output.chunks_exact_mut(2)) {
+            self.upsampling_history.push(*sample);
+            let history = self.upsampling_history.as_slice();
+
+            // The zero stuffed signal is filtered with twice the filter's gain to compensate for the
+            // zeroes. The odd output samples only depend on the center tap.
+            output[0] = 2.0 * dot(&self.coefficients, history);
+            output[1] = history[center_idx];
+        }
+    }
+
+    fn downsample(&mut self, input: &[f32], output: &mut [f32]) {
+        for (samples, output) in input.chunks_exact(2).zip(output.iter_mut()) {
+            self.downsampling_even_history.push(samples[0]);
+            self.downsampling_odd_history.push(samples[1]);
+
+            let even_history = self.downsampling_even_history.as_slice();
+            let odd_history = self.downsampling_odd_history.as_slice();
+            *output = dot(&self.coefficients, even_history) + (0.5 * odd_history[0]);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.upsampling_history.reset();
+        self.downsampling_even_history.reset();
+        self.downsampling_odd_history.reset();
+    }
+}
+
+impl IirStage {
+    fn new(num_coefficients: usize, transition: f64) -> Self {
+        let coefficients = design_half_band_iir(num_coefficients, transition);
+
+        // The even and odd coefficients are used for the two parallel all-pass chains
+        let chains = [
+            AllpassChain::new(coefficients.iter().copied().step_by(2).collect()),
+            AllpassChain::new(coefficients.iter().copied().skip(1).step_by(2).collect()),
+        ];
+
+        Self {
+            upsampling_chains: chains.clone(),
+            downsampling_chains: chains,
+        }
+    }
+
+    fn upsample(&mut self, input: &[f32], output: &mut [f32]) {
+        let [even_chain, odd_chain] = &mut self.upsampling_chains;
+        for (sample, output) in input.iter().zip(output.chunks_exact_mut(2)) {
+            output[0] = even_chain.process(*sample);
+            output[1] = odd_chain.process(*sample);
+        }
+    }
+
+    fn downsample(&mut self, input: &[f32], output: &mut [f32]) {
+        let [even_chain, odd_chain] = &mut self.downsampling_chains;
+        for (samples, output) in input.chunks_exact(2).zip(output.iter_mut()) {
+            *output = 0.5 * (even_chain.process(samples[1]) + odd_chain.process(samples[0]));
+        }
+    }
+
+    fn reset(&mut self) {
+        for chain in self
+            .upsampling_chains
+            .iter_mut()
+            .chain(self.downsampling_chains.iter_mut())
+        {
+            chain.reset();
+        }
+    }
+}
+
+impl AllpassChain {
+    fn new(coefficients: Vec<f32>) -> Self {
+        Self {
+            states: vec![(0.0, 0.0); coefficients.len()],
+            coefficients,
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, mut sample: f32) -> f32 {
+        for (a, (x1, y1)) in self.coefficients.iter().zip(self.states.iter_mut()) {
+            let output = a * (sample - *y1) + *x1;
+            *x1 = sample;
+            *y1 = output;
+
+            sample = output;
+        }
+
+        sample
+    }
+
+    fn reset(&mut self) {
+        self.states.fill((0.0, 0.0));
+    }
+}
+
+impl History {
+    pub(super) fn new(len: usize) -> Self {
+        Self {
+            samples: vec![0.0; len * 2],
+            pos: 0,
+        }
+    }
+
+    #[inline]
+    pub(super) fn push(&mut self, sample: f32) {
+        let len = self.samples.len() / 2;
+        self.samples[self.pos] = sample;
+        self.samples[self.pos + len] = sample;
+        self.pos = (self.pos + 1) % len;
+    }
+
+    /// The last `len` samples, ordered from oldest to newest.
+    #[inline]
+    pub(super) fn as_slice(&self) -> &[f32] {
+        let len = self.samples.len() / 2;
+        &self.samples[self.pos..self.pos + len]
+    }
+
+    pub(super) fn reset(&mut self) {
+        self.samples.fill(0.0);
+        self.pos = 0;
+    }
+}
+
+/// Convert an oversampling factor to a power of two, rounding down to the nearest supported factor.
+fn factor_to_log2(factor: usize) -> usize {
+    nih_debug_assert!(
+        factor.is_power_of_two() && factor <= (1 << MAX_FACTOR_LOG2),
+        "Unsupported oversampling factor: {}",
+        factor
+    );
+
+    (factor.max(1).trailing_zeros() as usize).min(MAX_FACTOR_LOG2)
+}
+
+#[inline]
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// Compute the coefficients for a half-band filter made from two parallel all-pass chains. The
+/// transition bandwidth is normalized to the lower sample rate, so a value of 0.04 results in a
+/// passband up to 46% of the lower sample rate. Ported from HIIR's `PolyphaseIir2Designer`.
+fn design_half_band_iir(num_coefficients: usize, transition: f64) -> Vec<f32> {
+    nih_debug_assert!(transition > 0.0 && transition < 0.5);
+
+    let k = ((1.0 - transition * 2.0) * consts::FRAC_PI_4).tan().powi(2);
+    let kksqrt = (1.0 - k * k).powf(0.25);
+    let e = 0.5 * (1.0 - kksqrt) / (1.0 + kksqrt);
+    let e4 = e.powi(4);
+    let q = e * (1.0 + e4 * (2.0 + e4 * (15.0 + 150.0 * e4)));
+
+    let order = (num_coefficients * 2 + 1) as f64;
+    (1..=num_coefficients)
+        .map(|c| {
+            let c = c as f64;
+
+            let mut numerator = 0.0;
+            let mut i = 0;
+            loop {
+                let term = q.powi(i * (i + 1))
+                    * ((i * 2 + 1) as f64 * c * consts::PI / order).sin()
+                    * if i % 2 == 0 { 1.0 } else { -1.0 };
+                numerator += term;
+                i += 1;
+                if term.abs() <= 1e-100 {
+                    break;
+                }
+            }
+            numerator *= q.powf(0.25);
+
+            let mut denominator = 0.5;
+            let mut i = 1;
+            loop {
+                let term = q.powi(i * i)
+                    * ((i * 2) as f64 * c * consts::PI / order).cos()
+                    * if i % 2 == 0 { 1.0 } else { -1.0 };
+                denominator += term;
+                i += 1;
+                if term.abs() <= 1e-100 {
+                    break;
+                }
+            }
+
+            let ww = (numerator / denominator).powi(2);
+            let x = ((1.0 - ww * k) * (1.0 - ww / k)).sqrt() / (1.0 + ww);
+
+            ((1.0 - x) / (1.0 + x)) as f32
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use realfft::num_complex::Complex64;
+
+    use super::*;
+
+    /// Run a 1 kHz sine wave at 48 kHz through the oversampler without any processing, and return
+    /// the input and the output.
+    fn roundtrip_sine(oversampler: &mut Oversampler, num_samples: usize) -> (Vec<f32>, Vec<f32>) {
+        let input: Vec<f32> = (0..num_samples)
+            .map(|i| (i as f32 * std::f32::consts::TAU * 1000.0 / 48000.0).sin())
+            .collect();
+
+        let mut output = input.clone();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.with_raw_vec(|output_slices| {
+                *output_slices = vec![&mut output[..]];
+            })
+        };
+        let mut num_oversampled_samples = 0;
+        oversampler.process(&mut buffer, |_, samples| {
+            num_oversampled_samples += samples.len()
+        });
+        assert_eq!(num_oversampled_samples, num_samples * oversampler.factor());
+
+        (input, output)
+    }
+
+    #[test]
+    fn uninitialized_passthrough() {
+        let mut oversampler = Oversampler::new(1, 4, OversamplingFilter::Fir);
+        oversampler.set_factor(4);
+
+        let mut samples = vec![0.5f32; 64];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.with_raw_vec(|output_slices| {
+                *output_slices = vec![&mut samples[..]];
+            })
+        };
+        oversampler.process(&mut buffer, |_, _| {
+            panic!("The closure should not be called")
+        });
+        assert!(samples.iter().all(|sample| *sample == 0.5));
+    }
+
+    #[test]
+    fn too_many_channels_passthrough() {
+        let mut oversampler = Oversampler::new(1, 4, OversamplingFilter::Fir);
+        oversampler.initialize(64);
+        oversampler.set_factor(4);
+
+        let mut left = vec![0.5f32; 64];
+        let mut right = vec![0.5f32; 64];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.with_raw_vec(|output_slices| {
+                *output_slices = vec![&mut left[..], &mut right[..]];
+            })
+        };
+        oversampler.process(&mut buffer, |_, _| {
+            panic!("The closure should not be called")
+        });
+        assert!(left.iter().chain(&right).all(|sample| *sample == 0.5));
+    }
+
+    #[test]
+    fn fir_latency_is_exact() {
+        for factor in [1, 2, 4, 8, 16] {
+            let mut oversampler = Oversampler::new(1, 16, OversamplingFilter::Fir);
+            oversampler.initialize(100);
+            oversampler.set_factor(factor);
+
+            let (input, output) = roundtrip_sine(&mut oversampler, 1024);
+            let latency = oversampler.latency_samples() as usize;
+            for (expected, actual) in input.iter().zip(&output[latency..]).skip(100) {
+                approx::assert_relative_eq!(expected, actual, epsilon = 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn iir_preserves_amplitude() {
+        for factor in [2, 4, 8, 16] {
+            let mut oversampler = Oversampler::new(1, factor, OversamplingFilter::Iir);
+            oversampler.initialize(64);
+            assert!(oversampler.latency_samples() < 10);
+
+            let (input, output) = roundtrip_sine(&mut oversampler, 4096);
+            let rms = |samples: &[f32]| {
+                (samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
+            };
+            // The phase shift doesn't matter as long as this covers a whole number of periods
+            approx::assert_relative_eq!(
+                rms(&input[960..3840]),
+                rms(&output[960..3840]),
+                epsilon = 1e-3
+            );
+        }
+    }
+
+    #[test]
+    fn iir_stopband_attenuation() {
+        // The combined response of the two all-pass chains should be a half-band low-pass filter
+        for (num_coefficients, transition) in IIR_PARAMETERS {
+            let stage = IirStage::new(num_coefficients, transition);
+            let response = |frequency: f64| {
+                // `frequency` is normalized to the higher sample rate. The chains run at half that
+                // rate, and the odd chain is delayed by one sample at the higher rate.
+                let z1 = Complex64::from_polar(1.0, -consts::TAU * frequency);
+                let z2 = z1 * z1;
+                let chain_response = |chain: &AllpassChain| {
+                    chain
+                        .coefficients
+                        .iter()
+                        .map(|&a| (z2 + a as f64) / ((z2 * a as f64) + 1.0))
+                        .product::<Complex64>()
+                };
+
+                ((chain_response(&stage.upsampling_chains[0])
+                    + (chain_response(&stage.upsampling_chains[1]) * z1))
+                    * 0.5)
+                    .norm()
+            };
+
+            let passband_edge = (0.5 - transition) / 2.0;
+            let stopband_edge = 0.5 - passband_edge;
+            approx::assert_relative_eq!(response(passband_edge * 0.5), 1.0, epsilon = 1e-3);
+            assert!(response(stopband_edge) < 1e-3);
+            assert!(response(0.45) < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,401 @@
+//! Fractional resampling for sample playback and sample rate conversion. The [`Resampler`] can be
+//! used for streaming variable ratio resampling, for instance to play back a sample at different
+//! pitches, and [`resample()`] converts an entire buffer from one sample rate to another in one go.
+
+use std::f32::consts;
+
+use super::oversampling::History;
+
+/// The number of entries per zero crossing in the precomputed sinc kernel. Values in between these
+/// entries are linearly interpolated.
+const SINC_KERNEL_RESOLUTION: usize = 512;
+
+/// The interpolation algorithm used by the [`Resampler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Linear interpolation between two samples. This is very cheap, but it attenuates high
+    /// frequencies and it does not prevent aliasing.
+    Linear,
+    /// Four point, third order Hermite interpolation. This sounds much better than linear
+    /// interpolation at only a slightly higher cost, but it also does not prevent aliasing.
+    Hermite,
+    /// Band-limited interpolation using a windowed sinc kernel with `zero_crossings` zero crossings
+    /// on either side. Higher values result in a steeper anti-aliasing filter, at the cost of more
+    /// computation and latency. 8 is a good starting point for real time use, while 32 or more can
+    /// be used for offline conversion. When reading the input faster than it was recorded, the
+    /// cutoff frequency is lowered to prevent aliasing, which makes the kernel proportionally
+    /// longer.
+    Sinc { zero_crossings: usize },
+}
+
+/// A streaming resampler that reads its input at a variable ratio. Audio is pushed into the
+/// resampler and interpolated output is pulled from it using [`process()`][Self::process()], and the
+/// ratio can be changed at any time using [`set_ratio()`][Self::set_ratio()]. All channels are
+/// resampled in lockstep.
+///
+/// The resampler allocates all of its buffers in [`new()`][Self::new()]. After that point all
+/// functions are realtime-safe.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    interpolation: Interpolation,
+    /// The windowed sinc kernel for [`Interpolation::Sinc`], starting at the center and sampled at
+    /// [`SINC_KERNEL_RESOLUTION`] points per zero crossing. Empty for the other interpolation modes.
+    sinc_kernel: Vec<f32>,
+
+    /// The largest ratio that can be set. This determines how many input samples need to be kept
+    /// around for the sinc interpolation.
+    max_ratio: f64,
+    /// The current ratio. This is the number of input samples that are consumed for every output
+    /// sample.
+    ratio: f64,
+    /// The position of the next output sample relative to the interpolation window's center, in
+    /// input samples. A new input sample needs to be read whenever this is at least 1.
+    position: f64,
+
+    /// The last input samples for every channel. These form the interpolation windows.
+    histories: Vec<History>,
+}
+
+impl Resampler {
+    /// Create a new resampler for `num_channels` channels. `max_ratio` is the largest ratio that
+    /// will be passed to [`set_ratio()`][Self::set_ratio()], e.g. 4.0 when playing back a sample
+    /// at most two octaves up. This is only relevant for sinc interpolation. The initial ratio is
+    /// 1.0.
+    pub fn new(num_channels: usize, interpolation: Interpolation, max_ratio: f64) -> Self {
+        nih_debug_assert!(max_ratio > 0.0);
+
+        let sinc_kernel = match interpolation {
+            Interpolation::Sinc { zero_crossings } => {
+                nih_debug_assert_ne!(zero_crossings, 0);
+                let zero_crossings = zero_crossings.max(1);
+
+                // The kernel is padded with a zero so the linear interpolation in
+                // `sinc_kernel_value()` doesn't need to special case the last entry
+                let num_entries = zero_crossings * SINC_KERNEL_RESOLUTION;
+                let mut kernel: Vec<f32> = (0..num_entries)
+                    .map(|i| {
+                        let x = i as f32 / SINC_KERNEL_RESOLUTION as f32;
+                        let sinc = if i == 0 {
+                            1.0
+                        } else {
+                            (consts::PI * x).sin() / (consts::PI * x)
+                        };
+
+                        // This is the right half of a Blackman window
+                        let window_x = consts::PI * (x / zero_crossings as f32);
+                        let window =
+                            0.42 + (0.5 * window_x.cos()) + (0.08 * (2.0 * window_x).cos());
+
+                        sinc * window
+                    })
+                    .collect();
+                kernel.push(0.0);
+
+                kernel
+            }
+            Interpolation::Linear | Interpolation::Hermite => Vec::new(),
+        };
+
+        let window_len = window_len(interpolation, max_ratio);
+        Self {
+            interpolation,
+            sinc_kernel,
+
+            max_ratio,
+            ratio: 1.0,
+            position: 1.0,
+
+            histories: vec![History::new(window_len); num_channels],
+        }
+    }
+
+    /// The interpolation algorithm used by this resampler.
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// The current ratio.
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Change the ratio between the input and the output. This is the number of input samples that
+    /// are consumed for every output sample, so 2.0 plays back audio twice as fast and an octave
+    /// higher, and `input_sample_rate / output_sample_rate` converts between two sample rates. This
+    /// is clamped to the maximum ratio passed to [`new()`][Self::new()].
+    pub fn set_ratio(&mut self, ratio: f64) {
+        nih_debug_assert!(ratio > 0.0);
+        nih_debug_assert!(ratio <= self.max_ratio);
+
+        self.ratio = ratio.clamp(f64::EPSILON, self.max_ratio);
+    }
+
+    /// The number of input samples between an input sample being pushed to the resampler and that
+    /// same sample appearing in the output.
+    pub fn latency_samples(&self) -> u32 {
+        window_len(self.interpolation, self.max_ratio) as u32 / 2
+    }
+
+    /// Clear the resampler's input history.
+    pub fn reset(&mut self) {
+        for history in &mut self.histories {
+            history.reset();
+        }
+        self.position = 1.0;
+    }
+
+    /// Resample audio from `input` to `output` at the current ratio. Both `input` and `output`
+    /// should contain one slice per channel, and all channels should have the same length. This
+    /// stops when either all input samples have been consumed or when the output is full, and it
+    /// returns the number of input samples consumed and the number of output samples written.
+    /// Input samples that have not been consumed should be passed to the next call.
+    pub fn process<I, O>(&mut self, input: &[I], output: &mut [O]) -> (usize, usize)
+    where
+        I: AsRef<[f32]>,
+        O: AsMut<[f32]>,
+    {
+        nih_debug_assert_eq!(input.len(), self.histories.len());
+        nih_debug_assert_eq!(output.len(), self.histories.len());
+
+        let num_input_samples = input.first().map(|c| c.as_ref().len()).unwrap_or(0);
+        let num_output_samples = output.first_mut().map(|c| c.as_mut().len()).unwrap_or(0);
+        let cutoff = self.cutoff(self.ratio);
+
+        let mut input_sample_idx = 0;
+        let mut output_sample_idx = 0;
+        while output_sample_idx < num_output_samples {
+            while self.position >= 1.0 {
+                if input_sample_idx == num_input_samples {
+                    return (input_sample_idx, output_sample_idx);
+                }
+
+                for (history, channel) in self.histories.iter_mut().zip(input) {
+                    history.push(channel.as_ref()[input_sample_idx]);
+                }
+                input_sample_idx += 1;
+                self.position -= 1.0;
+            }
+
+            for (history, channel) in self.histories.iter().zip(output.iter_mut()) {
+                channel.as_mut()[output_sample_idx] =
+                    self.interpolate(history.as_slice(), self.position as f32, cutoff);
+            }
+            output_sample_idx += 1;
+            self.position += self.ratio;
+        }
+
+        (input_sample_idx, output_sample_idx)
+    }
+
+    /// The cutoff frequency for the sinc kernel relative to the input's Nyquist frequency.
+    fn cutoff(&self, ratio: f64) -> f32 {
+        ratio.recip().min(1.0) as f32
+    }
+
+    /// Interpolate a value `frac` samples after the center of `window`. The window's length is
+    /// determined by [`window_len()`], and the center is the sample right before the middle.
+    fn interpolate(&self, window: &[f32], frac: f32, cutoff: f32) -> f32 {
+        let center = window.len() / 2 - 1;
+        match self.interpolation {
+            Interpolation::Linear => {
+                window[center] + ((window[center + 1] - window[center]) * frac)
+            }
+            Interpolation::Hermite => {
+                let (y0, y1, y2, y3) = (
+                    window[center - 1],
+                    window[center],
+                    window[center + 1],
+                    window[center + 2],
+                );
+
+                let c1 = 0.5 * (y2 - y0);
+                let c2 = y0 - (2.5 * y1) + (2.0 * y2) - (0.5 * y3);
+                let c3 = (0.5 * (y3 - y0)) + (1.5 * (y1 - y2));
+
+                ((((c3 * frac) + c2) * frac + c1) * frac) + y1
+            }
+            Interpolation::Sinc { zero_crossings } => {
+                // Only the samples within the (stretched) kernel's support need to be considered
+                let position = center as f32 + frac;
+                let half_width = zero_crossings as f32 / cutoff;
+                let first_idx = (position - half_width).ceil().max(0.0) as usize;
+                let last_idx = ((position + half_width).floor() as usize).min(window.len() - 1);
+
+                // The weights are normalized so the kernel always has unity gain at DC
+                let mut result = 0.0;
+                let mut total_weight = 0.0;
+                for (idx, sample) in window.iter().enumerate().take(last_idx + 1).skip(first_idx) {
+                    let weight = self.sinc_kernel_value((idx as f32 - position).abs() * cutoff);
+                    result += sample * weight;
+                    total_weight += weight;
+                }
+
+                if total_weight != 0.0 {
+                    result / total_weight
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    /// Look up the windowed sinc kernel's value at `x` zero crossings from the center.
+    #[inline]
+    fn sinc_kernel_value(&self, x: f32) -> f32 {
+        let pos = x * SINC_KERNEL_RESOLUTION as f32;
+        let idx = pos as usize;
+        if idx >= self.sinc_kernel.len() - 1 {
+            return 0.0;
+        }
+
+        let frac = pos - idx as f32;
+        self.sinc_kernel[idx] + ((self.sinc_kernel[idx + 1] - self.sinc_kernel[idx]) * frac)
+    }
+}
+
+/// Convert a single channel of audio from one sample rate to another. This does not introduce any
+/// latency, and the output contains enough samples to cover the entire input. This allocates, so
+/// it should not be called from the audio thread. This can be used to convert an audio file to the
+/// sample rate from [`BufferConfig::sample_rate`][crate::prelude::BufferConfig::sample_rate] when
+/// loading it.
+pub fn resample(
+    input: &[f32],
+    input_sample_rate: f32,
+    output_sample_rate: f32,
+    interpolation: Interpolation,
+) -> Vec<f32> {
+    nih_debug_assert!(input_sample_rate > 0.0 && output_sample_rate > 0.0);
+
+    let ratio = input_sample_rate as f64 / output_sample_rate as f64;
+    let resampler = Resampler::new(0, interpolation, ratio.max(1.0));
+    let cutoff = resampler.cutoff(ratio);
+
+    let mut window = vec![0.0; window_len(interpolation, ratio.max(1.0))];
+    let center = window.len() as isize / 2 - 1;
+    let num_output_samples = (input.len() as f64 / ratio).ceil() as usize;
+
+    (0..num_output_samples)
+        .map(|output_sample_idx| {
+            let position = output_sample_idx as f64 * ratio;
+            let input_sample_idx = position.floor() as isize;
+            let frac = (position - input_sample_idx as f64) as f32;
+
+            // The input is padded with silence on both ends
+            for (window_idx, sample) in window.iter_mut().enumerate() {
+                let idx = input_sample_idx - center + window_idx as isize;
+                *sample = if idx >= 0 {
+                    input.get(idx as usize).copied().unwrap_or(0.0)
+                } else {
+                    0.0
+                };
+            }
+
+            resampler.interpolate(&window, frac, cutoff)
+        })
+        .collect()
+}
+
+/// The number of input samples needed to interpolate a single output sample.
+fn window_len(interpolation: Interpolation, max_ratio: f64) -> usize {
+    match interpolation {
+        Interpolation::Linear => 2,
+        Interpolation::Hermite => 4,
+        Interpolation::Sinc { zero_crossings } => {
+            (zero_crossings.max(1) as f64 * max_ratio.max(1.0)).ceil() as usize * 2
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERPOLATIONS: [Interpolation; 3] = [
+        Interpolation::Linear,
+        Interpolation::Hermite,
+        Interpolation::Sinc { zero_crossings: 16 },
+    ];
+
+    fn sine(frequency: f32, sample_rate: f32, num_samples: usize) -> Vec<f32> {
+        (0..num_samples)
+            .map(|i| (i as f32 * consts::TAU * frequency / sample_rate).sin())
+            .collect()
+    }
+
+    #[test]
+    fn unity_ratio_delays_input() {
+        let input = sine(1000.0, 48000.0, 256);
+        for interpolation in INTERPOLATIONS {
+            let mut resampler = Resampler::new(1, interpolation, 1.0);
+            let mut output = vec![0.0; 256];
+            let (num_consumed, num_written) = resampler.process(&[&input], &mut [&mut output]);
+            assert_eq!(num_consumed, 256);
+            assert_eq!(num_written, 256);
+
+            let latency = resampler.latency_samples() as usize;
+            for (expected, actual) in input.iter().zip(&output[latency..]) {
+                approx::assert_relative_eq!(expected, actual, epsilon = 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn streaming_consumes_input_at_ratio() {
+        let input = sine(1000.0, 48000.0, 1000);
+        let mut resampler = Resampler::new(2, Interpolation::Sinc { zero_crossings: 8 }, 4.0);
+        resampler.set_ratio(2.5);
+
+        // Processing in small blocks should produce the same number of samples
+        let mut output = vec![vec![0.0; 1000]; 2];
+        let mut num_consumed = 0;
+        let mut num_written = 0;
+        while num_consumed < input.len() {
+            let input_block = &input[num_consumed..(num_consumed + 64).min(input.len())];
+            let (left, right) = output.split_at_mut(1);
+            let (consumed, written) = resampler.process(
+                &[input_block, input_block],
+                &mut [
+                    &mut left[0][num_written..(num_written + 16).min(1000)],
+                    &mut right[0][num_written..(num_written + 16).min(1000)],
+                ],
+            );
+            num_consumed += consumed;
+            num_written += written;
+        }
+
+        assert_eq!(num_written, 400);
+        assert_eq!(output[0], output[1]);
+    }
+
+    #[test]
+    fn one_shot_conversion() {
+        for (interpolation, epsilon) in INTERPOLATIONS.into_iter().zip([2e-2, 1e-3, 1e-3]) {
+            let input = sine(440.0, 48000.0, 4800);
+            let output = resample(&input, 48000.0, 44100.0, interpolation);
+            assert_eq!(output.len(), 4410);
+
+            // The edges are affected by the zero padding
+            let expected = sine(440.0, 44100.0, 4410);
+            for (expected, actual) in expected.iter().zip(&output).skip(32).take(4410 - 64) {
+                approx::assert_relative_eq!(expected, actual, epsilon = epsilon);
+            }
+        }
+    }
+
+    #[test]
+    fn sinc_downsampling_filters_aliases() {
+        // A 20 kHz tone would alias to 12 kHz when converting from 48 kHz to 32 kHz
+        let input = sine(20000.0, 48000.0, 4800);
+        let output = resample(
+            &input,
+            48000.0,
+            32000.0,
+            Interpolation::Sinc { zero_crossings: 32 },
+        );
+
+        let peak = output[200..output.len() - 200]
+            .iter()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        assert!(peak < 0.01, "{peak}");
+    }
+}
@@ -0,0 +1,413 @@
+//! A higher level spectral processing helper built on top of [`StftHelper`]. This takes care of the
+//! FFT plans, windowing, and gain compensation so the plugin only needs to modify the frequency
+//! domain bins.
+
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use std::sync::Arc;
+
+use super::stft::{StftInput, StftInputMut};
+use super::{window, StftHelper};
+
+/// Re-exported so plugins don't need to depend on `realfft` themselves.
+pub use realfft::num_complex::Complex32;
+
+/// A function that creates a window of the specified size, like [`window::hann()`].
+pub type WindowFunction = fn(usize) -> Vec<f32>;
+
+/// Processes audio in the frequency domain using overlapping windows. The input is split up into
+/// windows using a [`StftHelper`], the analysis window is applied, and the windows are transformed
+/// to the frequency domain using a real FFT. The plugin can then modify the complex frequency bins
+/// in a callback, after which the bins are transformed back to the time domain, the synthesis window
+/// is applied, and the results are overlap-added to form the output. The gain compensation for the
+/// FFT and for the overlapping windows is already included in the synthesis step, so a callback that
+/// leaves the bins alone results in the (delayed) input signal.
+///
+/// By default both the analysis and synthesis windows are Hann windows. These can be changed using
+/// [`set_window_functions()`][Self::set_window_functions()]. The combination of windows and overlap
+/// amount should satisfy the constant overlap-add constraint for the gain compensation to be
+/// correct.
+///
+/// Like with the [`StftHelper`], additional sidechain inputs can be analyzed by setting the
+/// `NUM_SIDECHAIN_INPUTS` constant. The bins for those inputs are passed to the callback before the
+/// main input's bins for the same window, and they are never transformed back to the time domain.
+pub struct SpectralHelper<const NUM_SIDECHAIN_INPUTS: usize = 0> {
+    stft: StftHelper<NUM_SIDECHAIN_INPUTS>,
+    /// Used to create new FFT plans when the window size changes.
+    planner: RealFftPlanner<f32>,
+    /// The algorithm for the FFT operation.
+    r2c_plan: Arc<dyn RealToComplex<f32>>,
+    /// The algorithm for the IFFT operation.
+    c2r_plan: Arc<dyn ComplexToReal<f32>>,
+    /// The output of our real->complex FFT, and the input for the inverse complex->real FFT.
+    complex_fft_buffer: Vec<Complex32>,
+    /// Scratch buffers for the FFT algorithms so they don't need to allocate.
+    r2c_scratch_buffer: Vec<Complex32>,
+    c2r_scratch_buffer: Vec<Complex32>,
+
+    /// The function used to create `analysis_window`.
+    analysis_window_function: WindowFunction,
+    /// The function used to create `synthesis_window`, if a synthesis window is used.
+    synthesis_window_function: Option<WindowFunction>,
+    /// The window applied before the FFT.
+    analysis_window: Vec<f32>,
+    /// The window applied after the IFFT. The gain compensation for both the FFT and the overlap-add
+    /// process is already baked into this. If no synthesis window is used, then this contains just
+    /// the gain compensation.
+    synthesis_window: Vec<f32>,
+
+    /// The number of times the windows overlap.
+    overlap_times: usize,
+}
+
+impl<const NUM_SIDECHAIN_INPUTS: usize> SpectralHelper<NUM_SIDECHAIN_INPUTS> {
+    /// Initialize the [`SpectralHelper`] for the specified number of channels and the given
+    /// maximum window size. The helper will initially use that maximum window size. Call
+    /// [`set_window_size()`][Self::set_window_size()] afterwards if you want to use a smaller
+    /// window. `overlap_times` is the number of windows that overlap at any given time, so a value
+    /// of 4 results in a hop size of a quarter of the window size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_channels == 0 || max_window_size == 0 || overlap_times == 0`.
+    pub fn new(num_channels: usize, max_window_size: usize, overlap_times: usize) -> Self {
+        assert_ne!(overlap_times, 0);
+
+        let mut planner = RealFftPlanner::new();
+        let r2c_plan = planner.plan_fft_forward(max_window_size);
+        let c2r_plan = planner.plan_fft_inverse(max_window_size);
+
+        let mut helper = Self {
+            stft: StftHelper::new(num_channels, max_window_size, 0),
+            complex_fft_buffer: r2c_plan.make_output_vec(),
+            r2c_scratch_buffer: r2c_plan.make_scratch_vec(),
+            c2r_scratch_buffer: c2r_plan.make_scratch_vec(),
+            planner,
+            r2c_plan,
+            c2r_plan,
+
+            analysis_window_function: window::hann,
+            synthesis_window_function: Some(window::hann),
+            analysis_window: Vec::with_capacity(max_window_size),
+            synthesis_window: Vec::with_capacity(max_window_size),
+
+            overlap_times,
+        };
+        helper.update_windows();
+
+        helper
+    }
+
+    /// Change the window size. This creates new FFT plans and window functions, so this should not
+    /// be called from the audio thread. Call this from your plugin's `initialize()` function
+    /// instead. This will also clear the buffers, causing the next window to output silence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window_size > max_window_size` or if `window_size == 0`.
+    pub fn set_window_size(&mut self, window_size: usize) {
+        assert_ne!(window_size, 0);
+
+        self.stft.set_block_size(window_size);
+        if self.r2c_plan.len() != window_size {
+            self.r2c_plan = self.planner.plan_fft_forward(window_size);
+            self.c2r_plan = self.planner.plan_fft_inverse(window_size);
+            self.complex_fft_buffer = self.r2c_plan.make_output_vec();
+            self.r2c_scratch_buffer = self.r2c_plan.make_scratch_vec();
+            self.c2r_scratch_buffer = self.c2r_plan.make_scratch_vec();
+        }
+
+        self.update_windows();
+    }
+
+    /// Change the number of overlapping windows. This recomputes the gain compensation and clears
+    /// the buffers, causing the next window to output silence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `overlap_times == 0`.
+    pub fn set_overlap_times(&mut self, overlap_times: usize) {
+        assert_ne!(overlap_times, 0);
+
+        self.overlap_times = overlap_times;
+        self.stft.set_block_size(self.window_size());
+        self.update_windows();
+    }
+
+    /// Change the analysis and synthesis window functions. If `synthesis_window_function` is
+    /// `None`, then the output of the inverse FFT is only scaled to compensate for the FFT and the
+    /// overlap-add gain. The windows are recomputed from these functions whenever the window size
+    /// changes. This allocates, so it should not be called from the audio thread.
+    pub fn set_window_functions(
+        &mut self,
+        analysis_window_function: WindowFunction,
+        synthesis_window_function: Option<WindowFunction>,
+    ) {
+        self.analysis_window_function = analysis_window_function;
+        self.synthesis_window_function = synthesis_window_function;
+        self.update_windows();
+    }
+
+    /// The number of channels this `SpectralHelper` was configured for.
+    pub fn num_channels(&self) -> usize {
+        self.stft.num_channels()
+    }
+
+    /// The current window size.
+    pub fn window_size(&self) -> usize {
+        self.r2c_plan.len()
+    }
+
+    /// The number of overlapping windows.
+    pub fn overlap_times(&self) -> usize {
+        self.overlap_times
+    }
+
+    /// The number of frequency bins passed to the callback. This is `window_size / 2 + 1`.
+    pub fn num_bins(&self) -> usize {
+        self.complex_fft_buffer.len()
+    }
+
+    /// The center frequency in Hertz corresponding to a bin index.
+    pub fn bin_frequency(&self, bin_idx: usize, sample_rate: f32) -> f32 {
+        bin_idx as f32 * sample_rate / self.window_size() as f32
+    }
+
+    /// The amount of latency introduced when processing audio through this [`SpectralHelper`].
+    pub fn latency_samples(&self) -> u32 {
+        self.stft.latency_samples()
+    }
+
+    /// Process the audio in `main_buffer` in the frequency domain. `process_cb` is called once for
+    /// every channel for every window with the arguments `process_cb(channel_idx, bins)`, where
+    /// `bins` contains the complex frequency bins for that channel. The modified bins will be
+    /// transformed back to the time domain and written back to `main_buffer` one window later. This
+    /// means that this function introduces [`latency_samples()`][Self::latency_samples()] samples
+    /// of latency.
+    ///
+    /// This function does not allocate, so it can be used from the audio thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `main_buffer` does not have the same number of channels as this
+    /// [`SpectralHelper`].
+    pub fn process<M, F>(&mut self, main_buffer: &mut M, mut process_cb: F)
+    where
+        M: StftInputMut + ?Sized,
+        F: FnMut(usize, &mut [Complex32]),
+    {
+        self.process_sidechain(
+            main_buffer,
+            [&NoSidechain; NUM_SIDECHAIN_INPUTS],
+            |channel_idx, sidechain_idx, bins| {
+                if sidechain_idx.is_none() {
+                    process_cb(channel_idx, bins);
+                }
+            },
+        );
+    }
+
+    /// The same as [`process()`][Self::process()], but with sidechain inputs that can be analyzed
+    /// before the main input gets processed. The extra argument in the process function is
+    /// `sidechain_buffer_idx`, which will be `None` for the main buffer. Changes made to the
+    /// sidechain buffers' bins are discarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `main_buffer` or the buffers in `sidechain_buffers` do not have the same number of
+    /// channels as this [`SpectralHelper`], or if the sidechain buffers do not contain the same
+    /// number of samples as the main buffer.
+    pub fn process_sidechain<M, S, F>(
+        &mut self,
+        main_buffer: &mut M,
+        sidechain_buffers: [&S; NUM_SIDECHAIN_INPUTS],
+        mut process_cb: F,
+    ) where
+        M: StftInputMut + ?Sized,
+        S: StftInput + ?Sized,
+        F: FnMut(usize, Option<usize>, &mut [Complex32]),
+    {
+        let Self {
+            stft,
+            r2c_plan,
+            c2r_plan,
+            complex_fft_buffer,
+            r2c_scratch_buffer,
+            c2r_scratch_buffer,
+            analysis_window,
+            synthesis_window,
+            overlap_times,
+            ..
+        } = self;
+
+        stft.process_overlap_add_sidechain(
+            main_buffer,
+            sidechain_buffers,
+            *overlap_times,
+            |channel_idx, sidechain_idx, real_fft_buffer| {
+                window::multiply_with_window(real_fft_buffer, analysis_window);
+
+                // This can only fail if the buffers have the wrong size
+                let result = r2c_plan.process_with_scratch(
+                    real_fft_buffer,
+                    complex_fft_buffer,
+                    r2c_scratch_buffer,
+                );
+                nih_debug_assert!(result.is_ok());
+
+                process_cb(channel_idx, sidechain_idx, complex_fft_buffer);
+
+                // The sidechain inputs are only analyzed, their output is discarded
+                if sidechain_idx.is_some() {
+                    return;
+                }
+
+                // The inverse FFT requires the imaginary parts of the DC and Nyquist bins to be
+                // zero. Those parts would be discarded anyways.
+                complex_fft_buffer[0].im = 0.0;
+                if real_fft_buffer.len() % 2 == 0 {
+                    complex_fft_buffer.last_mut().unwrap().im = 0.0;
+                }
+
+                let result = c2r_plan.process_with_scratch(
+                    complex_fft_buffer,
+                    real_fft_buffer,
+                    c2r_scratch_buffer,
+                );
+                nih_debug_assert!(result.is_ok());
+
+                window::multiply_with_window(real_fft_buffer, synthesis_window);
+            },
+        );
+    }
+
+    /// Similar to [`process()`][Self::process()], but without the inverse FFT. `buffer` will only
+    /// ever be read from. `analyze_cb` is called with the arguments `analyze_cb(channel_idx, bins)`
+    /// for every channel in every window. Only the analysis window is applied and the bins are not
+    /// normalized, so the bins' magnitudes need to be scaled by the window's coherent gain, which
+    /// can be computed from [`analysis_window()`][Self::analysis_window()]. This can be useful for
+    /// providing spectrum data to a plugin's editor. There is still a delay to the analysis equal
+    /// to the window size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer` does not have the same number of channels as this [`SpectralHelper`].
+    pub fn process_analyze_only<B, F>(&mut self, buffer: &B, mut analyze_cb: F)
+    where
+        B: StftInput + ?Sized,
+        F: FnMut(usize, &[Complex32]),
+    {
+        let Self {
+            stft,
+            r2c_plan,
+            complex_fft_buffer,
+            r2c_scratch_buffer,
+            analysis_window,
+            overlap_times,
+            ..
+        } = self;
+
+        stft.process_analyze_only(buffer, *overlap_times, |channel_idx, real_fft_buffer| {
+            window::multiply_with_window(real_fft_buffer, analysis_window);
+
+            let result = r2c_plan.process_with_scratch(
+                real_fft_buffer,
+                complex_fft_buffer,
+                r2c_scratch_buffer,
+            );
+            nih_debug_assert!(result.is_ok());
+
+            analyze_cb(channel_idx, complex_fft_buffer);
+        });
+    }
+
+    /// The analysis window for the current window size. This does not include any gain
+    /// compensation.
+    pub fn analysis_window(&self) -> &[f32] {
+        &self.analysis_window
+    }
+
+    /// Recompute the analysis and synthesis windows, including the gain compensation, for the
+    /// current window size and overlap amount.
+    fn update_windows(&mut self) {
+        let window_size = self.window_size();
+
+        self.analysis_window = (self.analysis_window_function)(window_size);
+        self.synthesis_window = match self.synthesis_window_function {
+            Some(synthesis_window_function) => synthesis_window_function(window_size),
+            None => vec![1.0; window_size],
+        };
+
+        // The unnormalized FFT followed by the IFFT also scales the signal by the window size
+        let gain_compensation = window::cola_normalization_factor(
+            &self.analysis_window,
+            Some(&self.synthesis_window),
+            self.overlap_times,
+        ) / window_size as f32;
+        for sample in &mut self.synthesis_window {
+            *sample *= gain_compensation;
+        }
+    }
+}
+
+/// Marker struct for the version without sidechaining.
+struct NoSidechain;
+
+impl StftInput for NoSidechain {
+    fn num_samples(&self) -> usize {
+        0
+    }
+
+    fn num_channels(&self) -> usize {
+        0
+    }
+
+    unsafe fn get_sample_unchecked(&self, _channel: usize, _sample_idx: usize) -> f32 {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Process a sine wave through the helper and return the output.
+    fn process_sine(helper: &mut SpectralHelper, num_samples: usize) -> Vec<f32> {
+        let mut samples: Vec<f32> = (0..num_samples).map(|i| (i as f32 * 0.05).sin()).collect();
+
+        // Process the signal in awkwardly sized chunks
+        for chunk in samples.chunks_mut(100) {
+            helper.process(&mut [chunk][..], |_, _| ());
+        }
+
+        samples
+    }
+
+    #[test]
+    fn identity() {
+        let mut helper = SpectralHelper::new(1, 1024, 4);
+        let output = process_sine(&mut helper, 8192);
+
+        // The output should be the input delayed by the window size. The first couple windows will
+        // still be fading in.
+        let latency = helper.latency_samples() as usize;
+        for (i, sample) in output.iter().enumerate().skip(latency * 2) {
+            let expected = ((i - latency) as f32 * 0.05).sin();
+            approx::assert_abs_diff_eq!(*sample, expected, epsilon = 0.01);
+        }
+    }
+
+    #[test]
+    fn identity_without_synthesis_window() {
+        let mut helper = SpectralHelper::new(1, 1024, 2);
+        helper.set_window_functions(window::hann, None);
+        helper.set_window_size(512);
+        assert_eq!(helper.num_bins(), 257);
+
+        let output = process_sine(&mut helper, 8192);
+        let latency = helper.latency_samples() as usize;
+        for (i, sample) in output.iter().enumerate().skip(latency * 2) {
+            let expected = ((i - latency) as f32 * 0.05).sin();
+            approx::assert_abs_diff_eq!(*sample, expected, epsilon = 0.01);
+        }
+    }
+}
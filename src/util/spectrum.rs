@@ -0,0 +1,519 @@
+//! A spectrum analyzer pipeline for plugin editors. A [`SpectrumInput`] computes smoothed magnitude
+//! spectra from the plugin's audio on the audio thread, and publishes them to the corresponding
+//! [`SpectrumOutput`] without any locking so the editor can draw them.
+
+use triple_buffer::TripleBuffer;
+
+use super::SpectralHelper;
+use crate::buffer::Buffer;
+
+/// The spectrum analyzer's configuration. This is fixed when creating a [`SpectrumInput`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpectrumConfig {
+    /// The size of the FFT window. Larger windows result in a higher frequency resolution but a
+    /// lower time resolution.
+    pub window_size: usize,
+    /// The number of overlapping windows. Higher values cause the spectrum to be updated more
+    /// often.
+    pub overlap_times: usize,
+    /// The time in milliseconds it takes for a bin's magnitude to rise by 12 dB. Set this to 0 to
+    /// make the spectrum jump up immediately like a peak meter.
+    pub attack_ms: f32,
+    /// The time in milliseconds it takes for a bin's magnitude to fall by 12 dB.
+    pub release_ms: f32,
+    /// How the FFT bins are mapped to the published spectrum's bins.
+    pub binning: SpectrumBinning,
+    /// How the FFT bins' magnitudes are scaled.
+    pub normalization: SpectrumNormalization,
+    /// How the spectra of multiple channels are combined.
+    pub channel_mode: SpectrumChannelMode,
+}
+
+/// Determines how the FFT's bins are mapped to the bins in a [`Spectrum`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpectrumBinning {
+    /// Publish the FFT's bins as is, from DC up to the Nyquist frequency. This results in
+    /// `window_size / 2 + 1` bins.
+    Linear,
+    /// Publish `num_bins` bins with logarithmically spaced center frequencies between
+    /// `min_frequency` and `max_frequency` (in Hertz). Each bin contains the highest magnitude of
+    /// the FFT bins that fall within that bin's range. If no FFT bins fall within that range, as
+    /// often happens for the lower frequencies, then the magnitude is interpolated from the
+    /// surrounding FFT bins instead.
+    Logarithmic {
+        min_frequency: f32,
+        max_frequency: f32,
+        num_bins: usize,
+    },
+}
+
+/// Determines how the FFT bins' magnitudes are scaled in a [`Spectrum`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpectrumNormalization {
+    /// Compensate for the analysis window's coherent gain so a sine wave with an amplitude of 1.0
+    /// results in a magnitude of roughly 1.0 in the closest bin.
+    Amplitude,
+    /// Divide the magnitudes by the window size without compensating for the analysis window. This
+    /// results in lower values than [`Amplitude`][Self::Amplitude], with a sine wave with an
+    /// amplitude of 1.0 resulting in a magnitude of roughly 0.25 when using the default Hann window.
+    WindowSize,
+}
+
+/// Determines how the spectra of multiple channels are combined into a single [`Spectrum`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpectrumChannelMode {
+    /// Average the channels' magnitudes before applying the ballistics. The spectrum is published
+    /// once all channels have been analyzed.
+    Average,
+    /// Apply the ballistics to every channel's magnitudes in turn, publishing the spectrum after
+    /// each channel. With an immediate attack this makes the spectrum follow the loudest channel
+    /// like a peak meter. The release time accounts for the extra updates.
+    Sequential,
+}
+
+/// A smoothed magnitude spectrum published by a [`SpectrumInput`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrum {
+    /// The bins' linear magnitudes, combined and scaled according to the
+    /// [`SpectrumConfig::channel_mode`] and [`SpectrumConfig::normalization`] options. Use
+    /// [`gain_to_db()`][super::gain_to_db()] to convert these to decibels.
+    pub magnitudes: Vec<f32>,
+    /// The center frequencies in Hertz for each bin in `magnitudes`. These are all zero until
+    /// [`SpectrumInput::update_sample_rate()`] has been called.
+    pub frequencies: Vec<f32>,
+}
+
+/// Computes spectra from the plugin's audio and sends them to the connected [`SpectrumOutput`].
+pub struct SpectrumInput {
+    /// Takes care of the STFT process and the FFTs.
+    spectral: SpectralHelper,
+    config: SpectrumConfig,
+
+    /// The weights used for the ballistics. If a bin's new magnitude is higher than its previous
+    /// magnitude, then the old value is multiplied by the attack weight and the new value by one
+    /// minus this weight. Same for the release weight.
+    attack_weight: f32,
+    release_weight: f32,
+    /// Scales the FFT bins' magnitudes to compensate for the analysis window's coherent gain and
+    /// the FFT's lack of normalization.
+    magnitude_scale: f32,
+
+    /// The FFT bins' magnitudes for the current window, averaged over all channels when using
+    /// [`SpectrumChannelMode::Average`].
+    window_magnitudes: Vec<f32>,
+    /// The smoothed FFT bin magnitudes.
+    smoothed_magnitudes: Vec<f32>,
+    /// The FFT bins used for every logarithmically spaced bin. Empty when using linear binning.
+    log_bins: Vec<LogBin>,
+    /// The spectrum that gets copied to the triple buffer. This also contains the bins' center
+    /// frequencies.
+    spectrum: Spectrum,
+
+    /// A way to send data to the corresponding [`SpectrumOutput`].
+    triple_buffer_input: triple_buffer::Input<Spectrum>,
+}
+
+/// Receives spectra computed by a [`SpectrumInput`]. This should be moved to the plugin's editor.
+pub struct SpectrumOutput {
+    triple_buffer_output: triple_buffer::Output<Spectrum>,
+}
+
+/// The FFT bins used to compute a logarithmically spaced bin.
+#[derive(Debug, Clone, Copy)]
+enum LogBin {
+    /// Use the highest magnitude from the FFT bins in this inclusive range.
+    Range(usize, usize),
+    /// Linearly interpolate between this FFT bin and the next one.
+    Interpolate(usize, f32),
+}
+
+impl Default for SpectrumConfig {
+    fn default() -> Self {
+        Self {
+            window_size: 2048,
+            overlap_times: 2,
+            attack_ms: 0.0,
+            release_ms: 100.0,
+            binning: SpectrumBinning::Linear,
+            normalization: SpectrumNormalization::Amplitude,
+            channel_mode: SpectrumChannelMode::Average,
+        }
+    }
+}
+
+impl SpectrumBinning {
+    /// The number of bins in a [`Spectrum`] for this binning mode and the given FFT window size.
+    pub fn num_bins(&self, window_size: usize) -> usize {
+        match self {
+            SpectrumBinning::Linear => window_size / 2 + 1,
+            SpectrumBinning::Logarithmic { num_bins, .. } => *num_bins,
+        }
+    }
+}
+
+impl SpectrumInput {
+    /// Create a new spectrum input and output pair for the specified number of channels. The output
+    /// should be moved to the editor. [`update_sample_rate()`][Self::update_sample_rate()] needs to
+    /// be called in the plugin's `initialize()` function before computing any spectra.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_channels`, the window size, or the overlap amount are zero, or if logarithmic
+    /// binning is used with fewer than two bins or with a non-positive frequency range.
+    pub fn new(num_channels: usize, config: SpectrumConfig) -> (SpectrumInput, SpectrumOutput) {
+        if let SpectrumBinning::Logarithmic {
+            min_frequency,
+            max_frequency,
+            num_bins,
+        } = config.binning
+        {
+            assert!(num_bins >= 2);
+            assert!(min_frequency > 0.0 && max_frequency > min_frequency);
+        }
+
+        let spectral = SpectralHelper::new(num_channels, config.window_size, config.overlap_times);
+        let num_fft_bins = spectral.num_bins();
+        let num_bins = config.binning.num_bins(config.window_size);
+        let spectrum = Spectrum {
+            magnitudes: vec![0.0; num_bins],
+            frequencies: vec![0.0; num_bins],
+        };
+
+        let (triple_buffer_input, triple_buffer_output) = TripleBuffer::new(&spectrum).split();
+
+        let magnitude_scale = match config.normalization {
+            // The analysis window's coherent gain is its average value, and the FFT adds up all of
+            // the window's samples. A sine wave's energy is split evenly between the positive and
+            // the negative frequencies, hence the factor two.
+            SpectrumNormalization::Amplitude => {
+                let window_sum: f32 = spectral.analysis_window().iter().sum();
+                2.0 / window_sum
+            }
+            SpectrumNormalization::WindowSize => (config.window_size as f32).recip(),
+        };
+
+        let input = Self {
+            spectral,
+            config,
+
+            // These are set in `update_sample_rate()`
+            attack_weight: 0.0,
+            release_weight: 0.0,
+            magnitude_scale,
+
+            window_magnitudes: vec![0.0; num_fft_bins],
+            smoothed_magnitudes: vec![0.0; num_fft_bins],
+            log_bins: Vec::with_capacity(num_bins),
+            spectrum,
+
+            triple_buffer_input,
+        };
+        let output = SpectrumOutput {
+            triple_buffer_output,
+        };
+
+        (input, output)
+    }
+
+    /// The configuration this input was created with.
+    pub fn config(&self) -> &SpectrumConfig {
+        &self.config
+    }
+
+    /// Update the ballistics and the bin frequencies for the specified sample rate. Call this in
+    /// your plugin's `initialize()` function.
+    pub fn update_sample_rate(&mut self, sample_rate: f32) {
+        // The spectrum gets updated once every hop, or once per channel every hop
+        let hop_size = self.config.window_size / self.config.overlap_times;
+        let updates_per_hop = match self.config.channel_mode {
+            SpectrumChannelMode::Average => 1,
+            SpectrumChannelMode::Sequential => self.spectral.num_channels(),
+        };
+        let effective_sample_rate = sample_rate / hop_size as f32 * updates_per_hop as f32;
+        self.attack_weight = ballistics_weight(self.config.attack_ms, effective_sample_rate);
+        self.release_weight = ballistics_weight(self.config.release_ms, effective_sample_rate);
+
+        match self.config.binning {
+            SpectrumBinning::Linear => {
+                for (bin_idx, frequency) in self.spectrum.frequencies.iter_mut().enumerate() {
+                    *frequency = self.spectral.bin_frequency(bin_idx, sample_rate);
+                }
+            }
+            SpectrumBinning::Logarithmic {
+                min_frequency,
+                max_frequency,
+                num_bins,
+            } => {
+                let max_fft_bin = self.spectral.num_bins() - 1;
+                let hz_per_fft_bin = sample_rate / self.config.window_size as f32;
+                let log_step = (max_frequency / min_frequency).ln() / (num_bins - 1) as f32;
+                let frequency_at = |idx: f32| min_frequency * (log_step * idx).exp();
+
+                self.log_bins.clear();
+                for (bin_idx, frequency) in self.spectrum.frequencies.iter_mut().enumerate() {
+                    *frequency = frequency_at(bin_idx as f32);
+
+                    // Each bin covers the range halfway between it and its neighbours
+                    let lower_fft_bin =
+                        (frequency_at(bin_idx as f32 - 0.5) / hz_per_fft_bin).ceil();
+                    let upper_fft_bin =
+                        (frequency_at(bin_idx as f32 + 0.5) / hz_per_fft_bin).floor();
+                    let log_bin = if upper_fft_bin >= lower_fft_bin {
+                        LogBin::Range(
+                            (lower_fft_bin as usize).min(max_fft_bin),
+                            (upper_fft_bin as usize).min(max_fft_bin),
+                        )
+                    } else {
+                        let fft_bin = *frequency / hz_per_fft_bin;
+                        let fft_bin_idx = (fft_bin.floor() as usize).min(max_fft_bin - 1);
+                        LogBin::Interpolate(fft_bin_idx, fft_bin - fft_bin_idx as f32)
+                    };
+
+                    self.log_bins.push(log_bin);
+                }
+            }
+        }
+    }
+
+    /// Compute the spectrum for a buffer and publish it to the corresponding [`SpectrumOutput`]
+    /// whenever a new window has been analyzed. This does not allocate, so it can safely be called
+    /// from the audio thread. Consider only calling this while the editor is open.
+    pub fn compute(&mut self, buffer: &Buffer) {
+        let Self {
+            spectral,
+            config,
+            attack_weight,
+            release_weight,
+            magnitude_scale,
+            window_magnitudes,
+            smoothed_magnitudes,
+            log_bins,
+            spectrum,
+            triple_buffer_input,
+        } = self;
+
+        let num_channels = spectral.num_channels();
+        let channel_mode = config.channel_mode;
+        let channel_weight = *magnitude_scale / num_channels as f32;
+        spectral.process_analyze_only(buffer, |channel_idx, bins| {
+            match channel_mode {
+                SpectrumChannelMode::Average => {
+                    if channel_idx == 0 {
+                        window_magnitudes.fill(0.0);
+                    }
+                    for (bin, magnitude) in bins.iter().zip(window_magnitudes.iter_mut()) {
+                        *magnitude += bin.norm() * channel_weight;
+                    }
+
+                    if channel_idx != num_channels - 1 {
+                        return;
+                    }
+                }
+                SpectrumChannelMode::Sequential => {
+                    for (bin, magnitude) in bins.iter().zip(window_magnitudes.iter_mut()) {
+                        *magnitude = bin.norm() * *magnitude_scale;
+                    }
+                }
+            }
+
+            for (new_magnitude, smoothed_magnitude) in
+                window_magnitudes.iter().zip(smoothed_magnitudes.iter_mut())
+            {
+                let weight = if *new_magnitude > *smoothed_magnitude {
+                    *attack_weight
+                } else {
+                    *release_weight
+                };
+
+                *smoothed_magnitude =
+                    (*smoothed_magnitude * weight) + (new_magnitude * (1.0 - weight));
+            }
+
+            if log_bins.is_empty() {
+                spectrum.magnitudes.copy_from_slice(smoothed_magnitudes);
+            } else {
+                for (log_bin, magnitude) in log_bins.iter().zip(spectrum.magnitudes.iter_mut()) {
+                    *magnitude = match *log_bin {
+                        LogBin::Range(start, end) => smoothed_magnitudes[start..=end]
+                            .iter()
+                            .fold(0.0f32, |max, magnitude| max.max(*magnitude)),
+                        LogBin::Interpolate(idx, t) => {
+                            (smoothed_magnitudes[idx] * (1.0 - t))
+                                + (smoothed_magnitudes[idx + 1] * t)
+                        }
+                    };
+                }
+            }
+
+            // The triple buffer's input buffer may contain older data, so both vectors are
+            // overwritten entirely. This does not allocate since the sizes never change.
+            let published_spectrum = triple_buffer_input.input_buffer();
+            published_spectrum
+                .magnitudes
+                .copy_from_slice(&spectrum.magnitudes);
+            published_spectrum
+                .frequencies
+                .copy_from_slice(&spectrum.frequencies);
+            triple_buffer_input.publish();
+        });
+    }
+}
+
+impl SpectrumOutput {
+    /// Get the most recently published spectrum.
+    pub fn read(&mut self) -> &Spectrum {
+        self.triple_buffer_output.read()
+    }
+
+    /// Whether a new spectrum has been published since the last call to [`read()`][Self::read()].
+    pub fn updated(&self) -> bool {
+        self.triple_buffer_output.updated()
+    }
+}
+
+/// Compute the weight for a one-pole smoother that moves 12 dB towards the new value in `time_ms`
+/// milliseconds when updated at `sample_rate` Hz.
+fn ballistics_weight(time_ms: f32, sample_rate: f32) -> f32 {
+    let time_samples = time_ms / 1000.0 * sample_rate;
+    if time_samples <= 0.0 {
+        0.0
+    } else {
+        0.25f64.powf((time_samples as f64).recip()) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    /// Feed a sine wave to a spectrum input and return the last published spectrum.
+    fn analyze_sine(config: SpectrumConfig, frequency: f32, sample_rate: f32) -> Spectrum {
+        let (mut input, mut output) = SpectrumInput::new(1, config);
+        input.update_sample_rate(sample_rate);
+
+        let mut samples: Vec<f32> = (0..16384)
+            .map(|i| (i as f32 / sample_rate * frequency * TAU).sin())
+            .collect();
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.with_raw_vec(|output_slices| {
+                *output_slices = vec![&mut samples[..]];
+            })
+        };
+        input.compute(&buffer);
+
+        assert!(output.updated());
+        output.read().clone()
+    }
+
+    #[test]
+    fn linear_peak() {
+        let spectrum = analyze_sine(SpectrumConfig::default(), 1000.0, 48000.0);
+        assert_eq!(spectrum.magnitudes.len(), 1025);
+
+        let (peak_idx, peak_magnitude) = spectrum.magnitudes.iter().enumerate().fold(
+            (0, 0.0f32),
+            |(max_idx, max), (idx, magnitude)| {
+                if *magnitude > max {
+                    (idx, *magnitude)
+                } else {
+                    (max_idx, max)
+                }
+            },
+        );
+        approx::assert_abs_diff_eq!(spectrum.frequencies[peak_idx], 1000.0, epsilon = 24.0);
+        // The sine's frequency falls between two bins, so some of its energy leaks to the
+        // neighbouring bin
+        assert!(peak_magnitude > 0.6 && peak_magnitude <= 1.01);
+    }
+
+    #[test]
+    fn logarithmic_bins() {
+        let config = SpectrumConfig {
+            binning: SpectrumBinning::Logarithmic {
+                min_frequency: 20.0,
+                max_frequency: 20000.0,
+                num_bins: 64,
+            },
+            ..SpectrumConfig::default()
+        };
+        let spectrum = analyze_sine(config, 1000.0, 48000.0);
+        assert_eq!(spectrum.magnitudes.len(), 64);
+        approx::assert_relative_eq!(spectrum.frequencies[0], 20.0);
+        approx::assert_relative_eq!(spectrum.frequencies[63], 20000.0, epsilon = 0.1);
+
+        let loudest_frequency = spectrum
+            .frequencies
+            .iter()
+            .zip(&spectrum.magnitudes)
+            .fold(
+                (0.0, 0.0f32),
+                |(max_frequency, max), (frequency, magnitude)| {
+                    if *magnitude > max {
+                        (*frequency, *magnitude)
+                    } else {
+                        (max_frequency, max)
+                    }
+                },
+            )
+            .0;
+        approx::assert_relative_eq!(loudest_frequency, 1000.0, max_relative = 0.1);
+    }
+
+    #[test]
+    fn window_size_normalization() {
+        let amplitude = analyze_sine(SpectrumConfig::default(), 1500.0, 48000.0);
+        let window_size = analyze_sine(
+            SpectrumConfig {
+                normalization: SpectrumNormalization::WindowSize,
+                ..SpectrumConfig::default()
+            },
+            1500.0,
+            48000.0,
+        );
+
+        // The Hann window's coherent gain is 0.5, and this doesn't include the factor two
+        let amplitude_peak = amplitude.magnitudes.iter().copied().fold(0.0f32, f32::max);
+        let window_size_peak = window_size
+            .magnitudes
+            .iter()
+            .copied()
+            .fold(0.0f32, f32::max);
+        approx::assert_relative_eq!(window_size_peak / amplitude_peak, 0.25, max_relative = 0.01);
+    }
+
+    #[test]
+    fn sequential_channels() {
+        let config = SpectrumConfig {
+            channel_mode: SpectrumChannelMode::Sequential,
+            ..SpectrumConfig::default()
+        };
+        let (mut input, mut output) = SpectrumInput::new(2, config);
+        input.update_sample_rate(48000.0);
+
+        // With an immediate attack the spectrum jumps up to the louder channel instead of averaging
+        // the two channels. The spectrum is published after each channel, so the louder channel
+        // comes last here to avoid reading the spectrum after it has already started decaying.
+        let mut loud: Vec<f32> = (0..16384)
+            .map(|i| (i as f32 / 48000.0 * 1500.0 * TAU).sin())
+            .collect();
+        let mut silent = vec![0.0f32; 16384];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.with_raw_vec(|output_slices| {
+                *output_slices = vec![&mut silent[..], &mut loud[..]];
+            })
+        };
+        input.compute(&buffer);
+
+        assert!(output.updated());
+        let peak = output
+            .read()
+            .magnitudes
+            .iter()
+            .copied()
+            .fold(0.0f32, f32::max);
+        assert!(peak > 0.9 && peak <= 1.01);
+    }
+}
@@ -55,6 +55,9 @@ pub struct StftHelper<const NUM_SIDECHAIN_INPUTS: usize = 0> {
     current_pos: usize,
     /// If padding is used, then this much extra capacity has been added to the buffers.
     padding: usize,
+    /// The overlap-added output is multiplied by this value before it's written back to the main
+    /// buffer. Defaults to 1.0. See [`StftHelper::set_output_gain()`].
+    output_gain: f32,
 }
 
 /// Marker struct for the version without sidechaining.
@@ -208,6 +211,7 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
 
             current_pos: 0,
             padding,
+            output_gain: 1.0,
         }
     }
 
@@ -245,6 +249,16 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
         self.current_pos = 0;
     }
 
+    /// Multiply the overlap-added output of [`process_overlap_add()`][Self::process_overlap_add()]
+    /// by this value before writing it back to the main buffer. This defaults to 1.0. Setting this
+    /// to [`cola_normalization_factor()`][crate::util::window::cola_normalization_factor()] for
+    /// the windows and overlap amount used in the callback, divided by the window size when using
+    /// an unnormalized FFT, compensates for the windowing and the overlap without having to scale
+    /// the blocks in the callback.
+    pub fn set_output_gain(&mut self, gain: f32) {
+        self.output_gain = gain;
+    }
+
     /// The number of channels this `StftHelper` was configured for
     pub fn num_channels(&self) -> usize {
         self.main_input_ring_buffers.len()
@@ -272,6 +286,12 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
     ///
     /// Since there are a couple different ways to do it, any window functions needs to be applied
     /// in the callbacks. Check the [`nih_plug::util::window`][crate::util::window] module for more information.
+    /// Because of that this helper cannot know which windows are used, so the output is not
+    /// gain compensated automatically. It needs to be scaled by
+    /// [`cola_normalization_factor()`][crate::util::window::cola_normalization_factor()] to
+    /// compensate for the windowing and the overlap. This can either be done in the callback, or
+    /// by passing that factor to [`set_output_gain()`][Self::set_output_gain()] whenever the
+    /// window or the overlap amount changes.
     ///
     /// For efficiency's sake this function will reuse the same vector for all calls to
     /// `process_cb`. This means you can only access a single channel's worth of windowed data at a
@@ -294,7 +314,7 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
         overlap_times: usize,
         mut process_cb: F,
     ) where
-        M: StftInputMut,
+        M: StftInputMut + ?Sized,
         F: FnMut(usize, &mut [f32]),
     {
         self.process_overlap_add_sidechain(
@@ -321,8 +341,8 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
         overlap_times: usize,
         mut process_cb: F,
     ) where
-        M: StftInputMut,
-        S: StftInput,
+        M: StftInputMut + ?Sized,
+        S: StftInput + ?Sized,
         F: FnMut(usize, Option<usize>, &mut [f32]),
     {
         assert_eq!(
@@ -337,6 +357,7 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
         let num_channels = main_buffer.num_channels();
         let block_size = self.main_input_ring_buffers[0].len();
         let window_interval = (block_size / overlap_times) as i32;
+        let output_gain = self.output_gain;
         let mut already_processed_samples = 0;
         while already_processed_samples < main_buffer_len {
             let remaining_samples = main_buffer_len - already_processed_samples;
@@ -369,7 +390,7 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
                             .get_unchecked_mut(self.current_pos + sample_offset)
                     };
                     *input_ring_buffer_sample = *sample;
-                    *sample = *output_ring_buffer_sample;
+                    *sample = *output_ring_buffer_sample * output_gain;
                     // Very important, or else we'll overlap-add ourselves into a feedback hell
                     *output_ring_buffer_sample = 0.0;
                 }
@@ -503,7 +524,7 @@ impl<const NUM_SIDECHAIN_INPUTS: usize> StftHelper<NUM_SIDECHAIN_INPUTS> {
         overlap_times: usize,
         mut analyze_cb: F,
     ) where
-        B: StftInput,
+        B: StftInput + ?Sized,
         F: FnMut(usize, &mut [f32]),
     {
         assert_eq!(buffer.num_channels(), self.main_input_ring_buffers.len());
@@ -597,3 +618,38 @@ fn add_scratch_to_ring_buffer(scratch_buffer: &[f32], current_pos: usize, ring_b
         *ring_sample += *scratch_sample;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::window;
+
+    #[test]
+    fn output_gain_compensates_overlap() {
+        const WINDOW_SIZE: usize = 64;
+        const OVERLAP_TIMES: usize = 4;
+
+        let mut stft: StftHelper = StftHelper::new(1, WINDOW_SIZE, 0);
+        let window_function = window::hann(WINDOW_SIZE);
+        stft.set_output_gain(window::cola_normalization_factor(
+            &window_function,
+            None,
+            OVERLAP_TIMES,
+        ));
+
+        let mut samples = vec![1.0f32; WINDOW_SIZE * 8];
+        stft.process_overlap_add(
+            &mut [&mut samples[..]][..],
+            OVERLAP_TIMES,
+            |_, real_fft_buffer| {
+                window::multiply_with_window(real_fft_buffer, &window_function);
+            },
+        );
+
+        // The output is delayed by one window, and it takes another window for all of the
+        // overlapping windows to be added up
+        for sample in &samples[WINDOW_SIZE * 2..] {
+            approx::assert_relative_eq!(*sample, 1.0, epsilon = 0.05);
+        }
+    }
+}
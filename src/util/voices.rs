@@ -0,0 +1,1045 @@
+//! Voice management for polyphonic instruments. See [`VoiceManager`] for more information.
+
+use crate::context::process::ProcessContext;
+use crate::midi::{control_change, NoteEvent};
+use crate::plugin::Plugin;
+
+/// The plugin's per-voice state, managed by a [`VoiceManager`]. The voice manager decides when
+/// voices are started, released, and terminated, and it routes polyphonic modulation and note
+/// expressions to the right voices. Rendering the voices is still up to the plugin.
+pub trait Voice {
+    /// Start playing a new note. This is called both for unused voices and for voices that are
+    /// being stolen, so any state left over from a previous note should be reset here.
+    fn start(&mut self, note: VoiceNote, sample_rate: f32);
+
+    /// Change the note this voice is playing without retriggering it. This is only used in
+    /// [`VoiceMode::Legato`], where it's called when a new key is pressed while the voice is still
+    /// held, and when a key is released while other keys are still being held. Defaults to
+    /// retriggering the voice using [`start()`][Self::start()].
+    fn legato(&mut self, note: VoiceNote, sample_rate: f32) {
+        self.start(note, sample_rate);
+    }
+
+    /// The note has been released, either because the key has been released or because the
+    /// sustain pedal has been lifted after the key was released. This should start the voice's
+    /// release stage.
+    fn release(&mut self, sample_rate: f32);
+
+    /// Whether the voice has finished playing. Finished voices are freed and the host is informed
+    /// that they have been terminated in [`VoiceManager::terminate_finished_voices()`]. This is
+    /// usually the case when the voice's release stage has ended.
+    fn is_finished(&self) -> bool;
+
+    /// Polyphonic modulation was sent for this voice. See [`NoteEvent::PolyModulation`] for more
+    /// information on how to handle this. `is_new_voice` is set when the voice was started during
+    /// the current block. In that case the modulated value should be used immediately instead of
+    /// smoothing towards it.
+    #[allow(unused_variables)]
+    fn poly_modulation(
+        &mut self,
+        poly_modulation_id: u32,
+        normalized_offset: f32,
+        is_new_voice: bool,
+        sample_rate: f32,
+    ) {
+    }
+
+    /// A polyphonically modulated parameter's monophonic value was changed. This is sent to all
+    /// active voices. See [`NoteEvent::MonoAutomation`] for more information on how to handle
+    /// this.
+    #[allow(unused_variables)]
+    fn mono_automation(
+        &mut self,
+        poly_modulation_id: u32,
+        normalized_value: f32,
+        sample_rate: f32,
+    ) {
+    }
+
+    /// A note expression was sent for the note played by this voice.
+    #[allow(unused_variables)]
+    fn note_expression(&mut self, expression: NoteExpression, sample_rate: f32) {}
+}
+
+/// The note a [`Voice`] should play.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoiceNote {
+    /// The note's voice ID. If the host did not provide one, then this is computed using
+    /// [`compute_fallback_voice_id()`].
+    pub voice_id: i32,
+    /// The note's channel, in `0..16`.
+    pub channel: u8,
+    /// The note's MIDI key number, in `0..128`.
+    pub note: u8,
+    /// The note's velocity, in `[0, 1]`.
+    pub velocity: f32,
+}
+
+/// A polyphonic note expression, converted from the `NoteEvent::Poly*` events. See the
+/// corresponding [`NoteEvent`] variants for the value ranges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoteExpression {
+    /// From [`NoteEvent::PolyPressure`].
+    Pressure(f32),
+    /// From [`NoteEvent::PolyVolume`].
+    Volume(f32),
+    /// From [`NoteEvent::PolyPan`].
+    Pan(f32),
+    /// From [`NoteEvent::PolyTuning`].
+    Tuning(f32),
+    /// From [`NoteEvent::PolyVibrato`].
+    Vibrato(f32),
+    /// From [`NoteEvent::PolyExpression`].
+    Expression(f32),
+    /// From [`NoteEvent::PolyBrightness`].
+    Brightness(f32),
+}
+
+/// Determines how notes are assigned to voices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoiceMode {
+    /// Every note gets its own voice, up to the manager's polyphony.
+    Poly,
+    /// Only a single voice plays at a time. Pressing a new key retriggers the voice, and releasing
+    /// a key while other keys are still held returns to the most recently pressed held key.
+    Mono,
+    /// The same as [`Mono`][Self::Mono], but overlapping notes change the playing voice's note
+    /// through [`Voice::legato()`] instead of retriggering it.
+    Legato,
+}
+
+/// Determines which voice is stolen when a new note is played while all voices are in use. Voices
+/// that are already in their release stage are always stolen first, starting with the oldest one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StealingPolicy {
+    /// Steal the voice that was started first.
+    Oldest,
+    /// Steal the voice playing the lowest note.
+    LowestNote,
+    /// Steal the voice playing the highest note.
+    HighestNote,
+    /// Steal the voice with the lowest velocity.
+    LowestVelocity,
+    /// Never steal voices that are still being held. New notes are ignored instead.
+    None,
+}
+
+/// Allocates voices for polyphonic instruments. The manager owns a fixed number of the plugin's
+/// [`Voice`]s, which are allocated up front. Every incoming [`NoteEvent`] should be passed to
+/// [`handle_event()`][Self::handle_event()], and after rendering the active voices for a block
+/// the plugin should call [`terminate_finished_voices()`][Self::terminate_finished_voices()].
+///
+/// This takes care of:
+///
+/// - Allocating voices for new notes, stealing voices according to the [`StealingPolicy`] when
+///   all voices are in use.
+/// - Monophonic and legato playing with last-note priority through [`VoiceMode`].
+/// - Sustain pedal handling. This requires [`MidiConfig::MidiCCs`][crate::prelude::MidiConfig]
+///   since the pedal is sent as CC 64.
+/// - Choke events.
+/// - Sending [`NoteEvent::VoiceTerminated`] events whenever a voice is stolen, choked, or has
+///   finished playing. This is required when using polyphonic modulation.
+/// - Informing the host about the voice capacity through
+///   [`ProcessContext::set_current_voice_capacity()`] whenever the polyphony or the voice mode
+///   changes. The initial capacity should still be reported by the plugin in its `initialize()`
+///   function using [`voice_capacity()`][Self::voice_capacity()].
+/// - Routing polyphonic modulation, monophonic automation for polyphonically modulated
+///   parameters, and note expressions to the right voices.
+#[derive(Debug)]
+pub struct VoiceManager<V> {
+    /// The plugin's voices. These are allocated up front.
+    voices: Vec<V>,
+    /// The state for each voice in `voices`, or `None` if the voice is not in use.
+    states: Vec<Option<VoiceState>>,
+
+    /// The number of voices that can be used in [`VoiceMode::Poly`]. This is at most
+    /// `voices.len()`.
+    polyphony: usize,
+    mode: VoiceMode,
+    stealing_policy: StealingPolicy,
+    /// The capacity last reported to the host, if any.
+    reported_capacity: Option<usize>,
+
+    /// Whether the sustain pedal is pressed, per MIDI channel.
+    sustain_pedal: [bool; 16],
+    /// The keys that are currently being held in the monophonic modes, with the most recently
+    /// pressed key at the end. This has capacity for every key on every channel so it never needs
+    /// to reallocate.
+    held_notes: Vec<VoiceNote>,
+    /// Incremented for every started voice. Used to find the oldest voice.
+    next_voice_age: u64,
+}
+
+/// The state of an active voice.
+#[derive(Debug, Clone, Copy)]
+struct VoiceState {
+    note: VoiceNote,
+    /// Used to find the oldest voice. A voice is older than another voice if this value is lower.
+    age: u64,
+    /// Set when the voice was started during the current block, cleared in
+    /// [`VoiceManager::terminate_finished_voices()`].
+    is_new: bool,
+    /// Whether the key has been released while the sustain pedal was held down. The voice is
+    /// released when the pedal is lifted.
+    sustained: bool,
+    /// Whether [`Voice::release()`] has been called for this voice.
+    releasing: bool,
+}
+
+impl VoiceState {
+    /// Whether a note event with this voice ID, channel and note refers to this voice. If the
+    /// event has a voice ID then only the voice ID is compared.
+    fn matches(&self, voice_id: Option<i32>, channel: u8, note: u8) -> bool {
+        match voice_id {
+            Some(voice_id) => self.note.voice_id == voice_id,
+            None => self.note.channel == channel && self.note.note == note,
+        }
+    }
+
+    fn terminated_event(&self, timing: u32) -> NoteEvent {
+        NoteEvent::VoiceTerminated {
+            timing,
+            voice_id: Some(self.note.voice_id),
+            channel: self.note.channel,
+            note: self.note.note,
+        }
+    }
+}
+
+impl<V: Voice> VoiceManager<V> {
+    /// Create a voice manager for `num_voices` voices. `create_voice` is called with the index of
+    /// each voice to create the plugin's voice state. This allocates, so it should not be called
+    /// from the audio thread. The manager starts out in [`VoiceMode::Poly`] with all voices
+    /// available and with the [`StealingPolicy::Oldest`] stealing policy.
+    pub fn new(num_voices: usize, create_voice: impl FnMut(usize) -> V) -> Self {
+        nih_debug_assert_ne!(num_voices, 0);
+
+        Self {
+            voices: (0..num_voices).map(create_voice).collect(),
+            states: vec![None; num_voices],
+
+            polyphony: num_voices,
+            mode: VoiceMode::Poly,
+            stealing_policy: StealingPolicy::Oldest,
+            reported_capacity: None,
+
+            sustain_pedal: [false; 16],
+            held_notes: Vec::with_capacity(128 * 16),
+            next_voice_age: 0,
+        }
+    }
+
+    /// Free all voices and forget about any held keys and sustain pedals. This does not send any
+    /// [`NoteEvent::VoiceTerminated`] events, so it should be called from the plugin's `reset()`
+    /// function.
+    pub fn reset(&mut self) {
+        self.states.fill(None);
+        self.sustain_pedal = [false; 16];
+        self.held_notes.clear();
+        self.next_voice_age = 0;
+    }
+
+    /// The total number of voices, regardless of the current polyphony.
+    pub fn num_voices(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// The number of voices that are currently in use.
+    pub fn num_active_voices(&self) -> usize {
+        self.states.iter().filter(|state| state.is_some()).count()
+    }
+
+    /// The maximum number of voices that can currently play at the same time. This is the
+    /// polyphony in [`VoiceMode::Poly`], and 1 in the monophonic modes.
+    pub fn voice_capacity(&self) -> usize {
+        match self.mode {
+            VoiceMode::Poly => self.polyphony,
+            VoiceMode::Mono | VoiceMode::Legato => 1,
+        }
+    }
+
+    /// The current polyphony.
+    pub fn polyphony(&self) -> usize {
+        self.polyphony
+    }
+
+    /// Change the number of voices that can be used in [`VoiceMode::Poly`]. This is clamped to
+    /// `[1, num_voices]`. When the polyphony is decreased, the voices that no longer fit are
+    /// terminated during the next [`handle_event()`][Self::handle_event()] or
+    /// [`terminate_finished_voices()`][Self::terminate_finished_voices()] call, which also
+    /// informs the host about the new capacity.
+    pub fn set_polyphony(&mut self, polyphony: usize) {
+        self.polyphony = polyphony.clamp(1, self.voices.len());
+    }
+
+    /// The current voice mode.
+    pub fn mode(&self) -> VoiceMode {
+        self.mode
+    }
+
+    /// Change the voice mode. When switching to a monophonic mode, all but the first voice are
+    /// terminated the same way as when changing the polyphony.
+    pub fn set_mode(&mut self, mode: VoiceMode) {
+        if mode != self.mode {
+            self.mode = mode;
+            self.held_notes.clear();
+        }
+    }
+
+    /// The current voice stealing policy.
+    pub fn stealing_policy(&self) -> StealingPolicy {
+        self.stealing_policy
+    }
+
+    /// Change the voice stealing policy.
+    pub fn set_stealing_policy(&mut self, policy: StealingPolicy) {
+        self.stealing_policy = policy;
+    }
+
+    /// All voices, including voices that are not in use. This can be used to configure the voices
+    /// in the plugin's `initialize()` and `reset()` functions.
+    pub fn voices_mut(&mut self) -> &mut [V] {
+        &mut self.voices
+    }
+
+    /// Iterate over the voices that are currently in use, along with the notes they're playing.
+    /// This is what the plugin should render.
+    pub fn active_voices_mut(&mut self) -> impl Iterator<Item = (&VoiceNote, &mut V)> {
+        self.states
+            .iter()
+            .zip(self.voices.iter_mut())
+            .filter_map(|(state, voice)| state.as_ref().map(|state| (&state.note, voice)))
+    }
+
+    /// Handle a note event. Events that are not relevant to voice management are ignored, so every
+    /// event can be passed to this function. The plugin is free to handle the events itself as
+    /// well.
+    pub fn handle_event<P: Plugin>(
+        &mut self,
+        context: &mut impl ProcessContext<P>,
+        event: NoteEvent,
+    ) {
+        let sample_rate = context.transport().sample_rate;
+
+        let new_capacity = self.update_capacity(event.timing(), &mut |event| {
+            context.send_event(event);
+        });
+        if let Some(capacity) = new_capacity {
+            context.set_current_voice_capacity(capacity as u32);
+        }
+
+        self.handle_event_with_output(event, sample_rate, &mut |event| {
+            context.send_event(event);
+        });
+    }
+
+    /// Free all voices for which [`Voice::is_finished()`] returns true, and inform the host that
+    /// those voices have been terminated. This should be called after rendering each block, with
+    /// `timing` being the sample index of the end of the block.
+    pub fn terminate_finished_voices<P: Plugin>(
+        &mut self,
+        context: &mut impl ProcessContext<P>,
+        timing: u32,
+    ) {
+        let new_capacity = self.update_capacity(timing, &mut |event| {
+            context.send_event(event);
+        });
+        if let Some(capacity) = new_capacity {
+            context.set_current_voice_capacity(capacity as u32);
+        }
+
+        self.terminate_finished_voices_with_output(timing, &mut |event| {
+            context.send_event(event);
+        });
+    }
+
+    /// Terminate voices that fall outside of the current capacity if the capacity has changed
+    /// since the last time it was reported. Returns the new capacity if it needs to be reported to
+    /// the host.
+    fn update_capacity(
+        &mut self,
+        timing: u32,
+        send_event: &mut impl FnMut(NoteEvent),
+    ) -> Option<usize> {
+        let capacity = self.voice_capacity();
+        if self.reported_capacity == Some(capacity) {
+            return None;
+        }
+
+        for state in &mut self.states[capacity..] {
+            if let Some(state) = state.take() {
+                send_event(state.terminated_event(timing));
+            }
+        }
+        self.reported_capacity = Some(capacity);
+
+        Some(capacity)
+    }
+
+    /// The implementation for [`handle_event()`][Self::handle_event()], with the outgoing events
+    /// sent to a callback.
+    fn handle_event_with_output(
+        &mut self,
+        event: NoteEvent,
+        sample_rate: f32,
+        send_event: &mut impl FnMut(NoteEvent),
+    ) {
+        match event {
+            NoteEvent::NoteOn {
+                timing,
+                voice_id,
+                channel,
+                note,
+                velocity,
+            } => {
+                let note = VoiceNote {
+                    voice_id: voice_id.unwrap_or_else(|| compute_fallback_voice_id(note, channel)),
+                    channel,
+                    note,
+                    velocity,
+                };
+
+                match self.mode {
+                    VoiceMode::Poly => self.start_poly_voice(timing, note, sample_rate, send_event),
+                    VoiceMode::Mono | VoiceMode::Legato => {
+                        self.held_notes.retain(|held_note| {
+                            !(held_note.channel == note.channel && held_note.note == note.note)
+                        });
+                        self.held_notes.push(note);
+
+                        self.switch_mono_voice(timing, note, sample_rate, send_event);
+                    }
+                }
+            }
+            NoteEvent::NoteOff {
+                timing,
+                voice_id,
+                channel,
+                note,
+                velocity: _,
+            } => {
+                if self.mode != VoiceMode::Poly {
+                    self.held_notes.retain(|held_note| match voice_id {
+                        Some(voice_id) => held_note.voice_id != voice_id,
+                        None => !(held_note.channel == channel && held_note.note == note),
+                    });
+
+                    // If other keys are still being held, then the voice returns to the most
+                    // recently pressed one instead of being released
+                    let releases_current_note = matches!(
+                        &self.states[0],
+                        Some(state) if state.matches(voice_id, channel, note) && !state.releasing
+                    );
+                    if releases_current_note {
+                        if let Some(&held_note) = self.held_notes.last() {
+                            self.switch_mono_voice(timing, held_note, sample_rate, send_event);
+                            return;
+                        }
+                    }
+                }
+
+                for (state, voice) in self.states.iter_mut().zip(self.voices.iter_mut()) {
+                    match state {
+                        Some(state)
+                            if state.matches(voice_id, channel, note)
+                                && !state.releasing
+                                && !state.sustained =>
+                        {
+                            if self.sustain_pedal[state.note.channel as usize] {
+                                state.sustained = true;
+                            } else {
+                                state.releasing = true;
+                                voice.release(sample_rate);
+                            }
+
+                            // There can be multiple overlapping voices for the same key if the
+                            // note event does not target a specific voice ID
+                            if voice_id.is_some() {
+                                break;
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            NoteEvent::Choke {
+                timing,
+                voice_id,
+                channel,
+                note,
+            } => {
+                self.held_notes.retain(|held_note| match voice_id {
+                    Some(voice_id) => held_note.voice_id != voice_id,
+                    None => !(held_note.channel == channel && held_note.note == note),
+                });
+
+                for state in self.states.iter_mut() {
+                    if matches!(state, Some(state) if state.matches(voice_id, channel, note)) {
+                        send_event(state.take().unwrap().terminated_event(timing));
+                    }
+                }
+            }
+            NoteEvent::MidiCC {
+                timing: _,
+                channel,
+                cc: control_change::DAMPER_PEDAL,
+                value,
+            } => {
+                let pedal_down = value >= 0.5;
+                let was_down =
+                    std::mem::replace(&mut self.sustain_pedal[channel as usize], pedal_down);
+
+                // Lifting the pedal releases all of the notes that were sustained by it
+                if was_down && !pedal_down {
+                    for (state, voice) in self.states.iter_mut().zip(self.voices.iter_mut()) {
+                        match state {
+                            Some(state) if state.sustained && state.note.channel == channel => {
+                                state.sustained = false;
+                                state.releasing = true;
+                                voice.release(sample_rate);
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+            }
+            NoteEvent::PolyModulation {
+                timing: _,
+                voice_id,
+                poly_modulation_id,
+                normalized_offset,
+            } => {
+                // The host will also send these events for voices that have already been
+                // terminated, so not finding the voice is not an error
+                for (state, voice) in self.states.iter().zip(self.voices.iter_mut()) {
+                    match state {
+                        Some(state) if state.note.voice_id == voice_id => {
+                            voice.poly_modulation(
+                                poly_modulation_id,
+                                normalized_offset,
+                                state.is_new,
+                                sample_rate,
+                            );
+                            break;
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            NoteEvent::MonoAutomation {
+                timing: _,
+                poly_modulation_id,
+                normalized_value,
+            } => {
+                for (_, voice) in self.active_voices_mut() {
+                    voice.mono_automation(poly_modulation_id, normalized_value, sample_rate);
+                }
+            }
+            NoteEvent::PolyPressure {
+                voice_id,
+                channel,
+                note,
+                pressure,
+                ..
+            } => self.send_note_expression(
+                voice_id,
+                channel,
+                note,
+                NoteExpression::Pressure(pressure),
+                sample_rate,
+            ),
+            NoteEvent::PolyVolume {
+                voice_id,
+                channel,
+                note,
+                gain,
+                ..
+            } => self.send_note_expression(
+                voice_id,
+                channel,
+                note,
+                NoteExpression::Volume(gain),
+                sample_rate,
+            ),
+            NoteEvent::PolyPan {
+                voice_id,
+                channel,
+                note,
+                pan,
+                ..
+            } => self.send_note_expression(
+                voice_id,
+                channel,
+                note,
+                NoteExpression::Pan(pan),
+                sample_rate,
+            ),
+            NoteEvent::PolyTuning {
+                voice_id,
+                channel,
+                note,
+                tuning,
+                ..
+            } => self.send_note_expression(
+                voice_id,
+                channel,
+                note,
+                NoteExpression::Tuning(tuning),
+                sample_rate,
+            ),
+            NoteEvent::PolyVibrato {
+                voice_id,
+                channel,
+                note,
+                vibrato,
+                ..
+            } => self.send_note_expression(
+                voice_id,
+                channel,
+                note,
+                NoteExpression::Vibrato(vibrato),
+                sample_rate,
+            ),
+            NoteEvent::PolyExpression {
+                voice_id,
+                channel,
+                note,
+                expression,
+                ..
+            } => self.send_note_expression(
+                voice_id,
+                channel,
+                note,
+                NoteExpression::Expression(expression),
+                sample_rate,
+            ),
+            NoteEvent::PolyBrightness {
+                voice_id,
+                channel,
+                note,
+                brightness,
+                ..
+            } => self.send_note_expression(
+                voice_id,
+                channel,
+                note,
+                NoteExpression::Brightness(brightness),
+                sample_rate,
+            ),
+            _ => (),
+        }
+    }
+
+    /// The implementation for
+    /// [`terminate_finished_voices()`][Self::terminate_finished_voices()], with the outgoing
+    /// events sent to a callback.
+    fn terminate_finished_voices_with_output(
+        &mut self,
+        timing: u32,
+        send_event: &mut impl FnMut(NoteEvent),
+    ) {
+        for (state, voice) in self.states.iter_mut().zip(self.voices.iter()) {
+            match state {
+                Some(voice_state) if voice.is_finished() => {
+                    send_event(voice_state.terminated_event(timing));
+                    *state = None;
+                }
+                Some(voice_state) => voice_state.is_new = false,
+                None => (),
+            }
+        }
+    }
+
+    /// Start a voice in [`VoiceMode::Poly`], stealing a voice if needed.
+    fn start_poly_voice(
+        &mut self,
+        timing: u32,
+        note: VoiceNote,
+        sample_rate: f32,
+        send_event: &mut impl FnMut(NoteEvent),
+    ) {
+        let capacity = self.voice_capacity();
+        let voice_idx = match self.states[..capacity]
+            .iter()
+            .position(|state| state.is_none())
+        {
+            Some(voice_idx) => voice_idx,
+            None => match self.find_voice_to_steal(capacity) {
+                Some(voice_idx) => {
+                    send_event(self.states[voice_idx].unwrap().terminated_event(timing));
+                    voice_idx
+                }
+                // This can only happen with `StealingPolicy::None`
+                None => return,
+            },
+        };
+
+        self.start_voice(voice_idx, note, sample_rate);
+    }
+
+    /// Find the index of the voice that should be stolen when all voices up to `capacity` are in
+    /// use.
+    fn find_voice_to_steal(&self, capacity: usize) -> Option<usize> {
+        let active_voices = || {
+            self.states[..capacity]
+                .iter()
+                .enumerate()
+                .filter_map(|(voice_idx, state)| state.as_ref().map(|state| (voice_idx, state)))
+        };
+
+        // Voices that are already releasing are always fair game
+        let oldest_releasing_voice = active_voices()
+            .filter(|(_, state)| state.releasing)
+            .min_by_key(|(_, state)| state.age);
+        if let Some((voice_idx, _)) = oldest_releasing_voice {
+            return Some(voice_idx);
+        }
+
+        // These use the voice's age as a tie breaker
+        let stolen_voice = match self.stealing_policy {
+            StealingPolicy::Oldest => active_voices().min_by_key(|(_, state)| state.age),
+            StealingPolicy::LowestNote => {
+                active_voices().min_by_key(|(_, state)| (state.note.note, state.age))
+            }
+            StealingPolicy::HighestNote => {
+                active_voices().min_by_key(|(_, state)| (u8::MAX - state.note.note, state.age))
+            }
+            StealingPolicy::LowestVelocity => active_voices().min_by(|(_, a), (_, b)| {
+                a.note
+                    .velocity
+                    .total_cmp(&b.note.velocity)
+                    .then(a.age.cmp(&b.age))
+            }),
+            StealingPolicy::None => None,
+        };
+
+        stolen_voice.map(|(voice_idx, _)| voice_idx)
+    }
+
+    /// Switch the single voice used in the monophonic modes to a new note. Depending on the voice
+    /// mode and the voice's current state this either retriggers the voice or changes the note
+    /// using [`Voice::legato()`]. The previous note's voice ID is always terminated.
+    fn switch_mono_voice(
+        &mut self,
+        timing: u32,
+        note: VoiceNote,
+        sample_rate: f32,
+        send_event: &mut impl FnMut(NoteEvent),
+    ) {
+        match &mut self.states[0] {
+            Some(state) if self.mode == VoiceMode::Legato && !state.releasing => {
+                if state.note.voice_id != note.voice_id {
+                    send_event(state.terminated_event(timing));
+                }
+
+                state.note = note;
+                state.sustained = false;
+                self.voices[0].legato(note, sample_rate);
+            }
+            state => {
+                if let Some(state) = state {
+                    send_event(state.terminated_event(timing));
+                }
+
+                self.start_voice(0, note, sample_rate);
+            }
+        }
+    }
+
+    /// Start the voice at `voice_idx`, overwriting its existing state.
+    fn start_voice(&mut self, voice_idx: usize, note: VoiceNote, sample_rate: f32) {
+        self.states[voice_idx] = Some(VoiceState {
+            note,
+            age: self.next_voice_age,
+            is_new: true,
+            sustained: false,
+            releasing: false,
+        });
+        self.next_voice_age = self.next_voice_age.wrapping_add(1);
+
+        self.voices[voice_idx].start(note, sample_rate);
+    }
+
+    /// Send a note expression to all voices matching the voice ID, or the channel and note if the
+    /// event does not have a voice ID.
+    fn send_note_expression(
+        &mut self,
+        voice_id: Option<i32>,
+        channel: u8,
+        note: u8,
+        expression: NoteExpression,
+        sample_rate: f32,
+    ) {
+        for (state, voice) in self.states.iter().zip(self.voices.iter_mut()) {
+            match state {
+                Some(state) if state.matches(voice_id, channel, note) => {
+                    voice.note_expression(expression, sample_rate);
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Compute a voice ID in case the host doesn't provide them. Polyphonic modulation will not work in
+/// this case, but playing notes will.
+pub const fn compute_fallback_voice_id(note: u8, channel: u8) -> i32 {
+    note as i32 | ((channel as i32) << 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 44_100.0;
+
+    #[derive(Debug, Default)]
+    struct TestVoice {
+        note: Option<VoiceNote>,
+        num_starts: usize,
+        released: bool,
+        finished: bool,
+        last_modulation: Option<(u32, f32, bool)>,
+        last_expression: Option<NoteExpression>,
+    }
+
+    impl Voice for TestVoice {
+        fn start(&mut self, note: VoiceNote, _sample_rate: f32) {
+            self.note = Some(note);
+            self.num_starts += 1;
+            self.released = false;
+            self.finished = false;
+        }
+
+        fn legato(&mut self, note: VoiceNote, _sample_rate: f32) {
+            self.note = Some(note);
+        }
+
+        fn release(&mut self, _sample_rate: f32) {
+            self.released = true;
+        }
+
+        fn is_finished(&self) -> bool {
+            self.finished
+        }
+
+        fn poly_modulation(
+            &mut self,
+            poly_modulation_id: u32,
+            normalized_offset: f32,
+            is_new_voice: bool,
+            _sample_rate: f32,
+        ) {
+            self.last_modulation = Some((poly_modulation_id, normalized_offset, is_new_voice));
+        }
+
+        fn note_expression(&mut self, expression: NoteExpression, _sample_rate: f32) {
+            self.last_expression = Some(expression);
+        }
+    }
+
+    fn note_on(note: u8) -> NoteEvent {
+        NoteEvent::NoteOn {
+            timing: 0,
+            voice_id: None,
+            channel: 0,
+            note,
+            velocity: 0.5,
+        }
+    }
+
+    fn note_off(note: u8) -> NoteEvent {
+        NoteEvent::NoteOff {
+            timing: 0,
+            voice_id: None,
+            channel: 0,
+            note,
+            velocity: 0.0,
+        }
+    }
+
+    fn sustain(pressed: bool) -> NoteEvent {
+        NoteEvent::MidiCC {
+            timing: 0,
+            channel: 0,
+            cc: control_change::DAMPER_PEDAL,
+            value: if pressed { 1.0 } else { 0.0 },
+        }
+    }
+
+    /// Handle the events and return the notes of the voices that were terminated.
+    fn handle_events(
+        manager: &mut VoiceManager<TestVoice>,
+        events: impl IntoIterator<Item = NoteEvent>,
+    ) -> Vec<u8> {
+        let mut terminated_notes = Vec::new();
+        for event in events {
+            manager.handle_event_with_output(event, SAMPLE_RATE, &mut |event| match event {
+                NoteEvent::VoiceTerminated { note, .. } => terminated_notes.push(note),
+                event => panic!("Unexpected output event: {event:?}"),
+            });
+        }
+
+        terminated_notes
+    }
+
+    fn active_notes(manager: &mut VoiceManager<TestVoice>) -> Vec<u8> {
+        manager
+            .active_voices_mut()
+            .map(|(note, _)| note.note)
+            .collect()
+    }
+
+    #[test]
+    fn steal_oldest_voice() {
+        let mut manager = VoiceManager::new(2, |_| TestVoice::default());
+
+        let terminated = handle_events(&mut manager, [note_on(60), note_on(62), note_on(64)]);
+        assert_eq!(terminated, [60]);
+        assert_eq!(active_notes(&mut manager), [64, 62]);
+
+        // Released voices are stolen before held voices
+        let terminated = handle_events(&mut manager, [note_off(64), note_on(65)]);
+        assert_eq!(terminated, [64]);
+        assert_eq!(active_notes(&mut manager), [65, 62]);
+
+        manager.set_stealing_policy(StealingPolicy::None);
+        let terminated = handle_events(&mut manager, [note_on(67)]);
+        assert!(terminated.is_empty());
+        assert_eq!(active_notes(&mut manager), [65, 62]);
+
+        manager.set_stealing_policy(StealingPolicy::HighestNote);
+        let terminated = handle_events(&mut manager, [note_on(67)]);
+        assert_eq!(terminated, [65]);
+        assert_eq!(active_notes(&mut manager), [67, 62]);
+    }
+
+    #[test]
+    fn sustain_pedal() {
+        let mut manager = VoiceManager::new(4, |_| TestVoice::default());
+
+        handle_events(&mut manager, [sustain(true), note_on(60), note_off(60)]);
+        assert!(!manager.voices_mut()[0].released);
+
+        handle_events(&mut manager, [sustain(false)]);
+        assert!(manager.voices_mut()[0].released);
+
+        // The voice is only freed once it's finished
+        let mut terminated_notes = Vec::new();
+        manager.terminate_finished_voices_with_output(0, &mut |event| {
+            terminated_notes.push(event.voice_id())
+        });
+        assert!(terminated_notes.is_empty());
+        manager.voices_mut()[0].finished = true;
+        manager.terminate_finished_voices_with_output(0, &mut |event| {
+            terminated_notes.push(event.voice_id())
+        });
+        assert_eq!(terminated_notes, [Some(compute_fallback_voice_id(60, 0))]);
+        assert_eq!(manager.num_active_voices(), 0);
+    }
+
+    #[test]
+    fn legato() {
+        let mut manager = VoiceManager::new(4, |_| TestVoice::default());
+        manager.set_mode(VoiceMode::Legato);
+
+        // The previous note's voice ID is terminated, but the voice keeps playing
+        let terminated = handle_events(&mut manager, [note_on(60), note_on(64)]);
+        assert_eq!(terminated, [60]);
+        assert_eq!(active_notes(&mut manager), [64]);
+        assert_eq!(manager.voices_mut()[0].num_starts, 1);
+
+        // Releasing the last key returns to the previously held key
+        let terminated = handle_events(&mut manager, [note_off(64)]);
+        assert_eq!(terminated, [64]);
+        assert_eq!(active_notes(&mut manager), [60]);
+        assert!(!manager.voices_mut()[0].released);
+
+        handle_events(&mut manager, [note_off(60)]);
+        assert!(manager.voices_mut()[0].released);
+
+        // Mono mode retriggers the voice instead
+        manager.set_mode(VoiceMode::Mono);
+        handle_events(&mut manager, [note_on(60), note_on(64)]);
+        assert_eq!(manager.voices_mut()[0].num_starts, 3);
+    }
+
+    #[test]
+    fn capacity_changes() {
+        let mut manager = VoiceManager::new(4, |_| TestVoice::default());
+        assert_eq!(manager.update_capacity(0, &mut |_| ()), Some(4));
+        assert_eq!(manager.update_capacity(0, &mut |_| ()), None);
+
+        handle_events(&mut manager, [note_on(60), note_on(62), note_on(64)]);
+        manager.set_mode(VoiceMode::Mono);
+
+        let mut terminated = Vec::new();
+        let capacity = manager.update_capacity(0, &mut |event| terminated.push(event.voice_id()));
+        assert_eq!(capacity, Some(1));
+        assert_eq!(
+            terminated,
+            [
+                Some(compute_fallback_voice_id(62, 0)),
+                Some(compute_fallback_voice_id(64, 0))
+            ]
+        );
+        assert_eq!(active_notes(&mut manager), [60]);
+    }
+
+    #[test]
+    fn modulation_routing() {
+        let mut manager = VoiceManager::new(4, |_| TestVoice::default());
+
+        handle_events(
+            &mut manager,
+            [
+                NoteEvent::NoteOn {
+                    timing: 0,
+                    voice_id: Some(10),
+                    channel: 0,
+                    note: 60,
+                    velocity: 1.0,
+                },
+                note_on(62),
+                NoteEvent::PolyModulation {
+                    timing: 0,
+                    voice_id: 10,
+                    poly_modulation_id: 3,
+                    normalized_offset: 0.25,
+                },
+                NoteEvent::PolyPressure {
+                    timing: 0,
+                    voice_id: None,
+                    channel: 0,
+                    note: 62,
+                    pressure: 0.75,
+                },
+            ],
+        );
+
+        let voices = manager.voices_mut();
+        assert_eq!(voices[0].last_modulation, Some((3, 0.25, true)));
+        assert_eq!(voices[0].last_expression, None);
+        assert_eq!(voices[1].last_modulation, None);
+        assert_eq!(
+            voices[1].last_expression,
+            Some(NoteExpression::Pressure(0.75))
+        );
+
+        // Voices are no longer new after the block has been rendered
+        manager.terminate_finished_voices_with_output(0, &mut |_| ());
+        handle_events(
+            &mut manager,
+            [NoteEvent::PolyModulation {
+                timing: 0,
+                voice_id: 10,
+                poly_modulation_id: 3,
+                normalized_offset: 0.5,
+            }],
+        );
+        assert_eq!(
+            manager.voices_mut()[0].last_modulation,
+            Some((3, 0.5, false))
+        );
+    }
+}
@@ -48,6 +48,204 @@ pub fn hann_in_place(window: &mut [f32]) {
     }
 }
 
+/// A Hamming window function.
+///
+/// <https://en.wikipedia.org/wiki/Window_function#Hann_and_Hamming_windows>
+pub fn hamming(size: usize) -> Vec<f32> {
+    let mut window = vec![0.0; size];
+    hamming_in_place(&mut window);
+
+    window
+}
+
+/// The same as [`hamming()`], but filling an existing slice instead.
+pub fn hamming_in_place(window: &mut [f32]) {
+    cosine_sum_in_place(window, &[0.54, 0.46]);
+}
+
+/// A four term Blackman-Harris window function. This has a much lower side lobe level than the
+/// regular Blackman window at the cost of a wider main lobe.
+///
+/// <https://en.wikipedia.org/wiki/Window_function#Blackman%E2%80%93Harris_window>
+pub fn blackman_harris(size: usize) -> Vec<f32> {
+    let mut window = vec![0.0; size];
+    blackman_harris_in_place(&mut window);
+
+    window
+}
+
+/// The same as [`blackman_harris()`], but filling an existing slice instead.
+pub fn blackman_harris_in_place(window: &mut [f32]) {
+    cosine_sum_in_place(window, &[0.35875, 0.48829, 0.14128, 0.01168]);
+}
+
+/// A four term Nuttall window function with a continuous first derivative.
+///
+/// <https://en.wikipedia.org/wiki/Window_function#Nuttall_window,_continuous_first_derivative>
+pub fn nuttall(size: usize) -> Vec<f32> {
+    let mut window = vec![0.0; size];
+    nuttall_in_place(&mut window);
+
+    window
+}
+
+/// The same as [`nuttall()`], but filling an existing slice instead.
+pub fn nuttall_in_place(window: &mut [f32]) {
+    cosine_sum_in_place(window, &[0.355768, 0.487396, 0.144232, 0.012604]);
+}
+
+/// A flat top window function. This has a very wide main lobe, but it barely has any scalloping
+/// loss so it's useful for accurately measuring the amplitude of sinusoids. Note that this window
+/// dips below zero.
+///
+/// <https://en.wikipedia.org/wiki/Window_function#Flat_top_window>
+pub fn flat_top(size: usize) -> Vec<f32> {
+    let mut window = vec![0.0; size];
+    flat_top_in_place(&mut window);
+
+    window
+}
+
+/// The same as [`flat_top()`], but filling an existing slice instead.
+pub fn flat_top_in_place(window: &mut [f32]) {
+    cosine_sum_in_place(
+        window,
+        &[0.21557895, 0.41663158, 0.27726316, 0.083578947, 0.006947368],
+    );
+}
+
+/// A Kaiser window function. `beta` trades off the main lobe width against the side lobe level. A
+/// beta of 0 results in a rectangular window, a beta of around 5 is similar to a Hamming window,
+/// and a beta of around 8.6 is similar to a Blackman window.
+///
+/// <https://en.wikipedia.org/wiki/Kaiser_window>
+pub fn kaiser(size: usize, beta: f32) -> Vec<f32> {
+    let mut window = vec![0.0; size];
+    kaiser_in_place(&mut window, beta);
+
+    window
+}
+
+/// The same as [`kaiser()`], but filling an existing slice instead.
+pub fn kaiser_in_place(window: &mut [f32], beta: f32) {
+    let size = window.len();
+    if size <= 1 {
+        window.fill(1.0);
+        return;
+    }
+
+    let scale = 2.0 / (size - 1) as f32;
+    let normalization = bessel_i0(beta).recip();
+    for (i, sample) in window.iter_mut().enumerate() {
+        let x = (i as f32 * scale) - 1.0;
+        *sample = bessel_i0(beta * (1.0 - (x * x)).max(0.0).sqrt()) * normalization;
+    }
+}
+
+/// A Tukey, or tapered cosine, window function. `alpha` is the fraction of the window that's
+/// tapered, where 0 results in a rectangular window and 1 results in a Hann window.
+///
+/// <https://en.wikipedia.org/wiki/Window_function#Tukey_window>
+pub fn tukey(size: usize, alpha: f32) -> Vec<f32> {
+    let mut window = vec![0.0; size];
+    tukey_in_place(&mut window, alpha);
+
+    window
+}
+
+/// The same as [`tukey()`], but filling an existing slice instead.
+pub fn tukey_in_place(window: &mut [f32], alpha: f32) {
+    let size = window.len();
+    let alpha = alpha.clamp(0.0, 1.0);
+
+    // The taper covers `alpha * (size - 1) / 2` samples on both sides of the window
+    let taper_length = alpha * (size as f32 - 1.0) / 2.0;
+    for (i, sample) in window.iter_mut().enumerate() {
+        let distance_from_edge = (i.min(size - 1 - i)) as f32;
+        *sample = if distance_from_edge < taper_length {
+            0.5 - (0.5 * (f32::consts::PI * distance_from_edge / taper_length).cos())
+        } else {
+            1.0
+        };
+    }
+}
+
+/// A sine window function. Using this as both the analysis and the synthesis window results in a
+/// Hann window when overlap-adding, which makes it a popular choice for spectral processing.
+///
+/// <https://en.wikipedia.org/wiki/Window_function#Sine_window>
+pub fn sine(size: usize) -> Vec<f32> {
+    let mut window = vec![0.0; size];
+    sine_in_place(&mut window);
+
+    window
+}
+
+/// The same as [`sine()`], but filling an existing slice instead.
+pub fn sine_in_place(window: &mut [f32]) {
+    let size = window.len();
+    if size <= 1 {
+        window.fill(1.0);
+        return;
+    }
+
+    let scale = (size as f32 - 1.0).recip() * f32::consts::PI;
+    for (i, sample) in window.iter_mut().enumerate() {
+        *sample = (i as f32 * scale).sin();
+    }
+}
+
+/// The coherent gain of a window function. This is the average value of the window, and it's the
+/// factor the amplitude of a sinusoid is scaled by after windowing. Divide the magnitudes of an
+/// unnormalized FFT by `window.len() * coherent_gain(window)` to get the sinusoids' amplitudes.
+pub fn coherent_gain(window: &[f32]) -> f32 {
+    window.iter().sum::<f32>() / window.len() as f32
+}
+
+/// The equivalent noise bandwidth of a window function, in FFT bins. This is the width of a
+/// rectangular filter that would let through as much white noise as the window's main lobe. A
+/// rectangular window has an ENBW of 1, and a Hann window has an ENBW of 1.5.
+pub fn equivalent_noise_bandwidth(window: &[f32]) -> f32 {
+    let sum: f32 = window.iter().sum();
+    let sum_of_squares: f32 = window.iter().map(|sample| sample * sample).sum();
+
+    window.len() as f32 * sum_of_squares / (sum * sum)
+}
+
+/// The factor the output of an overlap-add process should be multiplied by to get unity gain when
+/// the input is windowed with `analysis_window` before the FFT and, optionally, the output is
+/// windowed again with `synthesis_window` after the inverse FFT. The windows are overlapped
+/// `overlap_times` times, like in [`StftHelper`][super::StftHelper]. This is exact for window and
+/// overlap combinations that satisfy the constant overlap-add constraint, like a Hann window with
+/// an overlap of 2 or more, or a Hann window used for both analysis and synthesis with an overlap
+/// of 4 or more.
+///
+/// This does not include the FFT's own scaling. When using an unnormalized FFT and inverse FFT
+/// pair like RustFFT's, the output also needs to be divided by the window size.
+pub fn cola_normalization_factor(
+    analysis_window: &[f32],
+    synthesis_window: Option<&[f32]>,
+    overlap_times: usize,
+) -> f32 {
+    let window_size = analysis_window.len();
+    let combined_window_sum: f32 = match synthesis_window {
+        Some(synthesis_window) => {
+            nih_debug_assert_eq!(synthesis_window.len(), window_size);
+
+            analysis_window
+                .iter()
+                .zip(synthesis_window)
+                .map(|(analysis, synthesis)| analysis * synthesis)
+                .sum()
+        }
+        None => analysis_window.iter().sum(),
+    };
+
+    // The overlapping windows add up to the average of the combined window times the number of
+    // overlapping windows
+    (combined_window_sum / window_size as f32 * overlap_times as f32).recip()
+}
+
 /// Multiply a buffer with a window function.
 #[inline]
 pub fn multiply_with_window(buffer: &mut [f32], window_function: &[f32]) {
@@ -56,3 +254,137 @@ pub fn multiply_with_window(buffer: &mut [f32], window_function: &[f32]) {
         *sample *= window_sample;
     }
 }
+
+/// Fill a slice with a generalized cosine-sum window with the given coefficients. The signs of the
+/// coefficients alternate, so `[0.5, 0.5]` results in a Hann window.
+fn cosine_sum_in_place(window: &mut [f32], coefficients: &[f32]) {
+    let size = window.len();
+    if size <= 1 {
+        window.fill(1.0);
+        return;
+    }
+
+    let scale = (size as f32 - 1.0).recip() * f32::consts::TAU;
+    for (i, sample) in window.iter_mut().enumerate() {
+        let phase = i as f32 * scale;
+        *sample = coefficients
+            .iter()
+            .enumerate()
+            .map(|(k, coefficient)| {
+                let term = coefficient * (phase * k as f32).cos();
+                if k % 2 == 0 {
+                    term
+                } else {
+                    -term
+                }
+            })
+            .sum();
+    }
+}
+
+/// The zeroth order modified Bessel function of the first kind, used for the Kaiser window. This
+/// evaluates the power series until the terms become insignificant.
+fn bessel_i0(x: f32) -> f32 {
+    let half_x = x / 2.0;
+
+    let mut result = 1.0;
+    let mut term = 1.0;
+    for k in 1..50 {
+        term *= half_x / k as f32;
+        let term_squared = term * term;
+        result += term_squared;
+        if term_squared < result * 1e-9 {
+            break;
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_are_symmetric() {
+        for window in [
+            hamming(63),
+            blackman_harris(64),
+            nuttall(63),
+            flat_top(64),
+            kaiser(63, 8.6),
+            tukey(64, 0.5),
+            sine(63),
+        ] {
+            for (a, b) in window.iter().zip(window.iter().rev()) {
+                approx::assert_relative_eq!(a, b, epsilon = 1e-5);
+            }
+        }
+
+        // The odd sized windows should peak at exactly one in the middle
+        approx::assert_relative_eq!(hamming(63)[31], 1.0, epsilon = 1e-6);
+        approx::assert_relative_eq!(kaiser(63, 8.6)[31], 1.0, epsilon = 1e-6);
+        approx::assert_relative_eq!(sine(63)[31], 1.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn single_sample_windows() {
+        for window in [
+            hamming(1),
+            blackman_harris(1),
+            nuttall(1),
+            flat_top(1),
+            kaiser(1, 8.6),
+            tukey(1, 0.5),
+            sine(1),
+        ] {
+            assert_eq!(window, [1.0]);
+        }
+
+        assert!(sine(0).is_empty());
+        assert!(hamming(0).is_empty());
+    }
+
+    #[test]
+    fn window_metrics() {
+        let window = hann(4096);
+        approx::assert_relative_eq!(coherent_gain(&window), 0.5, epsilon = 1e-3);
+        approx::assert_relative_eq!(equivalent_noise_bandwidth(&window), 1.5, epsilon = 1e-3);
+
+        // A beta of zero is a rectangular window
+        let window = kaiser(128, 0.0);
+        approx::assert_relative_eq!(coherent_gain(&window), 1.0);
+        approx::assert_relative_eq!(equivalent_noise_bandwidth(&window), 1.0);
+        // Tukey windows sit in between a rectangular window and a Hann window
+        approx::assert_relative_eq!(equivalent_noise_bandwidth(&tukey(128, 0.0)), 1.0);
+        approx::assert_relative_eq!(
+            equivalent_noise_bandwidth(&tukey(4096, 1.0)),
+            1.5,
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn cola_normalization() {
+        // A periodic Hann window with 4x overlap sums to exactly 2 when used once, and to 1.5 when
+        // used for both analysis and synthesis
+        let window_size = 1024;
+        let overlap_times = 4;
+        let mut window = hann(window_size + 1);
+        window.pop();
+
+        let hop_size = window_size / overlap_times;
+        for synthesis_window in [None, Some(&window[..])] {
+            let factor = cola_normalization_factor(&window, synthesis_window, overlap_times);
+            for offset in 0..hop_size {
+                let overlapped_sum: f32 = (0..overlap_times)
+                    .map(|overlap_idx| {
+                        let idx = offset + (overlap_idx * hop_size);
+                        window[idx] * synthesis_window.map(|w| w[idx]).unwrap_or(1.0)
+                    })
+                    .sum();
+                approx::assert_relative_eq!(overlapped_sum * factor, 1.0, epsilon = 1e-4);
+            }
+        }
+    }
+}
@@ -6,8 +6,9 @@ use super::wrapper::{OutputParamEvent, Task, Wrapper};
 use crate::context::gui::GuiContext;
 use crate::context::init::InitContext;
 use crate::context::process::{ProcessContext, Transport};
-use crate::context::PluginApi;
+use crate::context::{PluginApi, TrackInfo};
 use crate::event_loop::EventLoop;
+use crate::midi::learn::MidiLearnStatus;
 use crate::midi::NoteEvent;
 use crate::params::internals::ParamPtr;
 use crate::plugin::ClapPlugin;
@@ -52,6 +53,22 @@ impl<P: ClapPlugin> InitContext<P> for WrapperInitContext<'_, P> {
     fn set_current_voice_capacity(&self, capacity: u32) {
         self.wrapper.set_current_voice_capacity(capacity)
     }
+
+    fn notify_note_names_changed(&self) {
+        self.wrapper.notify_note_names_changed()
+    }
+
+    fn aux_input_active(&self, index: usize) -> bool {
+        self.wrapper.aux_input_active(index)
+    }
+
+    fn aux_output_active(&self, index: usize) -> bool {
+        self.wrapper.aux_output_active(index)
+    }
+
+    fn track_info(&self) -> Option<TrackInfo> {
+        self.wrapper.track_info()
+    }
 }
 
 impl<P: ClapPlugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
@@ -89,6 +106,22 @@ impl<P: ClapPlugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
     fn set_current_voice_capacity(&self, capacity: u32) {
         self.wrapper.set_current_voice_capacity(capacity)
     }
+
+    fn notify_note_names_changed(&self) {
+        self.wrapper.notify_note_names_changed()
+    }
+
+    fn aux_input_active(&self, index: usize) -> bool {
+        self.wrapper.aux_input_active(index)
+    }
+
+    fn aux_output_active(&self, index: usize) -> bool {
+        self.wrapper.aux_output_active(index)
+    }
+
+    fn request_audio_ports_rescan(&self) {
+        self.wrapper.request_audio_ports_rescan()
+    }
 }
 
 impl<P: ClapPlugin> GuiContext for WrapperGuiContext<P> {
@@ -169,4 +202,54 @@ impl<P: ClapPlugin> GuiContext for WrapperGuiContext<P> {
     fn set_state(&self, state: crate::wrapper::state::PluginState) {
         self.wrapper.set_state_object(state)
     }
+
+    fn notify_note_names_changed(&self) {
+        self.wrapper.notify_note_names_changed()
+    }
+
+    fn request_audio_ports_rescan(&self) {
+        self.wrapper.request_audio_ports_rescan()
+    }
+
+    fn track_info(&self) -> Option<TrackInfo> {
+        self.wrapper.track_info()
+    }
+
+    unsafe fn raw_show_context_menu(&self, param: ParamPtr, x: i32, y: i32) -> bool {
+        match self.wrapper.param_ptr_to_hash.get(&param) {
+            Some(hash) => self.wrapper.show_context_menu(*hash, x, y),
+            None => {
+                nih_debug_assert_failure!("Unknown parameter: {:?}", param);
+                false
+            }
+        }
+    }
+
+    unsafe fn raw_begin_midi_learn(&self, param: ParamPtr) {
+        match self.wrapper.param_ptr_to_hash.get(&param) {
+            Some(hash) => self.wrapper.midi_learn.arm(*hash),
+            None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
+        }
+    }
+
+    fn cancel_midi_learn(&self) {
+        self.wrapper.midi_learn.cancel();
+    }
+
+    unsafe fn raw_clear_midi_mapping(&self, param: ParamPtr) {
+        match self.wrapper.param_ptr_to_hash.get(&param) {
+            Some(hash) => self.wrapper.midi_learn.clear(*hash),
+            None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
+        }
+    }
+
+    unsafe fn raw_midi_learn_status(&self, param: ParamPtr) -> MidiLearnStatus {
+        match self.wrapper.param_ptr_to_hash.get(&param) {
+            Some(hash) => self.wrapper.midi_learn.status(*hash),
+            None => {
+                nih_debug_assert_failure!("Unknown parameter: {:?}", param);
+                MidiLearnStatus::Unmapped
+            }
+        }
+    }
 }
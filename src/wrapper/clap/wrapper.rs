@@ -15,11 +15,34 @@ use clap_sys::events::{
     CLAP_TRANSPORT_IS_RECORDING, CLAP_TRANSPORT_IS_WITHIN_PRE_ROLL,
 };
 use clap_sys::ext::audio_ports::{
-    clap_audio_port_info, clap_plugin_audio_ports, CLAP_AUDIO_PORT_IS_MAIN, CLAP_EXT_AUDIO_PORTS,
-    CLAP_PORT_MONO, CLAP_PORT_STEREO,
+    clap_audio_port_info, clap_host_audio_ports, clap_plugin_audio_ports,
+    CLAP_AUDIO_PORTS_RESCAN_LIST, CLAP_AUDIO_PORT_IS_MAIN, CLAP_EXT_AUDIO_PORTS, CLAP_PORT_MONO,
+    CLAP_PORT_STEREO,
 };
 use clap_sys::ext::audio_ports_config::{
-    clap_audio_ports_config, clap_plugin_audio_ports_config, CLAP_EXT_AUDIO_PORTS_CONFIG,
+    clap_audio_ports_config, clap_host_audio_ports_config, clap_plugin_audio_ports_config,
+    CLAP_EXT_AUDIO_PORTS_CONFIG,
+};
+use clap_sys::ext::draft::audio_ports_activation::{
+    clap_plugin_audio_ports_activation, CLAP_EXT_AUDIO_PORTS_ACTIVATION,
+};
+use clap_sys::ext::draft::context_menu::{
+    clap_context_menu_builder, clap_context_menu_check_entry, clap_context_menu_entry,
+    clap_context_menu_target, clap_host_context_menu, clap_plugin_context_menu,
+    CLAP_CONTEXT_MENU_ITEM_CHECK_ENTRY, CLAP_CONTEXT_MENU_ITEM_ENTRY,
+    CLAP_CONTEXT_MENU_ITEM_SEPARATOR, CLAP_CONTEXT_MENU_TARGET_KIND_GLOBAL,
+    CLAP_CONTEXT_MENU_TARGET_KIND_PARAM, CLAP_EXT_CONTEXT_MENU,
+};
+use clap_sys::ext::draft::state_context::{
+    clap_plugin_state_context, clap_plugin_state_context_type, CLAP_EXT_STATE_CONTEXT,
+    CLAP_STATE_CONTEXT_FOR_DUPLICATE, CLAP_STATE_CONTEXT_FOR_PRESET,
+    CLAP_STATE_CONTEXT_FOR_PROJECT,
+};
+use clap_sys::ext::draft::track_info::{
+    clap_host_track_info, clap_plugin_track_info, clap_track_info, CLAP_EXT_TRACK_INFO,
+    CLAP_TRACK_INFO_HAS_AUDIO_CHANNEL, CLAP_TRACK_INFO_HAS_TRACK_COLOR,
+    CLAP_TRACK_INFO_HAS_TRACK_NAME, CLAP_TRACK_INFO_IS_FOR_BUS, CLAP_TRACK_INFO_IS_FOR_MASTER,
+    CLAP_TRACK_INFO_IS_FOR_RETURN_TRACK,
 };
 use clap_sys::ext::draft::voice_info::{
     clap_host_voice_info, clap_plugin_voice_info, clap_voice_info, CLAP_EXT_VOICE_INFO,
@@ -30,16 +53,25 @@ use clap_sys::ext::gui::{
     CLAP_WINDOW_API_COCOA, CLAP_WINDOW_API_WIN32, CLAP_WINDOW_API_X11,
 };
 use clap_sys::ext::latency::{clap_host_latency, clap_plugin_latency, CLAP_EXT_LATENCY};
+use clap_sys::ext::note_name::{
+    clap_host_note_name, clap_note_name, clap_plugin_note_name, CLAP_EXT_NOTE_NAME,
+};
 use clap_sys::ext::note_ports::{
     clap_note_port_info, clap_plugin_note_ports, CLAP_EXT_NOTE_PORTS, CLAP_NOTE_DIALECT_CLAP,
     CLAP_NOTE_DIALECT_MIDI,
 };
 use clap_sys::ext::params::{
     clap_host_params, clap_param_info, clap_plugin_params, CLAP_EXT_PARAMS,
-    CLAP_PARAM_IS_AUTOMATABLE, CLAP_PARAM_IS_BYPASS, CLAP_PARAM_IS_HIDDEN,
+    CLAP_PARAM_IS_AUTOMATABLE, CLAP_PARAM_IS_BYPASS, CLAP_PARAM_IS_ENUM, CLAP_PARAM_IS_HIDDEN,
     CLAP_PARAM_IS_MODULATABLE, CLAP_PARAM_IS_MODULATABLE_PER_NOTE_ID, CLAP_PARAM_IS_READONLY,
     CLAP_PARAM_IS_STEPPED, CLAP_PARAM_RESCAN_VALUES,
 };
+use clap_sys::ext::posix_fd_support::CLAP_EXT_POSIX_FD_SUPPORT;
+#[cfg(target_os = "linux")]
+use clap_sys::ext::posix_fd_support::{
+    clap_host_posix_fd_support, clap_plugin_posix_fd_support, clap_posix_fd_flags,
+    CLAP_POSIX_FD_READ,
+};
 use clap_sys::ext::render::{
     clap_plugin_render, clap_plugin_render_mode, CLAP_EXT_RENDER, CLAP_RENDER_OFFLINE,
     CLAP_RENDER_REALTIME,
@@ -47,6 +79,9 @@ use clap_sys::ext::render::{
 use clap_sys::ext::state::{clap_plugin_state, CLAP_EXT_STATE};
 use clap_sys::ext::tail::{clap_plugin_tail, CLAP_EXT_TAIL};
 use clap_sys::ext::thread_check::{clap_host_thread_check, CLAP_EXT_THREAD_CHECK};
+use clap_sys::ext::timer_support::{
+    clap_host_timer_support, clap_plugin_timer_support, CLAP_EXT_TIMER_SUPPORT,
+};
 use clap_sys::fixedpoint::{CLAP_BEATTIME_FACTOR, CLAP_SECTIME_FACTOR};
 use clap_sys::host::clap_host;
 use clap_sys::id::{clap_id, CLAP_INVALID_ID};
@@ -64,10 +99,12 @@ use raw_window_handle::RawWindowHandle;
 use std::any::Any;
 use std::cmp;
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::ffi::{c_void, CStr};
+use std::ffi::{c_void, CStr, CString};
 use std::mem;
 use std::os::raw::c_char;
 use std::ptr;
+#[cfg(target_os = "linux")]
+use std::sync::atomic::AtomicI32;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Weak};
 use std::thread::{self, ThreadId};
@@ -77,26 +114,34 @@ use super::context::{WrapperGuiContext, WrapperInitContext, WrapperProcessContex
 use super::descriptor::PluginDescriptor;
 use super::util::ClapPtr;
 use crate::buffer::Buffer;
-use crate::context::gui::AsyncExecutor;
+use crate::context::gui::{AsyncExecutor, ContextMenuEntry};
 use crate::context::process::Transport;
+use crate::context::{TrackColor, TrackInfo, TrackType};
 use crate::editor::{Editor, ParentWindowHandle, SpawnedWindow};
 use crate::event_loop::{BackgroundThread, EventLoop, MainThreadExecutor, TASK_QUEUE_CAPACITY};
-use crate::midi::{MidiConfig, NoteEvent};
+use crate::midi::learn::MidiLearn;
+use crate::midi::{MidiConfig, NoteEvent, NoteName};
 use crate::params::internals::ParamPtr;
+use crate::params::macros::MacroLinks;
+use crate::params::persist::StateContext;
 use crate::params::{ParamFlags, Params};
 use crate::plugin::{
-    AuxiliaryBuffers, BufferConfig, BusConfig, ClapPlugin, Plugin, ProcessMode, ProcessStatus,
-    TaskExecutor,
+    AuxiliaryBuffers, AuxiliaryIOConfig, BufferConfig, BusConfig, ClapPlugin, Plugin, ProcessMode,
+    ProcessStatus, TaskExecutor,
 };
 use crate::util::permit_alloc;
 use crate::wrapper::clap::util::{read_stream, write_stream};
 use crate::wrapper::state::{self, PluginState};
-use crate::wrapper::util::{hash_param_id, process_wrapper, strlcpy};
+use crate::wrapper::util::{hash_param_id, list_value_string, process_wrapper, strlcpy};
 
 /// How many output parameter changes we can store in our output parameter change queue. Storing
 /// more than this many parameters at a time will cause changes to get lost.
 const OUTPUT_EVENT_QUEUE_CAPACITY: usize = 2048;
 
+/// The interval in milliseconds at which the task queue is drained from the host's timer when the
+/// host supports the timer support extension.
+const TASKS_TIMER_PERIOD_MS: u32 = 30;
+
 pub struct Wrapper<P: ClapPlugin> {
     /// A reference to this object, upgraded to an `Arc<Self>` for the GUI context.
     this: AtomicRefCell<Weak<Self>>,
@@ -123,9 +168,19 @@ pub struct Wrapper<P: ClapPlugin> {
     editor_scaling_factor: AtomicF32,
 
     is_processing: AtomicBool,
+    /// Whether the plugin is currently activated. Set in `clap_plugin::activate()` and cleared in
+    /// `clap_plugin::deactivate()`. The audio port list can only be rescanned while the plugin is
+    /// deactivated.
+    is_active: AtomicBool,
     /// The current IO configuration, modified through the `clap_plugin_audio_ports_config`
     /// extension.
     current_bus_config: AtomicCell<BusConfig>,
+    /// Whether the current bus config's auxiliary input ports are active, modified through the
+    /// `clap_plugin_audio_ports_activation` extension. Ports are active by default. These can only
+    /// change while the plugin is deactivated, and they are reset whenever the bus config changes.
+    aux_inputs_active: AtomicRefCell<Vec<bool>>,
+    /// The same as [`aux_inputs_active`][Self::aux_inputs_active], but for the auxiliary outputs.
+    aux_outputs_active: AtomicRefCell<Vec<bool>>,
     /// The current buffer configuration, containing the sample rate and the maximum block size.
     /// Will be set in `clap_plugin::activate()`.
     current_buffer_config: AtomicCell<Option<BufferConfig>>,
@@ -178,14 +233,16 @@ pub struct Wrapper<P: ClapPlugin> {
     host_callback: ClapPtr<clap_host>,
 
     clap_plugin_audio_ports_config: clap_plugin_audio_ports_config,
+    host_audio_ports_config: AtomicRefCell<Option<ClapPtr<clap_host_audio_ports_config>>>,
     /// During initialization we'll ask `P` which bus configurations it supports. The host can then
-    /// use the audio ports config extension to choose a configuration. Right now we only query mono
-    /// and stereo configurations, with and without inputs, as well as the plugin's default input
-    /// and output channel counts if that does not match one of those configurations (to do the
-    /// least surprising thing).
-    ///
-    /// TODO: Support surround setups once a plugin needs that
-    supported_bus_configs: Vec<BusConfig>,
+    /// use the audio ports config extension to choose a configuration. See
+    /// [`query_supported_bus_configs()`][Self::query_supported_bus_configs()] for the
+    /// configurations we query. The plugin can request this list to be queried again using the
+    /// `request_audio_ports_rescan()` context methods. This is only accessed from the main thread.
+    supported_bus_configs: AtomicRefCell<Vec<BusConfig>>,
+    /// Set when the plugin requested an audio ports rescan while it was activated. The rescan is
+    /// then performed when the host deactivates the plugin after we requested a restart.
+    audio_ports_rescan_pending: AtomicBool,
 
     // The main `clap_plugin` vtable. A pointer to this `Wrapper<P>` instance is stored in the
     // `plugin_data` field. This pointer is set after creating the `Arc<Wrapper<P>>`.
@@ -195,6 +252,12 @@ pub struct Wrapper<P: ClapPlugin> {
     _plugin_descriptor: Box<PluginDescriptor<P>>,
 
     clap_plugin_audio_ports: clap_plugin_audio_ports,
+    host_audio_ports: AtomicRefCell<Option<ClapPtr<clap_host_audio_ports>>>,
+
+    clap_plugin_audio_ports_activation: clap_plugin_audio_ports_activation,
+
+    clap_plugin_context_menu: clap_plugin_context_menu,
+    host_context_menu: AtomicRefCell<Option<ClapPtr<clap_host_context_menu>>>,
 
     clap_plugin_gui: clap_plugin_gui,
     host_gui: AtomicRefCell<Option<ClapPtr<clap_host_gui>>>,
@@ -202,6 +265,17 @@ pub struct Wrapper<P: ClapPlugin> {
     clap_plugin_latency: clap_plugin_latency,
     host_latency: AtomicRefCell<Option<ClapPtr<clap_host_latency>>>,
 
+    clap_plugin_note_name: clap_plugin_note_name,
+    host_note_name: AtomicRefCell<Option<ClapPtr<clap_host_note_name>>>,
+    /// The note names returned by [`Plugin::note_names()`], cached so the plugin doesn't need to be
+    /// locked every time the host queries a single note name. This is only accessed from the main
+    /// thread.
+    note_names: AtomicRefCell<Vec<NoteName>>,
+    /// Set when the plugin signals that its note names have changed. The cache in
+    /// [`note_names`][Self::note_names] is then refreshed the next time the host queries the number
+    /// of note names.
+    note_names_dirty: AtomicBool,
+
     clap_plugin_note_ports: clap_plugin_note_ports,
 
     clap_plugin_params: clap_plugin_params,
@@ -230,6 +304,14 @@ pub struct Wrapper<P: ClapPlugin> {
     /// the parameter's poly modulation ID. These IDs are then passed to the plugin, so it can
     /// quickly refer to parameter by matching on constant IDs.
     poly_mod_ids_by_hash: HashMap<u32, u32>,
+    /// The targets for all of the plugin's macro parameters. Whenever a macro parameter is changed
+    /// in [`update_plain_value_by_hash()`][Self::update_plain_value_by_hash()], its targets are
+    /// updated and the host is asked to rescan the parameter values.
+    macro_links: MacroLinks,
+    /// MIDI CCs bound to parameters through MIDI learn. Mapped CCs are turned into parameter
+    /// changes in [`handle_in_event()`][Self::handle_in_event()] instead of being passed to the
+    /// plugin.
+    pub midi_learn: MidiLearn,
     /// A queue of parameter changes and gestures that should be output in either the next process
     /// call or in the next parameter flush.
     ///
@@ -238,14 +320,41 @@ pub struct Wrapper<P: ClapPlugin> {
     ///      in the same order, right?
     output_parameter_events: ArrayQueue<OutputParamEvent>,
 
+    #[cfg(target_os = "linux")]
+    clap_plugin_posix_fd_support: clap_plugin_posix_fd_support,
+    #[cfg(target_os = "linux")]
+    host_posix_fd_support: AtomicRefCell<Option<ClapPtr<clap_host_posix_fd_support>>>,
+    /// An eventfd that's registered with the host if it supports the POSIX fd support extension,
+    /// or -1 otherwise. Writing to this wakes up the host's main thread, which will then drain
+    /// [`tasks`][Self::tasks] in `on_fd()`. Tasks posted from the audio thread still request a
+    /// main thread callback from the host instead.
+    #[cfg(target_os = "linux")]
+    tasks_eventfd: AtomicI32,
+
     host_thread_check: AtomicRefCell<Option<ClapPtr<clap_host_thread_check>>>,
 
     clap_plugin_render: clap_plugin_render,
 
     clap_plugin_state: clap_plugin_state,
+    clap_plugin_state_context: clap_plugin_state_context,
 
     clap_plugin_tail: clap_plugin_tail,
 
+    clap_plugin_timer_support: clap_plugin_timer_support,
+    host_timer_support: AtomicRefCell<Option<ClapPtr<clap_host_timer_support>>>,
+    /// The ID of the timer registered with the host if it supports the timer support extension.
+    /// [`tasks`][Self::tasks] is drained periodically from `on_timer()` so tasks still get run on
+    /// the host's main thread if the host is slow to respond to main thread callback requests.
+    tasks_timer_id: AtomicRefCell<Option<clap_id>>,
+
+    clap_plugin_track_info: clap_plugin_track_info,
+    host_track_info: AtomicRefCell<Option<ClapPtr<clap_host_track_info>>>,
+    /// The most recent information about the track the plugin is inserted on, queried from the
+    /// host in `init()` and whenever the host says it has changed. This is `None` if the host does
+    /// not support the track info extension. This is read from the GUI thread while the host
+    /// updates it on the main thread, so it cannot be an `AtomicRefCell`.
+    track_info: Mutex<Option<TrackInfo>>,
+
     clap_plugin_voice_info: clap_plugin_voice_info,
     host_voice_info: AtomicRefCell<Option<ClapPtr<clap_host_voice_info>>>,
     /// If `P::CLAP_POLY_MODULATION_CONFIG` is set, then the plugin can configure the current number
@@ -257,7 +366,8 @@ pub struct Wrapper<P: ClapPlugin> {
     /// host callback directly, we don't need to use the OsEventLoop we use in our other plugin
     /// implementations. Instead, we'll post tasks to this queue, ask the host to call
     /// [`on_main_thread()`][Self::on_main_thread()] on the main thread, and then continue to pop
-    /// tasks off this queue there until it is empty.
+    /// tasks off this queue there until it is empty. If the host supports the timer support or
+    /// POSIX fd support extensions, then this queue is also drained from those callbacks.
     tasks: ArrayQueue<Task<P>>,
     /// The ID of the main thread. In practice this is the ID of the thread that created this
     /// object. If the host supports the thread check extension (and
@@ -280,8 +390,20 @@ pub enum Task<P: Plugin> {
     LatencyChanged,
     /// Inform the host that the voice info has changed.
     VoiceInfoChanged,
+    /// Inform the host that the plugin's note names have changed.
+    NoteNamesChanged,
+    /// Query the plugin's supported bus configurations again and inform the host about the
+    /// changes. If the plugin is currently active, then a restart is requested instead and the
+    /// rescan happens when the plugin gets deactivated.
+    RescanAudioPorts,
     /// Tell the host that it should rescan the current parameter values.
     RescanParamValues,
+    /// The same as [`RescanParamValues`][Self::RescanParamValues], but sent after a macro parameter
+    /// has changed the values of its targets. See [`MacroLinks`].
+    MacroTargetsChanged,
+    /// Show the host's context menu for a parameter. The context menu extension can only be used
+    /// from the main thread, while the editor may be running on its own thread.
+    ShowContextMenu { param_hash: u32, x: i32, y: i32 },
 }
 
 /// The types of CLAP parameter updates for events.
@@ -310,6 +432,16 @@ pub enum OutputParamEvent {
         /// [`params::step_size()`][crate::params::step_size()].
         clap_plain_value: f64,
     },
+    /// Inform the host about a parameter value that has already been set by the wrapper, for
+    /// instance because the parameter is bound to a MIDI CC. Unlike [`SetValue`] this does not
+    /// modify the parameter again when the events are written to the host.
+    NotifyValue {
+        /// The internal hash for the parameter.
+        param_hash: u32,
+        /// The 'plain' value as reported to CLAP. This is the normalized value multiplied by
+        /// [`params::step_size()`][crate::params::step_size()].
+        clap_plain_value: f64,
+    },
     /// Begin an automation gesture. This must always be sent after sending one or more [`SetValue`]
     /// events.
     EndGesture { param_hash: u32 },
@@ -330,8 +462,7 @@ impl<P: ClapPlugin> EventLoop<Task<P>, Wrapper<P>> for Wrapper<P> {
             let success = self.tasks.push(task).is_ok();
             if success {
                 // CLAP lets us use the host's event loop instead of having to implement our own
-                let host = &self.host_callback;
-                unsafe_clap_call! { host=>request_callback(&**host) };
+                self.wake_main_thread();
             }
 
             success
@@ -388,6 +519,24 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
                 }
                 None => nih_debug_assert_failure!("Host does not support the voice-info extension"),
             },
+            Task::RescanAudioPorts => {
+                nih_debug_assert!(is_gui_thread);
+
+                if self.is_active.load(Ordering::SeqCst) {
+                    self.audio_ports_rescan_pending
+                        .store(true, Ordering::SeqCst);
+                    unsafe_clap_call! { &*self.host_callback=>request_restart(&*self.host_callback) };
+                } else {
+                    self.rescan_audio_ports();
+                }
+            }
+            Task::NoteNamesChanged => {
+                // Not every host supports note names, so a missing extension is not an error
+                if let Some(host_note_name) = &*self.host_note_name.borrow() {
+                    nih_debug_assert!(is_gui_thread);
+                    unsafe_clap_call! { host_note_name=>changed(&*self.host_callback) };
+                }
+            }
             Task::RescanParamValues => match &*self.host_params.borrow() {
                 Some(host_params) => {
                     nih_debug_assert!(is_gui_thread);
@@ -395,6 +544,22 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
                 }
                 None => nih_debug_assert_failure!("The host does not support parameters? What?"),
             },
+            Task::MacroTargetsChanged => {
+                self.macro_links.host_notified();
+                match &*self.host_params.borrow() {
+                    Some(host_params) => {
+                        nih_debug_assert!(is_gui_thread);
+                        unsafe_clap_call! { host_params=>rescan(&*self.host_callback, CLAP_PARAM_RESCAN_VALUES) };
+                    }
+                    None => {
+                        nih_debug_assert_failure!("The host does not support parameters? What?")
+                    }
+                }
+            }
+            Task::ShowContextMenu { param_hash, x, y } => {
+                nih_debug_assert!(is_gui_thread);
+                self.popup_context_menu(param_hash, x, y);
+            }
         };
     }
 }
@@ -441,7 +606,7 @@ impl<P: ClapPlugin> Wrapper<P> {
             .iter()
             .map(|(_, hash, _, group)| (*hash, group.clone()))
             .collect();
-        let param_id_to_hash = param_id_hashes_ptrs_groups
+        let param_id_to_hash: HashMap<String, u32> = param_id_hashes_ptrs_groups
             .iter()
             .map(|(id, hash, _, _)| (id.clone(), *hash))
             .collect();
@@ -455,6 +620,8 @@ impl<P: ClapPlugin> Wrapper<P> {
                 ptr.poly_modulation_id().map(|id| (*hash, id))
             })
             .collect();
+        let macro_links = unsafe { MacroLinks::from_param_map(&params.param_map()) };
+        let midi_learn = MidiLearn::new(&param_id_to_hash);
 
         if cfg!(debug_assertions) {
             let param_map = params.param_map();
@@ -492,51 +659,7 @@ impl<P: ClapPlugin> Wrapper<P> {
             }
         }
 
-        // Query all sensible bus configurations supported by the plugin. We don't do surround or
-        // anything beyond stereo right now.
-        let mut supported_bus_configs = Vec::new();
-        for num_output_channels in [1, 2] {
-            for num_input_channels in [0, num_output_channels] {
-                #[allow(clippy::single_element_loop)]
-                for num_aux_channels in [num_output_channels] {
-                    let bus_config = BusConfig {
-                        num_input_channels,
-                        num_output_channels,
-                        // We won't support a variable number of busses until that's required, so
-                        // we'll always use the number of auxiliary busses specified by the plugin
-                        aux_input_busses: P::DEFAULT_AUX_INPUTS
-                            .map(|mut aux| {
-                                aux.num_channels = num_aux_channels;
-                                aux
-                            })
-                            .unwrap_or_default(),
-                        aux_output_busses: P::DEFAULT_AUX_OUTPUTS
-                            .map(|mut aux| {
-                                aux.num_channels = num_aux_channels;
-                                aux
-                            })
-                            .unwrap_or_default(),
-                    };
-                    if plugin.accepts_bus_config(&bus_config) {
-                        supported_bus_configs.push(bus_config);
-                    }
-                }
-            }
-        }
-
-        // In the off chance that the default config specified by the plugin is not in the above
-        // list, we'll try that as well.
-        let default_bus_config = BusConfig {
-            num_input_channels: P::DEFAULT_INPUT_CHANNELS,
-            num_output_channels: P::DEFAULT_OUTPUT_CHANNELS,
-            aux_input_busses: P::DEFAULT_AUX_INPUTS.unwrap_or_default(),
-            aux_output_busses: P::DEFAULT_AUX_OUTPUTS.unwrap_or_default(),
-        };
-        if !supported_bus_configs.contains(&default_bus_config)
-            && plugin.accepts_bus_config(&default_bus_config)
-        {
-            supported_bus_configs.push(default_bus_config);
-        }
+        let supported_bus_configs = Self::query_supported_bus_configs(&plugin);
 
         let wrapper = Self {
             this: AtomicRefCell::new(Weak::new()),
@@ -550,12 +673,23 @@ impl<P: ClapPlugin> Wrapper<P> {
             editor_scaling_factor: AtomicF32::new(1.0),
 
             is_processing: AtomicBool::new(false),
+            is_active: AtomicBool::new(false),
             current_bus_config: AtomicCell::new(BusConfig {
                 num_input_channels: P::DEFAULT_INPUT_CHANNELS,
                 num_output_channels: P::DEFAULT_OUTPUT_CHANNELS,
                 aux_input_busses: P::DEFAULT_AUX_INPUTS.unwrap_or_default(),
                 aux_output_busses: P::DEFAULT_AUX_OUTPUTS.unwrap_or_default(),
             }),
+            aux_inputs_active: AtomicRefCell::new(vec![
+                true;
+                P::DEFAULT_AUX_INPUTS.unwrap_or_default().num_busses
+                    as usize
+            ]),
+            aux_outputs_active: AtomicRefCell::new(vec![
+                true;
+                P::DEFAULT_AUX_OUTPUTS.unwrap_or_default().num_busses
+                    as usize
+            ]),
             current_buffer_config: AtomicCell::new(None),
             current_process_mode: AtomicCell::new(ProcessMode::Realtime),
             input_events: AtomicRefCell::new(VecDeque::with_capacity(512)),
@@ -597,12 +731,28 @@ impl<P: ClapPlugin> Wrapper<P> {
                 get: Some(Self::ext_audio_ports_config_get),
                 select: Some(Self::ext_audio_ports_config_select),
             },
-            supported_bus_configs,
+            host_audio_ports_config: AtomicRefCell::new(None),
+            supported_bus_configs: AtomicRefCell::new(supported_bus_configs),
+            audio_ports_rescan_pending: AtomicBool::new(false),
 
             clap_plugin_audio_ports: clap_plugin_audio_ports {
                 count: Some(Self::ext_audio_ports_count),
                 get: Some(Self::ext_audio_ports_get),
             },
+            host_audio_ports: AtomicRefCell::new(None),
+
+            clap_plugin_audio_ports_activation: clap_plugin_audio_ports_activation {
+                can_activate_while_processing: Some(
+                    Self::ext_audio_ports_activation_can_activate_while_processing,
+                ),
+                set_active: Some(Self::ext_audio_ports_activation_set_active),
+            },
+
+            clap_plugin_context_menu: clap_plugin_context_menu {
+                populate: Some(Self::ext_context_menu_populate),
+                perform: Some(Self::ext_context_menu_perform),
+            },
+            host_context_menu: AtomicRefCell::new(None),
 
             clap_plugin_gui: clap_plugin_gui {
                 is_api_supported: Some(Self::ext_gui_is_api_supported),
@@ -628,6 +778,14 @@ impl<P: ClapPlugin> Wrapper<P> {
             },
             host_latency: AtomicRefCell::new(None),
 
+            clap_plugin_note_name: clap_plugin_note_name {
+                count: Some(Self::ext_note_name_count),
+                get: Some(Self::ext_note_name_get),
+            },
+            host_note_name: AtomicRefCell::new(None),
+            note_names: AtomicRefCell::new(Vec::new()),
+            note_names_dirty: AtomicBool::new(true),
+
             clap_plugin_note_ports: clap_plugin_note_ports {
                 count: Some(Self::ext_note_ports_count),
                 get: Some(Self::ext_note_ports_get),
@@ -648,8 +806,19 @@ impl<P: ClapPlugin> Wrapper<P> {
             param_id_to_hash,
             param_ptr_to_hash,
             poly_mod_ids_by_hash,
+            macro_links,
+            midi_learn,
             output_parameter_events: ArrayQueue::new(OUTPUT_EVENT_QUEUE_CAPACITY),
 
+            #[cfg(target_os = "linux")]
+            clap_plugin_posix_fd_support: clap_plugin_posix_fd_support {
+                on_fd: Some(Self::ext_posix_fd_support_on_fd),
+            },
+            #[cfg(target_os = "linux")]
+            host_posix_fd_support: AtomicRefCell::new(None),
+            #[cfg(target_os = "linux")]
+            tasks_eventfd: AtomicI32::new(-1),
+
             host_thread_check: AtomicRefCell::new(None),
 
             clap_plugin_render: clap_plugin_render {
@@ -661,11 +830,27 @@ impl<P: ClapPlugin> Wrapper<P> {
                 save: Some(Self::ext_state_save),
                 load: Some(Self::ext_state_load),
             },
+            clap_plugin_state_context: clap_plugin_state_context {
+                save: Some(Self::ext_state_context_save),
+                load: Some(Self::ext_state_context_load),
+            },
 
             clap_plugin_tail: clap_plugin_tail {
                 get: Some(Self::ext_tail_get),
             },
 
+            clap_plugin_timer_support: clap_plugin_timer_support {
+                on_timer: Some(Self::ext_timer_support_on_timer),
+            },
+            host_timer_support: AtomicRefCell::new(None),
+            tasks_timer_id: AtomicRefCell::new(None),
+
+            clap_plugin_track_info: clap_plugin_track_info {
+                changed: Some(Self::ext_track_info_changed),
+            },
+            host_track_info: AtomicRefCell::new(None),
+            track_info: Mutex::new(None),
+
             clap_plugin_voice_info: clap_plugin_voice_info {
                 get: Some(Self::ext_voice_info_get),
             },
@@ -838,6 +1023,20 @@ impl<P: ClapPlugin> Wrapper<P> {
                             unsafe { param_ptr.update_smoother(sample_rate, false) };
                         }
 
+                        // If this was a macro parameter, then its targets have also changed and
+                        // the host needs to know about that
+                        if unsafe { self.macro_links.update_targets(*param_ptr, sample_rate) }
+                            && self.macro_links.begin_host_notification()
+                        {
+                            let task_posted = self.schedule_gui(Task::MacroTargetsChanged);
+                            if !task_posted {
+                                self.macro_links.host_notified();
+                                nih_debug_assert_failure!(
+                                    "The task queue is full, dropping task..."
+                                );
+                            }
+                        }
+
                         true
                     }
                     ClapParamUpdate::PlainValueMod(clap_plain_delta) => {
@@ -942,6 +1141,42 @@ impl<P: ClapPlugin> Wrapper<P> {
         None
     }
 
+    /// Set a parameter bound to a MIDI CC through MIDI learn to the CC's normalized value. The
+    /// value is applied immediately so it takes effect at the CC's sample position, and the host is
+    /// only informed about it through the output parameter event queue. Returns `false` if the
+    /// parameter does not exist.
+    fn update_midi_mapped_param(&self, param_hash: u32, normalized_value: f32) -> bool {
+        let param_ptr = match self.param_by_hash.get(&param_hash) {
+            Some(param_ptr) => param_ptr,
+            None => return false,
+        };
+
+        // Stepped parameters are snapped to the nearest step
+        let clap_plain_value = match unsafe { param_ptr.step_count() } {
+            Some(step_count) => (normalized_value as f64 * step_count as f64).round(),
+            None => normalized_value as f64,
+        };
+        self.update_plain_value_by_hash(
+            param_hash,
+            ClapParamUpdate::PlainValueSet(clap_plain_value),
+            self.current_buffer_config.load().map(|c| c.sample_rate),
+        );
+
+        let push_successful = self
+            .output_parameter_events
+            .push(OutputParamEvent::NotifyValue {
+                param_hash,
+                clap_plain_value,
+            })
+            .is_ok();
+        nih_debug_assert!(
+            push_successful,
+            "Parameter output event queue was full, parameter change will not be sent to the host"
+        );
+
+        true
+    }
+
     /// Write the unflushed parameter changes to the host's output event queue. The sample index is
     /// used as part of splitting up the input buffer for sample accurate automation changes. This
     /// will also modify the actual parameter values, since we should only do that while the wrapped
@@ -970,13 +1205,20 @@ impl<P: ClapPlugin> Wrapper<P> {
                 OutputParamEvent::SetValue {
                     param_hash,
                     clap_plain_value,
+                }
+                | OutputParamEvent::NotifyValue {
+                    param_hash,
+                    clap_plain_value,
                 } => {
-                    self.update_plain_value_by_hash(
-                        param_hash,
-                        ClapParamUpdate::PlainValueSet(clap_plain_value),
-                        sample_rate,
-                    );
-                    parameter_values_changed = true;
+                    // Values set through MIDI learn have already been applied before processing
+                    if let OutputParamEvent::SetValue { .. } = change {
+                        self.update_plain_value_by_hash(
+                            param_hash,
+                            ClapParamUpdate::PlainValueSet(clap_plain_value),
+                            sample_rate,
+                        );
+                        parameter_values_changed = true;
+                    }
 
                     let event = clap_event_param_value {
                         header: clap_event_header {
@@ -1587,6 +1829,20 @@ impl<P: ClapPlugin> Wrapper<P> {
                     ) if P::MIDI_INPUT >= MidiConfig::Basic => {
                         input_events.push_back(note_event);
                     }
+                    Ok(
+                        note_event @ NoteEvent::MidiCC {
+                            channel, cc, value, ..
+                        },
+                    ) if P::MIDI_INPUT >= MidiConfig::MidiCCs => {
+                        // CCs bound to a parameter through MIDI learn are turned into parameter
+                        // changes instead of being passed to the plugin
+                        match self.midi_learn.handle_cc(channel, cc) {
+                            Some(param_hash) => {
+                                return self.update_midi_mapped_param(param_hash, value);
+                            }
+                            None => input_events.push_back(note_event),
+                        }
+                    }
                     Ok(note_event) if P::MIDI_INPUT >= MidiConfig::MidiCCs => {
                         input_events.push_back(note_event);
                     }
@@ -1608,6 +1864,16 @@ impl<P: ClapPlugin> Wrapper<P> {
         }
     }
 
+    /// The MIDI learn state that should be stored in and restored from the plugin's state. This is
+    /// `None` when the plugin does not receive MIDI CCs, since nothing can be mapped then.
+    pub fn state_midi_learn(&self) -> Option<&MidiLearn> {
+        if P::MIDI_INPUT >= MidiConfig::MidiCCs {
+            Some(&self.midi_learn)
+        } else {
+            None
+        }
+    }
+
     /// Get the plugin's state object, may be called by the plugin's GUI as part of its own preset
     /// management. The wrapper doesn't use these functions and serializes and deserializes directly
     /// the JSON in the relevant plugin API methods instead.
@@ -1616,6 +1882,8 @@ impl<P: ClapPlugin> Wrapper<P> {
             state::serialize_object::<P>(
                 self.params.clone(),
                 state::make_params_iter(&self.param_by_hash, &self.param_id_to_hash),
+                self.state_midi_learn(),
+                StateContext::Preset,
             )
         }
     }
@@ -1661,7 +1929,9 @@ impl<P: ClapPlugin> Wrapper<P> {
                         &mut state,
                         self.params.clone(),
                         state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                        self.state_midi_learn(),
                         self.current_buffer_config.load().as_ref(),
+                        StateContext::Preset,
                     );
                 }
 
@@ -1717,15 +1987,398 @@ impl<P: ClapPlugin> Wrapper<P> {
         }
     }
 
+    /// Query all sensible bus configurations supported by the plugin. We don't do surround or
+    /// anything beyond stereo right now. For the auxiliary inputs and outputs we'll try every
+    /// number of busses up to the number of busses from `P::DEFAULT_AUX_INPUTS` and
+    /// `P::DEFAULT_AUX_OUTPUTS`, starting with the default number so the first configuration
+    /// matches the plugin's defaults as closely as possible.
+    ///
+    /// TODO: Support surround setups once a plugin needs that
+    fn query_supported_bus_configs(plugin: &P) -> Vec<BusConfig> {
+        let default_aux_inputs = P::DEFAULT_AUX_INPUTS.unwrap_or_default();
+        let default_aux_outputs = P::DEFAULT_AUX_OUTPUTS.unwrap_or_default();
+
+        let mut supported_bus_configs = Vec::new();
+        for num_output_channels in [1, 2] {
+            for num_input_channels in [0, num_output_channels] {
+                #[allow(clippy::single_element_loop)]
+                for num_aux_channels in [num_output_channels] {
+                    for num_aux_input_busses in (0..=default_aux_inputs.num_busses).rev() {
+                        for num_aux_output_busses in (0..=default_aux_outputs.num_busses).rev() {
+                            let bus_config = BusConfig {
+                                num_input_channels,
+                                num_output_channels,
+                                aux_input_busses: AuxiliaryIOConfig {
+                                    num_busses: num_aux_input_busses,
+                                    num_channels: if num_aux_input_busses > 0 {
+                                        num_aux_channels
+                                    } else {
+                                        default_aux_inputs.num_channels
+                                    },
+                                },
+                                aux_output_busses: AuxiliaryIOConfig {
+                                    num_busses: num_aux_output_busses,
+                                    num_channels: if num_aux_output_busses > 0 {
+                                        num_aux_channels
+                                    } else {
+                                        default_aux_outputs.num_channels
+                                    },
+                                },
+                            };
+                            if plugin.accepts_bus_config(&bus_config) {
+                                supported_bus_configs.push(bus_config);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // In the off chance that the default config specified by the plugin is not in the above
+        // list, we'll try that as well.
+        let default_bus_config = BusConfig {
+            num_input_channels: P::DEFAULT_INPUT_CHANNELS,
+            num_output_channels: P::DEFAULT_OUTPUT_CHANNELS,
+            aux_input_busses: default_aux_inputs,
+            aux_output_busses: default_aux_outputs,
+        };
+        if !supported_bus_configs.contains(&default_bus_config)
+            && plugin.accepts_bus_config(&default_bus_config)
+        {
+            supported_bus_configs.push(default_bus_config);
+        }
+
+        supported_bus_configs
+    }
+
+    pub fn request_audio_ports_rescan(&self) {
+        let task_posted = self.schedule_gui(Task::RescanAudioPorts);
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    }
+
+    /// Query the plugin's supported bus configurations again, switch to a different configuration
+    /// if the current one is no longer supported, and let the host know that it should rescan the
+    /// audio ports. This may only be called from the main thread while the plugin is deactivated.
+    fn rescan_audio_ports(&self) {
+        nih_debug_assert!(!self.is_active.load(Ordering::SeqCst));
+
+        let supported_bus_configs = Self::query_supported_bus_configs(&self.plugin.lock());
+        let current_bus_config = self.current_bus_config.load();
+        if !supported_bus_configs.contains(&current_bus_config) {
+            // We'll try to keep the same main channel counts if possible
+            let new_bus_config = supported_bus_configs
+                .iter()
+                .find(|config| {
+                    config.num_input_channels == current_bus_config.num_input_channels
+                        && config.num_output_channels == current_bus_config.num_output_channels
+                })
+                .or_else(|| supported_bus_configs.first());
+            match new_bus_config {
+                Some(new_bus_config) => self.set_current_bus_config(*new_bus_config),
+                None => nih_debug_assert_failure!(
+                    "The plugin no longer supports any bus configurations, keeping the old one"
+                ),
+            }
+        }
+        *self.supported_bus_configs.borrow_mut() = supported_bus_configs;
+
+        if let Some(host_audio_ports_config) = &*self.host_audio_ports_config.borrow() {
+            unsafe_clap_call! { host_audio_ports_config=>rescan(&*self.host_callback) };
+        }
+        match &*self.host_audio_ports.borrow() {
+            Some(host_audio_ports) => {
+                unsafe_clap_call! { host_audio_ports=>rescan(&*self.host_callback, CLAP_AUDIO_PORTS_RESCAN_LIST) };
+            }
+            None => nih_debug_assert_failure!("The host does not support audio ports? What?"),
+        }
+    }
+
+    /// Change the current bus config, and mark all of the new config's auxiliary ports as active.
+    fn set_current_bus_config(&self, bus_config: BusConfig) {
+        self.current_bus_config.store(bus_config);
+
+        let mut aux_inputs_active = self.aux_inputs_active.borrow_mut();
+        aux_inputs_active.clear();
+        aux_inputs_active.resize(bus_config.aux_input_busses.num_busses as usize, true);
+        let mut aux_outputs_active = self.aux_outputs_active.borrow_mut();
+        aux_outputs_active.clear();
+        aux_outputs_active.resize(bus_config.aux_output_busses.num_busses as usize, true);
+    }
+
+    pub fn aux_input_active(&self, index: usize) -> bool {
+        self.aux_inputs_active
+            .borrow()
+            .get(index)
+            .copied()
+            .unwrap_or(false)
+    }
+
+    pub fn aux_output_active(&self, index: usize) -> bool {
+        self.aux_outputs_active
+            .borrow()
+            .get(index)
+            .copied()
+            .unwrap_or(false)
+    }
+
+    pub fn notify_note_names_changed(&self) {
+        if P::MIDI_INPUT >= MidiConfig::Basic {
+            self.note_names_dirty.store(true, Ordering::SeqCst);
+            let task_posted = self.schedule_gui(Task::NoteNamesChanged);
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        }
+    }
+
+    /// Register a timer and, on Linux, an eventfd with the host if it supports the timer support
+    /// and POSIX fd support extensions. [`tasks`][Self::tasks] is then also drained from the
+    /// host's timer and fd callbacks. Called from [`init()`][Self::init()].
+    fn register_task_notifiers(&self) {
+        if let Some(timer_support) = &*self.host_timer_support.borrow() {
+            let mut timer_id = CLAP_INVALID_ID;
+            let registered = unsafe_clap_call! {
+                timer_support=>register_timer(
+                    &*self.host_callback,
+                    TASKS_TIMER_PERIOD_MS,
+                    &mut timer_id
+                )
+            };
+            if registered && timer_id != CLAP_INVALID_ID {
+                *self.tasks_timer_id.borrow_mut() = Some(timer_id);
+            } else {
+                nih_debug_assert_failure!("The host could not register the task queue's timer");
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        if let Some(posix_fd_support) = &*self.host_posix_fd_support.borrow() {
+            let eventfd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+            if eventfd == -1 {
+                nih_debug_assert_failure!("Could not create the task queue's eventfd");
+                return;
+            }
+
+            let registered = unsafe_clap_call! {
+                posix_fd_support=>register_fd(&*self.host_callback, eventfd, CLAP_POSIX_FD_READ)
+            };
+            if registered {
+                self.tasks_eventfd.store(eventfd, Ordering::SeqCst);
+            } else {
+                nih_debug_assert_failure!("The host could not register the task queue's eventfd");
+                unsafe { libc::close(eventfd) };
+            }
+        }
+    }
+
+    /// Unregister the timer and the eventfd registered in
+    /// [`register_task_notifiers()`][Self::register_task_notifiers()]. Called from
+    /// [`destroy()`][Self::destroy()].
+    fn unregister_task_notifiers(&self) {
+        if let Some(timer_id) = self.tasks_timer_id.borrow_mut().take() {
+            if let Some(timer_support) = &*self.host_timer_support.borrow() {
+                unsafe_clap_call! {
+                    timer_support=>unregister_timer(&*self.host_callback, timer_id)
+                };
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let eventfd = self.tasks_eventfd.swap(-1, Ordering::SeqCst);
+            if eventfd != -1 {
+                if let Some(posix_fd_support) = &*self.host_posix_fd_support.borrow() {
+                    unsafe_clap_call! {
+                        posix_fd_support=>unregister_fd(&*self.host_callback, eventfd)
+                    };
+                }
+
+                unsafe { libc::close(eventfd) };
+            }
+        }
+    }
+
+    /// The vtable for the POSIX fd support extension. The eventfd used with this extension is
+    /// Linux-specific, so this returns a null pointer on other platforms.
+    #[cfg(target_os = "linux")]
+    fn posix_fd_support_extension(&self) -> *const c_void {
+        &self.clap_plugin_posix_fd_support as *const _ as *const c_void
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn posix_fd_support_extension(&self) -> *const c_void {
+        ptr::null()
+    }
+
+    /// Make sure the tasks in [`tasks`][Self::tasks] get executed on the host's main thread. This
+    /// asks the host to call [`on_main_thread()`][Self::on_main_thread()]. Writing to the
+    /// registered eventfd instead is a system call, so that is only done when the host confirms
+    /// that this is not the audio thread.
+    fn wake_main_thread(&self) {
+        #[cfg(target_os = "linux")]
+        if !self.is_audio_thread_or_unknown() {
+            let eventfd = self.tasks_eventfd.load(Ordering::SeqCst);
+            if eventfd != -1 {
+                // This value is added to the eventfd's counter, which is reset again in `on_fd()`
+                let notify_value = 1u64;
+                const NOTIFY_VALUE_SIZE: usize = mem::size_of::<u64>();
+                let bytes_written = unsafe {
+                    libc::write(
+                        eventfd,
+                        &notify_value as *const _ as *const c_void,
+                        NOTIFY_VALUE_SIZE,
+                    )
+                };
+                if bytes_written == NOTIFY_VALUE_SIZE as isize {
+                    return;
+                }
+            }
+        }
+
+        let host = &self.host_callback;
+        unsafe_clap_call! { host=>request_callback(&**host) };
+    }
+
+    /// Whether the current thread is the audio thread, or whether that can't be determined because
+    /// the host does not support the thread check extension.
+    #[cfg(target_os = "linux")]
+    fn is_audio_thread_or_unknown(&self) -> bool {
+        match &*self.host_thread_check.borrow() {
+            Some(thread_check) => {
+                unsafe_clap_call! { thread_check=>is_audio_thread(&*self.host_callback) }
+            }
+            None => true,
+        }
+    }
+
+    pub fn track_info(&self) -> Option<TrackInfo> {
+        self.track_info.lock().clone()
+    }
+
+    /// Query the host for information about the track the plugin is inserted on. Returns `None`
+    /// if the host does not support the track info extension or if the query failed.
+    fn query_track_info(&self) -> Option<TrackInfo> {
+        let host_track_info = self.host_track_info.borrow();
+        let host_track_info = host_track_info.as_ref()?;
+
+        let mut info: clap_track_info = unsafe { mem::zeroed() };
+        let success = unsafe_clap_call! {
+            host_track_info=>get(&*self.host_callback, &mut info)
+        };
+        if success {
+            Some(track_info_from_clap(&info))
+        } else {
+            None
+        }
+    }
+
+    /// Ask the host to show its context menu for a parameter at the specified position relative to
+    /// the editor window. Returns `false` if the host does not support the context menu extension.
+    /// When called from the main thread this also returns `false` if the host cannot show popup
+    /// menus. Otherwise the menu is shown asynchronously from the main thread since the context
+    /// menu extension cannot be used from other threads.
+    pub fn show_context_menu(&self, param_hash: u32, x: i32, y: i32) -> bool {
+        if self.host_context_menu.borrow().is_none() {
+            return false;
+        }
+
+        if self.is_main_thread() {
+            self.popup_context_menu(param_hash, x, y)
+        } else {
+            let task_posted = self.schedule_gui(Task::ShowContextMenu { param_hash, x, y });
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+
+            task_posted
+        }
+    }
+
+    /// Show the host's context menu for a parameter. Returns `false` if the host does not support
+    /// the context menu extension or if it cannot show popup menus. This may only be called from
+    /// the main thread.
+    fn popup_context_menu(&self, param_hash: u32, x: i32, y: i32) -> bool {
+        let host_context_menu = self.host_context_menu.borrow();
+        let host_context_menu = match host_context_menu.as_ref() {
+            Some(host_context_menu) => host_context_menu,
+            None => return false,
+        };
+
+        if !unsafe_clap_call! { host_context_menu=>can_popup(&*self.host_callback) } {
+            return false;
+        }
+
+        let target = clap_context_menu_target {
+            kind: CLAP_CONTEXT_MENU_TARGET_KIND_PARAM,
+            id: param_hash,
+        };
+        unsafe_clap_call! {
+            host_context_menu=>popup(&*self.host_callback, &target, 0, x, y)
+        }
+    }
+
+    /// Get the ID of the parameter a context menu target refers to. Returns `Some(None)` if the
+    /// target refers to the plugin as a whole, and `None` if the target is not valid.
+    ///
+    /// # Safety
+    ///
+    /// `target` needs to be either a null pointer or a valid pointer to a context menu target.
+    unsafe fn context_menu_target_param_id(
+        &self,
+        target: *const clap_context_menu_target,
+    ) -> Option<Option<&str>> {
+        // A null pointer also refers to the plugin as a whole
+        if target.is_null() {
+            return Some(None);
+        }
+
+        match *target {
+            clap_context_menu_target {
+                kind: CLAP_CONTEXT_MENU_TARGET_KIND_GLOBAL,
+                ..
+            } => Some(None),
+            clap_context_menu_target {
+                kind: CLAP_CONTEXT_MENU_TARGET_KIND_PARAM,
+                id,
+            } => self
+                .param_id_to_hash
+                .iter()
+                .find(|(_, hash)| **hash == id)
+                .map(|(param_id, _)| Some(param_id.as_str())),
+            clap_context_menu_target { kind, .. } => {
+                nih_debug_assert_failure!("Unknown context menu target kind {}", kind);
+                None
+            }
+        }
+    }
+
+    /// Execute all tasks in [`tasks`][Self::tasks]. This is called from the host's main thread.
+    fn execute_queued_tasks(&self) {
+        while let Some(task) = self.tasks.pop() {
+            self.execute(task, true);
+        }
+    }
+
     unsafe extern "C" fn init(plugin: *const clap_plugin) -> bool {
         check_null_ptr!(false, plugin, (*plugin).plugin_data);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
         // We weren't allowed to query these in the constructor, so we need to do it now instead.
+        *wrapper.host_audio_ports.borrow_mut() = query_host_extension::<clap_host_audio_ports>(
+            &wrapper.host_callback,
+            CLAP_EXT_AUDIO_PORTS,
+        );
+        *wrapper.host_audio_ports_config.borrow_mut() =
+            query_host_extension::<clap_host_audio_ports_config>(
+                &wrapper.host_callback,
+                CLAP_EXT_AUDIO_PORTS_CONFIG,
+            );
+        *wrapper.host_context_menu.borrow_mut() = query_host_extension::<clap_host_context_menu>(
+            &wrapper.host_callback,
+            CLAP_EXT_CONTEXT_MENU,
+        );
         *wrapper.host_gui.borrow_mut() =
             query_host_extension::<clap_host_gui>(&wrapper.host_callback, CLAP_EXT_GUI);
         *wrapper.host_latency.borrow_mut() =
             query_host_extension::<clap_host_latency>(&wrapper.host_callback, CLAP_EXT_LATENCY);
+        *wrapper.host_note_name.borrow_mut() =
+            query_host_extension::<clap_host_note_name>(&wrapper.host_callback, CLAP_EXT_NOTE_NAME);
         *wrapper.host_params.borrow_mut() =
             query_host_extension::<clap_host_params>(&wrapper.host_callback, CLAP_EXT_PARAMS);
         *wrapper.host_voice_info.borrow_mut() = query_host_extension::<clap_host_voice_info>(
@@ -1736,12 +2389,41 @@ impl<P: ClapPlugin> Wrapper<P> {
             &wrapper.host_callback,
             CLAP_EXT_THREAD_CHECK,
         );
+        *wrapper.host_timer_support.borrow_mut() = query_host_extension::<clap_host_timer_support>(
+            &wrapper.host_callback,
+            CLAP_EXT_TIMER_SUPPORT,
+        );
+        *wrapper.host_track_info.borrow_mut() = query_host_extension::<clap_host_track_info>(
+            &wrapper.host_callback,
+            CLAP_EXT_TRACK_INFO,
+        );
+        #[cfg(target_os = "linux")]
+        {
+            *wrapper.host_posix_fd_support.borrow_mut() =
+                query_host_extension::<clap_host_posix_fd_support>(
+                    &wrapper.host_callback,
+                    CLAP_EXT_POSIX_FD_SUPPORT,
+                );
+        }
+
+        // If the host supports these extensions, then tasks will be run from the host's timer and
+        // fd callbacks on the host's main thread
+        wrapper.register_task_notifiers();
+
+        // The plugin can access this through its contexts, starting from the first `initialize()`
+        // call
+        *wrapper.track_info.lock() = wrapper.query_track_info();
 
         true
     }
 
     unsafe extern "C" fn destroy(plugin: *const clap_plugin) {
         assert!(!plugin.is_null() && !(*plugin).plugin_data.is_null());
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        // The host should not call our timer and fd callbacks after the plugin has been destroyed
+        wrapper.unregister_task_notifiers();
+
         drop(Arc::from_raw((*plugin).plugin_data as *mut Self));
     }
 
@@ -1830,6 +2512,7 @@ impl<P: ClapPlugin> Wrapper<P> {
 
             // Also store this for later, so we can reinitialize the plugin after restoring state
             wrapper.current_buffer_config.store(Some(buffer_config));
+            wrapper.is_active.store(true, Ordering::SeqCst);
 
             true
         } else {
@@ -1842,6 +2525,16 @@ impl<P: ClapPlugin> Wrapper<P> {
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
         wrapper.plugin.lock().deactivate();
+        wrapper.is_active.store(false, Ordering::SeqCst);
+
+        // If the plugin requested an audio ports rescan while it was active, then we'll have
+        // requested a restart and we can now finally perform the rescan
+        if wrapper
+            .audio_ports_rescan_pending
+            .swap(false, Ordering::SeqCst)
+        {
+            wrapper.rescan_audio_ports();
+        }
     }
 
     unsafe extern "C" fn start_processing(plugin: *const clap_plugin) -> bool {
@@ -2062,6 +2755,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                 // the `aux` parameter on the `process()` function.
                 let mut aux_input_storage = wrapper.aux_input_storage.borrow_mut();
                 let mut aux_input_buffers = wrapper.aux_input_buffers.borrow_mut();
+                let aux_inputs_active = wrapper.aux_inputs_active.borrow();
                 for (auxiliary_input_idx, (storage, buffer)) in aux_input_storage
                     .iter_mut()
                     .zip(aux_input_buffers.iter_mut())
@@ -2081,11 +2775,21 @@ impl<P: ClapPlugin> Wrapper<P> {
                             || storage.is_empty()
                             || (*host_input).channel_count != buffer.channels() as u32
                     {
-                        nih_debug_assert!(host_input_idx < process.audio_inputs_count as isize);
-                        nih_debug_assert!(!process.audio_inputs.is_null());
-                        nih_debug_assert!(!(*host_input).data32.is_null());
-                        nih_debug_assert!(!storage.is_empty());
-                        nih_debug_assert_eq!((*host_input).channel_count, buffer.channels() as u32);
+                        // The host doesn't need to provide buffers for deactivated ports
+                        if aux_inputs_active
+                            .get(auxiliary_input_idx)
+                            .copied()
+                            .unwrap_or(true)
+                        {
+                            nih_debug_assert!(host_input_idx < process.audio_inputs_count as isize);
+                            nih_debug_assert!(!process.audio_inputs.is_null());
+                            nih_debug_assert!(!(*host_input).data32.is_null());
+                            nih_debug_assert!(!storage.is_empty());
+                            nih_debug_assert_eq!(
+                                (*host_input).channel_count,
+                                buffer.channels() as u32
+                            );
+                        }
 
                         // If the host passes weird data then we need to be very sure that there are
                         // no dangling references to previous data
@@ -2124,6 +2828,7 @@ impl<P: ClapPlugin> Wrapper<P> {
 
                 // And the same thing for auxiliary output buffers
                 let mut aux_output_buffers = wrapper.aux_output_buffers.borrow_mut();
+                let aux_outputs_active = wrapper.aux_outputs_active.borrow();
                 for (auxiliary_output_idx, buffer) in aux_output_buffers.iter_mut().enumerate() {
                     let host_output_idx = if has_main_output {
                         auxiliary_output_idx as isize + 1
@@ -2136,9 +2841,17 @@ impl<P: ClapPlugin> Wrapper<P> {
                         || (*host_output).data32.is_null()
                         || buffer.channels() == 0
                     {
-                        nih_debug_assert!(host_output_idx < process.audio_outputs_count as isize);
-                        nih_debug_assert!(!process.audio_outputs.is_null());
-                        nih_debug_assert!(!(*host_output).data32.is_null());
+                        if aux_outputs_active
+                            .get(auxiliary_output_idx)
+                            .copied()
+                            .unwrap_or(true)
+                        {
+                            nih_debug_assert!(
+                                host_output_idx < process.audio_outputs_count as isize
+                            );
+                            nih_debug_assert!(!process.audio_outputs.is_null());
+                            nih_debug_assert!(!(*host_output).data32.is_null());
+                        }
 
                         // If the host passes weird data then we need to be very sure that there are
                         // no dangling references to previous data
@@ -2306,7 +3019,9 @@ impl<P: ClapPlugin> Wrapper<P> {
                     &mut state,
                     wrapper.params.clone(),
                     state::make_params_getter(&wrapper.param_by_hash, &wrapper.param_id_to_hash),
+                    wrapper.state_midi_learn(),
                     wrapper.current_buffer_config.load().as_ref(),
+                    StateContext::Preset,
                 );
 
                 wrapper.notify_param_values_changed();
@@ -2354,23 +3069,37 @@ impl<P: ClapPlugin> Wrapper<P> {
             &wrapper.clap_plugin_audio_ports_config as *const _ as *const c_void
         } else if id == CLAP_EXT_AUDIO_PORTS {
             &wrapper.clap_plugin_audio_ports as *const _ as *const c_void
+        } else if id == CLAP_EXT_AUDIO_PORTS_ACTIVATION {
+            &wrapper.clap_plugin_audio_ports_activation as *const _ as *const c_void
+        } else if id == CLAP_EXT_CONTEXT_MENU {
+            &wrapper.clap_plugin_context_menu as *const _ as *const c_void
         } else if id == CLAP_EXT_GUI && wrapper.editor.borrow().is_some() {
             // Only report that we support this extension if the plugin has an editor
             &wrapper.clap_plugin_gui as *const _ as *const c_void
         } else if id == CLAP_EXT_LATENCY {
             &wrapper.clap_plugin_latency as *const _ as *const c_void
+        } else if id == CLAP_EXT_NOTE_NAME && P::MIDI_INPUT >= MidiConfig::Basic {
+            &wrapper.clap_plugin_note_name as *const _ as *const c_void
         } else if id == CLAP_EXT_NOTE_PORTS
             && (P::MIDI_INPUT >= MidiConfig::Basic || P::MIDI_OUTPUT >= MidiConfig::Basic)
         {
             &wrapper.clap_plugin_note_ports as *const _ as *const c_void
         } else if id == CLAP_EXT_PARAMS {
             &wrapper.clap_plugin_params as *const _ as *const c_void
+        } else if id == CLAP_EXT_POSIX_FD_SUPPORT {
+            wrapper.posix_fd_support_extension()
         } else if id == CLAP_EXT_RENDER {
             &wrapper.clap_plugin_render as *const _ as *const c_void
         } else if id == CLAP_EXT_STATE {
             &wrapper.clap_plugin_state as *const _ as *const c_void
+        } else if id == CLAP_EXT_STATE_CONTEXT {
+            &wrapper.clap_plugin_state_context as *const _ as *const c_void
         } else if id == CLAP_EXT_TAIL {
             &wrapper.clap_plugin_tail as *const _ as *const c_void
+        } else if id == CLAP_EXT_TIMER_SUPPORT {
+            &wrapper.clap_plugin_timer_support as *const _ as *const c_void
+        } else if id == CLAP_EXT_TRACK_INFO {
+            &wrapper.clap_plugin_track_info as *const _ as *const c_void
         } else if id == CLAP_EXT_VOICE_INFO && P::CLAP_POLY_MODULATION_CONFIG.is_some() {
             &wrapper.clap_plugin_voice_info as *const _ as *const c_void
         } else {
@@ -2385,16 +3114,14 @@ impl<P: ClapPlugin> Wrapper<P> {
 
         // [Self::schedule_gui] posts a task to the queue and asks the host to call this function
         // on the main thread, so once that's done we can just handle all requests here
-        while let Some(task) = wrapper.tasks.pop() {
-            wrapper.execute(task, true);
-        }
+        wrapper.execute_queued_tasks();
     }
 
     unsafe extern "C" fn ext_audio_ports_config_count(plugin: *const clap_plugin) -> u32 {
         check_null_ptr!(0, plugin, (*plugin).plugin_data);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
-        wrapper.supported_bus_configs.len() as u32
+        wrapper.supported_bus_configs.borrow().len() as u32
     }
 
     unsafe extern "C" fn ext_audio_ports_config_get(
@@ -2405,11 +3132,9 @@ impl<P: ClapPlugin> Wrapper<P> {
         check_null_ptr!(false, plugin, (*plugin).plugin_data, config);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
-        match wrapper.supported_bus_configs.get(index as usize) {
+        match wrapper.supported_bus_configs.borrow().get(index as usize) {
             Some(bus_config) => {
-                // We don't support variable auxiliary IO configs right now, so we don't need to
-                // specify sidechain inputs and aux outputs in these descriptions
-                let name = match bus_config {
+                let mut name = match bus_config {
                     BusConfig {
                         num_input_channels: _,
                         num_output_channels: 1,
@@ -2426,6 +3151,23 @@ impl<P: ClapPlugin> Wrapper<P> {
                         ..
                     } => format!("{num_input_channels} inputs, {num_output_channels} outputs"),
                 };
+                // Configurations with the default number of auxiliary busses don't need to mention
+                // them, but if the plugin supports multiple variations then those should be
+                // distinguishable
+                let default_aux_inputs = P::DEFAULT_AUX_INPUTS.unwrap_or_default().num_busses;
+                if bus_config.aux_input_busses.num_busses != default_aux_inputs {
+                    name.push_str(&format!(
+                        ", {} sidechain inputs",
+                        bus_config.aux_input_busses.num_busses
+                    ));
+                }
+                let default_aux_outputs = P::DEFAULT_AUX_OUTPUTS.unwrap_or_default().num_busses;
+                if bus_config.aux_output_busses.num_busses != default_aux_outputs {
+                    name.push_str(&format!(
+                        ", {} auxiliary outputs",
+                        bus_config.aux_output_busses.num_busses
+                    ));
+                }
                 let input_port_type = match bus_config.num_input_channels {
                     1 => CLAP_PORT_MONO.as_ptr(),
                     2 => CLAP_PORT_STEREO.as_ptr(),
@@ -2480,9 +3222,14 @@ impl<P: ClapPlugin> Wrapper<P> {
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
         // We use the vector indices for the config ID
-        match wrapper.supported_bus_configs.get(config_id as usize) {
+        let bus_config = wrapper
+            .supported_bus_configs
+            .borrow()
+            .get(config_id as usize)
+            .copied();
+        match bus_config {
             Some(bus_config) => {
-                wrapper.current_bus_config.store(*bus_config);
+                wrapper.set_current_bus_config(bus_config);
 
                 true
             }
@@ -2639,6 +3386,158 @@ impl<P: ClapPlugin> Wrapper<P> {
         true
     }
 
+    unsafe extern "C" fn ext_audio_ports_activation_can_activate_while_processing(
+        _plugin: *const clap_plugin,
+    ) -> bool {
+        // The plugin is only told about the active ports in `Plugin::initialize()` and through the
+        // process context, so keeping this fixed while processing avoids surprises
+        false
+    }
+
+    unsafe extern "C" fn ext_audio_ports_activation_set_active(
+        plugin: *const clap_plugin,
+        is_input: bool,
+        port_index: u32,
+        is_active: bool,
+    ) -> bool {
+        check_null_ptr!(false, plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        let current_bus_config = wrapper.current_bus_config.load();
+        let has_main_port = if is_input {
+            current_bus_config.num_input_channels > 0
+        } else {
+            current_bus_config.num_output_channels > 0
+        };
+
+        // The main ports always stay active
+        if has_main_port && port_index == 0 {
+            return is_active;
+        }
+
+        let aux_port_idx = if has_main_port {
+            port_index - 1
+        } else {
+            port_index
+        } as usize;
+        let mut aux_ports_active = if is_input {
+            wrapper.aux_inputs_active.borrow_mut()
+        } else {
+            wrapper.aux_outputs_active.borrow_mut()
+        };
+        match aux_ports_active.get_mut(aux_port_idx) {
+            Some(port_active) => {
+                *port_active = is_active;
+
+                true
+            }
+            None => {
+                nih_debug_assert_failure!(
+                    "Host tried to activate or deactivate out of bounds audio port {} (input: {})",
+                    port_index,
+                    is_input
+                );
+
+                false
+            }
+        }
+    }
+
+    unsafe extern "C" fn ext_context_menu_populate(
+        plugin: *const clap_plugin,
+        target: *const clap_context_menu_target,
+        builder: *const clap_context_menu_builder,
+    ) -> bool {
+        check_null_ptr!(false, plugin, (*plugin).plugin_data, builder);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        let param_id = match wrapper.context_menu_target_param_id(target) {
+            Some(param_id) => param_id,
+            None => return false,
+        };
+
+        let entries = wrapper.plugin.lock().context_menu_entries(param_id);
+        for entry in entries {
+            // The labels only need to live until the host has copied them
+            let success = match entry {
+                ContextMenuEntry::Action {
+                    label,
+                    action_id,
+                    enabled,
+                } => {
+                    let label = CString::new(label).unwrap_or_default();
+                    let item = clap_context_menu_entry {
+                        label: label.as_ptr(),
+                        is_enabled: enabled,
+                        action_id,
+                    };
+
+                    clap_call! {
+                        builder=>add_item(
+                            builder,
+                            CLAP_CONTEXT_MENU_ITEM_ENTRY,
+                            &item as *const _ as *const c_void,
+                        )
+                    }
+                }
+                ContextMenuEntry::Toggle {
+                    label,
+                    action_id,
+                    enabled,
+                    checked,
+                } => {
+                    let label = CString::new(label).unwrap_or_default();
+                    let item = clap_context_menu_check_entry {
+                        label: label.as_ptr(),
+                        is_enabled: enabled,
+                        is_checked: checked,
+                        action_id,
+                    };
+
+                    clap_call! {
+                        builder=>add_item(
+                            builder,
+                            CLAP_CONTEXT_MENU_ITEM_CHECK_ENTRY,
+                            &item as *const _ as *const c_void,
+                        )
+                    }
+                }
+                ContextMenuEntry::Separator => {
+                    clap_call! {
+                        builder=>add_item(builder, CLAP_CONTEXT_MENU_ITEM_SEPARATOR, ptr::null())
+                    }
+                }
+            };
+
+            if !success {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    unsafe extern "C" fn ext_context_menu_perform(
+        plugin: *const clap_plugin,
+        target: *const clap_context_menu_target,
+        action_id: clap_id,
+    ) -> bool {
+        check_null_ptr!(false, plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        match wrapper.context_menu_target_param_id(target) {
+            Some(param_id) => {
+                wrapper
+                    .plugin
+                    .lock()
+                    .context_menu_action(param_id, action_id);
+
+                true
+            }
+            None => false,
+        }
+    }
+
     unsafe extern "C" fn ext_gui_is_api_supported(
         _plugin: *const clap_plugin,
         api: *const c_char,
@@ -2831,6 +3730,10 @@ impl<P: ClapPlugin> Wrapper<P> {
                     return false;
                 };
 
+                // NOTE: Unlike VST3's `IPlugView::onKeyDown()`, CLAP does not have a way for the host
+                //       to forward key events to the plugin, so `Editor::on_key_down()` and
+                //       `Editor::on_key_up()` are never called for CLAP plugins
+
                 // This extension is only exposed when we have an editor
                 *editor_handle = Some(wrapper.editor.borrow().as_ref().unwrap().lock().spawn(
                     ParentWindowHandle { handle },
@@ -2884,6 +3787,44 @@ impl<P: ClapPlugin> Wrapper<P> {
         wrapper.current_latency.load(Ordering::SeqCst)
     }
 
+    unsafe extern "C" fn ext_note_name_count(plugin: *const clap_plugin) -> u32 {
+        check_null_ptr!(0, plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        // The host will query the number of note names before fetching the names themselves, so
+        // this is the only place where the plugin needs to be queried
+        if wrapper.note_names_dirty.swap(false, Ordering::SeqCst) {
+            *wrapper.note_names.borrow_mut() = wrapper.plugin.lock().note_names();
+        }
+
+        wrapper.note_names.borrow().len() as u32
+    }
+
+    unsafe extern "C" fn ext_note_name_get(
+        plugin: *const clap_plugin,
+        index: u32,
+        note_name: *mut clap_note_name,
+    ) -> bool {
+        check_null_ptr!(false, plugin, (*plugin).plugin_data, note_name);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        match wrapper.note_names.borrow().get(index as usize) {
+            Some(name) => {
+                *note_name = std::mem::zeroed();
+
+                let note_name = &mut *note_name;
+                strlcpy(&mut note_name.name, &name.name);
+                // We only have a single note port, so -1 (all ports) and 0 are equivalent
+                note_name.port = -1;
+                note_name.key = name.note as i16;
+                note_name.channel = name.channel.map(|channel| channel as i16).unwrap_or(-1);
+
+                true
+            }
+            None => false,
+        }
+    }
+
     unsafe extern "C" fn ext_note_ports_count(_plugin: *const clap_plugin, is_input: bool) -> u32 {
         match is_input {
             true if P::MIDI_INPUT >= MidiConfig::Basic => 1,
@@ -2960,6 +3901,7 @@ impl<P: ClapPlugin> Wrapper<P> {
         let automatable = !flags.contains(ParamFlags::NON_AUTOMATABLE);
         let hidden = flags.contains(ParamFlags::HIDDEN);
         let is_bypass = flags.contains(ParamFlags::BYPASS);
+        let is_list = flags.contains(ParamFlags::LIST);
 
         *param_info = std::mem::zeroed();
 
@@ -2982,7 +3924,12 @@ impl<P: ClapPlugin> Wrapper<P> {
             param_info.flags |= CLAP_PARAM_IS_BYPASS
         }
         if step_count.is_some() {
-            param_info.flags |= CLAP_PARAM_IS_STEPPED
+            param_info.flags |= CLAP_PARAM_IS_STEPPED;
+
+            // The host can query the values' display strings using `value_to_text()`
+            if is_list {
+                param_info.flags |= CLAP_PARAM_IS_ENUM;
+            }
         }
         param_info.cookie = ptr::null_mut();
         strlcpy(&mut param_info.name, param_ptr.name());
@@ -3033,14 +3980,12 @@ impl<P: ClapPlugin> Wrapper<P> {
 
         match wrapper.param_by_hash.get(&param_id) {
             Some(param_ptr) => {
-                strlcpy(
-                    dest,
-                    // CLAP does not have a separate unit, so we'll include the unit here
-                    &param_ptr.normalized_value_to_string(
-                        value as f32 / param_ptr.step_count().unwrap_or(1) as f32,
-                        true,
-                    ),
-                );
+                let normalized = value as f32 / param_ptr.step_count().unwrap_or(1) as f32;
+                // CLAP does not have a separate unit, so we'll include the unit here. Parameters
+                // marked with `CLAP_PARAM_IS_ENUM` use their list of value strings.
+                let display = list_value_string(param_ptr, normalized, true)
+                    .unwrap_or_else(|| param_ptr.normalized_value_to_string(normalized, true));
+                strlcpy(dest, &display);
 
                 true
             }
@@ -3093,6 +4038,32 @@ impl<P: ClapPlugin> Wrapper<P> {
         }
     }
 
+    #[cfg(target_os = "linux")]
+    unsafe extern "C" fn ext_posix_fd_support_on_fd(
+        plugin: *const clap_plugin,
+        fd: i32,
+        _flags: clap_posix_fd_flags,
+    ) {
+        check_null_ptr!((), plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        if fd != wrapper.tasks_eventfd.load(Ordering::SeqCst) {
+            nih_debug_assert_failure!("Host called on_fd() for unknown fd {}", fd);
+            return;
+        }
+
+        // Reading from the eventfd resets its counter. This happens before running the tasks so
+        // tasks posted in the meantime will wake up the host's main thread again.
+        let mut notify_value = 0u64;
+        libc::read(
+            fd,
+            &mut notify_value as *mut _ as *mut c_void,
+            mem::size_of::<u64>(),
+        );
+
+        wrapper.execute_queued_tasks();
+    }
+
     unsafe extern "C" fn ext_render_has_hard_realtime_requirement(
         _plugin: *const clap_plugin,
     ) -> bool {
@@ -3127,22 +4098,63 @@ impl<P: ClapPlugin> Wrapper<P> {
         check_null_ptr!(false, plugin, (*plugin).plugin_data, stream);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
+        wrapper.save_state(&*stream, StateContext::Project)
+    }
+
+    unsafe extern "C" fn ext_state_load(
+        plugin: *const clap_plugin,
+        stream: *const clap_istream,
+    ) -> bool {
+        check_null_ptr!(false, plugin, (*plugin).plugin_data, stream);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        wrapper.load_state(&*stream, StateContext::Project)
+    }
+
+    unsafe extern "C" fn ext_state_context_save(
+        plugin: *const clap_plugin,
+        stream: *const clap_ostream,
+        context_type: clap_plugin_state_context_type,
+    ) -> bool {
+        check_null_ptr!(false, plugin, (*plugin).plugin_data, stream);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        wrapper.save_state(&*stream, state_context_from_clap(context_type))
+    }
+
+    unsafe extern "C" fn ext_state_context_load(
+        plugin: *const clap_plugin,
+        stream: *const clap_istream,
+        context_type: clap_plugin_state_context_type,
+    ) -> bool {
+        check_null_ptr!(false, plugin, (*plugin).plugin_data, stream);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        wrapper.load_state(&*stream, state_context_from_clap(context_type))
+    }
+
+    /// Write the plugin's state to a CLAP stream. This is shared between the `state` and
+    /// `state-context` extensions. Persistent fields that opted out of being stored in `context`
+    /// are left out.
+    unsafe fn save_state(&self, stream: &clap_ostream, context: StateContext) -> bool {
         let serialized = state::serialize_json::<P>(
-            wrapper.params.clone(),
-            state::make_params_iter(&wrapper.param_by_hash, &wrapper.param_id_to_hash),
+            self.params.clone(),
+            state::make_params_iter(&self.param_by_hash, &self.param_id_to_hash),
+            self.state_midi_learn(),
+            context,
         );
         match serialized {
             Ok(serialized) => {
                 // CLAP does not provide a way to tell how much data there is left in a stream, so
                 // we need to prepend it to our actual state data.
                 let length_bytes = (serialized.len() as u64).to_le_bytes();
-                if !write_stream(&*stream, &length_bytes) {
+                if !write_stream(stream, &length_bytes) {
                     nih_debug_assert_failure!(
                         "Error or end of stream while writing the state length to the stream."
                     );
                     return false;
                 }
-                if !write_stream(&*stream, &serialized) {
+                if !write_stream(stream, &serialized) {
                     nih_debug_assert_failure!(
                         "Error or end of stream while writing the state buffer to the stream."
                     );
@@ -3160,17 +4172,13 @@ impl<P: ClapPlugin> Wrapper<P> {
         }
     }
 
-    unsafe extern "C" fn ext_state_load(
-        plugin: *const clap_plugin,
-        stream: *const clap_istream,
-    ) -> bool {
-        check_null_ptr!(false, plugin, (*plugin).plugin_data, stream);
-        let wrapper = &*((*plugin).plugin_data as *const Self);
-
+    /// Restore the plugin's state from a CLAP stream. This is shared between the `state` and
+    /// `state-context` extensions.
+    unsafe fn load_state(&self, stream: &clap_istream, context: StateContext) -> bool {
         // CLAP does not have a way to tell how much data there is left in a stream, so we've
         // prepended the size in front of our JSON state
         let mut length_bytes = [0u8; 8];
-        if !read_stream(&*stream, length_bytes.as_mut_slice()) {
+        if !read_stream(stream, length_bytes.as_mut_slice()) {
             nih_debug_assert_failure!(
                 "Error or end of stream while reading the state length from the stream."
             );
@@ -3179,7 +4187,7 @@ impl<P: ClapPlugin> Wrapper<P> {
         let length = u64::from_le_bytes(length_bytes);
 
         let mut read_buffer: Vec<u8> = Vec::with_capacity(length as usize);
-        if !read_stream(&*stream, read_buffer.spare_capacity_mut()) {
+        if !read_stream(stream, read_buffer.spare_capacity_mut()) {
             nih_debug_assert_failure!(
                 "Error or end of stream while reading the state buffer from the stream."
             );
@@ -3189,25 +4197,23 @@ impl<P: ClapPlugin> Wrapper<P> {
 
         let success = state::deserialize_json::<P>(
             &read_buffer,
-            wrapper.params.clone(),
-            state::make_params_getter(&wrapper.param_by_hash, &wrapper.param_id_to_hash),
-            wrapper.current_buffer_config.load().as_ref(),
+            self.params.clone(),
+            state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+            self.state_midi_learn(),
+            self.current_buffer_config.load().as_ref(),
+            context,
         );
         if !success {
             return false;
         }
 
         // Reinitialize the plugin after loading state so it can respond to the new parameter values
-        wrapper.notify_param_values_changed();
+        self.notify_param_values_changed();
 
-        let bus_config = wrapper.current_bus_config.load();
-        if let Some(buffer_config) = wrapper.current_buffer_config.load() {
-            let mut plugin = wrapper.plugin.lock();
-            plugin.initialize(
-                &bus_config,
-                &buffer_config,
-                &mut wrapper.make_init_context(),
-            );
+        let bus_config = self.current_bus_config.load();
+        if let Some(buffer_config) = self.current_buffer_config.load() {
+            let mut plugin = self.plugin.lock();
+            plugin.initialize(&bus_config, &buffer_config, &mut self.make_init_context());
             // TODO: This also goes for the VST3 version, but should we call reset here? Won't the
             //       host always restart playback? Check this with a couple of hosts and remove the
             //       duplicate reset if it's not needed.
@@ -3230,6 +4236,30 @@ impl<P: ClapPlugin> Wrapper<P> {
         }
     }
 
+    unsafe extern "C" fn ext_timer_support_on_timer(plugin: *const clap_plugin, timer_id: clap_id) {
+        check_null_ptr!((), plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        if *wrapper.tasks_timer_id.borrow() != Some(timer_id) {
+            nih_debug_assert_failure!("Host called on_timer() for unknown timer {}", timer_id);
+            return;
+        }
+
+        wrapper.execute_queued_tasks();
+    }
+
+    unsafe extern "C" fn ext_track_info_changed(plugin: *const clap_plugin) {
+        check_null_ptr!((), plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        // If the query fails then the old information is kept, so `track_info()` keeps matching
+        // what the plugin received in its last `track_info_changed()` call
+        if let Some(track_info) = wrapper.query_track_info() {
+            *wrapper.track_info.lock() = Some(track_info.clone());
+            wrapper.plugin.lock().track_info_changed(&track_info);
+        }
+    }
+
     unsafe extern "C" fn ext_voice_info_get(
         plugin: *const clap_plugin,
         info: *mut clap_voice_info,
@@ -3273,3 +4303,60 @@ unsafe fn query_host_extension<T>(
         None
     }
 }
+
+/// Convert a CLAP `state-context` context type to the corresponding [`StateContext`]. Unknown
+/// context types are treated as project state.
+fn state_context_from_clap(context_type: clap_plugin_state_context_type) -> StateContext {
+    match context_type {
+        CLAP_STATE_CONTEXT_FOR_PRESET => StateContext::Preset,
+        CLAP_STATE_CONTEXT_FOR_DUPLICATE => StateContext::Duplicate,
+        CLAP_STATE_CONTEXT_FOR_PROJECT => StateContext::Project,
+        n => {
+            nih_debug_assert_failure!("Unknown state context type '{}', assuming project state", n);
+            StateContext::Project
+        }
+    }
+}
+
+/// Convert the track information reported by a CLAP host to a [`TrackInfo`] object.
+fn track_info_from_clap(info: &clap_track_info) -> TrackInfo {
+    let name = if info.flags & CLAP_TRACK_INFO_HAS_TRACK_NAME != 0 {
+        unsafe { CStr::from_ptr(info.name.as_ptr()) }
+            .to_str()
+            .ok()
+            .map(String::from)
+    } else {
+        None
+    };
+    let color = if info.flags & CLAP_TRACK_INFO_HAS_TRACK_COLOR != 0 {
+        Some(TrackColor {
+            red: info.color.red,
+            green: info.color.green,
+            blue: info.color.blue,
+            alpha: info.color.alpha,
+        })
+    } else {
+        None
+    };
+    let track_type = if info.flags & CLAP_TRACK_INFO_IS_FOR_MASTER != 0 {
+        TrackType::Master
+    } else if info.flags & CLAP_TRACK_INFO_IS_FOR_RETURN_TRACK != 0 {
+        TrackType::Return
+    } else if info.flags & CLAP_TRACK_INFO_IS_FOR_BUS != 0 {
+        TrackType::Bus
+    } else {
+        TrackType::Regular
+    };
+    let audio_channel_count = if info.flags & CLAP_TRACK_INFO_HAS_AUDIO_CHANNEL != 0 {
+        u32::try_from(info.audio_channel_count).ok()
+    } else {
+        None
+    };
+
+    TrackInfo {
+        name,
+        color,
+        track_type: Some(track_type),
+        audio_channel_count,
+    }
+}
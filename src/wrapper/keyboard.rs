@@ -0,0 +1,205 @@
+//! Format-agnostic helpers for converting the host's or the windowing system's key events to
+//! [`KeyboardEvent`]s for [`Editor::on_key_down()`][crate::prelude::Editor::on_key_down()] and
+//! [`Editor::on_key_up()`][crate::prelude::Editor::on_key_up()]. The VST3 wrapper uses these after
+//! decoding its virtual key codes, and the standalone wrapper uses them to normalize baseview's
+//! events so editors see the same events regardless of the plugin format.
+
+use keyboard_types::{Code, Key, KeyState, KeyboardEvent, Location, Modifiers};
+
+/// Build a [`KeyboardEvent`] from a key and a code, filling in the key's location based on the
+/// code. Composition and key repeats are not reported by any of the wrappers.
+pub fn create_keyboard_event(
+    key: Key,
+    code: Code,
+    modifiers: Modifiers,
+    state: KeyState,
+) -> KeyboardEvent {
+    KeyboardEvent {
+        state,
+        key,
+        location: code_to_location(code),
+        code,
+        modifiers,
+        repeat: false,
+        is_composing: false,
+    }
+}
+
+/// Make sure an event that did not come from one of our own conversion functions has a consistent
+/// location. Some windowing backends leave this at [`Location::Standard`] for numpad keys.
+#[cfg_attr(not(feature = "standalone"), allow(dead_code))]
+pub fn normalize_keyboard_event(mut event: KeyboardEvent) -> KeyboardEvent {
+    if event.location == Location::Standard {
+        event.location = code_to_location(event.code);
+    }
+
+    event
+}
+
+/// Map a character to the key that produces it on a US QWERTY keyboard without holding shift, if
+/// there is such a key. Letters are mapped regardless of their case.
+pub fn char_to_code(ch: char) -> Option<Code> {
+    Some(match ch {
+        '`' => Code::Backquote,
+        '\\' => Code::Backslash,
+        '[' => Code::BracketLeft,
+        ']' => Code::BracketRight,
+        ',' => Code::Comma,
+        '0' => Code::Digit0,
+        '1' => Code::Digit1,
+        '2' => Code::Digit2,
+        '3' => Code::Digit3,
+        '4' => Code::Digit4,
+        '5' => Code::Digit5,
+        '6' => Code::Digit6,
+        '7' => Code::Digit7,
+        '8' => Code::Digit8,
+        '9' => Code::Digit9,
+        '=' => Code::Equal,
+        'a' | 'A' => Code::KeyA,
+        'b' | 'B' => Code::KeyB,
+        'c' | 'C' => Code::KeyC,
+        'd' | 'D' => Code::KeyD,
+        'e' | 'E' => Code::KeyE,
+        'f' | 'F' => Code::KeyF,
+        'g' | 'G' => Code::KeyG,
+        'h' | 'H' => Code::KeyH,
+        'i' | 'I' => Code::KeyI,
+        'j' | 'J' => Code::KeyJ,
+        'k' | 'K' => Code::KeyK,
+        'l' | 'L' => Code::KeyL,
+        'm' | 'M' => Code::KeyM,
+        'n' | 'N' => Code::KeyN,
+        'o' | 'O' => Code::KeyO,
+        'p' | 'P' => Code::KeyP,
+        'q' | 'Q' => Code::KeyQ,
+        'r' | 'R' => Code::KeyR,
+        's' | 'S' => Code::KeyS,
+        't' | 'T' => Code::KeyT,
+        'u' | 'U' => Code::KeyU,
+        'v' | 'V' => Code::KeyV,
+        'w' | 'W' => Code::KeyW,
+        'x' | 'X' => Code::KeyX,
+        'y' | 'Y' => Code::KeyY,
+        'z' | 'Z' => Code::KeyZ,
+        '-' => Code::Minus,
+        '.' => Code::Period,
+        '\'' => Code::Quote,
+        ';' => Code::Semicolon,
+        '/' => Code::Slash,
+        '\n' => Code::Enter,
+        _ => return None,
+    })
+}
+
+/// Get a key's location from its code. This matches baseview's behavior.
+pub fn code_to_location(code: Code) -> Location {
+    match code {
+        Code::MetaLeft | Code::ShiftLeft | Code::AltLeft | Code::ControlLeft => Location::Left,
+        Code::MetaRight | Code::ShiftRight | Code::AltRight | Code::ControlRight => Location::Right,
+        Code::Numpad0
+        | Code::Numpad1
+        | Code::Numpad2
+        | Code::Numpad3
+        | Code::Numpad4
+        | Code::Numpad5
+        | Code::Numpad6
+        | Code::Numpad7
+        | Code::Numpad8
+        | Code::Numpad9
+        | Code::NumpadAdd
+        | Code::NumpadComma
+        | Code::NumpadDecimal
+        | Code::NumpadDivide
+        | Code::NumpadEnter
+        | Code::NumpadEqual
+        | Code::NumpadMultiply
+        | Code::NumpadSubtract => Location::Numpad,
+        _ => Location::Standard,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn char_codes_ignore_case() {
+        assert_eq!(char_to_code('a'), Some(Code::KeyA));
+        assert_eq!(char_to_code('A'), Some(Code::KeyA));
+        assert_eq!(char_to_code('1'), Some(Code::Digit1));
+        assert_eq!(char_to_code('/'), Some(Code::Slash));
+        assert_eq!(char_to_code('\n'), Some(Code::Enter));
+    }
+
+    #[test]
+    fn shifted_chars_have_no_code() {
+        // The VST3 wrapper has never reported codes for these, so that should stay the same
+        assert_eq!(char_to_code('!'), None);
+        assert_eq!(char_to_code('?'), None);
+    }
+
+    #[test]
+    fn char_codes_outside_us_layout() {
+        assert_eq!(char_to_code('ä'), None);
+        assert_eq!(char_to_code('€'), None);
+        assert_eq!(char_to_code('\u{3}'), None);
+    }
+
+    #[test]
+    fn locations() {
+        assert_eq!(code_to_location(Code::ShiftLeft), Location::Left);
+        assert_eq!(code_to_location(Code::ControlRight), Location::Right);
+        assert_eq!(code_to_location(Code::Numpad5), Location::Numpad);
+        assert_eq!(code_to_location(Code::NumpadEnter), Location::Numpad);
+        assert_eq!(code_to_location(Code::Enter), Location::Standard);
+        assert_eq!(code_to_location(Code::KeyQ), Location::Standard);
+    }
+
+    #[test]
+    fn events() {
+        let event = create_keyboard_event(
+            Key::Character(String::from("Q")),
+            Code::KeyQ,
+            Modifiers::SHIFT,
+            KeyState::Down,
+        );
+        assert_eq!(event.key, Key::Character(String::from("Q")));
+        assert_eq!(event.code, Code::KeyQ);
+        assert_eq!(event.location, Location::Standard);
+        assert_eq!(event.modifiers, Modifiers::SHIFT);
+        assert_eq!(event.state, KeyState::Down);
+        assert!(!event.repeat);
+        assert!(!event.is_composing);
+
+        let event = create_keyboard_event(
+            Key::Control,
+            Code::ControlRight,
+            Modifiers::CONTROL,
+            KeyState::Up,
+        );
+        assert_eq!(event.location, Location::Right);
+        assert_eq!(event.state, KeyState::Up);
+    }
+
+    #[test]
+    fn normalize_numpad_location() {
+        let mut event = create_keyboard_event(
+            Key::Character(String::from("7")),
+            Code::Numpad7,
+            Modifiers::empty(),
+            KeyState::Down,
+        );
+        event.location = Location::Standard;
+        assert_eq!(normalize_keyboard_event(event).location, Location::Numpad);
+
+        let mut event = create_keyboard_event(
+            Key::Shift,
+            Code::ShiftRight,
+            Modifiers::SHIFT,
+            KeyState::Down,
+        );
+        event.location = Location::Left;
+        assert_eq!(normalize_keyboard_event(event).location, Location::Left);
+    }
+}
@@ -6,8 +6,9 @@ use super::wrapper::{GuiTask, Wrapper};
 use crate::context::gui::GuiContext;
 use crate::context::init::InitContext;
 use crate::context::process::{ProcessContext, Transport};
-use crate::context::PluginApi;
+use crate::context::{PluginApi, TrackInfo};
 use crate::event_loop::EventLoop;
+use crate::midi::learn::MidiLearnStatus;
 use crate::midi::NoteEvent;
 use crate::params::internals::ParamPtr;
 use crate::plugin::Plugin;
@@ -60,6 +61,24 @@ impl<P: Plugin, B: Backend> InitContext<P> for WrapperInitContext<'_, P, B> {
     fn set_current_voice_capacity(&self, _capacity: u32) {
         // This is only supported by CLAP
     }
+
+    fn notify_note_names_changed(&self) {
+        // There's no host to display these names
+    }
+
+    fn aux_input_active(&self, _index: usize) -> bool {
+        // The standalone target doesn't support auxiliary busses yet
+        false
+    }
+
+    fn aux_output_active(&self, _index: usize) -> bool {
+        false
+    }
+
+    fn track_info(&self) -> Option<TrackInfo> {
+        // There's no host track the plugin could be inserted on
+        None
+    }
 }
 
 impl<P: Plugin, B: Backend> ProcessContext<P> for WrapperProcessContext<'_, P, B> {
@@ -105,6 +124,23 @@ impl<P: Plugin, B: Backend> ProcessContext<P> for WrapperProcessContext<'_, P, B
     fn set_current_voice_capacity(&self, _capacity: u32) {
         // This is only supported by CLAP
     }
+
+    fn notify_note_names_changed(&self) {
+        // There's no host to display these names
+    }
+
+    fn aux_input_active(&self, _index: usize) -> bool {
+        // The standalone target doesn't support auxiliary busses yet
+        false
+    }
+
+    fn aux_output_active(&self, _index: usize) -> bool {
+        false
+    }
+
+    fn request_audio_ports_rescan(&self) {
+        // The standalone target's bus configuration is fixed
+    }
 }
 
 impl<P: Plugin, B: Backend> GuiContext for WrapperGuiContext<P, B> {
@@ -143,4 +179,34 @@ impl<P: Plugin, B: Backend> GuiContext for WrapperGuiContext<P, B> {
     fn set_state(&self, state: crate::wrapper::state::PluginState) {
         self.wrapper.set_state_object(state)
     }
+
+    fn notify_note_names_changed(&self) {
+        // There's no host to display these names
+    }
+
+    fn request_audio_ports_rescan(&self) {
+        // The standalone target's bus configuration is fixed
+    }
+
+    fn track_info(&self) -> Option<TrackInfo> {
+        // There's no host track the plugin could be inserted on
+        None
+    }
+
+    unsafe fn raw_show_context_menu(&self, _param: ParamPtr, _x: i32, _y: i32) -> bool {
+        // There's no host that could show a context menu
+        false
+    }
+
+    unsafe fn raw_begin_midi_learn(&self, _param: ParamPtr) {
+        // MIDI learn is not supported by the standalone wrapper
+    }
+
+    fn cancel_midi_learn(&self) {}
+
+    unsafe fn raw_clear_midi_mapping(&self, _param: ParamPtr) {}
+
+    unsafe fn raw_midi_learn_status(&self, _param: ParamPtr) -> MidiLearnStatus {
+        MidiLearnStatus::Unmapped
+    }
 }
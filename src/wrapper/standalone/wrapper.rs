@@ -2,6 +2,7 @@ use atomic_refcell::AtomicRefCell;
 use baseview::{EventStatus, Window, WindowHandler, WindowOpenOptions};
 use crossbeam::channel;
 use crossbeam::queue::ArrayQueue;
+use keyboard_types::KeyState;
 use parking_lot::Mutex;
 use raw_window_handle::HasRawWindowHandle;
 use std::collections::{HashMap, HashSet};
@@ -18,12 +19,15 @@ use crate::editor::{Editor, ParentWindowHandle, SpawnedWindow};
 use crate::event_loop::{EventLoop, MainThreadExecutor, OsEventLoop};
 use crate::midi::NoteEvent;
 use crate::params::internals::ParamPtr;
+use crate::params::macros::MacroLinks;
+use crate::params::persist::StateContext;
 use crate::params::{ParamFlags, Params};
 use crate::plugin::{
     AuxiliaryBuffers, AuxiliaryIOConfig, BufferConfig, BusConfig, Plugin, ProcessMode,
     ProcessStatus, TaskExecutor,
 };
 use crate::util::permit_alloc;
+use crate::wrapper::keyboard::normalize_keyboard_event;
 use crate::wrapper::state::{self, PluginState};
 use crate::wrapper::util::process_wrapper;
 
@@ -49,6 +53,9 @@ pub struct Wrapper<P: Plugin, B: Backend> {
     known_parameters: HashSet<ParamPtr>,
     /// A mapping from parameter string IDs to parameter pointers.
     param_map: HashMap<String, ParamPtr>,
+    /// The targets for all of the plugin's macro parameters. There is no host to notify here, so
+    /// these are simply updated together with the macro parameters.
+    macro_links: MacroLinks,
     /// The plugin's editor, if it has one. This object does not do anything on its own, but we need
     /// to instantiate this in advance so we don't need to lock the entire [`Plugin`] object when
     /// creating an editor. Wrapped in an `AtomicRefCell` because it needs to be initialized late.
@@ -98,6 +105,9 @@ pub enum WrapperError {
 }
 
 struct WrapperWindowHandler {
+    /// The plugin's editor. Key events that reach the wrapper's window instead of the editor's own
+    /// window are forwarded to this, just like the plugin wrappers do with the host's key events.
+    editor: Arc<Mutex<Box<dyn Editor>>>,
     /// The editor handle for the plugin's open editor. The editor should clean itself up when it
     /// gets dropped.
     editor_handle: Box<dyn SpawnedWindow>,
@@ -135,15 +145,30 @@ impl WindowHandler for WrapperWindowHandler {
     }
 
     fn on_event(&mut self, _window: &mut Window, event: baseview::Event) -> EventStatus {
-        if let baseview::Event::Window(baseview::WindowEvent::Resized(window_info)) = event {
-            self.editor_handle.resize(
-                window_info.physical_size().width as f32,
-                window_info.physical_size().height as f32,
-                window_info.scale() as f32
-            );
-            return EventStatus::Captured
+        match event {
+            baseview::Event::Window(baseview::WindowEvent::Resized(window_info)) => {
+                self.editor_handle.resize(
+                    window_info.physical_size().width as f32,
+                    window_info.physical_size().height as f32,
+                    window_info.scale() as f32
+                );
+                EventStatus::Captured
+            }
+            baseview::Event::Keyboard(event) => {
+                let event = normalize_keyboard_event(event);
+                let handled = match event.state {
+                    KeyState::Down => self.editor.lock().on_key_down(&event),
+                    KeyState::Up => self.editor.lock().on_key_up(&event),
+                };
+
+                if handled {
+                    EventStatus::Captured
+                } else {
+                    EventStatus::Ignored
+                }
+            }
+            _ => EventStatus::Ignored,
         }
-        EventStatus::Ignored
     }
 }
 
@@ -214,6 +239,7 @@ impl<P: Plugin, B: Backend> Wrapper<P, B> {
             task_executor_wrapper: task_executor_wrapper.clone(),
             params,
             known_parameters: param_map.iter().map(|(_, ptr, _)| *ptr).collect(),
+            macro_links: unsafe { MacroLinks::from_param_map(&param_map) },
             param_map: param_map
                 .into_iter()
                 .map(|(param_id, param_ptr, _)| (param_id, param_ptr))
@@ -342,6 +368,7 @@ impl<P: Plugin, B: Backend> Wrapper<P, B> {
                         );
 
                         WrapperWindowHandler {
+                            editor,
                             editor_handle,
                             gui_task_receiver,
                         }
@@ -396,6 +423,8 @@ impl<P: Plugin, B: Backend> Wrapper<P, B> {
                 self.param_map
                     .iter()
                     .map(|(param_id, param_ptr)| (param_id, *param_ptr)),
+                None,
+                StateContext::Preset,
             )
         }
     }
@@ -471,6 +500,10 @@ impl<P: Plugin, B: Backend> Wrapper<P, B> {
                     {
                         unsafe { param_ptr.set_normalized_value(normalized_value) };
                         unsafe { param_ptr.update_smoother(sample_rate, false) };
+                        unsafe {
+                            self.macro_links
+                                .update_targets(param_ptr, Some(sample_rate))
+                        };
                         parameter_values_changed = true;
                     }
 
@@ -493,7 +526,9 @@ impl<P: Plugin, B: Backend> Wrapper<P, B> {
                                 &mut state,
                                 self.params.clone(),
                                 |param_id| self.param_map.get(param_id).copied(),
+                                None,
                                 Some(&self.buffer_config),
+                                StateContext::Preset,
                             );
                         }
 
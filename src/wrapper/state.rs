@@ -6,7 +6,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
+use crate::midi::learn::{MidiLearn, MIDI_MAPPINGS_FIELD_KEY};
 use crate::params::internals::ParamPtr;
+use crate::params::persist::StateContext;
 use crate::params::{Param, ParamMut, Params};
 use crate::plugin::{BufferConfig, Plugin};
 
@@ -50,6 +52,10 @@ pub struct PluginState {
     ///
     /// The individual fields are also serialized as JSON so they can safely be restored
     /// independently of the other fields.
+    ///
+    /// The wrappers also store the plugin's MIDI learn mappings here under
+    /// [`MIDI_MAPPINGS_FIELD_KEY`], except for preset state. The VST3 wrapper also stores the
+    /// selected program here when saving a project if the plugin has programs.
     pub fields: BTreeMap<String, Vec<u8>>,
 }
 
@@ -83,9 +89,15 @@ pub(crate) fn make_params_getter<'a>(
 /// allow passing the raw object directly to the plugin. The parameters are not pulled directly from
 /// `plugin_params` by default to avoid unnecessary allocations in the `.param_map()` method, as the
 /// plugin wrappers will already have a list of parameters handy. See [`make_params_iter()`].
+///
+/// Persistent fields that opted out of being stored in the given [`StateContext`] are left out. If
+/// the wrapper supports MIDI learn, then its mappings are stored as well unless this is preset
+/// state.
 pub(crate) unsafe fn serialize_object<'a, P: Plugin>(
     plugin_params: Arc<dyn Params>,
     params_iter: impl IntoIterator<Item = (&'a String, ParamPtr)>,
+    midi_learn: Option<&MidiLearn>,
+    context: StateContext,
 ) -> PluginState {
     // We'll serialize parameter values as a simple `string_param_id: display_value` map.
     // NOTE: If the plugin is being modulated (and the plugin is a CLAP plugin in Bitwig Studio),
@@ -120,7 +132,16 @@ pub(crate) unsafe fn serialize_object<'a, P: Plugin>(
 
     // The plugin can also persist arbitrary fields alongside its parameters. This is useful for
     // storing things like sample data.
-    let fields = plugin_params.serialize_fields();
+    let mut fields = plugin_params.serialize_fields_for_context(context);
+
+    // MIDI mappings belong to the plugin instance, so loading a preset should not change them
+    if let Some(midi_learn) = midi_learn {
+        if context != StateContext::Preset {
+            if let Some(serialized) = midi_learn.serialize_mappings() {
+                fields.insert(String::from(MIDI_MAPPINGS_FIELD_KEY), serialized);
+            }
+        }
+    }
 
     PluginState {
         version: String::from(P::VERSION),
@@ -135,9 +156,16 @@ pub(crate) unsafe fn serialize_object<'a, P: Plugin>(
 pub(crate) unsafe fn serialize_json<'a, P: Plugin>(
     plugin_params: Arc<dyn Params>,
     params_iter: impl IntoIterator<Item = (&'a String, ParamPtr)>,
+    midi_learn: Option<&MidiLearn>,
+    context: StateContext,
 ) -> Result<Vec<u8>> {
-    let plugin_state = serialize_object::<P>(plugin_params, params_iter);
-    let binary = bincode::serialize(&plugin_state).context("Could not serialize as bincode")?;
+    let plugin_state = serialize_object::<P>(plugin_params, params_iter, midi_learn, context);
+    encode_state(&plugin_state)
+}
+
+/// Encode an existing [`PluginState`] object in the same format used by [`serialize_json()`].
+pub(crate) fn encode_state(plugin_state: &PluginState) -> Result<Vec<u8>> {
+    let binary = bincode::serialize(plugin_state).context("Could not serialize as bincode")?;
 
     #[cfg(feature = "zstd")]
     {
@@ -161,12 +189,16 @@ pub(crate) unsafe fn serialize_json<'a, P: Plugin>(
 /// parameter values. The smoothers have already been reset by this function.
 ///
 /// The [`Plugin`] argument is used to call [`Plugin::filter_state()`] just before loading the
-/// state.
+/// state. Persistent fields that opted out of being stored in the given [`StateContext`] are not
+/// restored. Unless this is preset state, MIDI learn mappings are restored from the state, and
+/// state without mappings clears the current mappings.
 pub(crate) unsafe fn deserialize_object<P: Plugin>(
     state: &mut PluginState,
     plugin_params: Arc<dyn Params>,
     params_getter: impl Fn(&str) -> Option<ParamPtr>,
+    midi_learn: Option<&MidiLearn>,
     current_buffer_config: Option<&BufferConfig>,
+    context: StateContext,
 ) -> bool {
     // This lets the plugin perform migrations on old state if needed
     P::filter_state(state);
@@ -216,9 +248,21 @@ pub(crate) unsafe fn deserialize_object<P: Plugin>(
         }
     }
 
+    // The MIDI mappings are stored alongside the plugin's persistent fields, but the plugin doesn't
+    // know about them so they need to be removed before the fields are passed to the plugin
+    let midi_mappings = state.fields.remove(MIDI_MAPPINGS_FIELD_KEY);
+    if let Some(midi_learn) = midi_learn {
+        if context != StateContext::Preset {
+            match midi_mappings {
+                Some(serialized) => midi_learn.deserialize_mappings(&serialized),
+                None => midi_learn.set_mappings(&[]),
+            }
+        }
+    }
+
     // The plugin can also persist arbitrary fields alongside its parameters. This is useful for
     // storing things like sample data.
-    plugin_params.deserialize_fields(&state.fields);
+    plugin_params.deserialize_fields_for_context(&state.fields, context);
 
     true
 }
@@ -236,18 +280,39 @@ pub(crate) unsafe fn deserialize_json<P: Plugin>(
     state: &[u8],
     plugin_params: Arc<dyn Params>,
     params_getter: impl Fn(&str) -> Option<ParamPtr>,
+    midi_learn: Option<&MidiLearn>,
     current_buffer_config: Option<&BufferConfig>,
+    context: StateContext,
 ) -> bool {
+    let mut state = match decode_state(state) {
+        Some(state) => state,
+        None => return false,
+    };
+
+    deserialize_object::<P>(
+        &mut state,
+        plugin_params,
+        params_getter,
+        midi_learn,
+        current_buffer_config,
+        context,
+    )
+}
+
+/// Decode a [`PluginState`] object from data created by [`serialize_json()`] or
+/// [`encode_state()`] without loading it. Returns `None` and logs an error if the data could not
+/// be decoded.
+pub(crate) fn decode_state(state: &[u8]) -> Option<PluginState> {
     #[cfg(feature = "zstd")]
-    let mut state: PluginState = match zstd::decode_all(state) {
+    match zstd::decode_all(state) {
         Ok(decompressed) => match bincode::deserialize(decompressed.as_slice()) {
             Ok(s) => {
                 nih_log!("Deserialized compressed");
-                s
+                Some(s)
             }
             Err(err) => {
                 nih_debug_assert_failure!("Error while deserializing state: {}", err);
-                return false;
+                None
             }
         },
         // Uncompressed state files can still be loaded after enabling this feature to prevent
@@ -255,7 +320,7 @@ pub(crate) unsafe fn deserialize_json<P: Plugin>(
         Err(zstd_err) => match bincode::deserialize(state) {
             Ok(s) => {
                 nih_log!("Deserialized uncompressed");
-                s
+                Some(s)
             }
             Err(json_err) => {
                 nih_debug_assert_failure!(
@@ -264,24 +329,17 @@ pub(crate) unsafe fn deserialize_json<P: Plugin>(
                     zstd_err,
                     json_err
                 );
-                return false;
+                None
             }
         },
-    };
+    }
 
     #[cfg(not(feature = "zstd"))]
-    let mut state: PluginState = match bincode::deserialize(state) {
-        Ok(s) => s,
+    match bincode::deserialize(state) {
+        Ok(s) => Some(s),
         Err(err) => {
             nih_debug_assert_failure!("Error while deserializing state: {}", err);
-            return false;
+            None
         }
-    };
-
-    deserialize_object::<P>(
-        &mut state,
-        plugin_params,
-        params_getter,
-        current_buffer_config,
-    )
+    }
 }
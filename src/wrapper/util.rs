@@ -5,6 +5,8 @@ use std::marker::PhantomData;
 use std::os::raw::c_char;
 use std::sync::atomic::{AtomicBool, Ordering};
 
+use crate::params::internals::ParamPtr;
+use crate::params::ParamFlags;
 use crate::util::permit_alloc;
 
 /// The environment variable for controlling the logging behavior.
@@ -57,6 +59,35 @@ pub fn strlcpy(dest: &mut [c_char], src: &str) {
     dest[copy_len] = 0;
 }
 
+/// Get the display string for a parameter that should be displayed as a list of options. This is the
+/// same string [`ParamPtr::value_strings()`] would return for the option nearest to the normalized
+/// value, without formatting all of the other options. This returns `None` if the parameter should
+/// not be displayed as a list, in which case the parameter's regular value to string conversion
+/// should be used.
+///
+/// # Safety
+///
+/// `param_ptr` needs to point to a valid parameter.
+pub unsafe fn list_value_string(
+    param_ptr: &ParamPtr,
+    normalized: f32,
+    include_unit: bool,
+) -> Option<String> {
+    if !param_ptr.flags().contains(ParamFlags::LIST) {
+        return None;
+    }
+
+    // This needs to be computed the same way as in `Param::value_strings()`
+    let step_count = param_ptr.step_count()?;
+    let snapped_normalized = if step_count == 0 {
+        0.0
+    } else {
+        (normalized.clamp(0.0, 1.0) * step_count as f32).round() / step_count as f32
+    };
+
+    Some(param_ptr.normalized_value_to_string(snapped_normalized, include_unit))
+}
+
 /// Set up the logger so that the `nih_*!()` logging and assertion macros log output to a
 /// centralized location and panics also get written there. By default this logs to STDERR unless
 /// the user is running Windows and a debugger has been attached, in which case
@@ -288,3 +319,39 @@ mod miri {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::prelude::{IntParam, IntRange, Param};
+
+    #[test]
+    fn list_value_strings() {
+        let param = IntParam::new("Bands", 2, IntRange::Linear { min: 1, max: 3 })
+            .with_value_to_string(Arc::new(|value| format!("{value} bands")))
+            .show_as_list();
+        let param_ptr = param.as_ptr();
+        unsafe {
+            assert_eq!(
+                list_value_string(&param_ptr, 0.0, false).as_deref(),
+                Some("1 bands")
+            );
+            assert_eq!(
+                list_value_string(&param_ptr, 0.4, false).as_deref(),
+                Some("2 bands")
+            );
+            assert_eq!(
+                list_value_string(&param_ptr, 1.0, false).as_deref(),
+                Some("3 bands")
+            );
+        }
+
+        let param = IntParam::new("Bands", 2, IntRange::Linear { min: 1, max: 3 });
+        assert_eq!(
+            unsafe { list_value_string(&param.as_ptr(), 0.5, false) },
+            None
+        );
+    }
+}
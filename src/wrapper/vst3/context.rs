@@ -8,7 +8,8 @@ use super::inner::{Task, WrapperInner};
 use crate::context::gui::GuiContext;
 use crate::context::init::InitContext;
 use crate::context::process::{ProcessContext, Transport};
-use crate::context::PluginApi;
+use crate::context::{PluginApi, TrackInfo};
+use crate::midi::learn::MidiLearnStatus;
 use crate::midi::NoteEvent;
 use crate::params::internals::ParamPtr;
 use crate::plugin::Vst3Plugin;
@@ -54,6 +55,22 @@ impl<P: Vst3Plugin> InitContext<P> for WrapperInitContext<'_, P> {
     fn set_current_voice_capacity(&self, _capacity: u32) {
         // This is only supported by CLAP
     }
+
+    fn notify_note_names_changed(&self) {
+        self.inner.notify_note_names_changed()
+    }
+
+    fn aux_input_active(&self, index: usize) -> bool {
+        self.inner.aux_input_active(index)
+    }
+
+    fn aux_output_active(&self, index: usize) -> bool {
+        self.inner.aux_output_active(index)
+    }
+
+    fn track_info(&self) -> Option<TrackInfo> {
+        self.inner.track_info()
+    }
 }
 
 impl<P: Vst3Plugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
@@ -91,6 +108,22 @@ impl<P: Vst3Plugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
     fn set_current_voice_capacity(&self, _capacity: u32) {
         // This is only supported by CLAP
     }
+
+    fn notify_note_names_changed(&self) {
+        self.inner.notify_note_names_changed()
+    }
+
+    fn aux_input_active(&self, index: usize) -> bool {
+        self.inner.aux_input_active(index)
+    }
+
+    fn aux_output_active(&self, index: usize) -> bool {
+        self.inner.aux_output_active(index)
+    }
+
+    fn request_audio_ports_rescan(&self) {
+        // VST3 has no way to change the number of busses at runtime
+    }
 }
 
 impl<P: Vst3Plugin> GuiContext for WrapperGuiContext<P> {
@@ -171,4 +204,54 @@ impl<P: Vst3Plugin> GuiContext for WrapperGuiContext<P> {
     fn set_state(&self, state: PluginState) {
         self.inner.set_state_object(state)
     }
+
+    fn notify_note_names_changed(&self) {
+        self.inner.notify_note_names_changed()
+    }
+
+    fn request_audio_ports_rescan(&self) {
+        // VST3 has no way to change the number of busses at runtime
+    }
+
+    fn track_info(&self) -> Option<TrackInfo> {
+        self.inner.track_info()
+    }
+
+    unsafe fn raw_show_context_menu(&self, param: ParamPtr, x: i32, y: i32) -> bool {
+        match self.inner.param_ptr_to_hash.get(&param) {
+            Some(hash) => self.inner.show_context_menu(*hash, x, y),
+            None => {
+                nih_debug_assert_failure!("Unknown parameter: {:?}", param);
+                false
+            }
+        }
+    }
+
+    unsafe fn raw_begin_midi_learn(&self, param: ParamPtr) {
+        match self.inner.param_ptr_to_hash.get(&param) {
+            Some(hash) => self.inner.midi_learn.arm(*hash),
+            None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
+        }
+    }
+
+    fn cancel_midi_learn(&self) {
+        self.inner.midi_learn.cancel();
+    }
+
+    unsafe fn raw_clear_midi_mapping(&self, param: ParamPtr) {
+        match self.inner.param_ptr_to_hash.get(&param) {
+            Some(hash) => self.inner.midi_learn.clear(*hash),
+            None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
+        }
+    }
+
+    unsafe fn raw_midi_learn_status(&self, param: ParamPtr) -> MidiLearnStatus {
+        match self.inner.param_ptr_to_hash.get(&param) {
+            Some(hash) => self.inner.midi_learn.status(*hash),
+            None => {
+                nih_debug_assert_failure!("Unknown parameter: {:?}", param);
+                MidiLearnStatus::Unmapped
+            }
+        }
+    }
 }
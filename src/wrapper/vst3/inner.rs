@@ -3,27 +3,35 @@ use crossbeam::atomic::AtomicCell;
 use crossbeam::channel::{self, SendTimeoutError};
 use parking_lot::{Mutex, RwLock};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use vst3_sys::base::{kInvalidArgument, kResultOk, tresult};
-use vst3_sys::vst::{IComponentHandler, RestartFlags};
+use vst3_sys::vst::{IComponentHandler, IComponentHandler3, IUnitHandler, RestartFlags};
 
 use super::context::{WrapperGuiContext, WrapperInitContext, WrapperProcessContext};
 use super::note_expressions::NoteExpressionController;
 use super::param_units::ParamUnits;
-use super::util::{ObjectPtr, VstPtr, VST3_MIDI_PARAMS_END, VST3_MIDI_PARAMS_START};
+use super::util::{
+    ObjectPtr, VstPtr, NOTE_NAMES_PROGRAM_LIST_ID, PROGRAMS_PROGRAM_LIST_ID, VST3_MIDI_PARAMS_END,
+    VST3_MIDI_PARAMS_START, VST3_PROGRAM_CHANGE_PARAM_ID,
+};
 use super::view::WrapperView;
 use crate::buffer::Buffer;
 use crate::context::gui::AsyncExecutor;
 use crate::context::process::Transport;
+use crate::context::TrackInfo;
 use crate::editor::Editor;
 use crate::event_loop::{EventLoop, MainThreadExecutor, OsEventLoop};
-use crate::midi::{MidiConfig, NoteEvent};
+use crate::midi::learn::MidiLearn;
+use crate::midi::{MidiConfig, NoteEvent, NoteName};
 use crate::params::internals::ParamPtr;
+use crate::params::macros::MacroLinks;
+use crate::params::persist::StateContext;
 use crate::params::{ParamFlags, Params};
 use crate::plugin::{
     BufferConfig, BusConfig, Plugin, ProcessMode, ProcessStatus, TaskExecutor, Vst3Plugin,
+    Vst3Program,
 };
 use crate::wrapper::state::{self, PluginState};
 use crate::wrapper::util::{hash_param_id, process_wrapper};
@@ -69,6 +77,12 @@ pub(crate) struct WrapperInner<P: Vst3Plugin> {
     pub is_processing: AtomicBool,
     /// The current bus configuration, modified through `IAudioProcessor::setBusArrangements()`.
     pub current_bus_config: AtomicCell<BusConfig>,
+    /// Whether the plugin's auxiliary input busses are active, modified through
+    /// `IComponent::activateBus()`. All busses are active by default. The number of busses never
+    /// changes, and these can be read from the audio thread while the host changes them.
+    pub aux_inputs_active: Vec<AtomicBool>,
+    /// The same as [`aux_inputs_active`][Self::aux_inputs_active], but for the auxiliary outputs.
+    pub aux_outputs_active: Vec<AtomicBool>,
     /// The current buffer configuration, containing the sample rate and the maximum block size.
     /// Will be set in `IAudioProcessor::setupProcessing()`.
     pub current_buffer_config: AtomicCell<Option<BufferConfig>>,
@@ -111,6 +125,33 @@ pub(crate) struct WrapperInner<P: Vst3Plugin> {
     /// the most recent VST3 note IDs we've seen, and then map those back to MIDI note IDs and
     /// channels as needed.
     pub note_expression_controller: AtomicRefCell<NoteExpressionController>,
+    /// Whether the root unit exposes a program list for the plugin's note names. VST3 only supports
+    /// note names (or pitch names, as VST3 calls them) as part of a program list, and the number of
+    /// program lists cannot change at runtime. Since the plugin's note names can change at runtime,
+    /// this is set for every plugin that accepts MIDI input.
+    pub has_note_names_list: bool,
+    /// The plugin's note names, cached so the plugin doesn't need to be locked for every single
+    /// pitch name the host queries. This is only accessed from the main thread.
+    pub note_names: AtomicRefCell<Vec<NoteName>>,
+    /// Set when the plugin signals that its note names have changed. The cache in
+    /// [`note_names`][Self::note_names] is then refreshed the next time the host queries a pitch
+    /// name.
+    pub note_names_dirty: AtomicBool,
+    /// The programs returned from [`Vst3Plugin::vst3_programs()`] when the plugin was created. The
+    /// host can replace a program's state through `IProgramListData::set_program_data()`, but the
+    /// number of programs never changes. This is only accessed from the main thread.
+    pub programs: AtomicRefCell<Vec<Vst3Program>>,
+    /// The number of programs in [`programs`][Self::programs], so the audio thread doesn't need to
+    /// borrow that vector.
+    pub num_programs: usize,
+    /// The index of the most recently selected program. This is what the host sees as the value of
+    /// the program change parameter.
+    pub current_program: AtomicUsize,
+    /// The most recent information about the track the plugin is inserted on, as reported by the
+    /// host through `IInfoListener::set_channel_context_infos()`. This is `None` until the host
+    /// calls that function. This is read from the GUI thread while the host updates it on the main
+    /// thread, so it cannot be an `AtomicRefCell`.
+    pub track_info: Mutex<Option<TrackInfo>>,
     /// Unprocessed parameter changes and note events sent by the host during a process call.
     /// Parameter changes are sent as separate queues for each parameter, and note events are in
     /// another queue on top of that. And if `P::MIDI_INPUT >= MidiConfig::MidiCCs`, then we can
@@ -148,6 +189,13 @@ pub(crate) struct WrapperInner<P: Vst3Plugin> {
     /// having to add a setter function to the parameter (or even worse, have it be completely
     /// untyped).
     pub param_ptr_to_hash: HashMap<ParamPtr, u32>,
+    /// The targets for all of the plugin's macro parameters. Whenever a macro parameter is changed
+    /// in [`set_normalized_value_by_hash()`][Self::set_normalized_value_by_hash()], its targets
+    /// are updated and the host is told that the parameter values have changed.
+    pub macro_links: MacroLinks,
+    /// MIDI CCs bound to parameters through MIDI learn. Mapped CCs are turned into parameter
+    /// changes in the process function instead of being passed to the plugin.
+    pub midi_learn: MidiLearn,
 }
 
 /// Tasks that can be sent from the plugin to be executed on the main thread in a non-blocking
@@ -163,6 +211,20 @@ pub enum Task<P: Plugin> {
     /// Request the editor to be resized according to its current size. Right now there is no way to
     /// handle "denied resize" requests yet.
     RequestResize,
+    /// Trigger a `kParamValuesChanged` restart after a macro parameter has changed the values of
+    /// its targets. See [`MacroLinks`].
+    MacroTargetsChanged,
+    /// Trigger a `kParamValuesChanged` restart after a MIDI CC bound through MIDI learn has changed
+    /// a parameter's value from the audio thread. See [`MidiLearn`].
+    MidiMappedParamsChanged,
+    /// Load the state of one of the plugin's programs after the host selected it from the audio
+    /// thread.
+    LoadProgram(usize),
+    /// Inform the host that the pitch names in the note names program list have changed.
+    NoteNamesChanged,
+    /// Show the host's context menu for a parameter. `IComponentHandler3` can only be used from the
+    /// GUI thread, while the editor may be running on its own thread.
+    ShowContextMenu { param_hash: u32, x: i32, y: i32 },
 }
 
 /// VST3 makes audio processing pretty complicated. In order to support both block splitting for
@@ -253,6 +315,13 @@ impl<P: Vst3Plugin> WrapperInner<P> {
                         id
                     );
                 }
+                if *hash == VST3_PROGRAM_CHANGE_PARAM_ID {
+                    nih_debug_assert_failure!(
+                        "Parameter '{}' collides with the program change parameter, consider \
+                         giving it a different ID",
+                        id
+                    );
+                }
             }
         }
 
@@ -270,7 +339,7 @@ impl<P: Vst3Plugin> WrapperInner<P> {
                 .map(|(_, hash, _, group_name)| (*hash, group_name.as_str())),
         )
         .expect("Inconsistent parameter groups");
-        let param_id_to_hash = param_id_hashes_ptrs_groups
+        let param_id_to_hash: HashMap<String, u32> = param_id_hashes_ptrs_groups
             .iter()
             .map(|(id, hash, _, _)| (id.clone(), *hash))
             .collect();
@@ -278,6 +347,15 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             .into_iter()
             .map(|(_, hash, ptr, _)| (ptr, hash))
             .collect();
+        let macro_links = unsafe { MacroLinks::from_param_map(&params.param_map()) };
+        let midi_learn = MidiLearn::new(&param_id_to_hash);
+
+        let programs = plugin.vst3_programs();
+        let note_names = if P::MIDI_INPUT >= MidiConfig::Basic {
+            plugin.note_names()
+        } else {
+            Vec::new()
+        };
 
         let wrapper = Self {
             plugin: Mutex::new(plugin),
@@ -303,6 +381,12 @@ impl<P: Vst3Plugin> WrapperInner<P> {
                 aux_input_busses: P::DEFAULT_AUX_INPUTS.unwrap_or_default(),
                 aux_output_busses: P::DEFAULT_AUX_OUTPUTS.unwrap_or_default(),
             }),
+            aux_inputs_active: (0..P::DEFAULT_AUX_INPUTS.unwrap_or_default().num_busses)
+                .map(|_| AtomicBool::new(true))
+                .collect(),
+            aux_outputs_active: (0..P::DEFAULT_AUX_OUTPUTS.unwrap_or_default().num_busses)
+                .map(|_| AtomicBool::new(true))
+                .collect(),
             current_buffer_config: AtomicCell::new(None),
             current_process_mode: AtomicCell::new(ProcessMode::Realtime),
             last_process_status: AtomicCell::new(ProcessStatus::Normal),
@@ -314,6 +398,13 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             input_events: AtomicRefCell::new(VecDeque::with_capacity(1024)),
             output_events: AtomicRefCell::new(VecDeque::with_capacity(1024)),
             note_expression_controller: AtomicRefCell::new(NoteExpressionController::default()),
+            has_note_names_list: P::MIDI_INPUT >= MidiConfig::Basic,
+            note_names: AtomicRefCell::new(note_names),
+            note_names_dirty: AtomicBool::new(false),
+            num_programs: programs.len(),
+            programs: AtomicRefCell::new(programs),
+            current_program: AtomicUsize::new(0),
+            track_info: Mutex::new(None),
             process_events: AtomicRefCell::new(Vec::with_capacity(4096)),
             updated_state_sender,
             updated_state_receiver,
@@ -323,6 +414,8 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             param_units,
             param_id_to_hash,
             param_ptr_to_hash,
+            macro_links,
+            midi_learn,
         };
 
         // FIXME: Right now this is safe, but if we are going to have a singleton main thread queue
@@ -455,12 +548,34 @@ impl<P: Vst3Plugin> WrapperInner<P> {
                     _ => unsafe { param_ptr.set_normalized_value(normalized_value) },
                 }
 
+                // If this was a macro parameter, then its targets have also changed and the host
+                // needs to know about that
+                if unsafe { self.macro_links.update_targets(*param_ptr, sample_rate) }
+                    && self.macro_links.begin_host_notification()
+                {
+                    let task_posted = self.schedule_gui(Task::MacroTargetsChanged);
+                    if !task_posted {
+                        self.macro_links.host_notified();
+                        nih_debug_assert_failure!("The task queue is full, dropping task...");
+                    }
+                }
+
                 kResultOk
             }
             _ => kInvalidArgument,
         }
     }
 
+    /// The MIDI learn state that should be stored in and restored from the plugin's state. This is
+    /// `None` when the plugin does not receive MIDI CCs, since nothing can be mapped then.
+    pub fn state_midi_learn(&self) -> Option<&MidiLearn> {
+        if P::MIDI_INPUT >= MidiConfig::MidiCCs {
+            Some(&self.midi_learn)
+        } else {
+            None
+        }
+    }
+
     /// Get the plugin's state object, may be called by the plugin's GUI as part of its own preset
     /// management. The wrapper doesn't use these functions and serializes and deserializes directly
     /// the JSON in the relevant plugin API methods instead.
@@ -469,6 +584,8 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             state::serialize_object::<P>(
                 self.params.clone(),
                 state::make_params_iter(&self.param_by_hash, &self.param_id_to_hash),
+                self.state_midi_learn(),
+                StateContext::Preset,
             )
         }
     }
@@ -514,7 +631,9 @@ impl<P: Vst3Plugin> WrapperInner<P> {
                         &mut state,
                         self.params.clone(),
                         state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                        self.state_midi_learn(),
                         self.current_buffer_config.load().as_ref(),
+                        StateContext::Preset,
                     );
                 }
 
@@ -542,6 +661,37 @@ impl<P: Vst3Plugin> WrapperInner<P> {
         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
     }
 
+    /// Ask the host to show its context menu for a parameter at the specified position in the
+    /// editor. Returns `false` if the host does not support `IComponentHandler3` or if the editor
+    /// is not open. When called from the GUI thread this also returns `false` if the host could
+    /// not show the menu. Otherwise the menu is shown asynchronously from the GUI thread.
+    pub fn show_context_menu(&self, param_hash: u32, x: i32, y: i32) -> bool {
+        let supports_context_menus = match &*self.component_handler.borrow() {
+            Some(handler) => handler.cast::<dyn IComponentHandler3>().is_some(),
+            None => false,
+        };
+        if !supports_context_menus {
+            return false;
+        }
+
+        let is_gui_thread = self.event_loop.borrow().as_ref().unwrap().is_main_thread();
+        if is_gui_thread {
+            match &*self.plug_view.read() {
+                Some(plug_view) => unsafe { plug_view.show_context_menu(param_hash, x, y) },
+                None => false,
+            }
+        } else {
+            if self.plug_view.read().is_none() {
+                return false;
+            }
+
+            let task_posted = self.schedule_gui(Task::ShowContextMenu { param_hash, x, y });
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+
+            task_posted
+        }
+    }
+
     pub fn set_latency_samples(&self, samples: u32) {
         // Only trigger a restart if it's actually needed
         let old_latency = self.current_latency.swap(samples, Ordering::SeqCst);
@@ -551,6 +701,91 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
         }
     }
+
+    pub fn aux_input_active(&self, index: usize) -> bool {
+        self.aux_inputs_active
+            .get(index)
+            .map(|active| active.load(Ordering::SeqCst))
+            .unwrap_or(false)
+    }
+
+    pub fn aux_output_active(&self, index: usize) -> bool {
+        self.aux_outputs_active
+            .get(index)
+            .map(|active| active.load(Ordering::SeqCst))
+            .unwrap_or(false)
+    }
+
+    pub fn notify_note_names_changed(&self) {
+        // VST3 hosts can only query note names through the root unit's program list, and that only
+        // exists if the plugin accepts MIDI input
+        if self.has_note_names_list {
+            self.note_names_dirty.store(true, Ordering::SeqCst);
+            let task_posted = self.schedule_gui(Task::NoteNamesChanged);
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        }
+    }
+
+    /// Convert the program change parameter's normalized value to a program index.
+    pub fn program_index(&self, normalized_value: f32) -> usize {
+        program_index(self.num_programs, normalized_value)
+    }
+
+    /// The inverse of [`program_index()`][Self::program_index()].
+    pub fn program_normalized_value(&self, program_index: usize) -> f32 {
+        program_normalized_value(self.num_programs, program_index)
+    }
+
+    /// Load one of the plugin's programs. This should only be called from the main thread.
+    pub fn load_program(&self, program_index: usize) {
+        let state = match self.programs.borrow().get(program_index) {
+            Some(program) => program.state.clone(),
+            None => {
+                nih_debug_assert_failure!("Unknown program index: {}", program_index);
+                return;
+            }
+        };
+
+        self.current_program.store(program_index, Ordering::SeqCst);
+        self.set_state_object(state);
+    }
+
+    pub fn track_info(&self) -> Option<TrackInfo> {
+        self.track_info.lock().clone()
+    }
+
+    /// Whether the plugin currently has any note names, querying the plugin again if its note names
+    /// have changed. This should only be called from the main thread.
+    pub fn has_note_names(&self) -> bool {
+        self.update_note_names();
+        !self.note_names.borrow().is_empty()
+    }
+
+    /// Get the name for a MIDI note on a specific channel, querying the plugin again if its note
+    /// names have changed. This should only be called from the main thread.
+    pub fn note_name(&self, channel: u8, note: u8) -> Option<String> {
+        self.update_note_names();
+
+        // Names for specific channels take precedence over names that apply to all channels
+        let note_names = self.note_names.borrow();
+        note_names
+            .iter()
+            .find(|name| name.note == note && name.channel == Some(channel))
+            .or_else(|| {
+                note_names
+                    .iter()
+                    .find(|name| name.note == note && name.channel.is_none())
+            })
+            .map(|name| name.name.clone())
+    }
+
+    /// Refresh [`note_names`][Self::note_names] if the plugin has signaled that its note names have
+    /// changed.
+    fn update_note_names(&self) {
+        if self.note_names_dirty.swap(false, Ordering::SeqCst) {
+            *self.note_names.borrow_mut() = self.plugin.lock().note_names();
+        }
+    }
 }
 
 impl<P: Vst3Plugin> MainThreadExecutor<Task<P>> for WrapperInner<P> {
@@ -572,6 +807,106 @@ impl<P: Vst3Plugin> MainThreadExecutor<Task<P>> for WrapperInner<P> {
                 },
                 None => nih_debug_assert_failure!("Can't resize a closed editor"),
             },
+            Task::MacroTargetsChanged => {
+                self.macro_links.host_notified();
+                match &*self.component_handler.borrow() {
+                    Some(handler) => unsafe {
+                        nih_debug_assert!(is_gui_thread);
+                        handler.restart_component(RestartFlags::kParamValuesChanged as i32);
+                    },
+                    None => nih_debug_assert_failure!("Component handler not yet set"),
+                }
+            }
+            Task::MidiMappedParamsChanged => {
+                self.midi_learn.host_notified();
+                match &*self.component_handler.borrow() {
+                    Some(handler) => unsafe {
+                        nih_debug_assert!(is_gui_thread);
+                        handler.restart_component(RestartFlags::kParamValuesChanged as i32);
+                    },
+                    None => nih_debug_assert_failure!("Component handler not yet set"),
+                }
+            }
+            Task::LoadProgram(program_index) => {
+                nih_debug_assert!(is_gui_thread);
+                self.load_program(program_index);
+            }
+            Task::NoteNamesChanged => match &*self.component_handler.borrow() {
+                Some(handler) => {
+                    // Not every host implements `IUnitHandler`, so this is not an error. If the
+                    // plugin has programs, then the note names are also exposed for those.
+                    if let Some(unit_handler) = handler.cast::<dyn IUnitHandler>() {
+                        nih_debug_assert!(is_gui_thread);
+                        unsafe {
+                            unit_handler.notify_program_list_change(NOTE_NAMES_PROGRAM_LIST_ID, -1);
+                            if self.num_programs > 0 {
+                                unit_handler
+                                    .notify_program_list_change(PROGRAMS_PROGRAM_LIST_ID, -1);
+                            }
+                        };
+                    }
+                }
+                None => nih_debug_assert_failure!("Component handler not yet set"),
+            },
+            Task::ShowContextMenu { param_hash, x, y } => match &*self.plug_view.read() {
+                Some(plug_view) => unsafe {
+                    nih_debug_assert!(is_gui_thread);
+                    plug_view.show_context_menu(param_hash, x, y);
+                },
+                None => nih_debug_assert_failure!("Can't show a context menu for a closed editor"),
+            },
+        }
+    }
+}
+
+/// Convert the program change parameter's normalized value to an index in a list of
+/// `num_programs` programs.
+fn program_index(num_programs: usize, normalized_value: f32) -> usize {
+    let max_index = num_programs.saturating_sub(1);
+    ((normalized_value.clamp(0.0, 1.0) * max_index as f32).round() as usize).min(max_index)
+}
+
+/// The inverse of [`program_index()`].
+fn program_normalized_value(num_programs: usize, program_index: usize) -> f32 {
+    match num_programs {
+        0 | 1 => 0.0,
+        num_programs => program_index as f32 / (num_programs - 1) as f32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn program_index_round_trips() {
+        for num_programs in 1..=5 {
+            for index in 0..num_programs {
+                let normalized_value = program_normalized_value(num_programs, index);
+                assert_eq!(program_index(num_programs, normalized_value), index);
+            }
         }
     }
+
+    #[test]
+    fn program_index_snaps_and_clamps() {
+        assert_eq!(program_index(3, 0.2), 0);
+        assert_eq!(program_index(3, 0.3), 1);
+        assert_eq!(program_index(3, 0.8), 2);
+        assert_eq!(program_index(3, -1.0), 0);
+        assert_eq!(program_index(3, 2.0), 2);
+    }
+
+    #[test]
+    fn single_program_is_always_selected() {
+        assert_eq!(program_normalized_value(1, 0), 0.0);
+        assert_eq!(program_index(1, 0.0), 0);
+        assert_eq!(program_index(1, 1.0), 0);
+    }
+
+    #[test]
+    fn no_programs() {
+        assert_eq!(program_normalized_value(0, 0), 0.0);
+        assert_eq!(program_index(0, 0.5), 0);
+    }
 }
@@ -1,6 +1,8 @@
 use keyboard_types::*;
 use vst3_sys::base::char16;
 
+use crate::wrapper::keyboard::{char_to_code, create_keyboard_event};
+
 pub fn create_vst_keyboard_event(key_char: vst3_sys::base::char16, virtual_key_code: i16, vst_modifiers: i16, state: KeyState) -> Result<KeyboardEvent, ()> {
 
     let key_code = VstKeyCode::try_from(virtual_key_code).ok();
@@ -38,72 +40,14 @@ pub fn create_vst_keyboard_event(key_char: vst3_sys::base::char16, virtual_key_c
     }).unwrap_or(Code::Unidentified);
 
     let modifiers: Modifiers = VstKeyModifier::from_bits(vst_modifiers as usize).ok_or(())?.into();
-    let location = code_to_location(code);
 
-    Ok(KeyboardEvent { code, key, location, modifiers, state, is_composing: false, repeat: false })
+    Ok(create_keyboard_event(key, code, modifiers, state))
 }
 
 fn convert_char16(key: char16) -> Option<char> {
     char::decode_utf16([key as u16]).next().and_then(|x| x.ok())
 }
 
-fn char_to_code(ch: char) -> Option<Code> {
-    Some(match ch {
-        '`' => Code::Backquote,
-        '\\' => Code::Backslash,
-        '[' => Code::BracketLeft,
-        ']' => Code::BracketRight,
-        ',' => Code::Comma,
-        '0' => Code::Digit0,
-        '1' => Code::Digit1,
-        '2' => Code::Digit2,
-        '3' => Code::Digit3,
-        '4' => Code::Digit4,
-        '5' => Code::Digit5,
-        '6' => Code::Digit6,
-        '7' => Code::Digit7,
-        '8' => Code::Digit8,
-        '9' => Code::Digit9,
-        '=' => Code::Equal,
-        'a' | 'A' => Code::KeyA,
-        'b' | 'B' => Code::KeyB,
-        'c' | 'C' => Code::KeyC,
-        'd' | 'D' => Code::KeyD,
-        'e' | 'E' => Code::KeyE,
-        'f' | 'F' => Code::KeyF,
-        'g' | 'G' => Code::KeyG,
-        'h' | 'H' => Code::KeyH,
-        'i' | 'I' => Code::KeyI,
-        'j' | 'J' => Code::KeyJ,
-        'k' | 'K' => Code::KeyK,
-        'l' | 'L' => Code::KeyL,
-        'm' | 'M' => Code::KeyM,
-        'n' | 'N' => Code::KeyN,
-        'o' | 'O' => Code::KeyO,
-        'p' | 'P' => Code::KeyP,
-        'q' | 'Q' => Code::KeyQ,
-        'r' | 'R' => Code::KeyR,
-        's' | 'S' => Code::KeyS,
-        't' | 'T' => Code::KeyT,
-        'u' | 'U' => Code::KeyU,
-        'v' | 'V' => Code::KeyV,
-        'w' | 'W' => Code::KeyW,
-        'x' | 'X' => Code::KeyX,
-        'y' | 'Y' => Code::KeyY,
-        'z' | 'Z' => Code::KeyZ,
-        '-' => Code::Minus,
-        '.' => Code::Period,
-        '\'' => Code::Quote,
-        ';' => Code::Semicolon,
-        '/' => Code::Slash,
-        '\n' => Code::Enter,
-        _ => {
-            // TODO: can we do more here?
-            return None;
-        }
-    })
-}
-
 fn vst_code_to_key(key_code: VstKeyCode) -> Option<Key> {
     Some(match key_code {
         VstKeyCode::KEY_BACK => Key::Backspace,
@@ -319,29 +263,71 @@ impl Into<Modifiers> for VstKeyModifier {
     }
 }
 
-// copied from baseview::keyboard to make it available here
-pub fn code_to_location(code: Code) -> Location {
-    match code {
-        Code::MetaLeft | Code::ShiftLeft | Code::AltLeft | Code::ControlLeft => Location::Left,
-        Code::MetaRight | Code::ShiftRight | Code::AltRight | Code::ControlRight => Location::Right,
-        Code::Numpad0
-        | Code::Numpad1
-        | Code::Numpad2
-        | Code::Numpad3
-        | Code::Numpad4
-        | Code::Numpad5
-        | Code::Numpad6
-        | Code::Numpad7
-        | Code::Numpad8
-        | Code::Numpad9
-        | Code::NumpadAdd
-        | Code::NumpadComma
-        | Code::NumpadDecimal
-        | Code::NumpadDivide
-        | Code::NumpadEnter
-        | Code::NumpadEqual
-        | Code::NumpadMultiply
-        | Code::NumpadSubtract => Location::Numpad,
-        _ => Location::Standard,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_down(key_char: char, virtual_key_code: i16, vst_modifiers: i16) -> KeyboardEvent {
+        create_vst_keyboard_event(key_char as char16, virtual_key_code, vst_modifiers, KeyState::Down)
+            .expect("Invalid modifiers")
+    }
+
+    #[test]
+    fn virtual_key_codes() {
+        let event = key_down('\0', VstKeyCode::KEY_F1 as i16, 0);
+        assert_eq!(event.key, Key::F1);
+        assert_eq!(event.code, Code::F1);
+
+        let event = key_down('\0', VstKeyCode::KEY_LEFT as i16, 0);
+        assert_eq!(event.key, Key::ArrowLeft);
+        assert_eq!(event.code, Code::ArrowLeft);
+
+        let event = key_down('\0', VstKeyCode::KEY_SPACE as i16, 0);
+        assert_eq!(event.key, Key::Character(String::from(" ")));
+        assert_eq!(event.code, Code::Space);
+    }
+
+    #[test]
+    fn numpad_keys() {
+        let event = key_down('7', VstKeyCode::KEY_NUMPAD7 as i16, 0);
+        assert_eq!(event.key, Key::Character(String::from("7")));
+        assert_eq!(event.code, Code::Numpad7);
+        assert_eq!(event.location, Location::Numpad);
+    }
+
+    #[test]
+    fn characters() {
+        let event = key_down('a', 0, 0);
+        assert_eq!(event.key, Key::Character(String::from("a")));
+        assert_eq!(event.code, Code::KeyA);
+        assert_eq!(event.location, Location::Standard);
+
+        // KEY_EQUALS is ignored in favor of the character
+        let event = key_down('=', VstKeyCode::KEY_EQUALS as i16, 0);
+        assert_eq!(event.key, Key::Character(String::from("=")));
+        assert_eq!(event.code, Code::Equal);
+
+        let event = key_down('\0', 0, 0);
+        assert_eq!(event.key, Key::Unidentified);
+        assert_eq!(event.code, Code::Unidentified);
+    }
+
+    #[test]
+    fn control_characters_use_ascii_key_code() {
+        // This is what Ableton Live on Windows sends for Ctrl+C
+        let virtual_key_code = VKEY_FIRST_ASCII + ('C' as i16 - 0x30);
+        let event = key_down('\u{3}', virtual_key_code, VstKeyModifier::CONTROL_KEY.bits() as i16);
+        assert_eq!(event.key, Key::Character(String::from("C")));
+        assert_eq!(event.code, Code::KeyC);
+        assert_eq!(event.modifiers, Modifiers::CONTROL);
+    }
+
+    #[test]
+    fn modifiers() {
+        let vst_modifiers = VstKeyModifier::SHIFT_KEY | VstKeyModifier::ALTERNATE_KEY | VstKeyModifier::COMMAND_KEY;
+        let event = key_down('x', 0, vst_modifiers.bits() as i16);
+        assert_eq!(event.modifiers, Modifiers::SHIFT | Modifiers::ALT | Modifiers::META);
+
+        assert!(create_vst_keyboard_event('x' as char16, 0, 1 << 4, KeyState::Up).is_err());
     }
 }
@@ -19,6 +19,36 @@ pub const VST3_MIDI_PARAMS_START: u32 = VST3_MIDI_PARAMS_END - VST3_MIDI_NUM_PAR
 /// The (exclusive) end of the MIDI CC parameter range. Anything above this is reserved by the host.
 pub const VST3_MIDI_PARAMS_END: u32 = 1 << 31;
 
+/// The ID of the program list used to expose the plugin's note names. VST3 only supports note names
+/// (or pitch names) as part of a program list, so if the plugin has note names then the root unit
+/// gets a program list with a single program with this ID.
+pub const NOTE_NAMES_PROGRAM_LIST_ID: i32 = 0;
+/// The ID of the program list containing the programs from `Vst3Plugin::vst3_programs()`. If the
+/// plugin has programs, then this list is assigned to the root unit instead of the note names
+/// program list, and the plugin's note names are exposed for each of its programs.
+pub const PROGRAMS_PROGRAM_LIST_ID: i32 = 1;
+/// The ID of the hidden parameter used to select one of the plugin's programs. This sits right
+/// below the MIDI CC parameter range. Like with the MIDI CC parameters, an assertion failure is
+/// printed if any of the plugin's parameters collides with this ID.
+pub const VST3_PROGRAM_CHANGE_PARAM_ID: u32 = VST3_MIDI_PARAMS_START - 1;
+/// The controller number used in `IMidiMapping` for MIDI program change messages
+/// (`kCtrlProgramChange`). vst3-sys doesn't expose this constant.
+pub const VST3_MIDI_CTRL_PROGRAM_CHANGE: i16 = 130;
+/// The `ParameterInfo` flag that marks a parameter as the program change parameter for a unit's
+/// program list. vst3-sys doesn't expose this flag.
+#[allow(non_upper_case_globals)]
+pub const kIsProgramChange: i32 = 1 << 15;
+/// The key the index of the selected program is stored under in the plugin's
+/// [`PluginState::fields`][crate::prelude::PluginState::fields] when saving a project, so it can be
+/// restored together with the rest of the state.
+pub const VST3_PROGRAM_FIELD_KEY: &str = "nih_plug:vst3_program";
+
+/// The attribute list keys used in `IInfoListener::set_channel_context_infos()` for the track's name
+/// and color. These are null terminated so they can be passed to the host directly. vst3-sys
+/// doesn't expose these constants.
+pub const VST3_CHANNEL_NAME_KEY: &[u8] = b"channel name\0";
+pub const VST3_CHANNEL_COLOR_KEY: &[u8] = b"channel color\0";
+
 /// Early exit out of a VST3 function when one of the passed pointers is null
 macro_rules! check_null_ptr {
     ($ptr:expr $(, $ptrs:expr)* $(, )?) => {
@@ -9,12 +9,15 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use vst3_sys::base::{kInvalidArgument, kResultFalse, kResultOk, tresult, TBool, kResultTrue};
 use vst3_sys::gui::{IPlugFrame, IPlugView, IPlugViewContentScaleSupport, ViewRect};
+use vst3_sys::interfaces::IUnknown;
 use vst3_sys::utils::SharedVstPtr;
+use vst3_sys::vst::{IComponentHandler3, IContextMenu, IContextMenuItem, IContextMenuTarget};
 use vst3_sys::VST3;
 use keyboard_types::KeyState;
 
 use super::inner::{Task, WrapperInner};
-use super::util::{ObjectPtr, VstPtr};
+use super::util::{u16strlcpy, ObjectPtr, VstPtr};
+use crate::context::gui::ContextMenuEntry;
 use crate::editor::{Editor, ParentWindowHandle, SpawnedWindow};
 use crate::plugin::Vst3Plugin;
 
@@ -44,6 +47,11 @@ const VST3_PLATFORM_UIVIEW: &str = "UIView";
 #[allow(unused)]
 const VST3_PLATFORM_X11_WINDOW: &str = "X11EmbedWindowID";
 
+// `IContextMenuItem` flags, also missing from vst3-sys
+const VST3_CONTEXT_MENU_ITEM_IS_SEPARATOR: i32 = 1 << 0;
+const VST3_CONTEXT_MENU_ITEM_IS_DISABLED: i32 = 1 << 1;
+const VST3_CONTEXT_MENU_ITEM_IS_CHECKED: i32 = 1 << 2;
+
 /// FIXME: vst3-sys does not allow you to conditionally define fields with #[cfg()], so this is a
 ///        workaround to define the field outside of the struct
 #[cfg(target_os = "linux")]
@@ -73,6 +81,18 @@ pub(crate) struct WrapperView<P: Vst3Plugin> {
     scaling_factor: AtomicF32,
 }
 
+/// The target for the entries the plugin adds to the host's parameter context menus in
+/// [`WrapperView::show_context_menu()`]. Selecting one of those entries calls
+/// [`Plugin::context_menu_action()`][crate::prelude::Plugin::context_menu_action()] with the
+/// entry's action ID, which is used as the menu item's tag.
+#[VST3(implements(IContextMenuTarget))]
+struct ContextMenuTarget<P: Vst3Plugin> {
+    inner: Arc<WrapperInner<P>>,
+
+    /// The ID of the parameter the context menu was opened for.
+    param_id: Option<String>,
+}
+
 /// Allow handling tasks on the host's GUI thread on Linux. This doesn't need to be a separate
 /// struct, but vst3-sys does not let us implement interfaces conditionally and the interface is
 /// only exposed when compiling on Linux. The struct will register itself when calling
@@ -161,6 +181,93 @@ impl<P: Vst3Plugin> WrapperView<P> {
         }
     }
 
+    /// Ask the host to show its context menu for a parameter at the specified position in the
+    /// view. Any entries returned from
+    /// [`Plugin::context_menu_entries()`][crate::prelude::Plugin::context_menu_entries()] are added
+    /// to the host's menu. Returns `false` if the host does not support `IComponentHandler3`. This
+    /// **needs** to be run from the GUI thread.
+    pub unsafe fn show_context_menu(&self, param_hash: u32, x: i32, y: i32) -> bool {
+        let component_handler = self
+            .inner
+            .component_handler
+            .borrow()
+            .as_ref()
+            .and_then(|handler| handler.cast::<dyn IComponentHandler3>());
+        let component_handler = match component_handler {
+            Some(component_handler) => component_handler,
+            None => return false,
+        };
+
+        // See `request_resize()` for why this needs to be transmuted. The host returns a new
+        // reference to the menu, and `upgrade()` adds another one, so one of them is released
+        // right away.
+        let plug_view: SharedVstPtr<dyn IPlugView> =
+            mem::transmute(&self.__iplugviewvptr as *const *const _);
+        let menu: SharedVstPtr<dyn IContextMenu> =
+            mem::transmute(component_handler.create_context_menu(plug_view, &param_hash));
+        let menu = match menu.upgrade() {
+            Some(menu) => menu,
+            None => return false,
+        };
+        menu.release();
+
+        let param_id = self
+            .inner
+            .param_id_to_hash
+            .iter()
+            .find(|(_, hash)| **hash == param_hash)
+            .map(|(param_id, _)| param_id.as_str());
+        let entries = self.inner.plugin.lock().context_menu_entries(param_id);
+        if !entries.is_empty() {
+            let target = ContextMenuTarget::new(self.inner.clone(), param_id.map(String::from));
+            let target_ptr: SharedVstPtr<dyn IContextMenuTarget> =
+                mem::transmute(&target.__icontextmenutargetvptr as *const *const _);
+
+            for entry in entries {
+                let mut item: IContextMenuItem = mem::zeroed();
+                match entry {
+                    ContextMenuEntry::Action {
+                        label,
+                        action_id,
+                        enabled,
+                    } => {
+                        u16strlcpy(&mut item.name, &label);
+                        item.tag = action_id as i32;
+                        if !enabled {
+                            item.flags |= VST3_CONTEXT_MENU_ITEM_IS_DISABLED;
+                        }
+                    }
+                    ContextMenuEntry::Toggle {
+                        label,
+                        action_id,
+                        enabled,
+                        checked,
+                    } => {
+                        u16strlcpy(&mut item.name, &label);
+                        item.tag = action_id as i32;
+                        if !enabled {
+                            item.flags |= VST3_CONTEXT_MENU_ITEM_IS_DISABLED;
+                        }
+                        if checked {
+                            item.flags |= VST3_CONTEXT_MENU_ITEM_IS_CHECKED;
+                        }
+                    }
+                    ContextMenuEntry::Separator => {
+                        item.flags = VST3_CONTEXT_MENU_ITEM_IS_SEPARATOR;
+                    }
+                }
+
+                menu.add_item(&item, target_ptr);
+            }
+
+            // The menu holds on to its own references to the target, so we can release ours
+            let target = Box::into_raw(target);
+            (*target).release();
+        }
+
+        menu.popup(x, y) == kResultOk
+    }
+
     /// If the host supports `IRunLoop`, then this will post the task to a task queue that will be
     /// run on the host's UI thread. If not, then this will return an `Err` value containing the
     /// task so it can be run elsewhere.
@@ -182,6 +289,12 @@ impl<P: Vst3Plugin> WrapperView<P> {
     }
 }
 
+impl<P: Vst3Plugin> ContextMenuTarget<P> {
+    pub fn new(inner: Arc<WrapperInner<P>>, param_id: Option<String>) -> Box<Self> {
+        Self::allocate(inner, param_id)
+    }
+}
+
 #[cfg(target_os = "linux")]
 impl<P: Vst3Plugin> RunLoopEventHandler<P> {
     pub fn new(inner: Arc<WrapperInner<P>>, run_loop: VstPtr<dyn IRunLoop>) -> Box<Self> {
@@ -357,10 +470,14 @@ impl<P: Vst3Plugin> IPlugView for WrapperView<P> {
         }
     }
 
-    unsafe fn on_wheel(&self, _distance: f32) -> tresult {
-        // We'll let the plugin use the OS' input mechanisms because not all DAWs (or very few
-        // actually) implement these functions
-        kResultOk
+    unsafe fn on_wheel(&self, distance: f32) -> tresult {
+        // Most hosts send wheel events directly to the editor's window, but some Linux hosts only
+        // deliver them through this function
+        if self.editor.lock().on_mouse_wheel(distance) {
+            kResultTrue
+        } else {
+            kResultFalse
+        }
     }
 
     unsafe fn on_key_down(&self, key: vst3_sys::base::char16, key_code: i16, modifiers: i16) -> tresult {
@@ -421,8 +538,10 @@ impl<P: Vst3Plugin> IPlugView for WrapperView<P> {
         kResultOk
     }
 
-    unsafe fn on_focus(&self, _state: TBool) -> tresult {
-        return kResultOk;
+    unsafe fn on_focus(&self, state: TBool) -> tresult {
+        self.editor.lock().on_focus_changed(state != 0);
+
+        kResultOk
     }
 
     unsafe fn set_frame(&self, frame: *mut c_void) -> tresult {
@@ -476,6 +595,17 @@ impl<P: Vst3Plugin> IPlugViewContentScaleSupport for WrapperView<P> {
     }
 }
 
+impl<P: Vst3Plugin> IContextMenuTarget for ContextMenuTarget<P> {
+    unsafe fn execute_menu_item(&self, tag: i32) -> tresult {
+        self.inner
+            .plugin
+            .lock()
+            .context_menu_action(self.param_id.as_deref(), tag as u32);
+
+        kResultOk
+    }
+}
+
 #[cfg(target_os = "linux")]
 impl<P: Vst3Plugin> IEventHandler for RunLoopEventHandler<P> {
     unsafe fn on_fd_is_set(&self, _fd: FileDescriptor) {
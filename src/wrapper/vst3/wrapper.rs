@@ -1,31 +1,40 @@
 use std::cmp;
 use std::ffi::c_void;
 use std::mem::{self, MaybeUninit};
+use std::os::raw::c_char;
 use std::ptr;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use vst3_com::vst::{IProcessContextRequirementsFlags, ProcessModes};
-use vst3_sys::base::{kInvalidArgument, kNoInterface, kResultFalse, kResultOk, tresult, TBool};
+use vst3_sys::base::{
+    kInvalidArgument, kNoInterface, kResultFalse, kResultOk, kResultTrue, tresult, TBool,
+};
 use vst3_sys::base::{IBStream, IPluginBase};
 use vst3_sys::utils::SharedVstPtr;
 use vst3_sys::vst::{
-    kNoParamId, kNoParentUnitId, kNoProgramListId, kRootUnitId, Event, EventTypes, IAudioProcessor,
-    IComponent, IEditController, IEventList, IMidiMapping, INoteExpressionController,
-    IParamValueQueue, IParameterChanges, IProcessContextRequirements, IUnitInfo,
-    LegacyMidiCCOutEvent, NoteExpressionTypeInfo, NoteExpressionValueDescription, NoteOffEvent,
-    NoteOnEvent, ParameterFlags, PolyPressureEvent, ProgramListInfo, TChar, UnitInfo,
+    kNoParamId, kNoParentUnitId, kNoProgramListId, kRootUnitId, Event, EventTypes, IAttributeList,
+    IAudioProcessor, IComponent, IEditController, IEventList, IInfoListener, IMidiLearn,
+    IMidiMapping, INoteExpressionController, IParamValueQueue, IParameterChanges,
+    IProcessContextRequirements, IProgramListData, IUnitInfo, LegacyMidiCCOutEvent,
+    NoteExpressionTypeInfo, NoteExpressionValueDescription, NoteOffEvent, NoteOnEvent,
+    ParameterFlags, PolyPressureEvent, ProgramListInfo, TChar, UnitInfo,
 };
 use vst3_sys::VST3;
 use widestring::U16CStr;
 
 use super::inner::WrapperInner;
 use super::util::{
-    u16strlcpy, VstPtr, VST3_MIDI_CCS, VST3_MIDI_NUM_PARAMS, VST3_MIDI_PARAMS_START,
+    kIsProgramChange, u16strlcpy, VstPtr, NOTE_NAMES_PROGRAM_LIST_ID, PROGRAMS_PROGRAM_LIST_ID,
+    VST3_CHANNEL_COLOR_KEY, VST3_CHANNEL_NAME_KEY, VST3_MIDI_CCS, VST3_MIDI_CTRL_PROGRAM_CHANGE,
+    VST3_MIDI_NUM_PARAMS, VST3_MIDI_PARAMS_START, VST3_PROGRAM_CHANGE_PARAM_ID,
+    VST3_PROGRAM_FIELD_KEY,
 };
 use super::view::WrapperView;
 use crate::buffer::Buffer;
 use crate::context::process::Transport;
+use crate::context::{TrackColor, TrackInfo};
 use crate::midi::{MidiConfig, NoteEvent};
+use crate::params::persist::StateContext;
 use crate::params::ParamFlags;
 use crate::plugin::{
     AuxiliaryBuffers, AuxiliaryIOConfig, BufferConfig, BusConfig, ProcessMode, ProcessStatus,
@@ -33,8 +42,8 @@ use crate::plugin::{
 };
 use crate::util::permit_alloc;
 use crate::wrapper::state;
-use crate::wrapper::util::process_wrapper;
-use crate::wrapper::vst3::inner::ProcessEvent;
+use crate::wrapper::util::{list_value_string, process_wrapper};
+use crate::wrapper::vst3::inner::{ProcessEvent, Task};
 use crate::wrapper::vst3::note_expressions::{self, NoteExpressionController};
 use crate::wrapper::vst3::util::{VST3_MIDI_CHANNELS, VST3_MIDI_PARAMS_END};
 
@@ -45,10 +54,13 @@ use vst3_sys as vst3_com;
     IComponent,
     IEditController,
     IAudioProcessor,
+    IMidiLearn,
     IMidiMapping,
     INoteExpressionController,
     IProcessContextRequirements,
-    IUnitInfo
+    IUnitInfo,
+    IProgramListData,
+    IInfoListener
 ))]
 pub(crate) struct Wrapper<P: Vst3Plugin> {
     inner: Arc<WrapperInner<P>>,
@@ -58,6 +70,29 @@ impl<P: Vst3Plugin> Wrapper<P> {
     pub fn new() -> Box<Self> {
         Self::allocate(WrapperInner::new())
     }
+
+    /// Get the ID of the program list at an index. The note names program list, if it exists,
+    /// always comes first.
+    fn program_list_id(&self, list_index: i32) -> Option<i32> {
+        let has_programs = self.inner.num_programs > 0;
+        match (list_index, self.inner.has_note_names_list, has_programs) {
+            (0, true, _) => Some(NOTE_NAMES_PROGRAM_LIST_ID),
+            (0, false, true) | (1, true, true) => Some(PROGRAMS_PROGRAM_LIST_ID),
+            _ => None,
+        }
+    }
+
+    /// Whether a program in a program list exposes the plugin's note names. These are exposed for
+    /// the note names program list's single program and for all of the plugin's own programs.
+    fn is_pitch_names_program(&self, list_id: i32, program_index: i32) -> bool {
+        match list_id {
+            NOTE_NAMES_PROGRAM_LIST_ID => program_index == 0,
+            PROGRAMS_PROGRAM_LIST_ID => {
+                (0..self.inner.num_programs as i32).contains(&program_index)
+            }
+            _ => false,
+        }
+    }
 }
 
 impl<P: Vst3Plugin> IPluginBase for Wrapper<P> {
@@ -320,14 +355,15 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
         type_: vst3_sys::vst::MediaType,
         dir: vst3_sys::vst::BusDirection,
         index: i32,
-        _state: vst3_sys::base::TBool,
+        state: vst3_sys::base::TBool,
     ) -> tresult {
         // HACK: Bitwig will not call the process function at all if the plugin does not have any
         //       audio IO, so we'll add a zero channel output to work around this if that is the
         //       case
         let no_main_audio_io = P::DEFAULT_INPUT_CHANNELS == 0 && P::DEFAULT_OUTPUT_CHANNELS == 0;
 
-        // We don't need any special handling here
+        // The plugin can query which auxiliary busses are active through the init and process
+        // contexts
         match (type_, dir, index) {
             (t, d, _)
                 if t == vst3_sys::vst::MediaTypes::kAudio as i32
@@ -337,6 +373,11 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
                 let aux_busses = P::DEFAULT_AUX_INPUTS.unwrap_or_default().num_busses as i32;
 
                 if (0..main_busses + aux_busses).contains(&index) {
+                    if index >= main_busses {
+                        self.inner.aux_inputs_active[(index - main_busses) as usize]
+                            .store(state != 0, Ordering::SeqCst);
+                    }
+
                     kResultOk
                 } else {
                     kInvalidArgument
@@ -354,6 +395,11 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
                 let aux_busses = P::DEFAULT_AUX_OUTPUTS.unwrap_or_default().num_busses as i32;
 
                 if (0..main_busses + aux_busses).contains(&index) {
+                    if index >= main_busses {
+                        self.inner.aux_outputs_active[(index - main_busses) as usize]
+                            .store(state != 0, Ordering::SeqCst);
+                    }
+
                     kResultOk
                 } else {
                     kInvalidArgument
@@ -486,49 +532,49 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
     unsafe fn set_state(&self, state: SharedVstPtr<dyn IBStream>) -> tresult {
         check_null_ptr!(state);
 
-        let state = state.upgrade().unwrap();
-
-        // We need to know how large the state is before we can read it. The current position can be
-        // zero, but it can also be something else. Bitwig prepends the preset header in the stream,
-        // while some other hosts don't expose that to the plugin.
-        let mut current_pos = 0;
-        let mut eof_pos = 0;
-        if state.tell(&mut current_pos) != kResultOk
-            || state.seek(0, vst3_sys::base::kIBSeekEnd, &mut eof_pos) != kResultOk
-            || state.seek(current_pos, vst3_sys::base::kIBSeekSet, ptr::null_mut()) != kResultOk
-        {
-            nih_debug_assert_failure!("Could not get the stream length");
-            return kResultFalse;
-        }
+        let read_buffer = match read_stream(state) {
+            Some(read_buffer) => read_buffer,
+            None => return kResultFalse,
+        };
 
-        let stream_byte_size = (eof_pos - current_pos) as i32;
-        let mut num_bytes_read = 0;
-        let mut read_buffer: Vec<u8> = Vec::with_capacity(stream_byte_size as usize);
-        state.read(
-            read_buffer.as_mut_ptr() as *mut c_void,
-            read_buffer.capacity() as i32,
-            &mut num_bytes_read,
-        );
-        read_buffer.set_len(num_bytes_read as usize);
+        let mut plugin_state = match state::decode_state(&read_buffer) {
+            Some(plugin_state) => plugin_state,
+            None => return kResultFalse,
+        };
 
-        // If the size is zero, some hosts will always return `kResultFalse` even if the read was
-        // 'successful', so we can't check the return value but we can check the number of bytes
-        // read.
-        if read_buffer.len() != stream_byte_size as usize {
-            nih_debug_assert_failure!("Unexpected stream length");
-            return kResultFalse;
-        }
+        // The selected program is stored alongside the plugin's fields, so it needs to be removed
+        // before the plugin gets to see them. State without a selected program starts out at the
+        // first program.
+        let current_program = match plugin_state.fields.remove(VST3_PROGRAM_FIELD_KEY) {
+            Some(serialized) => match bincode::deserialize::<u32>(&serialized) {
+                Ok(program_index) if (program_index as usize) < self.inner.num_programs => {
+                    program_index as usize
+                }
+                Ok(_) => 0,
+                Err(err) => {
+                    nih_debug_assert_failure!("Could not deserialize the program index: {}", err);
+                    0
+                }
+            },
+            None => 0,
+        };
 
-        let success = state::deserialize_json::<P>(
-            &read_buffer,
+        let success = state::deserialize_object::<P>(
+            &mut plugin_state,
             self.inner.params.clone(),
             state::make_params_getter(&self.inner.param_by_hash, &self.inner.param_id_to_hash),
+            self.inner.state_midi_learn(),
             self.inner.current_buffer_config.load().as_ref(),
+            StateContext::Project,
         );
         if !success {
             return kResultFalse;
         }
 
+        self.inner
+            .current_program
+            .store(current_program, Ordering::SeqCst);
+
         // Reinitialize the plugin after loading state so it can respond to the new parameter values
         self.inner.notify_param_values_changed();
 
@@ -554,23 +600,32 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
     unsafe fn get_state(&self, state: SharedVstPtr<dyn IBStream>) -> tresult {
         check_null_ptr!(state);
 
-        let state = state.upgrade().unwrap();
-
-        let serialized = state::serialize_json::<P>(
+        let mut plugin_state = state::serialize_object::<P>(
             self.inner.params.clone(),
             state::make_params_iter(&self.inner.param_by_hash, &self.inner.param_id_to_hash),
+            self.inner.state_midi_learn(),
+            StateContext::Project,
         );
-        match serialized {
-            Ok(serialized) => {
-                let mut num_bytes_written = 0;
-                let result = state.write(
-                    serialized.as_ptr() as *const c_void,
-                    serialized.len() as i32,
-                    &mut num_bytes_written,
-                );
 
-                nih_debug_assert_eq!(result, kResultOk);
-                nih_debug_assert_eq!(num_bytes_written as usize, serialized.len());
+        // The selected program is not part of the plugin's own state, so it needs to be stored
+        // separately for it to survive a project reload
+        if self.inner.num_programs > 0 {
+            let current_program = self.inner.current_program.load(Ordering::SeqCst) as u32;
+            match bincode::serialize(&current_program) {
+                Ok(serialized) => {
+                    plugin_state
+                        .fields
+                        .insert(String::from(VST3_PROGRAM_FIELD_KEY), serialized);
+                }
+                Err(err) => {
+                    nih_debug_assert_failure!("Could not serialize the program index: {}", err)
+                }
+            }
+        }
+
+        match state::encode_state(&plugin_state) {
+            Ok(serialized) => {
+                write_stream(state, &serialized);
 
                 nih_trace!("Saved state ({} bytes)", serialized.len());
 
@@ -603,12 +658,17 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
     }
 
     unsafe fn get_parameter_count(&self) -> i32 {
+        let mut num_params = self.inner.param_hashes.len() as i32;
+        // The plugin's programs are selected through a program change parameter
+        if self.inner.num_programs > 0 {
+            num_params += 1;
+        }
         // We need to add a whole bunch of parameters if the plugin accepts MIDI CCs
         if P::MIDI_INPUT >= MidiConfig::MidiCCs {
-            self.inner.param_hashes.len() as i32 + VST3_MIDI_NUM_PARAMS as i32
-        } else {
-            self.inner.param_hashes.len() as i32
+            num_params += VST3_MIDI_NUM_PARAMS as i32;
         }
+
+        num_params
     }
 
     unsafe fn get_parameter_info(
@@ -625,11 +685,22 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
         *info = std::mem::zeroed();
         let info = &mut *info;
 
-        // If the parameter is a generated MIDI CC/channel pressure/pitch bend then it needs to be
-        // handled separately
+        // If the parameter is the program change parameter or a generated MIDI CC/channel
+        // pressure/pitch bend then it needs to be handled separately
         let num_actual_params = self.inner.param_hashes.len() as i32;
-        if P::MIDI_INPUT >= MidiConfig::MidiCCs && param_index >= num_actual_params {
-            let midi_param_relative_idx = (param_index - num_actual_params) as u32;
+        let num_program_params = if self.inner.num_programs > 0 { 1 } else { 0 };
+        if num_program_params > 0 && param_index == num_actual_params {
+            info.id = VST3_PROGRAM_CHANGE_PARAM_ID;
+            u16strlcpy(&mut info.title, "Program");
+            u16strlcpy(&mut info.short_title, "Program");
+            info.step_count = (self.inner.num_programs - 1) as i32;
+            info.unit_id = kRootUnitId;
+            info.flags = ParameterFlags::kIsList as i32 | kIsProgramChange;
+        } else if P::MIDI_INPUT >= MidiConfig::MidiCCs
+            && param_index >= num_actual_params + num_program_params
+        {
+            let midi_param_relative_idx =
+                (param_index - num_actual_params - num_program_params) as u32;
             // This goes up to 130 for the 128 CCs followed by channel pressure and pitch bend
             let midi_cc = midi_param_relative_idx % VST3_MIDI_CCS;
             let midi_channel = midi_param_relative_idx / VST3_MIDI_CCS;
@@ -658,12 +729,14 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
             let automatable = !flags.contains(ParamFlags::NON_AUTOMATABLE);
             let hidden = flags.contains(ParamFlags::HIDDEN);
             let is_bypass = flags.contains(ParamFlags::BYPASS);
+            let step_count = param_ptr.step_count();
+            let is_list = flags.contains(ParamFlags::LIST) && step_count.is_some();
 
             info.id = *param_hash;
             u16strlcpy(&mut info.title, param_ptr.name());
             u16strlcpy(&mut info.short_title, param_ptr.name());
             u16strlcpy(&mut info.units, param_ptr.unit());
-            info.step_count = param_ptr.step_count().unwrap_or(0) as i32;
+            info.step_count = step_count.unwrap_or(0) as i32;
             info.default_normalized_value = default_value as f64;
             info.unit_id = *param_unit;
             info.flags = 0;
@@ -676,6 +749,10 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
             if is_bypass {
                 info.flags |= ParameterFlags::kIsBypass as i32;
             }
+            // The host can query the values' display strings using `getParamStringByValue()`
+            if is_list {
+                info.flags |= ParameterFlags::kIsList as i32;
+            }
         }
 
         kResultOk
@@ -691,14 +768,27 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
 
         let dest = &mut *(string as *mut [TChar; 128]);
 
+        if id == VST3_PROGRAM_CHANGE_PARAM_ID && self.inner.num_programs > 0 {
+            let program_index = self.inner.program_index(value_normalized as f32);
+            return match self.inner.programs.borrow().get(program_index) {
+                Some(program) => {
+                    u16strlcpy(dest, &program.name);
+                    kResultOk
+                }
+                None => kInvalidArgument,
+            };
+        }
+
         // TODO: We don't implement these methods at all for our generated MIDI CC parameters,
         //       should be fine right? They should be hidden anyways.
         match self.inner.param_by_hash.get(&id) {
             Some(param_ptr) => {
-                u16strlcpy(
-                    dest,
-                    &param_ptr.normalized_value_to_string(value_normalized as f32, false),
-                );
+                // Parameters marked with `kIsList` use their list of value strings
+                let display = list_value_string(param_ptr, value_normalized as f32, false)
+                    .unwrap_or_else(|| {
+                        param_ptr.normalized_value_to_string(value_normalized as f32, false)
+                    });
+                u16strlcpy(dest, &display);
 
                 kResultOk
             }
@@ -719,6 +809,22 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
             Err(_) => return kInvalidArgument,
         };
 
+        if id == VST3_PROGRAM_CHANGE_PARAM_ID && self.inner.num_programs > 0 {
+            return match self
+                .inner
+                .programs
+                .borrow()
+                .iter()
+                .position(|program| program.name == string)
+            {
+                Some(program_index) => {
+                    *value_normalized = self.inner.program_normalized_value(program_index) as f64;
+                    kResultOk
+                }
+                None => kResultFalse,
+            };
+        }
+
         match self.inner.param_by_hash.get(&id) {
             Some(param_ptr) => {
                 let value = match param_ptr.string_to_normalized_value(&string) {
@@ -734,6 +840,11 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
     }
 
     unsafe fn normalized_param_to_plain(&self, id: u32, value_normalized: f64) -> f64 {
+        // The program change parameter's plain value is the program's index
+        if id == VST3_PROGRAM_CHANGE_PARAM_ID && self.inner.num_programs > 0 {
+            return self.inner.program_index(value_normalized as f32) as f64;
+        }
+
         match self.inner.param_by_hash.get(&id) {
             Some(param_ptr) => param_ptr.preview_plain(value_normalized as f32) as f64,
             _ => value_normalized,
@@ -741,6 +852,13 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
     }
 
     unsafe fn plain_param_to_normalized(&self, id: u32, plain_value: f64) -> f64 {
+        if id == VST3_PROGRAM_CHANGE_PARAM_ID && self.inner.num_programs > 0 {
+            return self
+                .inner
+                .program_normalized_value(plain_value.max(0.0).round() as usize)
+                as f64;
+        }
+
         match self.inner.param_by_hash.get(&id) {
             Some(param_ptr) => param_ptr.preview_normalized(plain_value as f32) as f64,
             _ => plain_value,
@@ -748,6 +866,11 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
     }
 
     unsafe fn get_param_normalized(&self, id: u32) -> f64 {
+        if id == VST3_PROGRAM_CHANGE_PARAM_ID && self.inner.num_programs > 0 {
+            let current_program = self.inner.current_program.load(Ordering::SeqCst);
+            return self.inner.program_normalized_value(current_program) as f64;
+        }
+
         match self.inner.param_by_hash.get(&id) {
             Some(param_ptr) => param_ptr.modulated_normalized_value() as f64,
             _ => 0.5,
@@ -761,6 +884,15 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
             return kResultOk;
         }
 
+        if id == VST3_PROGRAM_CHANGE_PARAM_ID && self.inner.num_programs > 0 {
+            let program_index = self.inner.program_index(value as f32);
+            if self.inner.current_program.load(Ordering::SeqCst) != program_index {
+                self.inner.load_program(program_index);
+            }
+
+            return kResultOk;
+        }
+
         let sample_rate = self
             .inner
             .current_buffer_config
@@ -1124,9 +1256,28 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                                 let timing = sample_offset as u32;
                                 let value = value as f32;
 
-                                // MIDI CC messages, channel pressure, and pitch bend are also sent
-                                // as parameter changes
-                                if P::MIDI_INPUT >= MidiConfig::MidiCCs
+                                // Program changes, MIDI CC messages, channel pressure, and pitch
+                                // bend are also sent as parameter changes. Program changes load
+                                // the program's state, which can't be done on the audio thread.
+                                if param_hash == VST3_PROGRAM_CHANGE_PARAM_ID
+                                    && self.inner.num_programs > 0
+                                {
+                                    let program_index = self.inner.program_index(value);
+                                    if self
+                                        .inner
+                                        .current_program
+                                        .swap(program_index, Ordering::SeqCst)
+                                        != program_index
+                                    {
+                                        let task_posted = self
+                                            .inner
+                                            .schedule_gui(Task::LoadProgram(program_index));
+                                        nih_debug_assert!(
+                                            task_posted,
+                                            "The task queue is full, dropping task..."
+                                        );
+                                    }
+                                } else if P::MIDI_INPUT >= MidiConfig::MidiCCs
                                     && (VST3_MIDI_PARAMS_START..VST3_MIDI_PARAMS_END)
                                         .contains(&param_hash)
                                 {
@@ -1136,6 +1287,40 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                                     let midi_cc = (midi_param_relative_idx % VST3_MIDI_CCS) as u8;
                                     let midi_channel =
                                         (midi_param_relative_idx / VST3_MIDI_CCS) as u8;
+
+                                    // CCs bound to a parameter through MIDI learn are turned into
+                                    // parameter changes instead of being passed to the plugin. The
+                                    // host won't know about these changes unless we tell it.
+                                    if let Some(learned_param_hash) =
+                                        self.inner.midi_learn.handle_cc(midi_channel, midi_cc)
+                                    {
+                                        if P::SAMPLE_ACCURATE_AUTOMATION {
+                                            process_events.push(ProcessEvent::ParameterChange {
+                                                timing,
+                                                hash: learned_param_hash,
+                                                normalized_value: value,
+                                            });
+                                        } else {
+                                            self.inner.set_normalized_value_by_hash(
+                                                learned_param_hash,
+                                                value,
+                                                Some(sample_rate),
+                                            );
+                                            parameter_values_changed = true;
+                                        }
+
+                                        if self.inner.midi_learn.begin_host_notification() {
+                                            let task_posted = self
+                                                .inner
+                                                .schedule_gui(Task::MidiMappedParamsChanged);
+                                            if !task_posted {
+                                                self.inner.midi_learn.host_notified();
+                                            }
+                                        }
+
+                                        continue;
+                                    }
+
                                     process_events.push(ProcessEvent::NoteEvent {
                                         timing,
                                         event: match midi_cc {
@@ -1810,7 +1995,9 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                         &self.inner.param_by_hash,
                         &self.inner.param_id_to_hash,
                     ),
+                    self.inner.state_midi_learn(),
                     self.inner.current_buffer_config.load().as_ref(),
+                    StateContext::Preset,
                 );
 
                 self.inner.notify_param_values_changed();
@@ -1863,6 +2050,19 @@ impl<P: Vst3Plugin> IMidiMapping for Wrapper<P> {
         midi_cc_number: vst3_com::vst::CtrlNumber,
         param_id: *mut vst3_com::vst::ParamID,
     ) -> tresult {
+        // MIDI program changes select one of the plugin's programs, if it has any
+        if midi_cc_number == VST3_MIDI_CTRL_PROGRAM_CHANGE
+            && P::MIDI_INPUT >= MidiConfig::Basic
+            && self.inner.num_programs > 0
+            && bus_index == 0
+            && (0..VST3_MIDI_CHANNELS as i16).contains(&channel)
+        {
+            check_null_ptr!(param_id);
+            *param_id = VST3_PROGRAM_CHANGE_PARAM_ID;
+
+            return kResultOk;
+        }
+
         if P::MIDI_INPUT < MidiConfig::MidiCCs
             || bus_index != 0
             || !(0..VST3_MIDI_CHANNELS as i16).contains(&channel)
@@ -1882,6 +2082,31 @@ impl<P: Vst3Plugin> IMidiMapping for Wrapper<P> {
     }
 }
 
+impl<P: Vst3Plugin> IMidiLearn for Wrapper<P> {
+    unsafe fn on_live_midi_controller_input(
+        &self,
+        bus_index: i32,
+        channel: i16,
+        midi_cc: vst3_com::vst::CtrlNumber,
+    ) -> tresult {
+        if P::MIDI_INPUT < MidiConfig::MidiCCs
+            || bus_index != 0
+            || !(0..VST3_MIDI_CHANNELS as i16).contains(&channel)
+            || !(0..128).contains(&midi_cc)
+        {
+            return kResultFalse;
+        }
+
+        // The host sends this for live MIDI input that's routed to the plugin, which is exactly
+        // what's needed to bind an armed parameter. Host automation for the CC is then still sent
+        // through the `IMidiMapping` parameters.
+        match self.inner.midi_learn.learn(channel as u8, midi_cc as u8) {
+            Some(_) => kResultTrue,
+            None => kResultFalse,
+        }
+    }
+}
+
 impl<P: Vst3Plugin> INoteExpressionController for Wrapper<P> {
     unsafe fn get_note_expression_count(&self, bus_idx: i32, _channel: i16) -> i32 {
         // Apparently you need to define the predefined note expressions. Thanks VST3.
@@ -1983,7 +2208,16 @@ impl<P: Vst3Plugin> IUnitInfo for Wrapper<P> {
                 info.id = unit_id;
                 info.parent_unit_id = unit_info.parent_id;
                 u16strlcpy(&mut info.name, &unit_info.name);
-                info.program_list_id = kNoProgramListId;
+                // The programs list also exposes the note names, so that takes precedence
+                info.program_list_id = if unit_id != kRootUnitId {
+                    kNoProgramListId
+                } else if self.inner.num_programs > 0 {
+                    PROGRAMS_PROGRAM_LIST_ID
+                } else if self.inner.has_note_names_list {
+                    NOTE_NAMES_PROGRAM_LIST_ID
+                } else {
+                    kNoProgramListId
+                };
 
                 kResultOk
             }
@@ -1992,25 +2226,62 @@ impl<P: Vst3Plugin> IUnitInfo for Wrapper<P> {
     }
 
     unsafe fn get_program_list_count(&self) -> i32 {
-        // TODO: Do we want program lists? Probably not, CLAP doesn't even support them.
-        0
+        // There's a single program list with a single program used to expose the plugin's note
+        // names since VST3 doesn't allow exposing those any other way, and a program list for the
+        // plugin's own programs
+        self.inner.has_note_names_list as i32 + (self.inner.num_programs > 0) as i32
     }
 
-    unsafe fn get_program_list_info(
-        &self,
-        _list_index: i32,
-        _info: *mut ProgramListInfo,
-    ) -> tresult {
-        kInvalidArgument
+    unsafe fn get_program_list_info(&self, list_index: i32, info: *mut ProgramListInfo) -> tresult {
+        check_null_ptr!(info);
+
+        match self.program_list_id(list_index) {
+            Some(NOTE_NAMES_PROGRAM_LIST_ID) => {
+                *info = mem::zeroed();
+
+                let info = &mut *info;
+                info.id = NOTE_NAMES_PROGRAM_LIST_ID;
+                u16strlcpy(&mut info.name, "Note Names");
+                info.program_count = 1;
+
+                kResultOk
+            }
+            Some(PROGRAMS_PROGRAM_LIST_ID) => {
+                *info = mem::zeroed();
+
+                let info = &mut *info;
+                info.id = PROGRAMS_PROGRAM_LIST_ID;
+                u16strlcpy(&mut info.name, "Programs");
+                info.program_count = self.inner.num_programs as i32;
+
+                kResultOk
+            }
+            _ => kInvalidArgument,
+        }
     }
 
-    unsafe fn get_program_name(
-        &self,
-        _list_id: i32,
-        _program_index: i32,
-        _name: *mut u16,
-    ) -> tresult {
-        kInvalidArgument
+    unsafe fn get_program_name(&self, list_id: i32, program_index: i32, name: *mut u16) -> tresult {
+        check_null_ptr!(name);
+
+        if list_id == NOTE_NAMES_PROGRAM_LIST_ID
+            && program_index == 0
+            && self.inner.has_note_names_list
+        {
+            u16strlcpy(&mut *(name as *mut [TChar; 128]), "Default");
+
+            kResultOk
+        } else if list_id == PROGRAMS_PROGRAM_LIST_ID && program_index >= 0 {
+            match self.inner.programs.borrow().get(program_index as usize) {
+                Some(program) => {
+                    u16strlcpy(&mut *(name as *mut [TChar; 128]), &program.name);
+
+                    kResultOk
+                }
+                None => kInvalidArgument,
+            }
+        } else {
+            kInvalidArgument
+        }
     }
 
     unsafe fn get_program_info(
@@ -2023,19 +2294,42 @@ impl<P: Vst3Plugin> IUnitInfo for Wrapper<P> {
         kInvalidArgument
     }
 
-    unsafe fn has_program_pitch_names(&self, _id: i32, _index: i32) -> tresult {
-        // TODO: Support note names once someone requests it
-        kInvalidArgument
+    unsafe fn has_program_pitch_names(&self, id: i32, index: i32) -> tresult {
+        if self.inner.has_note_names_list
+            && self.is_pitch_names_program(id, index)
+            && self.inner.has_note_names()
+        {
+            kResultOk
+        } else {
+            kResultFalse
+        }
     }
 
     unsafe fn get_program_pitch_name(
         &self,
-        _id: i32,
-        _index: i32,
-        _pitch: i16,
-        _name: *mut u16,
+        id: i32,
+        index: i32,
+        pitch: i16,
+        name: *mut u16,
     ) -> tresult {
-        kInvalidArgument
+        check_null_ptr!(name);
+
+        if !self.inner.has_note_names_list
+            || !self.is_pitch_names_program(id, index)
+            || !(0..128).contains(&pitch)
+        {
+            return kInvalidArgument;
+        }
+
+        // VST3's pitch names don't have a channel, so we'll use the names for the first channel
+        match self.inner.note_name(0, pitch as u8) {
+            Some(note_name) => {
+                u16strlcpy(&mut *(name as *mut [TChar; 128]), &note_name);
+
+                kResultOk
+            }
+            None => kResultFalse,
+        }
     }
 
     unsafe fn get_selected_unit(&self) -> i32 {
@@ -2061,10 +2355,179 @@ impl<P: Vst3Plugin> IUnitInfo for Wrapper<P> {
 
     unsafe fn set_unit_program_data(
         &self,
-        _list_or_unit: i32,
-        _program_idx: i32,
-        _data: SharedVstPtr<dyn IBStream>,
+        list_or_unit: i32,
+        program_idx: i32,
+        data: SharedVstPtr<dyn IBStream>,
     ) -> tresult {
-        kInvalidArgument
+        // This can refer to either a program list or a unit. The root unit uses the programs list
+        // if the plugin has programs, and the note names list doesn't have any data.
+        if list_or_unit == kRootUnitId && self.inner.num_programs > 0 {
+            self.set_program_data(PROGRAMS_PROGRAM_LIST_ID, program_idx, data)
+        } else {
+            self.set_program_data(list_or_unit, program_idx, data)
+        }
+    }
+}
+
+impl<P: Vst3Plugin> IProgramListData for Wrapper<P> {
+    unsafe fn program_data_supported(&self, list_id: i32) -> tresult {
+        if list_id == PROGRAMS_PROGRAM_LIST_ID && self.inner.num_programs > 0 {
+            kResultTrue
+        } else {
+            kResultFalse
+        }
+    }
+
+    unsafe fn get_program_data(
+        &self,
+        list_id: i32,
+        program_index: i32,
+        data: SharedVstPtr<dyn IBStream>,
+    ) -> tresult {
+        check_null_ptr!(data);
+
+        if list_id != PROGRAMS_PROGRAM_LIST_ID || program_index < 0 {
+            return kInvalidArgument;
+        }
+
+        // The program's data uses the same format as the plugin's regular state
+        let serialized = match self.inner.programs.borrow().get(program_index as usize) {
+            Some(program) => state::encode_state(&program.state),
+            None => return kInvalidArgument,
+        };
+        match serialized {
+            Ok(serialized) => {
+                write_stream(data, &serialized);
+
+                kResultOk
+            }
+            Err(err) => {
+                nih_debug_assert_failure!("Could not save program data: {:#}", err);
+                kResultFalse
+            }
+        }
+    }
+
+    unsafe fn set_program_data(
+        &self,
+        list_id: i32,
+        program_index: i32,
+        data: SharedVstPtr<dyn IBStream>,
+    ) -> tresult {
+        check_null_ptr!(data);
+
+        if list_id != PROGRAMS_PROGRAM_LIST_ID
+            || !(0..self.inner.num_programs as i32).contains(&program_index)
+        {
+            return kInvalidArgument;
+        }
+
+        let state = match read_stream(data).and_then(|data| state::decode_state(&data)) {
+            Some(state) => state,
+            None => return kResultFalse,
+        };
+        let program_index = program_index as usize;
+        self.inner.programs.borrow_mut()[program_index].state = state;
+
+        // If this is the currently selected program, then the new state should also be loaded
+        if self.inner.current_program.load(Ordering::SeqCst) == program_index {
+            self.inner.load_program(program_index);
+        }
+
+        kResultOk
     }
 }
+
+impl<P: Vst3Plugin> IInfoListener for Wrapper<P> {
+    unsafe fn set_channel_context_infos(&self, list: SharedVstPtr<dyn IAttributeList>) -> tresult {
+        check_null_ptr!(list);
+
+        let list = list.upgrade().unwrap();
+
+        // The host may only send some of these attributes, and it also only includes the
+        // attributes that have changed when the plugin's channel context changes
+        let mut track_info = self.inner.track_info().unwrap_or_default();
+
+        let mut name = [0 as TChar; 128];
+        if list.get_string(
+            VST3_CHANNEL_NAME_KEY.as_ptr() as *const c_char,
+            name.as_mut_ptr(),
+            mem::size_of_val(&name) as u32,
+        ) == kResultOk
+        {
+            track_info.name = U16CStr::from_ptr_str(name.as_ptr() as *const u16)
+                .to_string()
+                .ok();
+        }
+
+        // This is stored as a 32-bit ARGB value
+        let mut color = 0i64;
+        if list.get_int(VST3_CHANNEL_COLOR_KEY.as_ptr() as *const c_char, &mut color) == kResultOk {
+            track_info.color = Some(TrackColor {
+                red: (color >> 16) as u8,
+                green: (color >> 8) as u8,
+                blue: color as u8,
+                alpha: (color >> 24) as u8,
+            });
+        }
+
+        *self.inner.track_info.lock() = Some(track_info.clone());
+        self.inner.plugin.lock().track_info_changed(&track_info);
+
+        kResultOk
+    }
+}
+
+/// Read the remaining contents of a stream, from its current position to its end. Returns `None`
+/// and prints an assertion failure if the stream could not be read.
+unsafe fn read_stream(stream: SharedVstPtr<dyn IBStream>) -> Option<Vec<u8>> {
+    let stream = stream.upgrade().unwrap();
+
+    // We need to know how large the state is before we can read it. The current position can be
+    // zero, but it can also be something else. Bitwig prepends the preset header in the stream,
+    // while some other hosts don't expose that to the plugin.
+    let mut current_pos = 0;
+    let mut eof_pos = 0;
+    if stream.tell(&mut current_pos) != kResultOk
+        || stream.seek(0, vst3_sys::base::kIBSeekEnd, &mut eof_pos) != kResultOk
+        || stream.seek(current_pos, vst3_sys::base::kIBSeekSet, ptr::null_mut()) != kResultOk
+    {
+        nih_debug_assert_failure!("Could not get the stream length");
+        return None;
+    }
+
+    let stream_byte_size = (eof_pos - current_pos) as i32;
+    let mut num_bytes_read = 0;
+    let mut read_buffer: Vec<u8> = Vec::with_capacity(stream_byte_size as usize);
+    stream.read(
+        read_buffer.as_mut_ptr() as *mut c_void,
+        read_buffer.capacity() as i32,
+        &mut num_bytes_read,
+    );
+    read_buffer.set_len(num_bytes_read as usize);
+
+    // If the size is zero, some hosts will always return `kResultFalse` even if the read was
+    // 'successful', so we can't check the return value but we can check the number of bytes
+    // read.
+    if read_buffer.len() != stream_byte_size as usize {
+        nih_debug_assert_failure!("Unexpected stream length");
+        return None;
+    }
+
+    Some(read_buffer)
+}
+
+/// Write data to a stream.
+unsafe fn write_stream(stream: SharedVstPtr<dyn IBStream>, data: &[u8]) {
+    let stream = stream.upgrade().unwrap();
+
+    let mut num_bytes_written = 0;
+    let result = stream.write(
+        data.as_ptr() as *const c_void,
+        data.len() as i32,
+        &mut num_bytes_written,
+    );
+
+    nih_debug_assert_eq!(result, kResultOk);
+    nih_debug_assert_eq!(num_bytes_written as usize, data.len());
+}